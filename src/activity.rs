@@ -0,0 +1,164 @@
+use ratatui::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::i18n::{tr, trf};
+
+/// 热力图显示的周数
+pub const ACTIVITY_WEEKS: i64 = 12;
+
+const DAY_SECS: i64 = 86400;
+
+/// 仓库最近的提交活动, 只在 HEAD 改变时重新计算
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Activity {
+    /// 计算时 HEAD 指向的提交
+    pub head: String,
+    /// 本地日期 (1970-01-01 之后的天数) 和这一天的提交数量
+    pub days: BTreeMap<i64, u32>,
+}
+
+/// 时间戳对应的本地日期
+fn local_day(timestamp: i64, utc_offset: i64) -> i64 {
+    (timestamp + utc_offset).div_euclid(DAY_SECS)
+}
+
+/// 星期几, 星期一是 0, 1970-01-01 是星期四
+fn weekday(day: i64) -> i64 {
+    (day + 3).rem_euclid(7)
+}
+
+/// HEAD 指向的提交, 没有提交时为 None
+pub fn head_id(path: &Path) -> Option<String> {
+    let repo = git2::Repository::open(path).ok()?;
+    let oid = repo.head().ok()?.target()?;
+    Some(oid.to_string())
+}
+
+/// 从 HEAD 开始统计最近 ACTIVITY_WEEKS 周每天的提交数量
+pub fn commit_activity(path: &Path, now: u64, utc_offset: i64) -> Option<Activity> {
+    let repo = git2::Repository::open(path).ok()?;
+    let head = repo.head().ok()?.target()?;
+    let since = now as i64 - ACTIVITY_WEEKS * 7 * DAY_SECS;
+
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(git2::Sort::TIME).ok()?;
+    revwalk.push(head).ok()?;
+
+    let mut days = BTreeMap::new();
+    for oid in revwalk.flatten() {
+        let Ok(commit) = repo.find_commit(oid) else {
+            continue;
+        };
+        let time = commit.time().seconds();
+        if time < since {
+            break;
+        }
+        *days.entry(local_day(time, utc_offset)).or_insert(0) += 1;
+    }
+
+    Some(Activity {
+        head: head.to_string(),
+        days,
+    })
+}
+
+/// 一天的提交数量对应的符号
+fn level(count: u32) -> &'static str {
+    match count {
+        0 => "·",
+        1 => "░",
+        2..=3 => "▒",
+        4..=6 => "▓",
+        _ => "█",
+    }
+}
+
+/// 提交活动的热力图, 每一列是一周, 每一行是星期几, 最后一列是这一周
+pub fn heatmap_lines(
+    activity: &Activity,
+    now: u64,
+    utc_offset: i64,
+    heading: Style,
+) -> Vec<Line<'static>> {
+    let today = local_day(now as i64, utc_offset);
+    let start = today - weekday(today) - (ACTIVITY_WEEKS - 1) * 7;
+    let total: u32 = activity
+        .days
+        .range(start..=today)
+        .map(|(_, count)| count)
+        .sum();
+
+    let weekdays = [
+        tr("一", "Mon"),
+        tr("二", "Tue"),
+        tr("三", "Wed"),
+        tr("四", "Thu"),
+        tr("五", "Fri"),
+        tr("六", "Sat"),
+        tr("日", "Sun"),
+    ];
+    let mut lines = vec![Line::from(Span::styled(
+        trf!(
+            "提交活动 (最近 {} 周): {} 个提交",
+            "Activity (last {} weeks): {} commits",
+            ACTIVITY_WEEKS,
+            total
+        ),
+        heading,
+    ))];
+    for (row, name) in weekdays.iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("  {:<4}", name))];
+        for week in 0..ACTIVITY_WEEKS {
+            let day = start + week * 7 + row as i64;
+            if day > today {
+                break;
+            }
+            let count = activity.days.get(&day).copied().unwrap_or(0);
+            let cell = format!("{} ", level(count));
+            spans.push(if count > 0 {
+                Span::styled(cell, Style::default().fg(Color::Green))
+            } else {
+                Span::styled(cell, Style::default().fg(Color::DarkGray))
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_heatmap_lines() {
+        // 1970-01-05 是星期一
+        assert_eq!(weekday(4), 0);
+        assert_eq!(local_day(-1, 0), -1);
+        assert_eq!(local_day(DAY_SECS - 10, 3600), 1);
+
+        let today = 4 + 7 * 20 + 2; // 星期三
+        let activity = Activity {
+            head: String::from("abc"),
+            days: BTreeMap::from([(today, 5), (today - 2, 1), (today - 7 * 12, 9)]),
+        };
+        let lines = heatmap_lines(&activity, (today * DAY_SECS) as u64, 0, Style::default());
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect();
+        assert_eq!(text.len(), 8);
+        assert!(text[0].contains(": 6 "));
+        // 最后一列是这一周, 星期三之后的日子还没有到
+        assert!(text[1].trim_end().ends_with('░'));
+        assert!(text[3].trim_end().ends_with('▓'));
+        assert_eq!(text[1].matches(' ').count(), text[3].matches(' ').count());
+        assert_eq!(text[4].chars().filter(|c| *c == '·').count(), 11);
+    }
+}
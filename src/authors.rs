@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 一个仓库所有本地分支上的提交作者, 分支没有变化并且时间范围足够时不需要重新统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepoAuthors {
+    /// 统计时所有本地分支指向的提交, 排好序
+    pub tips: Vec<String>,
+    /// 统计的起始时间, 更早的提交没有统计
+    pub since: u64,
+    /// 提交时间, 作者名字和邮箱
+    pub commits: Vec<(u64, String, String)>,
+}
+
+impl RepoAuthors {
+    /// 缓存是否可以用于从 since 开始的统计
+    pub fn covers(&self, tips: &[String], since: u64) -> bool {
+        self.tips == tips && self.since <= since
+    }
+}
+
+/// 后台统计作者的进度, since 是这次统计的起始时间
+#[derive(Debug)]
+pub enum AuthorsEvent {
+    /// 统计完一个仓库, 缓存仍然可以使用或者无法打开仓库时为 None
+    Repo(PathBuf, Option<RepoAuthors>),
+    Done(u64),
+}
+
+/// 一个作者在所有仓库中的提交数量
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorSummary {
+    pub name: String,
+    pub email: String,
+    pub commits: usize,
+    /// 仓库名字和提交数量, 提交多的在前
+    pub repos: Vec<(String, usize)>,
+}
+
+/// 所有本地分支指向的提交
+pub fn branch_tips(path: &Path) -> Option<Vec<String>> {
+    let repo = git2::Repository::open(path).ok()?;
+    let mut tips: Vec<String> = repo
+        .branches(Some(git2::BranchType::Local))
+        .ok()?
+        .flatten()
+        .filter_map(|(branch, _)| branch.get().target())
+        .map(|oid| oid.to_string())
+        .collect();
+    tips.sort();
+    tips.dedup();
+    Some(tips)
+}
+
+/// 统计所有本地分支上 since 之后的提交
+pub fn repo_authors(path: &Path, tips: Vec<String>, since: u64) -> Option<RepoAuthors> {
+    let repo = git2::Repository::open(path).ok()?;
+    let mut revwalk = repo.revwalk().ok()?;
+    revwalk.set_sorting(git2::Sort::TIME).ok()?;
+    for tip in &tips {
+        revwalk.push(git2::Oid::from_str(tip).ok()?).ok()?;
+    }
+
+    let mut commits = Vec::new();
+    for oid in revwalk.flatten() {
+        let Ok(commit) = repo.find_commit(oid) else {
+            continue;
+        };
+        let time = commit.time().seconds().max(0) as u64;
+        if time < since {
+            break;
+        }
+        let author = commit.author();
+        commits.push((
+            time,
+            author.name().unwrap_or("").to_string(),
+            author.email().unwrap_or("").to_string(),
+        ));
+    }
+
+    Some(RepoAuthors {
+        tips,
+        since,
+        commits,
+    })
+}
+
+/// 按邮箱汇总 since 之后每个作者的提交, 名字使用最近一次提交的名字, 提交多的在前
+pub fn summarize<'a>(
+    repos: impl Iterator<Item = (&'a str, &'a RepoAuthors)>,
+    since: u64,
+) -> Vec<AuthorSummary> {
+    // 邮箱 -> (最近的提交时间, 汇总)
+    let mut authors: HashMap<String, (u64, AuthorSummary)> = HashMap::new();
+    for (repo_name, repo) in repos {
+        for (time, name, email) in repo.commits.iter().filter(|(time, _, _)| *time >= since) {
+            let (latest, summary) = authors.entry(email.to_lowercase()).or_insert_with(|| {
+                (
+                    0,
+                    AuthorSummary {
+                        name: String::new(),
+                        email: email.clone(),
+                        commits: 0,
+                        repos: Vec::new(),
+                    },
+                )
+            });
+            if *time >= *latest {
+                *latest = *time;
+                summary.name = name.clone();
+            }
+            summary.commits += 1;
+            match summary.repos.last_mut() {
+                Some((last, count)) if last == repo_name => *count += 1,
+                _ => summary.repos.push((repo_name.to_string(), 1)),
+            }
+        }
+    }
+
+    let mut res: Vec<AuthorSummary> = authors
+        .into_values()
+        .map(|(_, mut summary)| {
+            summary
+                .repos
+                .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            summary
+        })
+        .collect();
+    res.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then_with(|| a.email.cmp(&b.email))
+    });
+    res
+}
+
+/// 全局 git 配置中的 user.email, 用于标出自己的提交
+pub fn git_user_email() -> Option<String> {
+    git2::Config::open_default()
+        .ok()?
+        .get_string("user.email")
+        .ok()
+        .filter(|email| !email.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let commit = |time, name: &str, email: &str| (time, name.to_string(), email.to_string());
+        let a = RepoAuthors {
+            tips: vec![String::from("1")],
+            since: 0,
+            commits: vec![
+                commit(30, "Alice", "alice@example.com"),
+                commit(20, "alice", "Alice@example.com"),
+                commit(10, "Bob", "bob@example.com"),
+                commit(5, "Bob", "bob@example.com"),
+            ],
+        };
+        let b = RepoAuthors {
+            tips: vec![String::from("2")],
+            since: 0,
+            commits: vec![
+                commit(25, "Bob", "bob@example.com"),
+                commit(15, "Carol", "carol@example.com"),
+            ],
+        };
+        assert!(a.covers(&[String::from("1")], 10));
+        assert!(!a.covers(&[String::from("2")], 10));
+        assert!(!RepoAuthors {
+            since: 20,
+            ..a.clone()
+        }
+        .covers(&a.tips, 10));
+
+        let res = summarize([("a", &a), ("b", &b)].into_iter(), 10);
+        assert_eq!(
+            res.iter()
+                .map(|item| (item.name.as_str(), item.commits))
+                .collect::<Vec<_>>(),
+            vec![("Alice", 2), ("Bob", 2), ("Carol", 1)]
+        );
+        assert_eq!(
+            res[1].repos,
+            vec![(String::from("a"), 1), (String::from("b"), 1)]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::credentials::git2_credentials_callback;
+use crate::error::GitManagerError;
+use crate::fetch::{auth_required, FETCH_TIMEOUT};
+use crate::utils::{BDEResult, Cmd};
+
+/// 网络检查 (fetch) 使用的后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitBackend {
+    /// 调用 git 命令, 使用 git 自己的配置和凭据
+    #[default]
+    Cli,
+    /// 使用 libgit2, 不依赖 git 命令和它的配置
+    Libgit2,
+}
+
+impl fmt::Display for GitBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GitBackend::Cli => write!(f, "git CLI"),
+            GitBackend::Libgit2 => write!(f, "libgit2"),
+        }
+    }
+}
+
+/// 当前的全局后端, 可以在运行时切换
+static BACKEND: AtomicU8 = AtomicU8::new(0);
+
+/// 配置文件中单独指定了后端的仓库
+static REPO_BACKENDS: RwLock<BTreeMap<PathBuf, GitBackend>> = RwLock::new(BTreeMap::new());
+
+pub fn set_backend(backend: GitBackend) {
+    BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+pub fn backend() -> GitBackend {
+    match BACKEND.load(Ordering::Relaxed) {
+        1 => GitBackend::Libgit2,
+        _ => GitBackend::Cli,
+    }
+}
+
+/// 切换全局后端, 返回切换之后的后端
+pub fn toggle_backend() -> GitBackend {
+    let backend = match backend() {
+        GitBackend::Cli => GitBackend::Libgit2,
+        GitBackend::Libgit2 => GitBackend::Cli,
+    };
+    set_backend(backend);
+    backend
+}
+
+pub fn set_repo_backends(repo_backends: BTreeMap<PathBuf, GitBackend>) {
+    if let Ok(mut state) = REPO_BACKENDS.write() {
+        *state = repo_backends;
+    }
+}
+
+/// 仓库使用的后端, 配置中单独指定的优先
+pub fn backend_for(path: &Path) -> GitBackend {
+    REPO_BACKENDS
+        .read()
+        .ok()
+        .and_then(|state| state.get(path).copied())
+        .unwrap_or_else(backend)
+}
+
+/// 使用 libgit2 fetch 当前分支的上游所在的远程仓库, 没有上游时使用 origin
+pub fn libgit2_fetch(path: &Path) -> BDEResult<()> {
+    let repo = git2::Repository::open(path)?;
+
+    let remote_name = repo
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(String::from))
+        .and_then(|head| repo.branch_upstream_remote(&head).ok())
+        .and_then(|remote| remote.as_str().map(String::from))
+        .unwrap_or_else(|| String::from("origin"));
+
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(git2_credentials_callback());
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(callbacks);
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    // 空的 refspecs 表示使用远程仓库配置的 refspecs
+    remote
+        .fetch(&[] as &[&str], Some(&mut opts), None)
+        .map_err(|err| match err.code() {
+            git2::ErrorCode::Auth => GitManagerError::AuthRequired,
+            _ => err.into(),
+        })
+}
+
+/// 使用仓库对应的后端执行 fetch, 返回使用的后端
+///
+/// 和 git 命令一样, 只有超时和需要认证作为错误返回, fetch 失败时继续使用本地的状态
+pub async fn run_fetch(path: &Path) -> BDEResult<GitBackend> {
+    let backend = backend_for(path);
+    match backend {
+        GitBackend::Cli => {
+            let (success, stderr) = Cmd::git(path)
+                .arg("fetch")
+                .stderr_timeout(FETCH_TIMEOUT)
+                .await?;
+            if !success && auth_required(&stderr) {
+                return Err(GitManagerError::AuthRequired);
+            }
+        }
+        GitBackend::Libgit2 => {
+            let repo_path = path.to_path_buf();
+            let fetch = tokio::task::spawn_blocking(move || libgit2_fetch(&repo_path));
+            // 超时之后 libgit2 的线程无法中止, 只是不再等待它的结果
+            match tokio::time::timeout(Duration::from_secs(FETCH_TIMEOUT), fetch).await {
+                Err(_) => {
+                    tracing::warn!("libgit2 fetch {}: timed out", path.display());
+                    return Err(GitManagerError::CommandTimeout);
+                }
+                Ok(Ok(Err(GitManagerError::AuthRequired))) => {
+                    return Err(GitManagerError::AuthRequired);
+                }
+                Ok(Ok(Err(err))) => tracing::warn!("libgit2 fetch {}: {}", path.display(), err),
+                _ => {}
+            }
+        }
+    }
+    Ok(backend)
+}
@@ -0,0 +1,206 @@
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::process::{self, Stdio};
+
+use crate::i18n::trf;
+use crate::utils::{ba_error, BDEResult};
+
+/// 剪贴板后端, 运行时根据环境自动检测
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardBackend {
+    WlCopy,
+    Xclip,
+    Pbcopy,
+    /// 通过终端的 OSC52 转义序列写入剪贴板, 在 ssh 中也可以使用
+    Osc52,
+    /// 直接调用 Windows 的剪贴板 API
+    Windows,
+}
+
+fn command_exists(command: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| {
+            env::split_paths(&paths).any(|dir| {
+                let path = Path::new(&dir).join(command);
+                path.is_file() || (cfg!(windows) && path.with_extension("exe").is_file())
+            })
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn copy_with_windows(text: &str) -> BDEResult<()> {
+    use std::ptr;
+    use winapi::um::winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    use winapi::um::winuser::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData, CF_UNICODETEXT,
+    };
+
+    let data: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    // SAFETY: 打开剪贴板之后一定会关闭, 复制的长度和分配的大小一致,
+    // SetClipboardData 成功之后内存由系统管理, 失败时自己释放
+    unsafe {
+        if OpenClipboard(ptr::null_mut()) == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let res: BDEResult<()> = (|| {
+            EmptyClipboard();
+            let handle = GlobalAlloc(GMEM_MOVEABLE, std::mem::size_of_val(data.as_slice()));
+            if handle.is_null() {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let locked = GlobalLock(handle) as *mut u16;
+            if locked.is_null() {
+                GlobalFree(handle);
+                return Err(std::io::Error::last_os_error().into());
+            }
+            ptr::copy_nonoverlapping(data.as_ptr(), locked, data.len());
+            GlobalUnlock(handle);
+
+            if SetClipboardData(CF_UNICODETEXT, handle).is_null() {
+                let err = std::io::Error::last_os_error();
+                GlobalFree(handle);
+                return Err(err.into());
+            }
+            Ok(())
+        })();
+
+        CloseClipboard();
+        res
+    }
+}
+
+#[cfg(not(windows))]
+fn copy_with_windows(_text: &str) -> BDEResult<()> {
+    use crate::i18n::tr;
+
+    Err(ba_error(tr(
+        "只有 Windows 可以使用系统剪贴板 API",
+        "The Windows clipboard API is only available on Windows",
+    )))
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut res = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                res.push(BASE64_CHARS[((n >> (18 - i * 6)) & 0x3f) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+
+    res
+}
+
+impl ClipboardBackend {
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            ClipboardBackend::Windows
+        } else if cfg!(target_os = "macos") && command_exists("pbcopy") {
+            ClipboardBackend::Pbcopy
+        } else if env::var_os("WAYLAND_DISPLAY").is_some() && command_exists("wl-copy") {
+            ClipboardBackend::WlCopy
+        } else if env::var_os("DISPLAY").is_some() && command_exists("xclip") {
+            ClipboardBackend::Xclip
+        } else {
+            ClipboardBackend::Osc52
+        }
+    }
+
+    fn copy_with_command(command: &str, args: &[&str], text: &str) -> BDEResult<()> {
+        let mut child = process::Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+
+        let status = child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(ba_error(&trf!(
+                "复制到剪贴板失败({}): {}",
+                "failed to copy to clipboard ({}): {}",
+                command,
+                status
+            )))
+        }
+    }
+
+    fn copy_with_osc52(text: &str) -> BDEResult<()> {
+        let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+
+        // tmux 中需要使用 passthrough 才能传给外层终端
+        let sequence = if env::var_os("TMUX").is_some() {
+            format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+        } else {
+            sequence
+        };
+
+        // 界面画在标准错误上, 标准输出可能被 $(gtm --print-path) 捕获
+        let mut stderr = std::io::stderr();
+        stderr.write_all(sequence.as_bytes())?;
+        stderr.flush()?;
+        Ok(())
+    }
+
+    pub fn copy(&self, text: &str) -> BDEResult<()> {
+        match self {
+            ClipboardBackend::WlCopy => ClipboardBackend::copy_with_command("wl-copy", &[], text),
+            ClipboardBackend::Xclip => {
+                ClipboardBackend::copy_with_command("xclip", &["-selection", "clipboard"], text)
+            }
+            ClipboardBackend::Pbcopy => ClipboardBackend::copy_with_command("pbcopy", &[], text),
+            ClipboardBackend::Osc52 => ClipboardBackend::copy_with_osc52(text),
+            ClipboardBackend::Windows => copy_with_windows(text),
+        }
+    }
+}
+
+pub fn copy_to_clipboard(text: &str) -> BDEResult<()> {
+    ClipboardBackend::detect().copy(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{base64_encode, ClipboardBackend};
+
+    #[test]
+    fn test_clipboard() {
+        // 测试中不使用 OSC52, 避免向终端写入转义序列
+        let backend = ClipboardBackend::detect();
+        if backend != ClipboardBackend::Osc52 {
+            let _ = backend.copy("hello world bababa");
+        }
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"hello world"), "aGVsbG8gd29ybGQ=");
+    }
+}
@@ -0,0 +1,169 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::authors::AuthorSummary;
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 可以选择的统计天数
+const WINDOWS: [u64; 4] = [7, 30, 90, 365];
+
+/// 所有仓库中每个作者的提交数量
+#[derive(Debug)]
+pub struct Authors {
+    pub summaries: Vec<AuthorSummary>,
+    /// 后台统计的进度, 已经完成的仓库和仓库总数
+    pub progress: Option<(usize, usize)>,
+    /// git 配置中自己的邮箱
+    pub me: Option<String>,
+    window: usize,
+    state: ListState,
+}
+
+impl Authors {
+    pub fn new() -> Self {
+        Authors {
+            summaries: Vec::new(),
+            progress: None,
+            me: None,
+            window: 1,
+            state: ListState::default(),
+        }
+    }
+
+    /// 统计的天数
+    pub fn days(&self) -> u64 {
+        WINDOWS[self.window]
+    }
+
+    pub fn next_window(&mut self) {
+        self.window = (self.window + 1) % WINDOWS.len();
+    }
+
+    pub fn set_summaries(&mut self, summaries: Vec<AuthorSummary>) {
+        self.summaries = summaries;
+        self.progress = None;
+        self.state.select((!self.summaries.is_empty()).then_some(0));
+    }
+
+    fn mep(&self, summary: &AuthorSummary) -> bool {
+        self.me
+            .as_ref()
+            .is_some_and(|me| me.eq_ignore_ascii_case(&summary.email))
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.summaries.is_empty() {
+            return;
+        }
+        let len = self.summaries.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Authors {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitAuthors),
+            KeyCode::Char('w') | KeyCode::Tab => Some(AppAction::NextAuthorsWindow),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Authors {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let title = match self.progress {
+            Some((done, total)) => trf!(
+                "提交作者 (最近 {} 天), 正在统计 {}/{}",
+                "Authors (last {} days), counting {}/{}",
+                self.days(),
+                done,
+                total
+            ),
+            None => trf!(
+                "提交作者 (最近 {} 天)",
+                "Authors (last {} days)",
+                self.days()
+            ),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        f.render_widget(Clear, area);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        if self.summaries.is_empty() {
+            let text = if self.progress.is_some() {
+                tr("正在统计...", "Counting...")
+            } else {
+                tr("这段时间没有提交", "No commits in this window")
+            };
+            f.render_widget(Paragraph::new(text), inner);
+            return Ok(());
+        }
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(inner);
+
+        let items: Vec<ListItem> = self
+            .summaries
+            .iter()
+            .map(|summary| {
+                let line = format!(
+                    "{:>5}  {} <{}>",
+                    summary.commits, summary.name, summary.email
+                );
+                ListItem::new(if self.mep(summary) {
+                    Line::from(line.bold())
+                } else {
+                    Line::from(line)
+                })
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, columns[0], &mut self.state);
+
+        let repos: Vec<Line> = self
+            .state
+            .selected()
+            .and_then(|index| self.summaries.get(index))
+            .map(|summary| {
+                summary
+                    .repos
+                    .iter()
+                    .map(|(repo, count)| Line::from(format!("{:>5}  {}", count, repo)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        f.render_widget(
+            Paragraph::new(repos).block(
+                Block::default()
+                    .borders(Borders::LEFT)
+                    .title(tr("仓库", "Repos")),
+            ),
+            columns[1],
+        );
+
+        Ok(())
+    }
+}
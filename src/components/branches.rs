@@ -0,0 +1,222 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::PathBuf;
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::gitrepo::GitRepo;
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 分支列表中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchTarget {
+    /// 切换到本地分支
+    Local(String),
+    /// 从远程分支创建同名的本地分支并切换
+    Remote(String),
+    /// 为当前分支设置上游分支
+    SetUpstream(String),
+}
+
+impl BranchTarget {
+    /// 执行的 git 参数, 分支名字作为单独的参数传给 git, 不经过 shell
+    pub fn git_args(&self) -> Vec<String> {
+        match self {
+            BranchTarget::Local(name) => vec![String::from("switch"), name.clone()],
+            BranchTarget::Remote(name) => {
+                vec![
+                    String::from("switch"),
+                    String::from("--track"),
+                    name.clone(),
+                ]
+            }
+            BranchTarget::SetUpstream(name) => vec![
+                String::from("branch"),
+                format!("--set-upstream-to={}", name),
+            ],
+        }
+    }
+}
+
+/// 切换分支的弹窗, 使用刷新时缓存的分支列表, 打开时不需要读取仓库
+#[derive(Debug)]
+pub struct Branches {
+    pub path: Option<PathBuf>,
+    items: Vec<(BranchTarget, Line<'static>)>,
+    /// 缓存已经过时, 正在后台刷新
+    pub stalep: bool,
+    state: ListState,
+}
+
+impl Branches {
+    pub fn new() -> Self {
+        Branches {
+            path: None,
+            items: Vec::new(),
+            stalep: false,
+            state: ListState::default(),
+        }
+    }
+
+    pub fn start(&mut self, repo: &GitRepo, stalep: bool) {
+        self.path = Some(repo.path.clone());
+        self.stalep = stalep;
+        self.update(repo);
+        self.state.select((!self.items.is_empty()).then_some(0));
+    }
+
+    /// 根据仓库的缓存重新生成列表, 保留选中的位置
+    pub fn update(&mut self, repo: &GitRepo) {
+        let mut items = Vec::new();
+
+        let current = repo.branches.iter().find(|item| item.name == repo.branch);
+        if let Some(current) = current.filter(|item| item.upstream.is_none()) {
+            let upstream = repo
+                .remote_branches
+                .iter()
+                .find(|item| item.split_once('/').map(|(_, name)| name) == Some(&current.name))
+                .or(repo.default_branch.as_ref());
+            if let Some(upstream) = upstream {
+                items.push((
+                    BranchTarget::SetUpstream(upstream.clone()),
+                    Line::from(
+                        trf!(
+                            "设置 {} 的上游分支为 {}",
+                            "Set upstream of {} to {}",
+                            current.name,
+                            upstream
+                        )
+                        .yellow(),
+                    ),
+                ));
+            }
+        }
+
+        for branch in &repo.branches {
+            let mut text = branch.name.clone();
+            if branch.name == repo.branch {
+                text.push_str(tr(" (当前)", " (current)"));
+            }
+            if let Some(upstream) = &branch.upstream {
+                text.push_str(&format!(
+                    " -> {} ↑{} ↓{}",
+                    upstream, branch.ahead, branch.behind
+                ));
+            }
+            items.push((BranchTarget::Local(branch.name.clone()), Line::from(text)));
+        }
+
+        // 已经有同名本地分支的远程分支不再显示
+        for remote_branch in &repo.remote_branches {
+            let trackedp = repo
+                .branches
+                .iter()
+                .any(|item| item.upstream.as_ref() == Some(remote_branch));
+            let localp = remote_branch
+                .split_once('/')
+                .is_some_and(|(_, name)| repo.branches.iter().any(|item| item.name == name));
+            if trackedp || localp {
+                continue;
+            }
+
+            let mut text = remote_branch.clone();
+            if repo.default_branch.as_ref() == Some(remote_branch) {
+                text.push_str(tr(" (默认)", " (default)"));
+            }
+            items.push((
+                BranchTarget::Remote(remote_branch.clone()),
+                Line::from(text.dark_gray()),
+            ));
+        }
+
+        self.items = items;
+        if let Some(index) = self.state.selected() {
+            self.state.select(if self.items.is_empty() {
+                None
+            } else {
+                Some(index.min(self.items.len() - 1))
+            });
+        }
+    }
+
+    pub fn get_select_target(&self) -> Option<BranchTarget> {
+        self.state
+            .selected()
+            .and_then(|index| self.items.get(index))
+            .map(|(target, _)| target.clone())
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let len = self.items.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Branches {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitBranches),
+            KeyCode::Enter => Some(AppAction::ApplyBranch),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Branches {
+            return Ok(());
+        }
+
+        let height = (self.items.len() as u16 + 2).clamp(5, rect.height.max(5));
+        let area = centered_rect(70, height, rect);
+        let mut title = vec![Span::from(tr("分支", "Branches"))];
+        if self.stalep {
+            title.push(
+                Span::from(tr(
+                    " (缓存已过时, 正在刷新…)",
+                    " (cache is stale, refreshing…)",
+                ))
+                .yellow(),
+            );
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(title));
+        f.render_widget(Clear, area);
+
+        if self.items.is_empty() {
+            f.render_widget(
+                Paragraph::new(tr("没有分支", "No branches")).block(block),
+                area,
+            );
+            return Ok(());
+        }
+
+        let items: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|(_, line)| ListItem::new(line.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::PathBuf;
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::config::CustomCommand;
+use crate::gitrepo::GitRepo;
+use crate::i18n::trf;
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 选择在仓库中执行的自定义命令, 每一项下面显示替换变量之后的命令
+#[derive(Debug, Default)]
+pub struct CustomCommands {
+    pub commands: Vec<CustomCommand>,
+    /// 执行命令的仓库
+    pub path: Option<PathBuf>,
+    name: String,
+    branch: String,
+    state: ListState,
+}
+
+impl CustomCommands {
+    pub fn new() -> Self {
+        CustomCommands::default()
+    }
+
+    pub fn start(&mut self, repo: &GitRepo) {
+        self.path = Some(repo.path.clone());
+        self.name = repo.name.clone();
+        self.branch = repo.branch.clone();
+        self.state.select((!self.commands.is_empty()).then_some(0));
+    }
+
+    /// 选中的命令的名字和替换变量之后的命令
+    pub fn get_select(&self) -> Option<(String, String)> {
+        let path = self.path.as_ref()?;
+        let command = self.commands.get(self.state.selected()?)?;
+        Some((
+            command.name.clone(),
+            command.expand(&self.name, path, &self.branch),
+        ))
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.commands.is_empty() {
+            return;
+        }
+        let len = self.commands.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for CustomCommands {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitCustomCommands),
+            KeyCode::Enter => Some(AppAction::ApplyCustomCommand),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::CustomCommands {
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let height = (self.commands.len() as u16 * 2 + 2).min(rect.height.saturating_sub(2));
+        let area = centered_rect(80, height.max(4), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "在 {} 中执行",
+            "Run in {}",
+            path.display()
+        ));
+        f.render_widget(Clear, area);
+
+        let items: Vec<ListItem> = self
+            .commands
+            .iter()
+            .map(|command| {
+                ListItem::new(vec![
+                    Line::from(command.name.clone()),
+                    Line::from(
+                        format!("    {}", command.expand(&self.name, path, &self.branch))
+                            .dark_gray(),
+                    ),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
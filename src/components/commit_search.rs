@@ -0,0 +1,148 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::format::format_age;
+use crate::i18n::{tr, trf};
+use crate::search::CommitMatch;
+use crate::states::{AppAction, AppMode};
+use crate::utils::{now_timestamp, BDEResult};
+
+/// 在所有仓库中搜索提交的结果, 新的提交在前
+#[derive(Debug, Default)]
+pub struct CommitSearch {
+    pub query: String,
+    pub results: Vec<CommitMatch>,
+    pub searchingp: bool,
+    /// 当前搜索的编号, 之前的搜索的结果直接丢弃
+    pub id: u64,
+    state: ListState,
+}
+
+impl CommitSearch {
+    pub fn new() -> Self {
+        CommitSearch::default()
+    }
+
+    /// 开始新的搜索, 返回搜索的编号
+    pub fn start(&mut self, query: &str) -> u64 {
+        self.id += 1;
+        self.query = String::from(query);
+        self.results.clear();
+        self.searchingp = true;
+        self.state.select(None);
+        self.id
+    }
+
+    pub fn add(&mut self, id: u64, matches: Vec<CommitMatch>) {
+        if id != self.id {
+            return;
+        }
+        let selected = self.get_select().cloned();
+        self.results.extend(matches);
+        self.results
+            .sort_by_key(|result| std::cmp::Reverse(result.time));
+        // 新的结果插入之后保持选中原来的提交
+        let index = selected
+            .and_then(|item| self.results.iter().position(|result| *result == item))
+            .unwrap_or(0);
+        self.state.select(Some(index));
+    }
+
+    pub fn done(&mut self, id: u64) {
+        if id == self.id {
+            self.searchingp = false;
+        }
+    }
+
+    pub fn get_select(&self) -> Option<&CommitMatch> {
+        self.results.get(self.state.selected()?)
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.results.is_empty() {
+            return;
+        }
+        let len = self.results.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for CommitSearch {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitCommitSearch),
+            KeyCode::Enter => Some(AppAction::ShowSearchCommit),
+            KeyCode::Char('/') => Some(AppAction::StartCommitSearch),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::CommitSearch {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let title = if self.searchingp {
+            trf!(
+                "搜索提交: {} (正在搜索, {} 个结果)",
+                "Commit search: {} (searching, {} results)",
+                self.query,
+                self.results.len()
+            )
+        } else {
+            trf!(
+                "搜索提交: {} ({} 个结果)",
+                "Commit search: {} ({} results)",
+                self.query,
+                self.results.len()
+            )
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        f.render_widget(Clear, area);
+
+        if self.results.is_empty() {
+            let text = if self.searchingp {
+                tr("正在搜索...", "Searching...")
+            } else {
+                tr("没有找到提交", "No matching commits")
+            };
+            f.render_widget(Paragraph::new(text).block(block), area);
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|result| {
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<20} ", result.repo_name)),
+                    Span::raw(format!("{} ", &result.hash[..result.hash.len().min(8)])).yellow(),
+                    Span::raw(format!("{:<10} ", format_age(result.time, now))).dark_gray(),
+                    Span::raw(result.subject.clone()),
+                    Span::raw(format!(" ({})", result.author)).dark_gray(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::i18n::tr;
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 确认弹窗, 按 y/Enter 之后返回保存的 action, 按 n/Esc 取消
+#[derive(Debug)]
+pub struct Confirm {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    pub action: Option<AppAction>,
+    /// 取消之后回到的模式, 为空时回到仓库列表
+    pub back: Option<AppMode>,
+    scroll: u16,
+}
+
+impl Confirm {
+    pub fn new() -> Self {
+        Confirm {
+            title: String::new(),
+            lines: Vec::new(),
+            action: None,
+            back: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn start(&mut self, title: &str, lines: Vec<Line<'static>>, action: AppAction) {
+        self.title = String::from(title);
+        self.lines = lines;
+        self.action = Some(action);
+        self.back = None;
+        self.scroll = 0;
+    }
+}
+
+impl Component for Confirm {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let action = self.action.take();
+                action.or(Some(AppAction::ExitConfirm))
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                self.action = None;
+                Some(AppAction::ExitConfirm)
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                if (self.scroll as usize) + 1 < self.lines.len() {
+                    self.scroll += 1;
+                }
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Confirm {
+            return Ok(());
+        }
+
+        let height = (self.lines.len() as u16 + 3).clamp(5, rect.height.max(5));
+        let area = centered_rect(80, height, rect);
+
+        let mut lines = self.lines.clone();
+        lines.push(Line::from(vec![
+            "y".bold(),
+            tr(" 确认, ", " confirm, ").into(),
+            "n".bold(),
+            tr(" 取消", " cancel").into(),
+        ]));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str()),
+            )
+            .scroll((self.scroll, 0));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+
+        Ok(())
+    }
+}
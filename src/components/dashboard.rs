@@ -0,0 +1,246 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::PathBuf;
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::format::format_relative_time;
+use crate::gitrepo::{GitRepo, GitStatus};
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 保留的扫描耗时记录数量
+const SCAN_HISTORY_LEN: usize = 30;
+
+/// 最早的没有推送的提交
+#[derive(Debug, Clone)]
+struct UnpushedCommit {
+    repo: String,
+    time: u64,
+    summary: String,
+}
+
+/// 所有仓库的统计信息, 打开时计算
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    /// 每次扫描的耗时, 单位是毫秒, 最新的在最后
+    pub scan_durations: Vec<u64>,
+    counts: Vec<(GitStatus, u64)>,
+    total: usize,
+    stale_fetch: usize,
+    dirty: Vec<PathBuf>,
+    oldest_unpushed: Option<UnpushedCommit>,
+    now: u64,
+    state: ListState,
+}
+
+impl Dashboard {
+    pub fn new() -> Self {
+        Dashboard::default()
+    }
+
+    /// 记录一次扫描的耗时
+    pub fn record_scan(&mut self, duration: std::time::Duration) {
+        self.scan_durations.push(duration.as_millis() as u64);
+        if self.scan_durations.len() > SCAN_HISTORY_LEN {
+            self.scan_durations.remove(0);
+        }
+    }
+
+    /// stale_fetch_secs 为 0 时不统计 fetch 过时的仓库
+    pub fn start(&mut self, repos: &[GitRepo], now: u64, stale_fetch_secs: u64) {
+        self.now = now;
+        self.total = repos.len();
+        self.counts = GitStatus::ALL
+            .into_iter()
+            .map(|status| {
+                let count = repos.iter().filter(|repo| repo.status == status).count();
+                (status, count as u64)
+            })
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        self.stale_fetch = if stale_fetch_secs == 0 {
+            0
+        } else {
+            repos
+                .iter()
+                .filter(|repo| repo.fetch_stalep(now, stale_fetch_secs))
+                .count()
+        };
+        self.dirty = repos
+            .iter()
+            .filter(|repo| matches!(repo.status, GitStatus::NeedCommit | GitStatus::Untracked))
+            .map(|repo| repo.path.clone())
+            .collect();
+        self.oldest_unpushed = repos
+            .iter()
+            .filter(|repo| repo.status == GitStatus::NeedPush || repo.other_branch_need_push())
+            .filter_map(|repo| {
+                repo.oldest_unpushed_commit()
+                    .map(|(time, summary)| UnpushedCommit {
+                        repo: repo.name.clone(),
+                        time,
+                        summary,
+                    })
+            })
+            .min_by_key(|commit| commit.time);
+        self.state.select((!self.dirty.is_empty()).then_some(0));
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.dirty.is_empty() {
+            return;
+        }
+        let len = self.dirty.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+
+    fn summary_lines(&self) -> Vec<Line<'static>> {
+        let count = |status: GitStatus| {
+            self.counts
+                .iter()
+                .find(|(item, _)| *item == status)
+                .map(|(_, count)| *count)
+                .unwrap_or(0)
+        };
+        let mut lines = vec![
+            Line::from(trf!("仓库总数: {}", "Total repos: {}", self.total)),
+            Line::from(trf!("有修改: {}", "Dirty: {}", self.dirty.len())),
+            Line::from(trf!(
+                "需要推送: {}",
+                "Need push: {}",
+                count(GitStatus::NeedPush)
+            )),
+            Line::from(trf!(
+                "需要拉取: {}",
+                "Need pull: {}",
+                count(GitStatus::NeedPull)
+            )),
+            Line::from(trf!("fetch 过时: {}", "Stale fetch: {}", self.stale_fetch)),
+            Line::from(""),
+            Line::from(tr("最早没有推送的提交:", "Oldest unpushed commit:")),
+        ];
+        match &self.oldest_unpushed {
+            Some(commit) => {
+                lines.push(Line::from(format!(
+                    "  {} ({})",
+                    commit.repo,
+                    format_relative_time(commit.time, self.now)
+                )));
+                lines.push(Line::from(format!("  {}", commit.summary).dark_gray()));
+            }
+            None => lines.push(Line::from(format!("  {}", tr("无", "none")).dark_gray())),
+        }
+        lines
+    }
+}
+
+impl Component for Dashboard {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('I') => {
+                Some(AppAction::ExitDashboard)
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Dashboard {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(16), rect);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(tr("统计", "Dashboard"));
+        f.render_widget(Clear, area);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(inner);
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[0]);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(rows[1]);
+
+        let bars: Vec<Bar> = self
+            .counts
+            .iter()
+            .map(|(status, count)| {
+                Bar::default()
+                    .value(*count)
+                    .label(Line::from(status.glyph()))
+            })
+            .collect();
+        let chart = BarChart::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(tr("各状态的仓库数量", "Repos per status")),
+            )
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+        f.render_widget(chart, top[0]);
+
+        f.render_widget(
+            Paragraph::new(self.summary_lines())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(tr("概况", "Summary")),
+                )
+                .wrap(Wrap { trim: false }),
+            top[1],
+        );
+
+        let dirty_block = Block::default().borders(Borders::ALL).title(trf!(
+            "有修改的仓库: {}",
+            "Dirty repos: {}",
+            self.dirty.len()
+        ));
+        let items: Vec<ListItem> = self
+            .dirty
+            .iter()
+            .map(|path| ListItem::new(path.display().to_string()))
+            .collect();
+        let list = List::new(items)
+            .block(dirty_block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, bottom[0], &mut self.state);
+
+        let last = self.scan_durations.last().copied().unwrap_or(0);
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(trf!(
+                "扫描耗时: {} ms",
+                "Scan duration: {} ms",
+                last
+            )))
+            .data(&self.scan_durations)
+            .style(Style::default().fg(Color::Green));
+        f.render_widget(sparkline, bottom[1]);
+
+        Ok(())
+    }
+}
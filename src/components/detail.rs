@@ -0,0 +1,76 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::states::{AppAction, AppMode};
+use crate::theme::Theme;
+use crate::utils::BDEResult;
+
+/// 仓库详情弹窗
+#[derive(Debug)]
+pub struct Detail {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    pub theme: Theme,
+    scroll: u16,
+}
+
+impl Detail {
+    pub fn new() -> Self {
+        Detail {
+            title: String::new(),
+            lines: Vec::new(),
+            theme: Theme::default_theme(),
+            scroll: 0,
+        }
+    }
+
+    pub fn start(&mut self, title: &str, lines: Vec<Line<'static>>) {
+        self.title = String::from(title);
+        self.lines = lines;
+        self.scroll = 0;
+    }
+}
+
+impl Component for Detail {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => Some(AppAction::ExitDetail),
+            KeyCode::Char('j') | KeyCode::Down => {
+                if (self.scroll as usize) + 1 < self.lines.len() {
+                    self.scroll += 1;
+                }
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Detail {
+            return Ok(());
+        }
+
+        let height = (self.lines.len() as u16 + 2).clamp(5, rect.height.max(5));
+        let area = centered_rect(80, height, rect);
+
+        let paragraph = Paragraph::new(self.lines.clone())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border)
+                    .title(Span::styled(self.title.as_str(), self.theme.heading)),
+            )
+            .scroll((self.scroll, 0));
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use std::path::{Path, PathBuf};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::gitrepo::RepoError;
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 上一次刷新中检查失败的仓库和失败原因, 可以逐个重试
+#[derive(Debug, Default)]
+pub struct Errors {
+    pub errors: Vec<RepoError>,
+    /// 正在重试的仓库
+    pub retrying: Vec<PathBuf>,
+    state: ListState,
+}
+
+impl Errors {
+    pub fn new() -> Self {
+        Errors::default()
+    }
+
+    pub fn start(&mut self) {
+        self.state.select((!self.errors.is_empty()).then_some(0));
+    }
+
+    /// 完整刷新之后替换所有错误
+    pub fn replace_all(&mut self, errors: Vec<RepoError>) {
+        self.errors = errors;
+        self.retrying.clear();
+        self.clamp_select();
+    }
+
+    /// 重试仍然失败, 更新失败原因
+    pub fn upsert(&mut self, error: RepoError) {
+        self.retrying.retain(|path| *path != error.path);
+        match self.errors.iter_mut().find(|item| item.path == error.path) {
+            Some(item) => *item = error,
+            None => self.errors.push(error),
+        }
+        self.clamp_select();
+    }
+
+    /// 仓库检查成功, 移除它的错误
+    pub fn remove(&mut self, path: &Path) {
+        self.retrying.retain(|item| item != path);
+        self.errors.retain(|item| item.path != path);
+        self.clamp_select();
+    }
+
+    pub fn get_select(&self) -> Option<&RepoError> {
+        self.errors.get(self.state.selected()?)
+    }
+
+    fn clamp_select(&mut self) {
+        self.state.select(if self.errors.is_empty() {
+            None
+        } else {
+            Some(
+                self.state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.errors.len() - 1),
+            )
+        });
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.errors.is_empty() {
+            return;
+        }
+        let len = self.errors.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Errors {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitErrors),
+            KeyCode::Enter | KeyCode::Char('r') => Some(AppAction::RetryRepoError),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Errors {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "检查失败的仓库: {}",
+            "Failed repos: {}",
+            self.errors.len()
+        ));
+        f.render_widget(Clear, area);
+
+        if self.errors.is_empty() {
+            f.render_widget(
+                Paragraph::new(tr("没有检查失败的仓库", "No failed repos")).block(block),
+                area,
+            );
+            return Ok(());
+        }
+
+        let items: Vec<ListItem> = self
+            .errors
+            .iter()
+            .map(|error| {
+                let path = if error.path.as_os_str().is_empty() {
+                    String::from(tr("(扫描)", "(scan)"))
+                } else {
+                    error.path.display().to_string()
+                };
+                let retrying = if self.retrying.contains(&error.path) {
+                    tr(" (正在重试)", " (retrying)")
+                } else {
+                    ""
+                };
+                ListItem::new(vec![
+                    Line::from(format!("{}{}", path, retrying)),
+                    Line::from(format!("    {}", error.error).dark_gray()),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::i18n::{tr, trf};
+use crate::inbox::AttentionItem;
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 所有仓库中需要处理的问题, 每项按 Enter 直接处理
+#[derive(Debug, Default)]
+pub struct Inbox {
+    pub items: Vec<AttentionItem>,
+    state: ListState,
+}
+
+impl Inbox {
+    pub fn new() -> Self {
+        Inbox::default()
+    }
+
+    /// 更新列表, 尽量保持选中原来的仓库
+    pub fn update(&mut self, items: Vec<AttentionItem>) {
+        let selected = self
+            .get_select()
+            .map(|item| (item.path.clone(), item.message.clone()));
+        self.items = items;
+
+        let index = selected
+            .and_then(|(path, message)| {
+                self.items
+                    .iter()
+                    .position(|item| item.path == path && item.message == message)
+                    .or_else(|| self.items.iter().position(|item| item.path == path))
+            })
+            .or_else(|| {
+                self.state
+                    .selected()
+                    .map(|index| index.min(self.items.len().saturating_sub(1)))
+            })
+            .unwrap_or(0);
+        self.state.select((!self.items.is_empty()).then_some(index));
+    }
+
+    pub fn get_select(&self) -> Option<&AttentionItem> {
+        self.items.get(self.state.selected()?)
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let len = self.items.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Inbox {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitInbox),
+            KeyCode::Enter => Some(AppAction::ResolveAttention),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Inbox {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "待处理: {}",
+            "Needs attention: {}",
+            self.items.len()
+        ));
+        f.render_widget(Clear, area);
+
+        if self.items.is_empty() {
+            f.render_widget(
+                Paragraph::new(tr("所有仓库都不需要处理", "Nothing needs attention")).block(block),
+                area,
+            );
+            return Ok(());
+        }
+
+        let items: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|item| {
+                ListItem::new(Line::from(vec![
+                    Span::from(format!("{}: ", item.name)).bold(),
+                    Span::from(item.message.clone()),
+                    Span::from(format!("  [Enter: {}]", item.action_name)).dark_gray(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
@@ -1,32 +1,114 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{prelude::*, widgets::*};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use super::popup::{CompletionItem, CompletionPopup};
 use super::Component;
+use crate::gitrepo::{GitRepo, GitStatus};
 use crate::states::{AppAction, AppMode};
 use crate::utils::BDEResult;
 
+/// 最多保留这么多条过滤历史
+pub const FILTER_HISTORY_LEN: usize = 50;
+
 #[derive(Debug)]
 pub struct Input {
     pub input: String,
     /// Position of cursor in the editor area.
     cursor_position: usize,
+    /// 之前使用过的过滤条件, 最早的在前面
+    pub history: Vec<String>,
+    /// 正在查看的历史, 为 None 时是正在输入的内容
+    history_index: Option<usize>,
+    /// 开始查看历史之前输入的内容, 回到最后时恢复
+    history_draft: String,
 
     component_popup: CompletionPopup,
 }
 
 impl Input {
-    pub fn new() -> Self {
+    pub fn new(history: Vec<String>) -> Self {
         Input {
             input: String::from(""),
             cursor_position: 0,
+            history,
+            history_index: None,
+            history_draft: String::new(),
             component_popup: CompletionPopup::default(),
         }
     }
 
+    /// 把当前的过滤条件加入历史, 重复的移动到最后, 历史有变化时返回 true
+    pub fn push_history(&mut self) -> bool {
+        self.history_index = None;
+        let query = self.input.trim();
+        if query.is_empty() || self.history.last().is_some_and(|last| last == query) {
+            return false;
+        }
+
+        let query = String::from(query);
+        self.history.retain(|item| *item != query);
+        self.history.push(query);
+        if self.history.len() > FILTER_HISTORY_LEN {
+            self.history
+                .drain(..self.history.len() - FILTER_HISTORY_LEN);
+        }
+        true
+    }
+
+    /// 查看上一条 (offset 为 -1) 或者下一条历史
+    fn browse_history(&mut self, offset: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let index = match (self.history_index, offset < 0) {
+            (None, true) => {
+                self.history_draft = self.input.clone();
+                Some(self.history.len() - 1)
+            }
+            (None, false) => return,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) => (index + 1 < self.history.len()).then_some(index + 1),
+        };
+        self.history_index = index;
+        let input = match index {
+            Some(index) => self.history[index].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.set_input(&input);
+        // 选择历史时不弹出补全
+        self.component_popup.completions.clear();
+        self.component_popup.complection_finish = true;
+    }
+
+    pub fn set_input(&mut self, input: &str) {
+        self.input = String::from(input);
+        self.cursor_position = self.grapheme_count();
+    }
+
+    /// 输入的字符数量, 按字形簇计算, 组合字符和 emoji 序列算作一个
+    fn grapheme_count(&self) -> usize {
+        self.input.graphemes(true).count()
+    }
+
+    /// 第 index 个字形簇在字符串中的字节位置
+    fn byte_index(&self, index: usize) -> usize {
+        self.input
+            .grapheme_indices(true)
+            .nth(index)
+            .map(|(byte, _)| byte)
+            .unwrap_or(self.input.len())
+    }
+
+    /// 光标前面的文字占的列数, 中文等宽字符占两列
+    fn cursor_width(&self) -> u16 {
+        self.input[..self.byte_index(self.cursor_position)].width() as u16
+    }
+
     fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+        self.move_cursor_left_n(1);
     }
 
     fn move_cursor_left_n(&mut self, n: usize) {
@@ -39,68 +121,36 @@ impl Input {
         self.cursor_position = self.clamp_cursor(cursor_moved_right);
     }
 
-    fn move_cursor_right_n(&mut self, n: usize) {
-        let cursor_moved_right = self.cursor_position.saturating_add(n);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
-    }
-
+    /// 在光标处插入, 插入的字符可能和前面的字符组成一个字形簇, 所以重新计算光标位置
     fn enter_string(&mut self, new_str: &str) {
-        self.input.push_str(new_str);
-        self.move_cursor_right_n(new_str.len());
+        let start = self.byte_index(self.cursor_position);
+        self.input.insert_str(start, new_str);
+        self.cursor_position = self.input[..start + new_str.len()].graphemes(true).count();
     }
 
     fn enter_char(&mut self, new_char: char) {
-        self.input.insert(self.cursor_position, new_char);
-
-        self.move_cursor_right();
+        self.enter_string(new_char.encode_utf8(&mut [0; 4]));
     }
 
+    /// 删除光标前面的 n 个字形簇
     fn delete_n_char(&mut self, n: usize) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost && self.cursor_position + 1 >= n {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - n;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left_n(n);
+        let n = n.min(self.cursor_position);
+        if n == 0 {
+            return;
         }
+
+        let start = self.byte_index(self.cursor_position - n);
+        let end = self.byte_index(self.cursor_position);
+        self.input.replace_range(start..end, "");
+        self.move_cursor_left_n(n);
     }
 
     fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
-        }
+        self.delete_n_char(1);
     }
 
     fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.len())
+        new_cursor_pos.clamp(0, self.grapheme_count())
     }
 
     // fn reset_cursor(&mut self) {
@@ -112,44 +162,41 @@ impl Input {
     //     self.reset_cursor();
     // }
 
-    fn calc_item_score(input: &str, item: &str) -> u16 {
+    /// 模糊匹配的分数, input 中的字符需要按顺序出现在 item 中, 不匹配时为 0
+    pub fn calc_item_score(input: &str, item: &str) -> u16 {
         if input.is_empty() {
             return 1;
         }
 
         let mut score = 0;
         let mut new_pos: usize = 0;
-        let item_find_str = item.to_lowercase();
+        // 按字符而不是字节查找, 中文命令名也可以匹配
+        let item_chars: Vec<char> = item.chars().collect();
+        let item_len = item_chars.len() as u16;
 
         for c in input.chars() {
-            if let Some(pos) = item_find_str[new_pos..].find(c.to_ascii_lowercase()) {
-                new_pos = pos + 1;
-                let item_char = item.chars().nth(pos).unwrap();
-                score += (item.len() as u16 - pos as u16 + 1) / item.len() as u16;
-                if c == item_char {
-                    score += 2;
-                } else {
-                    score += 1;
-                }
-            } else {
+            let Some(pos) = item_chars[new_pos..]
+                .iter()
+                .position(|item_char| item_char.to_lowercase().eq(c.to_lowercase()))
+                .map(|pos| pos + new_pos)
+            else {
                 return 0;
+            };
+            new_pos = pos + 1;
+            score += (item_len - pos as u16 + 1) / item_len;
+            if c == item_chars[pos] {
+                score += 2;
+            } else {
+                score += 1;
             }
         }
 
         score
     }
 
-    pub fn update_complection(&mut self) -> BDEResult<()> {
-        let complection_all = vec![
-            String::from("path"),
-            String::from("match_case"),
-            String::from("NeedPull"),
-            String::from("Clean"),
-            String::from("NeedPush"),
-            String::from("NeedCommit"),
-            String::from("Timeout"),
-        ];
-
+    /// 根据正在输入的词补全:
+    /// +tag: 之后补全已有的标签, + 之后补全关键字和状态, 其它时候补全仓库名字
+    pub fn update_complection(&mut self, repos: &[GitRepo]) -> BDEResult<()> {
         if self.input.is_empty() {
             self.component_popup.completions.clear();
             return Ok(());
@@ -164,38 +211,65 @@ impl Input {
             return Ok(());
         }
 
-        let input_split: Vec<&str> = self.input.split(' ').collect();
-
-        if let Some(last_input) = input_split.last() {
-            if let Some(stripped) = last_input.strip_prefix('+') {
-                let filter_complection_input = stripped;
-
-                let mut filter_complections: Vec<CompletionItem> = complection_all
-                    .into_iter()
-                    .filter_map(|item| {
-                        let score = Input::calc_item_score(filter_complection_input, &item);
-                        if score == 0 {
-                            None
-                        } else {
-                            Some(CompletionItem {
-                                score,
-                                text: item.clone(),
-                            })
-                        }
-                    })
+        let last_input = self.input.rsplit(' ').next().unwrap_or_default();
+        let (word, candidates) = match last_input.strip_prefix('+') {
+            Some(stripped) if stripped.starts_with("tag:") => {
+                let mut tags: Vec<String> = repos
+                    .iter()
+                    .flat_map(|repo| repo.tags.iter())
+                    .map(|tag| format!("tag:{}", tag))
                     .collect();
+                tags.sort();
+                tags.dedup();
+                (stripped, tags)
+            }
+            Some(stripped) => {
+                let mut keywords: Vec<String> =
+                    ["path", "match_case", "SubmoduleDirty", "NoRemote", "stale"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect();
+                keywords.extend(GitStatus::ALL.iter().map(|status| format!("{:?}", status)));
+                keywords.extend(
+                    ["tag:", "owner:", "host:", "remote:"]
+                        .into_iter()
+                        .map(String::from),
+                );
+                (stripped, keywords)
+            }
+            None => {
+                let mut names: Vec<String> = repos.iter().map(|repo| repo.name.clone()).collect();
+                names.sort();
+                names.dedup();
+                (last_input, names)
+            }
+        };
+
+        let mut filter_complections: Vec<CompletionItem> = candidates
+            .into_iter()
+            // 已经输入完整的不再提示
+            .filter(|item| item != word)
+            .filter_map(|item| {
+                let score = Input::calc_item_score(word, &item);
+                if score == 0 {
+                    None
+                } else {
+                    Some(CompletionItem { score, text: item })
+                }
+            })
+            .collect();
 
-                filter_complections.sort_by_key(|item| item.score);
-                filter_complections.reverse();
+        filter_complections.sort_by_key(|item| item.score);
+        filter_complections.reverse();
 
-                if !filter_complections.is_empty() {
-                    if self.component_popup.get_select().is_none() {
-                        self.component_popup.state.select(Some(0));
-                    }
-                    self.component_popup.input_len = filter_complection_input.len();
-                    self.component_popup.completions = filter_complections.clone();
-                }
+        if filter_complections.is_empty() {
+            self.component_popup.completions.clear();
+        } else {
+            if self.component_popup.get_select().is_none() {
+                self.component_popup.state.select(Some(0));
             }
+            self.component_popup.input_len = word.graphemes(true).count();
+            self.component_popup.completions = filter_complections;
         }
 
         Ok(())
@@ -206,12 +280,22 @@ impl Component for Input {
     fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
         Ok(match key.code {
             KeyCode::Esc => Some(AppAction::ExitFilter),
+            KeyCode::Up => {
+                self.browse_history(-1);
+                None
+            }
+            KeyCode::Down => {
+                self.browse_history(1);
+                None
+            }
             KeyCode::Char(to_insert) => {
+                self.history_index = None;
                 self.component_popup.complection_finish = false;
                 self.enter_char(to_insert);
                 None
             }
             KeyCode::Backspace => {
+                self.history_index = None;
                 self.component_popup.complection_finish = false;
                 self.delete_char();
                 None
@@ -266,24 +350,21 @@ impl Component for Input {
 
         let input = Paragraph::new(Line::from(text))
             .style(match mode {
-                AppMode::Normal => Style::default(),
                 // AppMode::Editing => Style::default().bg(Color::Yellow),
                 AppMode::Editing => Style::default(),
+                _ => Style::default(),
             })
             .block(Block::default().borders(Borders::ALL).title("Filter"));
         f.render_widget(input, rect);
 
-        match mode {
-            AppMode::Normal => {}
-            AppMode::Editing => {
-                f.set_cursor(
-                    // Draw the cursor at the current position in the input field.
-                    // This position is can be controlled via the left and right arrow key
-                    rect.x + self.cursor_position as u16 + 1,
-                    // Move one line down, from the border to the input line
-                    rect.y + 1,
-                )
-            }
+        if mode == AppMode::Editing {
+            f.set_cursor(
+                // Draw the cursor at the current position in the input field.
+                // This position is can be controlled via the left and right arrow key
+                rect.x + self.cursor_width() + 1,
+                // Move one line down, from the border to the input line
+                rect.y + 1,
+            )
         }
 
         // 需要在整个区域内最后绘制, 否则会被覆盖
@@ -291,19 +372,11 @@ impl Component for Input {
             && mode == AppMode::Editing
             && !self.component_popup.complection_finish
         {
-            let mut need_height =
-                (self.component_popup.completions.len() as f32 * 2.0).round() as u16;
-
-            if need_height < 4 {
-                need_height = 4;
-            }
-
-            if need_height > 10 {
-                need_height = 10;
-            }
+            let need_height =
+                ((self.component_popup.completions.len() as f32 * 2.0).round() as u16).clamp(4, 10);
 
             let area = Rect::new(
-                rect.x + self.cursor_position as u16 + 1,
+                rect.x + self.cursor_width() + 1,
                 rect.y + 2,
                 20,
                 need_height,
@@ -313,3 +386,60 @@ impl Component for Input {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Input, FILTER_HISTORY_LEN};
+
+    #[test]
+    fn test_edit_unicode() {
+        let mut input = Input::new(Vec::new());
+        for c in "仓库ab".chars() {
+            input.enter_char(c);
+        }
+        assert_eq!(input.cursor_width(), 6);
+
+        input.move_cursor_left();
+        input.move_cursor_left();
+        input.delete_char();
+        assert_eq!(input.input, "仓ab");
+        assert_eq!(input.cursor_width(), 2);
+
+        input.enter_char('管');
+        assert_eq!(input.input, "仓管ab");
+
+        // 组合字符和前面的字母是同一个字形簇
+        input.set_input("cafe");
+        input.enter_char('\u{301}');
+        assert_eq!(input.cursor_position, 4);
+        input.delete_n_char(2);
+        assert_eq!(input.input, "ca");
+    }
+
+    #[test]
+    fn test_filter_history() {
+        let mut input = Input::new(Vec::new());
+        for query in ["+NeedPush", " +tag:work ", "", "+NeedPush"] {
+            input.set_input(query);
+            input.push_history();
+        }
+        assert_eq!(input.history, vec!["+tag:work", "+NeedPush"]);
+
+        input.set_input("draft");
+        input.browse_history(-1);
+        assert_eq!(input.input, "+NeedPush");
+        input.browse_history(-1);
+        input.browse_history(-1);
+        assert_eq!(input.input, "+tag:work");
+        input.browse_history(1);
+        input.browse_history(1);
+        assert_eq!(input.input, "draft");
+
+        for index in 0..FILTER_HISTORY_LEN + 5 {
+            input.set_input(&index.to_string());
+            input.push_history();
+        }
+        assert_eq!(input.history.len(), FILTER_HISTORY_LEN);
+        assert_eq!(input.history.last().map(String::as_str), Some("54"));
+    }
+}
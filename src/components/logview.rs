@@ -0,0 +1,89 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::i18n::{tr, trf};
+use crate::logging::{log_dir, recent_lines};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 最近的日志, 用于查看仓库检查失败的原因
+#[derive(Debug, Default)]
+pub struct LogView {
+    /// 从最后一行往上滚动的行数, 为 0 时跟随新日志
+    scroll_back: usize,
+}
+
+impl LogView {
+    pub fn new() -> Self {
+        LogView::default()
+    }
+
+    pub fn start(&mut self) {
+        self.scroll_back = 0;
+    }
+}
+
+impl Component for LogView {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l') => Some(AppAction::ExitLog),
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll_back += 1;
+                None
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.scroll_back = self.scroll_back.saturating_sub(1);
+                None
+            }
+            KeyCode::Char('G') | KeyCode::End => {
+                self.scroll_back = 0;
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Log {
+            return Ok(());
+        }
+
+        let area = centered_rect(95, rect.height.saturating_sub(2).max(10), rect);
+        let title = match log_dir() {
+            Ok(dir) => trf!("日志: {}", "Log: {}", dir.join("log").display()),
+            Err(_) => String::from(tr("日志", "Log")),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+
+        let lines = recent_lines();
+        let height = area.height.saturating_sub(2) as usize;
+        self.scroll_back = self.scroll_back.min(lines.len().saturating_sub(height));
+        let end = lines.len() - self.scroll_back;
+        let start = end.saturating_sub(height);
+
+        let text: Vec<Line> = if lines.is_empty() {
+            vec![Line::from(tr("还没有日志", "No log yet"))]
+        } else {
+            lines[start..end]
+                .iter()
+                .map(|line| {
+                    let style = if line.contains("ERROR") {
+                        Style::default().fg(Color::Red)
+                    } else if line.contains("WARN") {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default()
+                    };
+                    Line::styled(line.clone(), style)
+                })
+                .collect()
+        };
+
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(text).block(block), area);
+
+        Ok(())
+    }
+}
@@ -1,10 +1,29 @@
 use crossterm::event::KeyEvent;
 use ratatui::prelude::{Frame, Rect};
 
+pub mod authors;
+pub mod branches;
+pub mod commands;
+pub mod commit_search;
+pub mod confirm;
+pub mod dashboard;
+pub mod detail;
+pub mod errors;
+pub mod file_search;
+pub mod inbox;
 pub mod input;
+pub mod logview;
+pub mod output;
+pub mod palette;
 pub mod popup;
+pub mod prompt;
 pub mod reposhow;
+pub mod stale;
 pub mod statusbar;
+pub mod tabs;
+pub mod toast;
+pub mod tombstones;
+pub mod wizard;
 
 use crate::states::{AppAction, AppMode};
 use crate::utils::BDEResult;
@@ -14,7 +33,7 @@ pub trait Component {
     fn handle_events(&mut self, event: KeyEvent) -> BDEResult<Option<AppAction>> {
         Ok(None)
     }
-    #[allow(unused_variables)]
+    #[allow(unused_variables, dead_code)]
     fn update(&mut self, mode: AppMode, action: AppAction) -> BDEResult<Option<AppAction>> {
         Ok(None)
     }
@@ -0,0 +1,279 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::{BDEResult, CommandOutput};
+
+/// 底部的命令输出面板, 保留 ANSI 颜色
+#[derive(Debug)]
+pub struct OutputPane {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    pub showp: bool,
+    pub runningp: bool,
+    scroll: usize,
+    /// 自动滚动到最新的输出
+    followp: bool,
+    height: usize,
+}
+
+impl OutputPane {
+    pub fn new() -> Self {
+        OutputPane {
+            title: String::new(),
+            lines: Vec::new(),
+            showp: false,
+            runningp: false,
+            scroll: 0,
+            followp: true,
+            height: 0,
+        }
+    }
+
+    pub fn start(&mut self, title: &str) {
+        self.title = String::from(title);
+        self.lines.clear();
+        self.showp = true;
+        self.runningp = true;
+        self.scroll = 0;
+        self.followp = true;
+    }
+
+    pub fn push(&mut self, output: CommandOutput) {
+        match output {
+            CommandOutput::Stdout(line) => self.lines.push(ansi_to_line(&line)),
+            CommandOutput::Stderr(line) => self.lines.push(ansi_to_line(&line)),
+            CommandOutput::Exit(code) => {
+                self.runningp = false;
+                let exit_line = match code {
+                    Some(0) => Line::from(tr("[命令执行成功]", "[command succeeded]").green()),
+                    Some(code) => Line::from(
+                        trf!(
+                            "[命令执行失败, 退出码: {}]",
+                            "[command failed, exit code: {}]",
+                            code
+                        )
+                        .red(),
+                    ),
+                    None => Line::from(tr("[命令被终止]", "[command killed]").red()),
+                };
+                self.lines.push(exit_line);
+            }
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+}
+
+fn ansi_color(code: u8) -> Color {
+    match code {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// 解析扩展颜色 `38;5;n` 和 `38;2;r;g;b`
+fn ansi_extend_color(params: &mut std::slice::Iter<'_, u16>) -> Option<Color> {
+    match params.next()? {
+        5 => Some(Color::Indexed(*params.next()? as u8)),
+        2 => {
+            let r = *params.next()? as u8;
+            let g = *params.next()? as u8;
+            let b = *params.next()? as u8;
+            Some(Color::Rgb(r, g, b))
+        }
+        _ => None,
+    }
+}
+
+fn apply_sgr(style: Style, params: &[u16]) -> Style {
+    let mut style = style;
+    let mut params = params.iter();
+
+    while let Some(param) = params.next() {
+        style = match param {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            30..=37 => style.fg(ansi_color((param - 30) as u8)),
+            38 => match ansi_extend_color(&mut params) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(ansi_color((param - 40) as u8)),
+            48 => match ansi_extend_color(&mut params) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(ansi_color((param - 90 + 8) as u8)),
+            100..=107 => style.bg(ansi_color((param - 100 + 8) as u8)),
+            _ => style,
+        };
+    }
+
+    style
+}
+
+/// 将带有 ANSI 转义序列的文本转换为 Line, 只保留 SGR 样式, 其它控制序列会被丢弃
+pub fn ansi_to_line(text: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut now_text = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.peek() != Some(&'[') {
+                continue;
+            }
+            chars.next();
+
+            let mut sequence = String::new();
+            let mut end = None;
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    end = Some(c);
+                    break;
+                }
+                sequence.push(c);
+            }
+
+            if end == Some('m') {
+                if !now_text.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut now_text), style));
+                }
+
+                let params: Vec<u16> = if sequence.is_empty() {
+                    vec![0]
+                } else {
+                    sequence
+                        .split(';')
+                        .map(|item| item.parse().unwrap_or(0))
+                        .collect()
+                };
+                style = apply_sgr(style, &params);
+            }
+        } else if c == '\r' {
+            // git 的进度信息会用 \r 覆盖同一行, 只保留最后的内容
+            now_text.clear();
+            spans.clear();
+        } else {
+            now_text.push(c);
+        }
+    }
+
+    if !now_text.is_empty() {
+        spans.push(Span::styled(now_text, style));
+    }
+
+    Line::from(spans)
+}
+
+impl Component for OutputPane {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitOutput),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.scroll = (self.scroll + 1).min(self.max_scroll());
+                self.followp = self.scroll == self.max_scroll();
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.scroll = self.scroll.saturating_sub(1);
+                self.followp = false;
+                None
+            }
+            KeyCode::Char('g') => {
+                self.scroll = 0;
+                self.followp = false;
+                None
+            }
+            KeyCode::Char('G') => {
+                self.followp = true;
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if !self.showp {
+            return Ok(());
+        }
+
+        self.height = rect.height.saturating_sub(2) as usize;
+        if self.followp {
+            self.scroll = self.max_scroll();
+        }
+
+        let title = if self.runningp {
+            trf!("{} (运行中)", "{} (running)", self.title)
+        } else {
+            self.title.clone()
+        };
+
+        let border_style = if mode == AppMode::Output {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+
+        let paragraph = Paragraph::new(self.lines.clone())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title(title),
+            )
+            .scroll((self.scroll as u16, 0));
+
+        f.render_widget(Clear, rect);
+        f.render_widget(paragraph, rect);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ansi_to_line;
+    use ratatui::prelude::*;
+
+    #[test]
+    fn test_ansi_to_line() {
+        let line = ansi_to_line("a \x1b[31mred\x1b[m b\x1b[K");
+        assert_eq!(line.spans.len(), 3);
+        assert_eq!(line.spans[0].content, "a ");
+        assert_eq!(line.spans[1].content, "red");
+        assert_eq!(line.spans[1].style.fg, Some(Color::Red));
+        assert_eq!(line.spans[2].content, " b");
+        assert_eq!(line.spans[2].style, Style::default());
+    }
+}
@@ -0,0 +1,394 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use unicode_width::UnicodeWidthStr;
+
+use super::input::Input;
+use super::popup::{centered_rect, CompletionItem, CompletionPopup};
+use super::Component;
+use crate::gitrepo::GitStatus;
+use crate::i18n::tr;
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 命令面板中的一个命令
+#[derive(Debug, Clone)]
+pub struct PaletteCommand {
+    pub name: &'static str,
+    /// 英文名字, 使用中文界面时也可以用英文搜索
+    pub alias: &'static str,
+    /// 普通模式下的快捷键, 只能在命令面板中使用时为空
+    pub key: &'static str,
+    pub action: AppAction,
+}
+
+impl PaletteCommand {
+    fn new(key: &'static str, zh: &'static str, en: &'static str, action: AppAction) -> Self {
+        PaletteCommand {
+            name: tr(zh, en),
+            alias: en,
+            key,
+            action,
+        }
+    }
+
+    /// 列表中显示和搜索的文字
+    fn text(&self) -> String {
+        if self.key.is_empty() {
+            String::from(self.name)
+        } else {
+            format!("{} [{}]", self.name, self.key)
+        }
+    }
+}
+
+/// 所有可以从命令面板执行的命令
+fn palette_commands() -> Vec<PaletteCommand> {
+    vec![
+        PaletteCommand::new(
+            "g",
+            "刷新所有仓库",
+            "Refresh all repos",
+            AppAction::StartRefresh,
+        ),
+        PaletteCommand::new(
+            "r",
+            "刷新选中的仓库",
+            "Refresh selected repo",
+            AppAction::RefreshSelected,
+        ),
+        PaletteCommand::new("f", "过滤仓库", "Filter repos", AppAction::StartFilter),
+        PaletteCommand::new("p", "拉取", "Pull", AppAction::SelectPull),
+        PaletteCommand::new("U", "推送", "Push", AppAction::SelectPush),
+        PaletteCommand::new(
+            "S",
+            "同步 (拉取之后推送)",
+            "Sync (pull then push)",
+            AppAction::SelectSync,
+        ),
+        PaletteCommand::new("K", "备份仓库", "Back up repos", AppAction::StartBackup),
+        PaletteCommand::new("Enter", "打开仓库", "Open repo", AppAction::SelectEnter),
+        PaletteCommand::new(
+            "e",
+            "在编辑器中打开",
+            "Open in editor",
+            AppAction::SelectOpenEditor,
+        ),
+        PaletteCommand::new(
+            "Z",
+            "在 tmux/zellij 新窗口中打开",
+            "Open in a new tmux/zellij window",
+            AppAction::SelectOpenMultiplexer,
+        ),
+        PaletteCommand::new(
+            "W",
+            "在浏览器中打开",
+            "Open in browser",
+            AppAction::SelectOpenBrowser,
+        ),
+        PaletteCommand::new(
+            "c",
+            "退出并进入仓库目录",
+            "Exit and cd into repo",
+            AppAction::SelectCdExit,
+        ),
+        PaletteCommand::new("y", "复制路径", "Copy path", AppAction::SelectCopyPath),
+        PaletteCommand::new(
+            "u",
+            "复制远程仓库地址",
+            "Copy remote URL",
+            AppAction::SelectCopyRemoteUrl,
+        ),
+        PaletteCommand::new(
+            "C",
+            "复制克隆命令",
+            "Copy clone command",
+            AppAction::SelectCopyCloneCommand,
+        ),
+        PaletteCommand::new("t", "编辑标签", "Edit tags", AppAction::StartTagEdit),
+        PaletteCommand::new(
+            "n",
+            "初始化新仓库",
+            "Init new repo",
+            AppAction::StartInitRepo,
+        ),
+        PaletteCommand::new("z", "添加远程仓库", "Add remote", AppAction::StartAddRemote),
+        PaletteCommand::new(
+            "+",
+            "创建附注标签",
+            "Create annotated tag",
+            AppAction::StartCreateTag,
+        ),
+        PaletteCommand::new(
+            "=",
+            "用 mergetool 解决冲突",
+            "Resolve conflicts in mergetool",
+            AppAction::OpenMergeTool,
+        ),
+        PaletteCommand::new("N", "移动仓库", "Move repo", AppAction::StartRenameRepo),
+        PaletteCommand::new("D", "删除仓库", "Delete repo", AppAction::StartDeleteRepo),
+        PaletteCommand::new(
+            "Y",
+            "清理丢失的仓库",
+            "Prune missing repos",
+            AppAction::StartPruneMissing,
+        ),
+        PaletteCommand::new(
+            "x",
+            "隐藏或者取消隐藏仓库",
+            "Ignore or unignore repo",
+            AppAction::ToggleIgnoreRepo,
+        ),
+        PaletteCommand::new(
+            "H",
+            "显示隐藏的仓库",
+            "Show ignored repos",
+            AppAction::ToggleShowHidden,
+        ),
+        PaletteCommand::new(
+            "i",
+            "忽略未跟踪的大目录",
+            "Ignore large untracked dirs",
+            AppAction::StartIgnoreUntracked,
+        ),
+        PaletteCommand::new(
+            "b",
+            "添加 .gitignore 模板",
+            "Add .gitignore template",
+            AppAction::StartGitignoreTemplate,
+        ),
+        PaletteCommand::new(
+            "w",
+            "分叉解决向导",
+            "Divergence wizard",
+            AppAction::StartResolveDiverge,
+        ),
+        PaletteCommand::new(
+            "h",
+            "执行自定义命令",
+            "Run custom command",
+            AppAction::OpenCustomCommands,
+        ),
+        PaletteCommand::new(
+            "d",
+            "在显示的所有仓库中执行命令",
+            "Run command in all shown repos",
+            AppAction::StartForeach,
+        ),
+        PaletteCommand::new(
+            "",
+            "维护所有仓库 (gc, remote prune)",
+            "Maintain all repos (gc, remote prune)",
+            AppAction::StartMaintenance,
+        ),
+        PaletteCommand::new("s", "切换排序方式", "Change sort", AppAction::ToggleSort),
+        PaletteCommand::new(
+            "P",
+            "切换分页显示",
+            "Toggle paged view",
+            AppAction::TogglePaged,
+        ),
+        PaletteCommand::new(
+            "a",
+            "切换自动刷新",
+            "Toggle auto refresh",
+            AppAction::ToggleAutoRefresh,
+        ),
+        PaletteCommand::new(
+            "L",
+            "切换 git 后端",
+            "Toggle git backend",
+            AppAction::ToggleGitBackend,
+        ),
+        PaletteCommand::new(
+            "F",
+            "切换离线模式",
+            "Toggle offline mode",
+            AppAction::ToggleOffline,
+        ),
+        PaletteCommand::new(
+            "X",
+            "切换仓库的网络检查",
+            "Toggle network checks for repo",
+            AppAction::ToggleRepoFetch,
+        ),
+        PaletteCommand::new("", "切换主题", "Next theme", AppAction::NextTheme),
+        PaletteCommand::new("m", "标记仓库", "Mark repo", AppAction::ToggleMark),
+        PaletteCommand::new("V", "标记所有仓库", "Mark all repos", AppAction::MarkAll),
+        PaletteCommand::new("M", "清除标记", "Clear marks", AppAction::ClearMarks),
+        PaletteCommand::new(
+            "!",
+            "只显示需要提交的仓库",
+            "Filter: need commit",
+            AppAction::ToggleStatusFilter(GitStatus::NeedCommit),
+        ),
+        PaletteCommand::new(
+            "@",
+            "只显示需要拉取的仓库",
+            "Filter: need pull",
+            AppAction::ToggleStatusFilter(GitStatus::NeedPull),
+        ),
+        PaletteCommand::new(
+            "#",
+            "只显示需要推送的仓库",
+            "Filter: need push",
+            AppAction::ToggleStatusFilter(GitStatus::NeedPush),
+        ),
+        PaletteCommand::new(
+            ")",
+            "清除状态过滤",
+            "Clear status filter",
+            AppAction::ClearStatusFilter,
+        ),
+        PaletteCommand::new("R", "导出报告", "Export report", AppAction::StartReport),
+        PaletteCommand::new("v", "仓库详情", "Repo detail", AppAction::OpenDetail),
+        PaletteCommand::new("B", "分支列表", "Branches", AppAction::OpenBranches),
+        PaletteCommand::new(
+            "O",
+            "过时的分支",
+            "Stale branches",
+            AppAction::OpenStaleBranches,
+        ),
+        PaletteCommand::new(
+            "A",
+            "需要处理的事项",
+            "Attention inbox",
+            AppAction::OpenInbox,
+        ),
+        PaletteCommand::new(
+            "T",
+            "已删除和隐藏的仓库",
+            "Deleted and ignored repos",
+            AppAction::OpenTombstones,
+        ),
+        PaletteCommand::new("E", "检查失败的仓库", "Errors", AppAction::OpenErrors),
+        PaletteCommand::new("I", "统计", "Dashboard", AppAction::OpenDashboard),
+        PaletteCommand::new("J", "提交作者", "Authors", AppAction::OpenAuthors),
+        PaletteCommand::new(
+            "/",
+            "在所有仓库中搜索提交",
+            "Search commits in all repos",
+            AppAction::StartCommitSearch,
+        ),
+        PaletteCommand::new(
+            "?",
+            "在所有仓库中搜索文件",
+            "Search files in all repos",
+            AppAction::StartFileSearch,
+        ),
+        PaletteCommand::new("l", "日志", "Log", AppAction::OpenLog),
+        PaletteCommand::new("o", "命令输出", "Command output", AppAction::FocusOutput),
+        PaletteCommand::new("Tab", "下一个标签页", "Next tab", AppAction::NextTab),
+        PaletteCommand::new("q", "退出", "Quit", AppAction::Quit),
+    ]
+}
+
+/// 按 : 打开的命令面板, 模糊搜索所有命令
+#[derive(Debug)]
+pub struct Palette {
+    pub input: String,
+    commands: Vec<PaletteCommand>,
+    component_popup: CompletionPopup,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Palette {
+            input: String::new(),
+            commands: Vec::new(),
+            component_popup: CompletionPopup::default(),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.input.clear();
+        // 命令名字和当前语言有关, 打开时再生成
+        self.commands = palette_commands();
+        self.update_commands();
+    }
+
+    /// 根据输入过滤命令, 没有输入时按原来的顺序显示所有命令
+    fn update_commands(&mut self) {
+        let mut items: Vec<(usize, CompletionItem)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, command)| {
+                let text = command.text();
+                let score = Input::calc_item_score(&self.input, &text)
+                    .max(Input::calc_item_score(&self.input, command.alias));
+                (score > 0).then_some((index, CompletionItem { score, text }))
+            })
+            .collect();
+        items.sort_by_key(|(index, item)| (std::cmp::Reverse(item.score), *index));
+
+        self.component_popup.completions = items.into_iter().map(|(_, item)| item).collect();
+        self.component_popup
+            .state
+            .select((!self.component_popup.completions.is_empty()).then_some(0));
+    }
+
+    fn get_select(&self) -> Option<AppAction> {
+        let text = self.component_popup.get_select()?;
+        self.commands
+            .iter()
+            .find(|command| command.text() == text)
+            .map(|command| command.action)
+    }
+}
+
+impl Component for Palette {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc => Some(AppAction::ExitPalette),
+            KeyCode::Char(to_insert) => {
+                self.input.push(to_insert);
+                self.update_commands();
+                None
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.update_commands();
+                None
+            }
+            KeyCode::Enter => self.get_select(),
+            _ => {
+                self.component_popup.handle_events(key)?;
+                None
+            }
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Palette {
+            return Ok(());
+        }
+
+        let area = centered_rect(60, rect.height.saturating_sub(2).clamp(5, 20), rect);
+        f.render_widget(Clear, area);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        f.render_widget(
+            Paragraph::new(format!(": {}", self.input)).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(tr("命令", "Commands")),
+            ),
+            layout[0],
+        );
+        f.set_cursor(layout[0].x + self.input.width() as u16 + 3, layout[0].y + 1);
+        if self.component_popup.showp() {
+            self.component_popup.draw(mode, f, layout[1])?;
+        } else {
+            f.render_widget(
+                Paragraph::new(tr("没有匹配的命令", "No matching command"))
+                    .block(Block::default().borders(Borders::ALL)),
+                layout[1],
+            );
+        }
+
+        Ok(())
+    }
+}
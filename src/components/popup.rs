@@ -5,6 +5,19 @@ use super::Component;
 use crate::states::{AppAction, AppMode};
 use crate::utils::BDEResult;
 
+/// 在 rect 中间取出一块区域, 宽度为百分比, 高度为固定行数
+pub fn centered_rect(percent_x: u16, height: u16, rect: Rect) -> Rect {
+    let width = rect.width * percent_x / 100;
+    let height = height.min(rect.height);
+
+    Rect::new(
+        rect.x + (rect.width - width) / 2,
+        rect.y + (rect.height - height) / 2,
+        width,
+        height,
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct CompletionItem {
     pub score: u16,
@@ -75,11 +88,11 @@ impl CompletionPopup {
 impl Component for CompletionPopup {
     fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
         Ok(match key.code {
-            KeyCode::Tab => {
+            KeyCode::Tab | KeyCode::Down => {
                 self.next();
                 None
             }
-            KeyCode::BackTab => {
+            KeyCode::BackTab | KeyCode::Up => {
                 self.previous();
                 None
             }
@@ -0,0 +1,121 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 单行输入弹窗, 按 Enter 之后返回保存的 action, 按 Esc 取消
+#[derive(Debug)]
+pub struct Prompt {
+    pub title: String,
+    pub input: String,
+    pub action: Option<AppAction>,
+    /// 输入密码时用 * 显示
+    pub secretp: bool,
+    cursor_position: usize,
+}
+
+impl Prompt {
+    pub fn new() -> Self {
+        Prompt {
+            title: String::new(),
+            input: String::new(),
+            action: None,
+            secretp: false,
+            cursor_position: 0,
+        }
+    }
+
+    pub fn start(&mut self, title: &str, input: &str, action: AppAction) {
+        self.title = String::from(title);
+        self.input = String::from(input);
+        self.action = Some(action);
+        self.secretp = false;
+        self.cursor_position = self.input.chars().count();
+    }
+
+    pub fn start_secret(&mut self, title: &str, action: AppAction) {
+        self.start(title, "", action);
+        self.secretp = true;
+    }
+
+    fn byte_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .nth(self.cursor_position)
+            .map(|(index, _)| index)
+            .unwrap_or(self.input.len())
+    }
+
+    fn enter_char(&mut self, new_char: char) {
+        let index = self.byte_index();
+        self.input.insert(index, new_char);
+        self.cursor_position += 1;
+    }
+
+    fn delete_char(&mut self) {
+        if self.cursor_position != 0 {
+            self.cursor_position -= 1;
+            let index = self.byte_index();
+            self.input.remove(index);
+        }
+    }
+}
+
+impl Component for Prompt {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc => {
+                self.action = None;
+                Some(AppAction::ExitPrompt)
+            }
+            KeyCode::Enter => {
+                let action = self.action.take();
+                action.or(Some(AppAction::ExitPrompt))
+            }
+            KeyCode::Char(to_insert) => {
+                self.enter_char(to_insert);
+                None
+            }
+            KeyCode::Backspace => {
+                self.delete_char();
+                None
+            }
+            KeyCode::Left => {
+                self.cursor_position = self.cursor_position.saturating_sub(1);
+                None
+            }
+            KeyCode::Right => {
+                self.cursor_position = (self.cursor_position + 1).min(self.input.chars().count());
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Prompt {
+            return Ok(());
+        }
+
+        let area = centered_rect(60, 3, rect);
+        let text = if self.secretp {
+            "*".repeat(self.input.chars().count())
+        } else {
+            self.input.clone()
+        };
+        let input = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.title.as_str()),
+        );
+
+        f.render_widget(Clear, area);
+        f.render_widget(input, area);
+        f.set_cursor(area.x + self.cursor_position as u16 + 1, area.y + 1);
+
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{prelude::*, widgets::*};
@@ -7,12 +10,89 @@ use super::Component;
 use crate::states::{AppAction, AppMode};
 use crate::utils::BDEResult;
 
+use crate::config::NestedRepos;
+use crate::format::{format_age, truncate_middle, Formatter};
 use crate::gitrepo::{GitRepo, GitStatus};
+use crate::health::{health_breakdown, health_score, HealthWeights};
+use crate::i18n::{tr, trf};
+use crate::ops::{GitOperation, OpState};
+use crate::paths::tildify;
+use crate::theme::Theme;
+use crate::utils::now_timestamp;
+
+/// 仓库列表的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// 最近提交的在前
+    CommitTime,
+    /// 健康分数低的在前
+    Health,
+    /// 按远程仓库的主机和所有者分组
+    Owner,
+    /// 占用磁盘空间大的在前, 还没有计算的在最后
+    Size,
+    /// 经常打开并且最近打开过的在前
+    Frecency,
+}
+
+/// 表格中显示的一行
+#[derive(Debug, Clone)]
+pub struct ShowRepo {
+    /// 在所有仓库中的下标
+    pub id: usize,
+    pub name: String,
+    pub path: String,
+    pub status: GitStatus,
+    pub status_text: String,
+    pub branch: String,
+    pub tags: String,
+    pub health: u8,
+    pub last_commit_time: u64,
+    pub last_commit_summary: String,
+    pub disk_bytes: Option<u64>,
+    /// 使用频率的分数
+    pub frecency: u64,
+    pub markedp: bool,
+    /// 远程仓库的 host/owner, 没有远程仓库时为空
+    pub owner: String,
+    /// 正在执行或者失败的拉取, 推送任务
+    pub operation: Option<OpState>,
+}
 
 #[derive(Debug)]
 pub struct ReposShow {
-    pub show_repos: Vec<(usize, String, String, String)>,
+    pub show_repos: Vec<ShowRepo>,
     pub refresh_repop: bool,
+    pub show_hiddenp: bool,
+    /// 分页显示, 代替滚动
+    pub pagedp: bool,
+    /// 上一次绘制时每页可以显示的行数
+    page_size: usize,
+    /// 滚动显示时第一行的位置
+    offset: usize,
+    /// 已经输入的行号, 按其他键时清空
+    goto_input: String,
+    pub theme: Theme,
+    pub sort_by: SortBy,
+    pub health_weights: HealthWeights,
+    pub active_days: u64,
+    /// 超过多少天没有成功 fetch 时显示警告, 0 时不显示
+    pub stale_fetch_days: u64,
+    pub formatter: Formatter,
+    /// 最后提交时间显示为日期, 而不是多久之前
+    pub absolute_dates: bool,
+    /// 显示最后一次提交信息一列
+    pub show_commit_summary: bool,
+    /// 嵌套仓库的显示方式, tree 时缩进显示在外层仓库下面
+    pub nested_repos: NestedRepos,
+    /// 用快捷键打开的状态过滤, 和输入的 +状态 一起生效
+    pub status_filter: Vec<GitStatus>,
+    /// 标记的仓库, 拉取和推送时代替选中的仓库
+    pub marked: HashSet<PathBuf>,
+    /// 每个仓库上排队, 正在执行或者失败的操作任务
+    pub operations: HashMap<PathBuf, OpState>,
+    /// 减少动画, 正在执行的任务不显示转动的图标
+    pub reduced_motion: bool,
     pub state: TableState,
 }
 
@@ -21,18 +101,48 @@ impl ReposShow {
         ReposShow {
             show_repos: Vec::new(),
             refresh_repop: true,
+            show_hiddenp: false,
+            pagedp: false,
+            page_size: 1,
+            offset: 0,
+            goto_input: String::new(),
+            theme: Theme::default_theme(),
+            sort_by: SortBy::CommitTime,
+            health_weights: HealthWeights::default(),
+            active_days: 30,
+            stale_fetch_days: 7,
+            formatter: Formatter::default(),
+            absolute_dates: false,
+            show_commit_summary: false,
+            nested_repos: NestedRepos::default(),
+            status_filter: Vec::new(),
+            marked: HashSet::new(),
+            operations: HashMap::new(),
+            reduced_motion: false,
             state: TableState::default(),
         }
     }
 
-    pub fn update_show_repos(&mut self, repos: &[GitRepo], input: &str) -> BDEResult<()> {
+    pub fn update_show_repos(
+        &mut self,
+        repos: &[GitRepo],
+        ignore_repos: &[PathBuf],
+        input: &str,
+    ) -> BDEResult<()> {
         if self.refresh_repop {
             return Ok(());
         }
 
         let mut use_path_search = false;
         let mut use_match_case = false;
+        let mut filter_submodule_dirty = false;
+        let mut filter_no_remote = false;
+        let mut filter_stale = false;
         let mut filter_key: Vec<GitStatus> = Vec::new();
+        let mut filter_tags: Vec<&str> = Vec::new();
+        let mut filter_owners: Vec<String> = Vec::new();
+        let mut filter_hosts: Vec<String> = Vec::new();
+        let mut filter_remotes: Vec<String> = Vec::new();
         let mut other_search: Vec<&str> = Vec::new();
 
         let key_lst: Vec<&str> = input.trim().split(' ').collect();
@@ -48,6 +158,49 @@ impl ReposShow {
                 continue;
             }
 
+            if key == "+SubmoduleDirty" {
+                filter_submodule_dirty = true;
+                continue;
+            }
+
+            if key == "+NoRemote" {
+                filter_no_remote = true;
+                continue;
+            }
+
+            if key == "+stale" {
+                filter_stale = true;
+                continue;
+            }
+
+            if let Some(tag) = key.strip_prefix("+tag:") {
+                if !tag.is_empty() {
+                    filter_tags.push(tag);
+                }
+                continue;
+            }
+
+            if let Some(owner) = key.strip_prefix("+owner:") {
+                if !owner.is_empty() {
+                    filter_owners.push(owner.to_lowercase());
+                }
+                continue;
+            }
+
+            if let Some(host) = key.strip_prefix("+host:") {
+                if !host.is_empty() {
+                    filter_hosts.push(host.to_lowercase());
+                }
+                continue;
+            }
+
+            if let Some(remote) = key.strip_prefix("+remote:") {
+                if !remote.is_empty() {
+                    filter_remotes.push(remote.to_lowercase());
+                }
+                continue;
+            }
+
             if key.len() > 1 && key.starts_with('+') {
                 if let Ok(filter_status) = GitStatus::from_str(&key[1..]) {
                     filter_key.push(filter_status);
@@ -60,31 +213,90 @@ impl ReposShow {
         }
 
         // let search_key = other_search.join(" ");
+        filter_key.extend(&self.status_filter);
+        let filterp = !input.is_empty() || !self.status_filter.is_empty();
+
+        let now = now_timestamp();
 
         self.show_repos.clear();
         for (index, repo) in repos.iter().enumerate() {
+            let ignoredp = ignore_repos.contains(&repo.path);
+            if ignoredp && !self.show_hiddenp {
+                continue;
+            }
+
             let name = repo.name.clone();
-            let repo_path = repo.path.display().to_string();
-            let mut path: Vec<&str> = repo_path.split('/').collect();
-            if path.len() >= 2 {
-                path.drain(..3);
+            let path = tildify(&repo.path);
+            let mut status = format!("{} {}", repo.status.glyph(), repo.status);
+            if !repo.large_untracked.is_empty() {
+                status.push_str(tr(
+                    "(大目录未忽略, 按 i 处理)",
+                    "(large dirs not ignored, press i)",
+                ));
+            }
+            if repo.other_branch_need_push() {
+                status.push_str(tr("(其它分支需要推送)", "(other branches need push)"));
+            }
+            if repo.submodule_dirty() {
+                status.push_str(tr("(子模块有修改)", "(submodules changed)"));
+            }
+            if !repo.has_remote && repo.status != GitStatus::Missing {
+                status.push_str(tr("(没有远程仓库, 按 z 添加)", "(no remote, press z)"));
+            }
+            let stalep = self.stale_fetch_days > 0
+                && repo.fetch_stalep(now, self.stale_fetch_days * 24 * 3600);
+            if stalep && repo.last_fetch_success == 0 {
+                status.push_str(tr("(⚠ 从未 fetch)", "(⚠ never fetched)"));
+            } else if stalep {
+                status.push_str(&trf!(
+                    "(⚠ {} 天没有 fetch)",
+                    "(⚠ not fetched for {}d)",
+                    now.saturating_sub(repo.last_fetch_success) / (24 * 3600)
+                ));
+            }
+            if ignoredp {
+                status.push_str(tr("(已隐藏)", "(ignored)"));
             }
-            path.insert(0, "~");
-            let status = repo.status.to_string();
 
-            if !input.is_empty() {
+            if filterp {
                 let filter_status_inp = if filter_key.is_empty() {
                     true
                 } else {
-                    filter_key.iter().any(|item| *item == repo.status)
+                    filter_key.contains(&repo.status)
                 };
 
-                if !filter_status_inp {
+                if !filter_status_inp
+                    || (filter_submodule_dirty && !repo.submodule_dirty())
+                    || (filter_no_remote && repo.has_remote)
+                    || (filter_stale && !stalep)
+                {
+                    continue;
+                }
+
+                if !filter_tags.iter().all(|tag| repo.has_tag(tag)) {
+                    continue;
+                }
+
+                // owner 匹配所有者本身或者它的子组
+                let owner = repo.owner().unwrap_or_default().to_lowercase();
+                if !filter_owners
+                    .iter()
+                    .all(|item| owner == *item || owner.starts_with(&format!("{}/", item)))
+                {
+                    continue;
+                }
+
+                let host = repo.host().unwrap_or_default().to_lowercase();
+                if !filter_hosts.iter().all(|item| host == *item) {
+                    continue;
+                }
+
+                if !filter_remotes.iter().all(|item| repo.remote_matches(item)) {
                     continue;
                 }
 
                 let search_item = if use_path_search {
-                    path.join("/")
+                    path.clone()
                 } else {
                     name.clone()
                 };
@@ -115,16 +327,126 @@ impl ReposShow {
                 }
             }
 
-            self.show_repos
-                .push((index, name, path.join("/"), status.to_string()));
+            self.show_repos.push(ShowRepo {
+                id: index,
+                name,
+                path,
+                status: repo.status,
+                status_text: status,
+                branch: repo.branch.clone(),
+                tags: repo.tags.join(" "),
+                health: health_score(&health_breakdown(
+                    repo,
+                    &self.health_weights,
+                    self.active_days,
+                    now,
+                )),
+                last_commit_time: repo.last_commit_time,
+                last_commit_summary: repo.last_commit_summary.clone(),
+                disk_bytes: repo.size.as_ref().map(|size| size.disk_bytes),
+                frecency: repo.frecency(now),
+                markedp: self.marked.contains(&repo.path),
+                owner: match (repo.host(), repo.owner()) {
+                    (Some(host), Some(owner)) => format!("{}/{}", host, owner),
+                    (Some(host), None) => host,
+                    _ => String::new(),
+                },
+                operation: self.operations.get(&repo.path).copied(),
+            });
+        }
+
+        // 仓库本身已经按照提交时间排序
+        match self.sort_by {
+            SortBy::CommitTime => {}
+            SortBy::Health => self.show_repos.sort_by_key(|item| item.health),
+            // 没有远程仓库的放在最后, 组内保持提交时间的顺序
+            SortBy::Owner => self
+                .show_repos
+                .sort_by_key(|item| (item.owner.is_empty(), item.owner.clone())),
+            SortBy::Size => self
+                .show_repos
+                .sort_by_key(|item| std::cmp::Reverse(item.disk_bytes)),
+            // 没有打开过的保持提交时间的顺序
+            SortBy::Frecency => self
+                .show_repos
+                .sort_by_key(|item| std::cmp::Reverse(item.frecency)),
+        }
+
+        // 工作树显示在主仓库下面, tree 模式下嵌套的仓库显示在外层仓库下面,
+        // 上一层没有显示时保持原来的位置
+        let shown: HashSet<&Path> = self
+            .show_repos
+            .iter()
+            .map(|item| repos[item.id].path.as_path())
+            .collect();
+        let tree = self.nested_repos == NestedRepos::Tree;
+        let parent_of = |id: usize| {
+            let repo = &repos[id];
+            repo.worktree_of
+                .as_deref()
+                .filter(|parent| shown.contains(parent))
+                .or_else(|| {
+                    repo.nested_in
+                        .as_deref()
+                        .filter(|parent| tree && shown.contains(parent))
+                })
+        };
+        let mut roots = Vec::new();
+        let mut children: HashMap<&Path, Vec<(usize, ShowRepo)>> = HashMap::new();
+        for (index, item) in std::mem::take(&mut self.show_repos).into_iter().enumerate() {
+            match parent_of(item.id) {
+                Some(parent) => children.entry(parent).or_default().push((index, item)),
+                None => roots.push(item),
+            }
+        }
+        let mut rows = Vec::new();
+        for root in roots {
+            let mut stack = vec![(root, 0)];
+            while let Some((mut item, depth)) = stack.pop() {
+                if depth > 0 {
+                    item.name = format!("{}└ {}", "  ".repeat(depth - 1), item.name);
+                }
+                if let Some(items) = children.remove(repos[item.id].path.as_path()) {
+                    stack.extend(items.into_iter().rev().map(|(_, child)| (child, depth + 1)));
+                }
+                rows.push(item);
+            }
         }
+        // 互相作为上一层的仓库 (比如主仓库在工作树目录中) 不会被访问到, 放在最后
+        let mut rest: Vec<(usize, ShowRepo)> = children.into_values().flatten().collect();
+        rest.sort_by_key(|(index, _)| *index);
+        rows.extend(rest.into_iter().map(|(_, item)| item));
+        self.show_repos = rows;
 
         Ok(())
     }
 
+    /// 状态一列中显示的任务状态, 正在执行时前面加上转动的图标
+    fn operation_text(&self, operation: OpState, now_millis: u128) -> String {
+        const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+        let verb = |operation: GitOperation| match operation {
+            GitOperation::Pull => tr("拉取", "pull"),
+            GitOperation::Push => tr("推送", "push"),
+            GitOperation::Sync => tr("同步", "sync"),
+            GitOperation::PushTags => tr("推送标签", "tag push"),
+        };
+        match operation {
+            OpState::Queued(operation) => trf!("等待{}", "{} queued", verb(operation)),
+            OpState::Running(operation) => {
+                let spinner = if self.reduced_motion {
+                    "…"
+                } else {
+                    SPINNER[(now_millis / 100) as usize % SPINNER.len()]
+                };
+                trf!("{} 正在{}", "{} {}ing", spinner, verb(operation))
+            }
+            OpState::Failed(operation) => trf!("✖ {}失败", "✖ {} failed", verb(operation)),
+        }
+    }
+
     pub fn get_select_repo_id(&self) -> Option<usize> {
         let show_repo_index = self.state.selected()?;
-        Some(self.show_repos[show_repo_index].0)
+        self.show_repos.get(show_repo_index).map(|item| item.id)
     }
 
     pub fn next(&mut self) {
@@ -145,6 +467,125 @@ impl ReposShow {
         self.state.select(Some(i));
     }
 
+    pub fn page_len(&self) -> usize {
+        self.show_repos.len().div_ceil(self.page_size).max(1)
+    }
+
+    pub fn now_page(&self) -> usize {
+        self.state.selected().unwrap_or(0) / self.page_size
+    }
+
+    pub fn next_page(&mut self) {
+        if self.show_repos.is_empty() {
+            return;
+        }
+
+        let page = (self.now_page() + 1) % self.page_len();
+        self.state.select(Some(page * self.page_size));
+    }
+
+    pub fn previous_page(&mut self) {
+        if self.show_repos.is_empty() {
+            return;
+        }
+
+        let page = (self.now_page() + self.page_len() - 1) % self.page_len();
+        self.state.select(Some(page * self.page_size));
+    }
+
+    pub fn select_first(&mut self) {
+        if !self.show_repos.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        if !self.show_repos.is_empty() {
+            self.state.select(Some(self.show_repos.len() - 1));
+        }
+    }
+
+    /// 选中路径为 path 的仓库, 仓库没有显示时返回 false
+    pub fn select_repo_path(&mut self, repos: &[GitRepo], path: &Path) -> bool {
+        match self
+            .show_repos
+            .iter()
+            .position(|item| repos.get(item.id).is_some_and(|repo| repo.path == path))
+        {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 选中 ID 列为 row 的行
+    pub fn select_row(&mut self, row: usize) {
+        if row < self.show_repos.len() {
+            self.state.select(Some(row));
+        }
+    }
+
+    /// 输入行号的一位数字, 返回要跳转的行
+    ///
+    /// 数字依次累加, 超过最后一行时从这一位重新开始
+    fn push_goto_digit(&mut self, digit: char) -> Option<usize> {
+        self.goto_input.push(digit);
+        match self.goto_input.parse::<usize>() {
+            Ok(row) if row < self.show_repos.len() => Some(row),
+            _ => {
+                self.goto_input = digit.to_string();
+                self.goto_input
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|row| *row < self.show_repos.len())
+            }
+        }
+    }
+
+    /// 向下移动一页, 到最后一行时停止
+    pub fn scroll_page_down(&mut self) {
+        if self.show_repos.is_empty() {
+            return;
+        }
+
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| (i + self.page_size).min(self.show_repos.len() - 1));
+        self.state.select(Some(i));
+    }
+
+    /// 向上移动一页, 到第一行时停止
+    pub fn scroll_page_up(&mut self) {
+        if self.show_repos.is_empty() {
+            return;
+        }
+
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(self.page_size));
+        self.state.select(Some(i));
+    }
+
+    /// 滚动显示时调整第一行的位置, 保证选中的行可以看到
+    fn scroll_to_selected(&mut self) {
+        let len = self.show_repos.len();
+        let selected = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .min(len.saturating_sub(1));
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + self.page_size {
+            self.offset = selected + 1 - self.page_size;
+        }
+        self.offset = self.offset.min(len.saturating_sub(self.page_size));
+    }
+
     pub fn previous(&mut self) {
         if self.show_repos.is_empty() {
             return;
@@ -164,14 +605,130 @@ impl ReposShow {
     }
 }
 
+/// 路径一列的最小宽度, 更窄时不显示路径
+const MIN_PATH_WIDTH: u16 = 16;
+
+/// 根据表格的宽度计算每一列的宽度, 路径一列使用剩下的宽度, 为 0 时不显示
+///
+/// 顺序为 ID, 名字, 路径, 分支, 状态, 最后提交, 提交信息, 标签, 健康
+fn column_widths(width: u16, age_width: u16, summary_width: u16) -> [u16; 9] {
+    let mut widths = [5, 20, 0, 15, 20, age_width, summary_width, 20, 6];
+    // 路径可以使用的宽度, 去掉边框, 选中行前面的 >> 和列之间的空格
+    let path_rest = |widths: &[u16; 9]| {
+        let columns = widths.iter().filter(|width| **width > 0).count() as u16 + 1;
+        width.saturating_sub(2 + 2 + widths.iter().sum::<u16>() + columns - 1)
+    };
+
+    // 放不下路径时先不显示提交信息, 再缩小标签和名字, 仍然放不下时不显示路径
+    if path_rest(&widths) < MIN_PATH_WIDTH {
+        widths[6] = 0;
+    }
+    let rest = path_rest(&widths);
+    let shrinkable = [(7, 8), (1, 12)];
+    let max_shrink: u16 = shrinkable
+        .iter()
+        .map(|(index, min)| widths[*index] - min)
+        .sum();
+    if rest >= MIN_PATH_WIDTH {
+        widths[2] = rest;
+    } else if rest + max_shrink >= MIN_PATH_WIDTH {
+        let mut need = MIN_PATH_WIDTH - rest;
+        for (index, min) in shrinkable {
+            let shrink = need.min(widths[index] - min);
+            widths[index] -= shrink;
+            need -= shrink;
+        }
+        widths[2] = MIN_PATH_WIDTH;
+    }
+    widths
+}
+
+/// 去掉宽度为 0 的列
+fn visible_columns<T>(columns: Vec<T>, widths: &[u16]) -> Vec<T> {
+    columns
+        .into_iter()
+        .zip(widths)
+        .filter(|(_, width)| **width > 0)
+        .map(|(column, _)| column)
+        .collect()
+}
+
 impl Component for ReposShow {
     fn handle_events(&mut self, event: KeyEvent) -> BDEResult<Option<AppAction>> {
+        if let KeyCode::Char(digit) = event.code {
+            if digit.is_ascii_digit() {
+                return Ok(self.push_goto_digit(digit).map(AppAction::SelectRow));
+            }
+        }
+        self.goto_input.clear();
+
         Ok(match event.code {
             KeyCode::Char('g') => Some(AppAction::StartRefresh),
             KeyCode::Char('f') => Some(AppAction::StartFilter),
+            KeyCode::Char(':') => Some(AppAction::OpenPalette),
             KeyCode::Char('j') => Some(AppAction::SelectNext),
             KeyCode::Char('k') => Some(AppAction::SelectPervious),
             KeyCode::Char('y') => Some(AppAction::SelectCopyPath),
+            KeyCode::Char('u') => Some(AppAction::SelectCopyRemoteUrl),
+            KeyCode::Char('C') => Some(AppAction::SelectCopyCloneCommand),
+            KeyCode::Char('W') => Some(AppAction::SelectOpenBrowser),
+            KeyCode::Char('t') => Some(AppAction::StartTagEdit),
+            KeyCode::Char('n') => Some(AppAction::StartInitRepo),
+            KeyCode::Char('D') => Some(AppAction::StartDeleteRepo),
+            KeyCode::Char('Y') => Some(AppAction::StartPruneMissing),
+            KeyCode::Char('x') => Some(AppAction::ToggleIgnoreRepo),
+            KeyCode::Char('H') => Some(AppAction::ToggleShowHidden),
+            KeyCode::Char('!') => Some(AppAction::ToggleStatusFilter(GitStatus::NeedCommit)),
+            KeyCode::Char('@') => Some(AppAction::ToggleStatusFilter(GitStatus::NeedPull)),
+            KeyCode::Char('#') => Some(AppAction::ToggleStatusFilter(GitStatus::NeedPush)),
+            KeyCode::Char(')') => Some(AppAction::ClearStatusFilter),
+            KeyCode::Char('i') => Some(AppAction::StartIgnoreUntracked),
+            KeyCode::Char('b') => Some(AppAction::StartGitignoreTemplate),
+            KeyCode::Char('a') => Some(AppAction::ToggleAutoRefresh),
+            KeyCode::Char('L') => Some(AppAction::ToggleGitBackend),
+            KeyCode::Char('F') => Some(AppAction::ToggleOffline),
+            KeyCode::Tab => Some(AppAction::NextTab),
+            KeyCode::BackTab => Some(AppAction::PreviousTab),
+            KeyCode::Char('X') => Some(AppAction::ToggleRepoFetch),
+            KeyCode::Char('p') => Some(AppAction::SelectPull),
+            KeyCode::Char('o') => Some(AppAction::FocusOutput),
+            KeyCode::Char(']') => Some(AppAction::NextPage),
+            KeyCode::Char('[') => Some(AppAction::PerviousPage),
+            KeyCode::PageDown => Some(AppAction::ScrollPageDown),
+            KeyCode::PageUp => Some(AppAction::ScrollPageUp),
+            KeyCode::Home => Some(AppAction::SelectFirst),
+            KeyCode::End | KeyCode::Char('G') => Some(AppAction::SelectLast),
+            KeyCode::Char('P') => Some(AppAction::TogglePaged),
+            KeyCode::Char('s') => Some(AppAction::ToggleSort),
+            KeyCode::Char('w') => Some(AppAction::StartResolveDiverge),
+            KeyCode::Char('h') => Some(AppAction::OpenCustomCommands),
+            KeyCode::Char('d') => Some(AppAction::StartForeach),
+            KeyCode::Char('z') => Some(AppAction::StartAddRemote),
+            KeyCode::Char('U') => Some(AppAction::SelectPush),
+            KeyCode::Char('K') => Some(AppAction::StartBackup),
+            KeyCode::Char('m') => Some(AppAction::ToggleMark),
+            KeyCode::Char('V') => Some(AppAction::MarkAll),
+            KeyCode::Char('M') => Some(AppAction::ClearMarks),
+            KeyCode::Char('R') => Some(AppAction::StartReport),
+            KeyCode::Char('v') => Some(AppAction::OpenDetail),
+            KeyCode::Char('c') => Some(AppAction::SelectCdExit),
+            KeyCode::Char('e') => Some(AppAction::SelectOpenEditor),
+            KeyCode::Char('Z') => Some(AppAction::SelectOpenMultiplexer),
+            KeyCode::Char('S') => Some(AppAction::SelectSync),
+            KeyCode::Char('T') => Some(AppAction::OpenTombstones),
+            KeyCode::Char('B') => Some(AppAction::OpenBranches),
+            KeyCode::Char('A') => Some(AppAction::OpenInbox),
+            KeyCode::Char('l') => Some(AppAction::OpenLog),
+            KeyCode::Char('E') => Some(AppAction::OpenErrors),
+            KeyCode::Char('I') => Some(AppAction::OpenDashboard),
+            KeyCode::Char('J') => Some(AppAction::OpenAuthors),
+            KeyCode::Char('/') => Some(AppAction::StartCommitSearch),
+            KeyCode::Char('?') => Some(AppAction::StartFileSearch),
+            KeyCode::Char('+') => Some(AppAction::StartCreateTag),
+            KeyCode::Char('=') => Some(AppAction::OpenMergeTool),
+            KeyCode::Char('O') => Some(AppAction::OpenStaleBranches),
+            KeyCode::Char('r') => Some(AppAction::RefreshSelected),
+            KeyCode::Char('N') => Some(AppAction::StartRenameRepo),
             KeyCode::Enter => Some(AppAction::SelectEnter),
             _ => None,
         })
@@ -180,46 +737,164 @@ impl Component for ReposShow {
     fn draw(&mut self, _: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
         if self.show_repos.is_empty() {
             let repo_message = if self.refresh_repop {
-                "正在查找 Git 仓库..."
+                tr("正在查找 Git 仓库...", "Searching for Git repositories...")
             } else {
-                "需要刷新仓库"
+                tr("需要刷新仓库", "Refresh to load repositories")
             };
 
             f.render_widget(
-                Paragraph::new(repo_message)
-                    .block(Block::default().title("仓库").borders(Borders::ALL)),
+                Paragraph::new(repo_message).block(
+                    Block::default()
+                        .title(tr("仓库", "Repos"))
+                        .borders(Borders::ALL),
+                ),
                 rect,
             );
         } else {
+            // 去掉边框和表头之后可以显示的行数
+            self.page_size = (rect.height.saturating_sub(4) as usize).max(1);
+
+            let (page_start, page_end, title) = if self.pagedp {
+                let page = self.now_page().min(self.page_len() - 1);
+                let start = page * self.page_size;
+                let end = (start + self.page_size).min(self.show_repos.len());
+                (
+                    start,
+                    end,
+                    trf!("仓库 {}/{}", "Repos {}/{}", page + 1, self.page_len()),
+                )
+            } else if self.show_repos.len() > self.page_size {
+                self.scroll_to_selected();
+                let end = (self.offset + self.page_size).min(self.show_repos.len());
+                (
+                    self.offset,
+                    end,
+                    trf!(
+                        "仓库 {}-{}/{}",
+                        "Repos {}-{}/{}",
+                        self.offset + 1,
+                        end,
+                        self.show_repos.len()
+                    ),
+                )
+            } else {
+                self.offset = 0;
+                (0, self.show_repos.len(), String::from(tr("仓库", "Repos")))
+            };
+            let title = match self.sort_by {
+                SortBy::CommitTime => title,
+                SortBy::Health => trf!("{} (按健康分数排序)", "{} (sorted by health)", title),
+                SortBy::Owner => trf!("{} (按所有者分组)", "{} (grouped by owner)", title),
+                SortBy::Size => trf!("{} (按大小排序)", "{} (sorted by size)", title),
+                SortBy::Frecency => {
+                    trf!("{} (按使用频率排序)", "{} (sorted by frecency)", title)
+                }
+            };
+
+            let now = now_timestamp();
+            let now_millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis());
+            let age_width = if self.absolute_dates {
+                self.formatter.timestamp(now).len() as u16
+            } else {
+                9
+            };
+            let summary_width = if self.show_commit_summary { 30 } else { 0 };
+            let widths = column_widths(rect.width, age_width, summary_width);
+            let path_width = widths[2];
+
             let mut table_rows = Vec::new();
 
-            for (index, repo) in self.show_repos.iter().enumerate() {
-                table_rows.push(Row::new(vec![
-                    format!("{}", index),
-                    repo.1.clone(),
-                    repo.2.clone(),
-                    repo.3.clone(),
-                ]));
+            for (index, repo) in self.show_repos[page_start..page_end]
+                .iter()
+                .enumerate()
+                .map(|(index, repo)| (index + page_start, repo))
+            {
+                // 分组时路径一列显示所有者, 只在每组的第一行显示
+                let path_cell = if self.sort_by != SortBy::Owner {
+                    Cell::from(truncate_middle(&repo.path, path_width as usize))
+                } else if index == page_start || self.show_repos[index - 1].owner != repo.owner {
+                    let owner = if repo.owner.is_empty() {
+                        String::from(tr("(没有远程仓库)", "(no remote)"))
+                    } else {
+                        repo.owner.clone()
+                    };
+                    Cell::from(truncate_middle(&owner, path_width as usize))
+                        .style(self.theme.heading)
+                } else {
+                    Cell::from("")
+                };
+                let cells = vec![
+                    Cell::from(format!("{}{}", if repo.markedp { "*" } else { "" }, index)),
+                    Cell::from(repo.name.clone()),
+                    path_cell,
+                    Cell::from(repo.branch.clone()),
+                    match repo.operation {
+                        Some(operation) => Cell::from(self.operation_text(operation, now_millis))
+                            .style(match operation {
+                                OpState::Failed(_) => self.theme.conflicted,
+                                _ => self.theme.in_progress,
+                            }),
+                        None => Cell::from(repo.status_text.clone())
+                            .style(self.theme.status_style(repo.status)),
+                    },
+                    Cell::from(match repo.last_commit_time {
+                        0 => String::new(),
+                        time if self.absolute_dates => self.formatter.timestamp(time),
+                        time => format_age(time, now),
+                    }),
+                    Cell::from(repo.last_commit_summary.clone()),
+                    Cell::from(repo.tags.clone()),
+                    Cell::from(format!("{:>3}", repo.health)),
+                ];
+                table_rows.push(Row::new(visible_columns(cells, &widths)));
             }
 
-            let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+            let selected_style = self.theme.selected;
 
-            let header_cells = ["ID", "仓库名字", "仓库路径", "仓库状态"];
-            let header = Row::new(header_cells)
-                .style(Style::default().fg(Color::Yellow))
+            let header_cells = [
+                "ID",
+                tr("仓库名字", "Name"),
+                if self.sort_by == SortBy::Owner {
+                    tr("所有者", "Owner")
+                } else {
+                    tr("仓库路径", "Path")
+                },
+                tr("分支", "Branch"),
+                tr("仓库状态", "Status"),
+                // 默认按最后提交时间从新到旧排序
+                if self.sort_by == SortBy::CommitTime {
+                    tr("更新 ▼", "Updated ▼")
+                } else {
+                    tr("更新", "Updated")
+                },
+                tr("提交信息", "Message"),
+                tr("标签", "Tags"),
+                tr("健康", "Health"),
+            ];
+            let header = Row::new(visible_columns(header_cells.to_vec(), &widths))
+                .style(self.theme.header)
                 .height(1)
                 .bottom_margin(1);
 
+            let constraints = visible_columns(
+                widths
+                    .iter()
+                    .map(|width| Constraint::Length(*width))
+                    .collect(),
+                &widths,
+            );
             let t = Table::new(table_rows)
                 .header(header)
-                .style(Style::default().fg(Color::White))
-                .block(Block::default().title("仓库").borders(Borders::ALL))
-                .widths(&[
-                    Constraint::Length(5),
-                    Constraint::Length(20),
-                    Constraint::Length(50),
-                    Constraint::Length(20),
-                ])
+                .style(self.theme.text)
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(self.theme.border),
+                )
+                .widths(&constraints)
                 // ...and they can be separated by a fixed spacing.
                 .column_spacing(1)
                 // If you wish to highlight a row in any specific way when it is selected...
@@ -227,7 +902,26 @@ impl Component for ReposShow {
                 // ...and potentially show a symbol in front of the selection.
                 .highlight_symbol(">>");
 
-            f.render_stateful_widget(t, rect, &mut self.state);
+            // 只绘制可以看到的行, 选中的位置相对于第一行
+            let mut page_state = TableState::default().with_selected(
+                self.state
+                    .selected()
+                    .map(|selected| selected.saturating_sub(page_start)),
+            );
+            f.render_stateful_widget(t, rect, &mut page_state);
+
+            if self.show_repos.len() > self.page_size {
+                let mut scrollbar_state = ScrollbarState::new(self.show_repos.len())
+                    .position(self.state.selected().unwrap_or(0));
+                f.render_stateful_widget(
+                    Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                    rect.inner(&Margin {
+                        vertical: 1,
+                        horizontal: 0,
+                    }),
+                    &mut scrollbar_state,
+                );
+            }
         };
 
         Ok(())
@@ -0,0 +1,131 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::format::format_age;
+use crate::i18n::{tr, trf};
+use crate::stale::{StaleBranch, StaleReason};
+use crate::states::{AppAction, AppMode};
+use crate::utils::{now_timestamp, BDEResult};
+
+/// 所有仓库中过时的本地分支, 可以逐个删除
+#[derive(Debug, Default)]
+pub struct StaleBranches {
+    pub branches: Vec<StaleBranch>,
+    /// 多久没有提交算作过时, 单位月
+    pub months: u64,
+    state: ListState,
+}
+
+impl StaleBranches {
+    pub fn new() -> Self {
+        StaleBranches::default()
+    }
+
+    pub fn start(&mut self, branches: Vec<StaleBranch>, months: u64) {
+        self.branches = branches;
+        self.months = months;
+        self.state.select((!self.branches.is_empty()).then_some(0));
+    }
+
+    pub fn get_select(&self) -> Option<&StaleBranch> {
+        self.branches.get(self.state.selected()?)
+    }
+
+    /// 删除成功之后从列表中去掉
+    pub fn remove_select(&mut self) {
+        if let Some(index) = self.state.selected() {
+            if index < self.branches.len() {
+                self.branches.remove(index);
+            }
+        }
+        self.state.select(if self.branches.is_empty() {
+            None
+        } else {
+            Some(
+                self.state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.branches.len() - 1),
+            )
+        });
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.branches.is_empty() {
+            return;
+        }
+        let len = self.branches.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for StaleBranches {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitStaleBranches),
+            KeyCode::Char('d') => Some(AppAction::DeleteStaleBranch),
+            KeyCode::Char('D') => Some(AppAction::StartForceDeleteStaleBranch),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::StaleBranches {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "过时的分支: {} (上游已删除或者 {} 个月没有提交)",
+            "Stale branches: {} (upstream gone or inactive for {} months)",
+            self.branches.len(),
+            self.months
+        ));
+        f.render_widget(Clear, area);
+
+        if self.branches.is_empty() {
+            f.render_widget(
+                Paragraph::new(tr("没有过时的分支", "No stale branches")).block(block),
+                area,
+            );
+            return Ok(());
+        }
+
+        let now = now_timestamp();
+        let items: Vec<ListItem> = self
+            .branches
+            .iter()
+            .map(|branch| {
+                let reason_style = match branch.reason {
+                    StaleReason::UpstreamGone => Style::default().fg(Color::Yellow),
+                    StaleReason::Inactive => Style::default().fg(Color::DarkGray),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:<20} ", branch.repo_name)),
+                    Span::raw(format!("{:<30} ", branch.branch)),
+                    Span::styled(format!("{:<14} ", branch.reason.to_string()), reason_style),
+                    Span::raw(format_age(branch.last_commit_time, now)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
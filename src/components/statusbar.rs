@@ -1,43 +1,124 @@
 use ratatui::{prelude::*, widgets::*};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use super::Component;
+use crate::backend::{backend, GitBackend};
+use crate::fetch::offline;
+use crate::format::Formatter;
+use crate::gitrepo::{GitRepo, GitStatus};
+use crate::metrics::MetricsSnapshot;
+use crate::paths::tildify;
 use crate::states::AppMode;
+use crate::store::RepoObserver;
+use crate::theme::Theme;
 use crate::utils::BDEResult;
 
+/// 状态栏显示的仓库数量, 注册到 RepoStore 中自动更新
+#[derive(Debug, Default)]
+pub struct RepoCounts {
+    pub all: usize,
+    pub missing: usize,
+}
+
+impl RepoObserver for RepoCounts {
+    fn on_added(&mut self, repo: &GitRepo) {
+        self.all += 1;
+        if repo.status == GitStatus::Missing {
+            self.missing += 1;
+        }
+    }
+
+    fn on_updated(&mut self, old: &GitRepo, repo: &GitRepo) {
+        self.on_removed(old);
+        self.on_added(repo);
+    }
+
+    fn on_removed(&mut self, repo: &GitRepo) {
+        self.all -= 1;
+        if repo.status == GitStatus::Missing {
+            self.missing -= 1;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct StatusBar {
-    pub search_repo_duration: f64,
+    pub search_repo_duration: Duration,
     pub show_repo_len: usize,
-    pub all_repo_len: usize,
+    pub repo_counts: Rc<RefCell<RepoCounts>>,
+    pub hidden_repo_len: usize,
+    /// 上一次刷新中检查失败的仓库数量
+    pub error_len: usize,
+    pub show_hiddenp: bool,
+    /// 用快捷键打开的状态过滤
+    pub status_filter: Vec<GitStatus>,
+    /// 另一个实例正在运行, 不写入缓存
+    pub read_onlyp: bool,
+    /// 开启自动刷新时的刷新间隔
+    pub auto_refresh_interval: Option<u64>,
+    pub last_refresh: Option<Instant>,
+    pub metrics: MetricsSnapshot,
+    pub formatter: Formatter,
+    pub theme: Theme,
+    /// 减少动画, 提示不闪烁
+    pub reduced_motion: bool,
+    /// 正在查找的搜索目录和找到的仓库数量, 还没有完成时为 None
+    pub root_progress: Vec<(PathBuf, Option<usize>)>,
 }
 
 impl StatusBar {
     pub fn new() -> Self {
         StatusBar {
-            search_repo_duration: 0.0,
+            search_repo_duration: Duration::ZERO,
             show_repo_len: 0,
-            all_repo_len: 0,
+            repo_counts: Rc::new(RefCell::new(RepoCounts::default())),
+            hidden_repo_len: 0,
+            error_len: 0,
+            show_hiddenp: false,
+            status_filter: Vec::new(),
+            read_onlyp: false,
+            auto_refresh_interval: None,
+            last_refresh: None,
+            metrics: MetricsSnapshot::default(),
+            formatter: Formatter::default(),
+            theme: Theme::default_theme(),
+            reduced_motion: false,
+            root_progress: Vec::new(),
         }
     }
 }
 
 impl Component for StatusBar {
     fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        let line_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(rect);
+
         let status_bar_layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(rect);
+            .split(line_layout[0]);
 
-        let (msg, style) = match mode {
+        let (mut msg, style) = match mode {
             AppMode::Normal => (
                 vec![
                     "Press ".into(),
                     "q".bold(),
                     " to exit, ".into(),
+                    ":".bold(),
+                    " for commands, ".into(),
                     "f".bold(),
                     " to start filter repo, ".bold(),
                     "g".into(),
-                    " to refresh repo.".bold(),
+                    " to refresh repo, ".bold(),
+                    "r".into(),
+                    " to refresh selected, ".bold(),
+                    "t".into(),
+                    " to edit tags.".bold(),
                 ],
                 Style::default().add_modifier(Modifier::RAPID_BLINK),
             ),
@@ -45,26 +126,335 @@ impl Component for StatusBar {
                 vec!["Press ".into(), "Esc".bold(), " to stop search, ".into()],
                 Style::default(),
             ),
+            AppMode::Prompt => (
+                vec![
+                    "Press ".into(),
+                    "Enter".bold(),
+                    " to submit, ".into(),
+                    "Esc".bold(),
+                    " to cancel.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Output => (
+                vec![
+                    "Press ".into(),
+                    "Esc".bold(),
+                    " to close output, ".into(),
+                    "j/k".bold(),
+                    " to scroll, ".into(),
+                    "G".bold(),
+                    " to follow.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Detail => (
+                vec![
+                    "Press ".into(),
+                    "Esc".bold(),
+                    " to close detail, ".into(),
+                    "j/k".bold(),
+                    " to scroll.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Wizard => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose plan, ".into(),
+                    "Enter".bold(),
+                    " to execute, ".into(),
+                    "Esc".bold(),
+                    " to cancel.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Tombstones => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "d".bold(),
+                    " to purge (repo comes back on next refresh), ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Branches => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose branch, ".into(),
+                    "Enter".bold(),
+                    " to switch, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Inbox => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "Enter".bold(),
+                    " to resolve, ".into(),
+                    "Esc".bold(),
+                    " to show all repos.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Errors => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "Enter".bold(),
+                    " to retry, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::FileSearch => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "Enter".bold(),
+                    " to open in editor, ".into(),
+                    "?".bold(),
+                    " to search again, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::CommitSearch => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "Enter".bold(),
+                    " to show commit, ".into(),
+                    "/".bold(),
+                    " to search again, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Authors => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose author, ".into(),
+                    "w".bold(),
+                    " to change window, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Dashboard => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to scroll dirty repos, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::StaleBranches => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose, ".into(),
+                    "d".bold(),
+                    " to delete merged, ".into(),
+                    "D".bold(),
+                    " to force delete, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::CustomCommands => (
+                vec![
+                    "Press ".into(),
+                    "j/k".bold(),
+                    " to choose command, ".into(),
+                    "Enter".bold(),
+                    " to run, ".into(),
+                    "Esc".bold(),
+                    " to cancel.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Log => (
+                vec![
+                    "Press ".into(),
+                    "Esc".bold(),
+                    " to close log, ".into(),
+                    "j/k".bold(),
+                    " to scroll, ".into(),
+                    "G".bold(),
+                    " to follow.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Palette => (
+                vec![
+                    "Type to search, ".into(),
+                    "Up/Down".bold(),
+                    " to choose, ".into(),
+                    "Enter".bold(),
+                    " to run, ".into(),
+                    "Esc".bold(),
+                    " to close.".into(),
+                ],
+                Style::default(),
+            ),
+            AppMode::Confirm => (
+                vec![
+                    "Press ".into(),
+                    "y".bold(),
+                    " to confirm, ".into(),
+                    "n".bold(),
+                    " to cancel, ".into(),
+                    "j/k".bold(),
+                    " to scroll.".into(),
+                ],
+                Style::default(),
+            ),
+        };
+
+        // 减少动画时用静态的标记代替闪烁
+        let style = if self.reduced_motion && style.add_modifier.contains(Modifier::RAPID_BLINK) {
+            msg.insert(0, "» ".bold());
+            style.remove_modifier(Modifier::RAPID_BLINK)
+        } else {
+            style
         };
 
         let mut text = Text::from(Line::from(msg));
         text.patch_style(style);
         f.render_widget(Paragraph::new(text), status_bar_layout[0]);
 
-        let use_time = format!("search time: {}s", self.search_repo_duration);
-        let repo_number = if self.all_repo_len == 0 {
+        let repo_counts = self.repo_counts.borrow();
+        let mut repo_number = if repo_counts.all == 0 {
             String::from("repo: 0")
         } else {
-            format!("repo: {}/{}", self.show_repo_len, self.all_repo_len)
+            format!("repo: {}/{}", self.show_repo_len, repo_counts.all)
         };
+        if repo_counts.missing > 0 {
+            repo_number.push_str(&format!(" (missing: {}, Y to prune)", repo_counts.missing));
+        }
 
-        let text = Text::from(Line::from(vec![
-            use_time.into(),
+        let hidden_number = if self.show_hiddenp {
+            format!("hidden: {} (shown)", self.hidden_repo_len)
+        } else {
+            format!("hidden: {}", self.hidden_repo_len)
+        };
+
+        let auto_refresh = match self.auto_refresh_interval {
+            Some(interval) => format!("auto: {}s", interval),
+            None => String::from("auto: off"),
+        };
+        let last_refresh = match self.last_refresh {
+            Some(last_refresh) => format!(
+                "last: {} ago",
+                self.formatter
+                    .duration(Duration::from_secs(last_refresh.elapsed().as_secs()))
+            ),
+            None => String::from("last: -"),
+        };
+
+        let mut spans = vec![
+            auto_refresh.into(),
+            " | ".into(),
+            last_refresh.into(),
             " | ".into(),
             repo_number.into(),
-        ]));
+            " | ".into(),
+            hidden_number.into(),
+        ];
+        if !self.status_filter.is_empty() {
+            spans.push(" | filter:".into());
+            for status in &self.status_filter {
+                spans.push(" ".into());
+                spans.push(Span::styled(
+                    format!("[{:?}]", status),
+                    self.theme.status_style(*status),
+                ));
+            }
+            spans.push(" ()=clear)".into());
+        }
+        if self.error_len > 0 {
+            spans.push(" | ".into());
+            spans.push(Span::styled(
+                format!("errors: {} (E)", self.error_len),
+                self.theme.status_style(GitStatus::Conflicted),
+            ));
+        }
+        if backend() != GitBackend::default() {
+            spans.push(" | ".into());
+            spans.push(format!("backend: {}", backend()).into());
+        }
+        if offline() {
+            spans.push(" | ".into());
+            spans.push(Span::styled("offline (F)", self.theme.read_only));
+        }
+        if self.read_onlyp {
+            spans.push(" | ".into());
+            spans.push(Span::styled("cache: read-only", self.theme.read_only));
+        }
+
+        let text = Text::from(Line::from(spans));
         f.render_widget(Paragraph::new(text), status_bar_layout[1]);
 
+        let metrics = &self.metrics;
+        let use_time = format!(
+            "refresh: {} (scan {}, status Σ{}, fetch Σ{})",
+            self.formatter.duration(self.search_repo_duration),
+            self.formatter.duration(metrics.scan),
+            self.formatter.duration(metrics.status),
+            self.formatter.duration(metrics.fetch)
+        );
+        let tasks = format!(
+            "tasks: {} queued, {} running",
+            metrics.queued, metrics.running
+        );
+        let network = format!("network: {}", metrics.network);
+
+        let mut spans = vec![
+            use_time.into(),
+            " | ".into(),
+            tasks.into(),
+            " | ".into(),
+            network.into(),
+        ];
+        if !self.root_progress.is_empty() {
+            let roots: Vec<String> = self
+                .root_progress
+                .iter()
+                .map(|(path, repos)| match repos {
+                    Some(repos) => format!("{} {} ✔", tildify(path), repos),
+                    None => format!("{} …", tildify(path)),
+                })
+                .collect();
+            spans.push(" | ".into());
+            spans.push(format!("scan: {}", roots.join(", ")).into());
+        }
+        let text = Text::from(Line::from(spans));
+        f.render_widget(
+            Paragraph::new(text).style(self.theme.metrics),
+            line_layout[1],
+        );
+
         Ok(())
     }
 }
@@ -0,0 +1,54 @@
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::states::AppMode;
+use crate::theme::Theme;
+use crate::utils::BDEResult;
+
+/// 搜索目录的标签页, 只有一个标签页时不显示
+#[derive(Debug)]
+pub struct RootTabs {
+    pub names: Vec<String>,
+    pub selected: usize,
+    pub theme: Theme,
+}
+
+impl RootTabs {
+    pub fn new() -> Self {
+        RootTabs {
+            names: Vec::new(),
+            selected: 0,
+            theme: Theme::default_theme(),
+        }
+    }
+
+    /// 需要占用的高度
+    pub fn height(&self) -> u16 {
+        if self.names.len() > 1 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl Component for RootTabs {
+    fn draw(&mut self, _mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if self.names.len() <= 1 {
+            return Ok(());
+        }
+
+        let titles: Vec<Line> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Line::from(format!("{} {}", index + 1, name)))
+            .collect();
+        let tabs = Tabs::new(titles)
+            .select(self.selected)
+            .highlight_style(self.theme.heading.add_modifier(Modifier::REVERSED));
+        f.render_widget(tabs, rect);
+
+        Ok(())
+    }
+}
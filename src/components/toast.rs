@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use ratatui::{prelude::*, widgets::*};
+use unicode_width::UnicodeWidthStr;
+
+use super::Component;
+use crate::states::AppMode;
+use crate::theme::Theme;
+use crate::utils::BDEResult;
+
+/// 提示显示多久之后消失
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+/// 同时最多显示这么多条提示, 多出来的先去掉最早的
+const TOAST_MAX: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+struct Toast {
+    level: ToastLevel,
+    text: String,
+    time: Instant,
+}
+
+/// 显示在仓库列表右下角的短暂提示, 用于复制, 拉取, 推送等操作的结果
+#[derive(Debug)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+    pub theme: Theme,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Toasts {
+            toasts: VecDeque::new(),
+            theme: Theme::default_theme(),
+        }
+    }
+
+    pub fn push(&mut self, level: ToastLevel, text: impl Into<String>) {
+        self.toasts.push_back(Toast {
+            level,
+            text: text.into(),
+            time: Instant::now(),
+        });
+        while self.toasts.len() > TOAST_MAX {
+            self.toasts.pop_front();
+        }
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(ToastLevel::Info, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(ToastLevel::Error, text);
+    }
+}
+
+impl Component for Toasts {
+    fn draw(&mut self, _mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        self.toasts
+            .retain(|toast| toast.time.elapsed() < TOAST_DURATION);
+        if self.toasts.is_empty() {
+            return Ok(());
+        }
+
+        // 加上左右边框
+        let width = self
+            .toasts
+            .iter()
+            .map(|toast| toast.text.width() as u16 + 2)
+            .max()
+            .unwrap_or(0)
+            .min(rect.width.saturating_sub(2));
+        let height = (self.toasts.len() as u16 + 2).min(rect.height);
+        let area = Rect::new(
+            rect.x + rect.width.saturating_sub(width + 1),
+            rect.y + rect.height.saturating_sub(height + 1),
+            width,
+            height,
+        );
+
+        let lines: Vec<Line> = self
+            .toasts
+            .iter()
+            .map(|toast| {
+                let style = match toast.level {
+                    ToastLevel::Info => self.theme.clean,
+                    ToastLevel::Error => self.theme.conflicted,
+                };
+                Line::from(Span::styled(toast.text.clone(), style))
+            })
+            .collect();
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(lines).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border),
+            ),
+            area,
+        );
+
+        Ok(())
+    }
+}
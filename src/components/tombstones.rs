@@ -0,0 +1,128 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::format::Formatter;
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::tombstone::Tombstone;
+use crate::utils::BDEResult;
+
+/// 隐藏和删除的仓库留下的墓碑, 可以逐个清除
+#[derive(Debug)]
+pub struct Tombstones {
+    pub tombstones: Vec<Tombstone>,
+    pub formatter: Formatter,
+    state: ListState,
+}
+
+impl Tombstones {
+    pub fn new(tombstones: Vec<Tombstone>) -> Self {
+        Tombstones {
+            tombstones,
+            formatter: Formatter::default(),
+            state: ListState::default(),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.state
+            .select((!self.tombstones.is_empty()).then_some(0));
+    }
+
+    /// 移除选中的墓碑并返回
+    pub fn take_select(&mut self) -> Option<Tombstone> {
+        let index = self.state.selected()?;
+        if index >= self.tombstones.len() {
+            return None;
+        }
+
+        let tombstone = self.tombstones.remove(index);
+        self.state.select(if self.tombstones.is_empty() {
+            None
+        } else {
+            Some(index.min(self.tombstones.len() - 1))
+        });
+        Some(tombstone)
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.tombstones.is_empty() {
+            return;
+        }
+        let len = self.tombstones.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Tombstones {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitTombstones),
+            KeyCode::Char('d') => Some(AppAction::PurgeTombstone),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Tombstones {
+            return Ok(());
+        }
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "墓碑: {} (重新扫描时不会加入这些仓库)",
+            "Tombstones: {} (skipped when rescanning)",
+            self.tombstones.len()
+        ));
+        f.render_widget(Clear, area);
+
+        if self.tombstones.is_empty() {
+            f.render_widget(
+                Paragraph::new(tr("没有墓碑", "No tombstones")).block(block),
+                area,
+            );
+            return Ok(());
+        }
+
+        let items: Vec<ListItem> = self
+            .tombstones
+            .iter()
+            .map(|tombstone| {
+                ListItem::new(vec![
+                    Line::from(format!(
+                        "{} {} {}",
+                        tombstone.reason,
+                        self.formatter.timestamp(tombstone.time),
+                        tombstone.path.display()
+                    )),
+                    Line::from(
+                        format!(
+                            "    origin: {}",
+                            tombstone.origin.as_deref().unwrap_or(tr("无", "none"))
+                        )
+                        .dark_gray(),
+                    ),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, area, &mut self.state);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::popup::centered_rect;
+use super::Component;
+use crate::diverge::{DivergeInfo, ResolvePlan};
+use crate::i18n::{tr, trf};
+use crate::states::{AppAction, AppMode};
+use crate::utils::BDEResult;
+
+/// 分叉分支的解决向导, 左边选择方案, 右边预览执行之后的历史
+#[derive(Debug)]
+pub struct Wizard {
+    pub info: Option<DivergeInfo>,
+    state: ListState,
+}
+
+impl Wizard {
+    pub fn new() -> Self {
+        Wizard {
+            info: None,
+            state: ListState::default(),
+        }
+    }
+
+    pub fn start(&mut self, info: DivergeInfo) {
+        self.info = Some(info);
+        self.state.select(Some(0));
+    }
+
+    pub fn get_select_plan(&self) -> Option<ResolvePlan> {
+        self.state
+            .selected()
+            .and_then(|index| ResolvePlan::ALL.get(index).copied())
+    }
+
+    fn select(&mut self, offset: isize) {
+        let len = ResolvePlan::ALL.len() as isize;
+        let index = self.state.selected().unwrap_or(0) as isize;
+        self.state
+            .select(Some((index + offset).rem_euclid(len) as usize));
+    }
+}
+
+impl Component for Wizard {
+    fn handle_events(&mut self, key: KeyEvent) -> BDEResult<Option<AppAction>> {
+        Ok(match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => Some(AppAction::ExitWizard),
+            KeyCode::Enter => Some(AppAction::ApplyResolvePlan),
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.select(1);
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.select(-1);
+                None
+            }
+            _ => None,
+        })
+    }
+
+    fn draw(&mut self, mode: AppMode, f: &mut Frame<'_>, rect: Rect) -> BDEResult<()> {
+        if mode != AppMode::Wizard {
+            return Ok(());
+        }
+        let (Some(info), Some(plan)) = (&self.info, self.get_select_plan()) else {
+            return Ok(());
+        };
+
+        let area = centered_rect(90, rect.height.saturating_sub(2).max(10), rect);
+        let block = Block::default().borders(Borders::ALL).title(trf!(
+            "{} 和 {} 已经分叉: 本地 {} 个提交, 上游 {} 个提交",
+            "{} and {} have diverged: {} local commits, {} upstream commits",
+            info.branch,
+            info.upstream,
+            info.local_commits.len(),
+            info.upstream_commits.len()
+        ));
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(28), Constraint::Min(0)])
+            .split(inner);
+
+        let items: Vec<ListItem> = ResolvePlan::ALL
+            .iter()
+            .map(|plan| ListItem::new(plan.title()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::RIGHT))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, layout[0], &mut self.state);
+
+        let mut lines = vec![Line::from(plan.description()), Line::from("")];
+        if !info.worktree_cleanp {
+            lines.push(Line::from(
+                tr(
+                    "工作区有未提交的修改, 请先提交或者 stash 之后再执行.",
+                    "The worktree has uncommitted changes, commit or stash them first.",
+                )
+                .red(),
+            ));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(
+            tr("执行之后的历史:", "History after running:").bold(),
+        ));
+        lines.extend(plan.preview(info));
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            trf!("命令: {}", "Command: {}", plan.command(info)).dark_gray(),
+        ));
+
+        let preview = Paragraph::new(lines).wrap(Wrap { trim: false });
+        f.render_widget(preview, layout[1].inner(&Margin::new(1, 0)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::GitBackend;
+use crate::fetch::{DEFAULT_FETCH_JITTER_MS, DEFAULT_HOST_CONCURRENCY};
+use crate::format::{env_locale, local_utc_offset, locale_decimal_comma, Formatter, TimeFormat};
+use crate::gitrepo::{ActivePolicy, SearchOptions, DEFAULT_SCAN_EXCLUDE};
+use crate::health::HealthWeights;
+use crate::i18n::Language;
+use crate::ops::DEFAULT_OP_JOBS;
+use crate::paths::{expand_path_with, home_dir, network_mounts, ExcludePattern};
+use crate::states::AppAction;
+use crate::theme::StyleConfig;
+use crate::utils::{shell_quote, BDEResult};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// 搜索 Git 仓库的根目录
+    pub search_paths: Vec<String>,
+    /// 命名的搜索目录, 每个显示为一个标签页, 有各自的仓库列表和缓存, 为空时只使用 search_paths
+    pub roots: Vec<SearchRoot>,
+    /// 允许扫描 HOME 之外的目录, 否则每次启动时需要确认
+    pub allow_outside_home: bool,
+    /// 启动后自动定时刷新仓库状态
+    pub auto_refresh: bool,
+    /// 自动刷新间隔, 单位秒
+    pub auto_refresh_interval: u64,
+    /// 自动刷新时只对最近有提交的仓库进行网络检查
+    pub auto_refresh_active_only: bool,
+    /// 最近多少天内有提交算作活跃仓库
+    pub active_days: u64,
+    /// 不活跃仓库的网络检查间隔, 单位秒
+    pub dormant_fetch_interval: u64,
+    /// 仓库列表使用分页显示
+    pub paged: bool,
+    /// 配色名字, 可选 default, deuteranopia
+    pub theme: String,
+    /// 覆盖配色中的样式, 比如 {"need_commit": {"fg": "#ff8000", "bold": true}}
+    pub theme_styles: BTreeMap<String, StyleConfig>,
+    /// 删除仓库时移动到这个目录, 为空时移动到回收站
+    pub archive_dir: Option<String>,
+    /// 健康分数各项的权重
+    pub health_weights: HealthWeights,
+    /// fetch/pull/push 使用的私钥, 在 ssh-agent 和 ~/.ssh/config 之后尝试
+    pub ssh_key: Option<String>,
+    /// 私钥有密码时在界面中询问密码
+    pub prompt_passphrase: bool,
+    /// 在仓库列表中按 Enter 执行的操作
+    pub enter_action: EnterAction,
+    /// 打开仓库使用的编辑器, 为空时使用 $VISUAL 或者 $EDITOR
+    pub editor: Option<String>,
+    /// 解决冲突时传给 git mergetool --tool 的工具名字, 比如 meld, 为空时使用 git 配置中的 merge.tool
+    pub merge_tool: Option<String>,
+    /// 在 tmux 或者 zellij 中打开仓库的命令, 按复用器的名字覆盖默认的命令, {name} 和 {path} 会被替换,
+    /// 比如 {"tmux": ["tmux", "split-window", "-h", "-c", "{path}"]}
+    pub multiplexer_commands: BTreeMap<String, Vec<String>>,
+    /// 时间显示为 24h 或者 12h
+    pub time_format: TimeFormat,
+    /// 仓库列表中最后提交时间显示为日期, 而不是多久之前
+    pub absolute_dates: bool,
+    /// 仓库列表中显示最后一次提交信息的第一行
+    pub show_commit_summary: bool,
+    /// 数字格式使用的 locale, 比如 de_DE, 为空时使用 $LC_ALL, $LC_NUMERIC 或者 $LANG
+    pub locale: Option<String>,
+    /// 网络检查使用的后端, cli 或者 libgit2, 运行时可以按 L 切换
+    pub git_backend: GitBackend,
+    /// 单独指定后端的仓库, 比如 {"~/work/repo": "libgit2"}
+    pub repo_backends: BTreeMap<String, GitBackend>,
+    /// 离线模式, 检查状态时不进行网络检查, 运行时可以按 F 切换
+    pub offline: bool,
+    /// 界面语言, zh 或者 en
+    pub language: Language,
+    /// 减少动画, 不使用闪烁等效果, 改为显示静态的标记
+    pub reduced_motion: bool,
+    /// 启动时打开待处理列表, 不需要时按 Esc 回到仓库列表
+    pub open_inbox: bool,
+    /// 超过多少天没有成功 fetch 时在状态后面显示警告, 可以用 +stale 过滤, 0 时不显示
+    pub stale_fetch_days: u64,
+    /// 超过多少个月没有提交的本地分支算作过时
+    pub stale_branch_months: u64,
+    /// 在详情中显示 GitHub/GitLab 上默认分支的 CI 状态和打开的 PR 数量, 需要访问平台的 API
+    pub forge_status: bool,
+    /// 访问平台 API 使用的 token, 按主机名, 比如 {"github.com": "ghp_..."}, 没有时匿名访问
+    pub forge_tokens: BTreeMap<String, String>,
+    /// 备份远程仓库地址的模板, {name} 为仓库名字, {owner} 为 origin 的所有者,
+    /// 比如 git@backup-host:{name}.git
+    pub backup_remote: Option<String>,
+    /// 在其它仓库目录中的仓库 (vendored 依赖, ~/.emacs.d 中的包): flat, skip 或者 tree
+    pub nested_repos: NestedRepos,
+    /// 拉取, 推送, 同步时同时操作的仓库数量
+    pub op_jobs: usize,
+    /// 刷新时同一主机 (比如 github.com) 同时进行的 fetch 批次数量
+    pub fetch_host_concurrency: usize,
+    /// 每批 fetch 开始之前随机等待的最长时间, 单位毫秒, 0 时不等待
+    pub fetch_jitter_ms: u64,
+    /// 维护仓库时除了 gc 和 remote prune 之外再执行 git maintenance run
+    pub maintenance_run: bool,
+    /// 每隔多少天自动维护一次所有仓库, 0 时只在命令面板中手动执行
+    pub maintenance_interval_days: u64,
+    /// 按 h 在选中的仓库中执行的自定义命令
+    pub custom_commands: Vec<CustomCommand>,
+    /// 扫描时跳过的目录, 不含 / 的模式匹配目录名字, 其它匹配完整路径,
+    /// 比如 ["node_modules", "**/target/**", "/mnt/**", "~/vendor/*"]
+    pub scan_exclude: Vec<String>,
+    /// 仓库最多在搜索目录下几层, 0 时不限制
+    pub scan_max_depth: usize,
+    /// 扫描时进入指向目录的符号链接, 同一个目录只扫描一次
+    pub scan_follow_symlinks: bool,
+    /// 扫描 NFS, SSHFS, SMB 等网络文件系统的挂载点, 默认跳过, 搜索目录本身在其中时仍然扫描
+    pub scan_network_mounts: bool,
+}
+
+/// 自定义命令, 在仓库目录中用 bash 执行
+///
+/// 命令中的 {path}, {name}, {branch} 会被替换成加上引号的仓库路径, 名字和当前分支,
+/// 比如 {"name": "deploy", "command": "make deploy BRANCH={branch}"}
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CustomCommand {
+    pub name: String,
+    pub command: String,
+}
+
+impl CustomCommand {
+    /// 替换命令中的变量, 得到可以交给 shell 执行的命令
+    pub fn expand(&self, name: &str, path: &Path, branch: &str) -> String {
+        self.command
+            .replace("{path}", &shell_quote(path))
+            .replace("{name}", &shell_quote(Path::new(name)))
+            .replace("{branch}", &shell_quote(Path::new(branch)))
+    }
+}
+
+/// 嵌套在其它仓库中的仓库的显示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NestedRepos {
+    /// 和其它仓库一样显示
+    #[default]
+    Flat,
+    /// 扫描时跳过
+    Skip,
+    /// 缩进显示在外层仓库的下面
+    Tree,
+}
+
+/// 一个命名的搜索目录, 在界面中显示为一个标签页
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SearchRoot {
+    /// 标签页的名字, 同时用于缓存文件的名字
+    pub name: String,
+    pub search_paths: Vec<String>,
+}
+
+/// 按 Enter 执行的操作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterAction {
+    /// 打开详情
+    #[default]
+    Detail,
+    /// 复制路径
+    CopyPath,
+    /// 退出并输出路径, 配合 shell 函数切换目录
+    CdOnExit,
+    /// 用编辑器打开仓库
+    Editor,
+    /// 在 tmux 或者 zellij 的新窗口中打开仓库
+    Multiplexer,
+    /// 拉取之后推送
+    QuickSync,
+}
+
+impl EnterAction {
+    pub fn action(&self) -> AppAction {
+        match self {
+            EnterAction::Detail => AppAction::OpenDetail,
+            EnterAction::CopyPath => AppAction::SelectCopyPath,
+            EnterAction::CdOnExit => AppAction::SelectCdExit,
+            EnterAction::Editor => AppAction::SelectOpenEditor,
+            EnterAction::Multiplexer => AppAction::SelectOpenMultiplexer,
+            EnterAction::QuickSync => AppAction::SelectSync,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            search_paths: vec![String::from("~/")],
+            roots: Vec::new(),
+            allow_outside_home: false,
+            auto_refresh: false,
+            auto_refresh_interval: 300,
+            auto_refresh_active_only: false,
+            active_days: 30,
+            dormant_fetch_interval: 24 * 3600,
+            paged: false,
+            theme: String::from("default"),
+            theme_styles: BTreeMap::new(),
+            archive_dir: None,
+            health_weights: HealthWeights::default(),
+            ssh_key: None,
+            prompt_passphrase: true,
+            enter_action: EnterAction::default(),
+            editor: None,
+            merge_tool: None,
+            multiplexer_commands: BTreeMap::new(),
+            time_format: TimeFormat::default(),
+            absolute_dates: false,
+            show_commit_summary: false,
+            locale: None,
+            git_backend: GitBackend::default(),
+            offline: false,
+            repo_backends: BTreeMap::new(),
+            language: Language::default(),
+            reduced_motion: false,
+            open_inbox: false,
+            stale_fetch_days: 7,
+            stale_branch_months: 6,
+            forge_status: false,
+            forge_tokens: BTreeMap::new(),
+            backup_remote: None,
+            nested_repos: NestedRepos::default(),
+            op_jobs: DEFAULT_OP_JOBS,
+            fetch_host_concurrency: DEFAULT_HOST_CONCURRENCY,
+            fetch_jitter_ms: DEFAULT_FETCH_JITTER_MS,
+            custom_commands: Vec::new(),
+            maintenance_run: false,
+            maintenance_interval_days: 0,
+            scan_exclude: DEFAULT_SCAN_EXCLUDE.map(String::from).to_vec(),
+            scan_max_depth: 0,
+            scan_follow_symlinks: false,
+            scan_network_mounts: false,
+        }
+    }
+}
+
+impl Config {
+    /// 根据配置生成显示格式
+    pub fn formatter(&self) -> Formatter {
+        let locale = self.locale.clone().or_else(env_locale).unwrap_or_default();
+        Formatter {
+            time_format: self.time_format,
+            decimal_comma: locale_decimal_comma(&locale),
+            utc_offset: local_utc_offset(),
+        }
+    }
+
+    /// 编辑器命令, 依次使用配置, $VISUAL, $EDITOR, 最后使用 vi
+    pub fn editor_command(&self) -> String {
+        self.editor
+            .clone()
+            .or_else(|| std::env::var("VISUAL").ok())
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|editor| !editor.trim().is_empty())
+            .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }))
+    }
+
+    /// 所有标签页, 没有设置 roots 时只有一个没有名字的标签页, 使用 search_paths
+    pub fn search_roots(&self) -> Vec<SearchRoot> {
+        if self.roots.is_empty() {
+            vec![SearchRoot {
+                name: String::new(),
+                search_paths: self.search_paths.clone(),
+            }]
+        } else {
+            self.roots.clone()
+        }
+    }
+
+    /// 展开后的搜索目录, 分为允许直接扫描的和需要确认的 (在 HOME 之外)
+    pub fn split_search_paths(
+        &self,
+        search_paths: &[String],
+    ) -> BDEResult<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let home = home_dir()?;
+        let cwd = std::env::current_dir()?;
+        Ok(self.split_search_paths_with(search_paths, &home, &cwd))
+    }
+
+    fn split_search_paths_with(
+        &self,
+        search_paths: &[String],
+        home: &Path,
+        cwd: &Path,
+    ) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut allow_paths = Vec::new();
+        let mut outside_paths = Vec::new();
+        // 存在的路径解析符号链接后再判断, 避免 HOME 中指向外部的链接绕过确认
+        let resolve = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let home = resolve(home);
+
+        for search_path in search_paths {
+            let path = expand_path_with(search_path, &home, cwd, |name| std::env::var(name).ok());
+            if self.allow_outside_home || resolve(&path).starts_with(&home) {
+                allow_paths.push(path);
+            } else {
+                outside_paths.push(path);
+            }
+        }
+
+        (allow_paths, outside_paths)
+    }
+
+    pub fn search_options(&self) -> BDEResult<SearchOptions> {
+        let home = home_dir()?;
+        Ok(SearchOptions {
+            exclude: self
+                .scan_exclude
+                .iter()
+                .map(|pattern| ExcludePattern::new(pattern, &home))
+                .collect(),
+            max_depth: self.scan_max_depth,
+            follow_symlinks: self.scan_follow_symlinks,
+            skip_mounts: if self.scan_network_mounts {
+                Vec::new()
+            } else {
+                network_mounts()
+            },
+        })
+    }
+
+    pub fn active_policy(&self) -> Option<ActivePolicy> {
+        self.auto_refresh_active_only.then_some(ActivePolicy {
+            active_days: self.active_days,
+            dormant_fetch_interval: self.dormant_fetch_interval,
+        })
+    }
+}
+
+fn get_config_path() -> BDEResult<PathBuf> {
+    Ok(home_dir()?
+        .join(".config")
+        .join("git_manager")
+        .join("config.json"))
+}
+
+/// 读取配置文件, 文件不存在时使用默认配置
+pub fn load_config() -> BDEResult<Config> {
+    let config_path = get_config_path()?;
+
+    if !config_path.exists() {
+        Ok(Config::default())
+    } else {
+        let config_json = fs::read_to_string(config_path)?;
+        Ok(serde_json::from_str(&config_json)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, CustomCommand, EnterAction};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_split_search_paths() {
+        let home = Path::new("/home/test");
+        let cwd = Path::new("/");
+        let mut config = Config {
+            search_paths: vec![
+                String::from("~/"),
+                String::from("/srv"),
+                String::from("~/../../srv"),
+            ],
+            ..Config::default()
+        };
+
+        let (allow_paths, outside_paths) =
+            config.split_search_paths_with(&config.search_paths, home, cwd);
+        assert_eq!(allow_paths, vec![PathBuf::from("/home/test")]);
+        assert_eq!(
+            outside_paths,
+            vec![PathBuf::from("/srv"), PathBuf::from("/srv")]
+        );
+
+        config.allow_outside_home = true;
+        let (allow_paths, outside_paths) =
+            config.split_search_paths_with(&config.search_paths, home, cwd);
+        assert_eq!(allow_paths.len(), 3);
+        assert!(outside_paths.is_empty());
+    }
+
+    #[test]
+    fn test_enter_action() {
+        let config: Config = serde_json::from_str(r#"{"enter_action": "cd_on_exit"}"#).unwrap();
+        assert_eq!(config.enter_action, EnterAction::CdOnExit);
+        assert_eq!(Config::default().enter_action, EnterAction::Detail);
+    }
+
+    #[test]
+    fn test_custom_command_expand() {
+        let command = CustomCommand {
+            name: String::from("deploy"),
+            command: String::from("make deploy NAME={name} BRANCH={branch} -C {path}"),
+        };
+        assert_eq!(
+            command.expand("it's", Path::new("/srv/my repo"), "main"),
+            "make deploy NAME='it'\\''s' BRANCH='main' -C '/srv/my repo'"
+        );
+    }
+}
@@ -0,0 +1,513 @@
+use git2::{Cred, CredentialType};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::RwLock;
+
+use crate::i18n::tr;
+use crate::paths::home_dir;
+use crate::utils::{shell_quote, BDEResult};
+
+/// fetch/pull/push 使用的凭据设置
+#[derive(Debug, Default)]
+struct CredentialState {
+    /// 配置文件中指定的私钥
+    ssh_key: Option<PathBuf>,
+    /// 在界面中输入的私钥密码
+    passphrase: Option<String>,
+    /// 把密码交给 ssh 的 askpass 脚本
+    askpass: Option<PathBuf>,
+}
+
+static CREDENTIALS: RwLock<CredentialState> = RwLock::new(CredentialState {
+    ssh_key: None,
+    passphrase: None,
+    askpass: None,
+});
+
+const PASSPHRASE_ENV: &str = "GTM_SSH_PASSPHRASE";
+
+/// 设置配置文件中指定的私钥, 在 ssh-agent 和 ~/.ssh/config 之后使用
+pub fn set_ssh_key(ssh_key: Option<PathBuf>) {
+    if let Ok(mut state) = CREDENTIALS.write() {
+        state.ssh_key = ssh_key;
+    }
+}
+
+/// 写入 askpass 脚本, 脚本从环境变量中读取密码, 不在文件中保存密码
+#[cfg(unix)]
+fn write_askpass(dir: &Path) -> BDEResult<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let askpass = dir.join("askpass.sh");
+    fs::write(
+        &askpass,
+        format!("#!/bin/sh\nprintf '%s\\n' \"${}\"\n", PASSPHRASE_ENV),
+    )?;
+    fs::set_permissions(&askpass, fs::Permissions::from_mode(0o700))?;
+    Ok(askpass)
+}
+
+/// Windows 的 OpenSSH 同样支持 SSH_ASKPASS, 延迟展开时密码中的 & | 等字符不会被 cmd 解析
+#[cfg(not(unix))]
+fn write_askpass(dir: &Path) -> BDEResult<PathBuf> {
+    let askpass = dir.join("askpass.cmd");
+    fs::write(
+        &askpass,
+        format!(
+            "@echo off\r\nsetlocal EnableDelayedExpansion\r\necho(!{}!\r\n",
+            PASSPHRASE_ENV
+        ),
+    )?;
+    Ok(askpass)
+}
+
+/// 保存私钥密码, askpass 脚本放在 dir 中, libgit2 后端直接使用密码
+pub fn set_passphrase(passphrase: &str, dir: &Path) -> BDEResult<()> {
+    let askpass = write_askpass(dir)?;
+
+    if let Ok(mut state) = CREDENTIALS.write() {
+        state.passphrase = Some(String::from(passphrase));
+        state.askpass = Some(askpass);
+    }
+    Ok(())
+}
+
+fn ssh_agent_has_identities() -> bool {
+    std::env::var_os("SSH_AUTH_SOCK").is_some()
+        && process::Command::new("ssh-add")
+            .arg("-l")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+fn ssh_key_encrypted(key: &Path) -> bool {
+    process::Command::new("ssh-keygen")
+        .args(["-y", "-P", "", "-f"])
+        .arg(key)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| !status.success())
+}
+
+/// 配置的私钥有密码, ssh-agent 中没有可用的密钥, 并且还没有输入过密码时需要询问密码
+pub fn need_passphrase() -> bool {
+    let Ok(state) = CREDENTIALS.read() else {
+        return false;
+    };
+    match &state.ssh_key {
+        Some(ssh_key) if state.passphrase.is_none() => {
+            ssh_key.exists() && !ssh_agent_has_identities() && ssh_key_encrypted(ssh_key)
+        }
+        _ => false,
+    }
+}
+
+/// 执行 git 命令时需要设置的环境变量
+///
+/// ssh 本身会使用 ssh-agent 和 ~/.ssh/config 中的密钥, 这里只在配置了私钥或者输入了密码时
+/// 设置 GIT_SSH_COMMAND, 追加配置的私钥, 没有输入密码时使用 BatchMode, 避免 ssh 直接读取终端破坏界面.
+/// 都没有时不覆盖用户的 core.sshCommand 和 GIT_SSH.
+/// 同样禁止 git 询问 HTTPS 的用户名和密码, 需要认证时直接失败
+pub fn git_env() -> Vec<(String, String)> {
+    let Ok(state) = CREDENTIALS.read() else {
+        return Vec::new();
+    };
+    credential_env(&state, std::env::var("GIT_SSH_COMMAND").ok())
+}
+
+fn credential_env(state: &CredentialState, ssh_command: Option<String>) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if state.ssh_key.is_none() && state.passphrase.is_none() {
+        env.push((String::from("GIT_TERMINAL_PROMPT"), String::from("0")));
+        return env;
+    }
+
+    let mut ssh_command = ssh_command.unwrap_or_else(|| String::from("ssh"));
+    if let Some(ssh_key) = &state.ssh_key {
+        ssh_command.push_str(&format!(" -i {}", shell_quote(ssh_key)));
+    }
+
+    match (&state.passphrase, &state.askpass) {
+        (Some(passphrase), Some(askpass)) => {
+            env.push((String::from("SSH_ASKPASS"), askpass.display().to_string()));
+            env.push((String::from("SSH_ASKPASS_REQUIRE"), String::from("force")));
+            env.push((String::from(PASSPHRASE_ENV), passphrase.clone()));
+        }
+        _ => ssh_command.push_str(" -o BatchMode=yes"),
+    }
+    env.push((String::from("GIT_SSH_COMMAND"), ssh_command));
+    env.push((String::from("GIT_TERMINAL_PROMPT"), String::from("0")));
+
+    env
+}
+
+/// 从远程仓库地址中取出主机名, 支持 ssh://user@host:port/path 和 user@host:path
+pub fn url_host(url: &str) -> Option<String> {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None if url.contains(':') => url,
+        None => return None,
+    };
+    let authority = rest.split(['/', ':']).next()?;
+    let host = authority.rsplit('@').next()?;
+    (!host.is_empty()).then(|| String::from(host))
+}
+
+/// 从远程仓库地址中取出所有者 (用户或者组织), GitLab 的子组返回 group/subgroup
+pub fn url_owner(url: &str) -> Option<String> {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => url.split_once(':')?.1,
+    };
+    let (owner, _) = path.trim_matches('/').rsplit_once('/')?;
+    (!owner.is_empty()).then(|| String::from(owner))
+}
+
+/// 远程仓库地址统一为小写的 host/owner/repo, 不同协议的同一个仓库结果相同, 本地路径保持原样
+pub fn url_slug(url: &str) -> String {
+    let Some(host) = url_host(url) else {
+        return url.to_lowercase();
+    };
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map(|(_, path)| path),
+        None => url.split_once(':').map(|(_, path)| path),
+    }
+    .unwrap_or_default();
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("{}/{}", host, path).to_lowercase()
+}
+
+fn pattern_match(pattern: &[char], host: &[char]) -> bool {
+    match (pattern.first(), host.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            pattern_match(&pattern[1..], host)
+                || (!host.is_empty() && pattern_match(pattern, &host[1..]))
+        }
+        (Some('?'), Some(_)) => pattern_match(&pattern[1..], &host[1..]),
+        (Some(p), Some(h)) => p.eq_ignore_ascii_case(h) && pattern_match(&pattern[1..], &host[1..]),
+        _ => false,
+    }
+}
+
+/// Host 一行的模式是否匹配主机名, 以 ! 开头的模式匹配时整行不匹配
+fn host_patterns_match(patterns: &[&str], host: &str) -> bool {
+    let host: Vec<char> = host.chars().collect();
+    let mut matchp = false;
+    for pattern in patterns {
+        if let Some(pattern) = pattern.strip_prefix('!') {
+            if pattern_match(&pattern.chars().collect::<Vec<char>>(), &host) {
+                return false;
+            }
+        } else if pattern_match(&pattern.chars().collect::<Vec<char>>(), &host) {
+            matchp = true;
+        }
+    }
+    matchp
+}
+
+/// ~/.ssh/config 中给主机设置的 IdentityFile, 按出现的顺序
+pub fn ssh_config_identity_files(config: &str, host: &str, home: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut matchp = true;
+
+    for line in config.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((key, value)) => (key, value.trim_start_matches(['=', ' ', '\t']).trim()),
+            None => continue,
+        };
+
+        if key.eq_ignore_ascii_case("Host") {
+            let patterns: Vec<&str> = value.split_whitespace().collect();
+            matchp = host_patterns_match(&patterns, host);
+        } else if key.eq_ignore_ascii_case("Match") {
+            // 不支持 Match, 跳过整个块
+            matchp = false;
+        } else if matchp && key.eq_ignore_ascii_case("IdentityFile") {
+            let value = value.trim_matches('"');
+            let path = match value.strip_prefix("~/") {
+                Some(stripped) => home.join(stripped),
+                None => PathBuf::from(value),
+            };
+            files.push(path);
+        }
+    }
+
+    files
+}
+
+/// git credential 的输入, 只给出 url 和用户名, 其它属性由 git 从 url 中解析
+fn credential_input(url: &str, username: Option<&str>, password: Option<&str>) -> String {
+    let mut input = format!("url={}\n", url);
+    if let Some(username) = username {
+        input.push_str(&format!("username={}\n", username));
+    }
+    if let Some(password) = password {
+        input.push_str(&format!("password={}\n", password));
+    }
+    input.push('\n');
+    input
+}
+
+/// 从 git credential fill 的输出中取出用户名和密码
+fn parse_credential(output: &str) -> Option<(String, String)> {
+    let mut username = None;
+    let mut password = None;
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some(("username", value)) => username = Some(String::from(value)),
+            Some(("password", value)) => password = Some(String::from(value)),
+            _ => {}
+        }
+    }
+    Some((username?, password?))
+}
+
+/// 执行 git credential, 禁止终端提示, 没有保存的凭据时直接失败而不是读取终端
+fn git_credential(action: &str, input: &str) -> Option<String> {
+    let mut child = process::Command::new("git")
+        .args(["credential", action])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(input.as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 用 git credential fill 取得 HTTPS 远程仓库的用户名和密码,
+/// 和 git 命令一样使用配置的 credential helper (store, cache, credential manager 等)
+pub fn credential_fill(url: &str, username: Option<&str>) -> Option<(String, String)> {
+    parse_credential(&git_credential(
+        "fill",
+        &credential_input(url, username, None),
+    )?)
+}
+
+/// 凭据被远程仓库拒绝, 通知 credential helper 删除保存的凭据
+pub fn credential_reject(url: &str, username: &str, password: &str) {
+    git_credential(
+        "reject",
+        &credential_input(url, Some(username), Some(password)),
+    );
+}
+
+/// 凭据回调无法提供凭据时的错误, fetch 时据此把仓库标记为需要认证
+fn auth_error(message: &str) -> git2::Error {
+    git2::Error::new(git2::ErrorCode::Auth, git2::ErrorClass::Callback, message)
+}
+
+/// 给 libgit2 使用的凭据回调
+///
+/// libgit2 在认证失败时会再次调用回调, 每次依次尝试 ssh-agent, ~/.ssh/config
+/// 中主机对应的私钥, 配置的私钥, 全部失败后返回错误.
+/// HTTPS 使用 git credential fill 取得的用户名和密码, 被拒绝之后通知 helper 删除.
+/// 不支持的凭据类型和 credential helper 没有密码时返回 ErrorCode::Auth
+pub fn git2_credentials_callback(
+) -> impl FnMut(&str, Option<&str>, CredentialType) -> Result<Cred, git2::Error> {
+    let mut attempt = 0;
+    let mut filled: Option<(String, String)> = None;
+
+    move |url, username, allowed| {
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            // 再次询问说明上次的凭据被拒绝
+            if let Some((username, password)) = filled.take() {
+                credential_reject(url, &username, &password);
+                return Err(auth_error(tr(
+                    "远程仓库拒绝了 credential helper 中的凭据",
+                    "the remote rejected the credentials from the credential helper",
+                )));
+            }
+            let (username, password) = credential_fill(url, username).ok_or_else(|| {
+                auth_error(tr(
+                    "credential helper 中没有密码",
+                    "no password from the credential helper",
+                ))
+            })?;
+            let cred = Cred::userpass_plaintext(&username, &password);
+            filled = Some((username, password));
+            return cred;
+        }
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+        if !allowed.contains(CredentialType::SSH_KEY) {
+            return Err(auth_error(tr(
+                "远程仓库需要不支持的认证方式",
+                "the remote requires an unsupported authentication method",
+            )));
+        }
+        let username = username.unwrap_or("git");
+
+        if attempt == 0 {
+            attempt += 1;
+            if std::env::var_os("SSH_AUTH_SOCK").is_some() {
+                return Cred::ssh_key_from_agent(username);
+            }
+        }
+
+        let home = home_dir().unwrap_or_default();
+        let mut keys = match url_host(url) {
+            Some(host) => fs::read_to_string(home.join(".ssh").join("config"))
+                .map(|config| ssh_config_identity_files(&config, &host, &home))
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let state = CREDENTIALS
+            .read()
+            .map_err(|_| git2::Error::from_str(tr("凭据被锁定", "credentials are locked")))?;
+        keys.extend(state.ssh_key.clone());
+
+        let key = keys
+            .into_iter()
+            .filter(|key| key.exists())
+            .nth(attempt - 1)
+            .ok_or_else(|| auth_error(tr("没有可用的 SSH 凭据", "no usable SSH credentials")))?;
+        attempt += 1;
+
+        Cred::ssh_key(username, None, &key, state.passphrase.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        credential_env, credential_input, parse_credential, ssh_config_identity_files, url_host,
+        url_owner, url_slug, CredentialState,
+    };
+
+    #[test]
+    fn test_credential_env() {
+        // 没有配置私钥和密码时不覆盖用户的 ssh 命令
+        let env = credential_env(&CredentialState::default(), None);
+        assert_eq!(
+            env,
+            vec![(String::from("GIT_TERMINAL_PROMPT"), String::from("0"))]
+        );
+
+        let state = CredentialState {
+            ssh_key: Some(PathBuf::from("/home/a/it's key")),
+            ..CredentialState::default()
+        };
+        let env = credential_env(&state, Some(String::from("ssh -v")));
+        assert!(env.contains(&(
+            String::from("GIT_SSH_COMMAND"),
+            String::from("ssh -v -i '/home/a/it'\\''s key' -o BatchMode=yes")
+        )));
+    }
+
+    #[test]
+    fn test_credential() {
+        assert_eq!(
+            credential_input("https://example.com/repo.git", Some("me"), None),
+            "url=https://example.com/repo.git\nusername=me\n\n"
+        );
+        assert_eq!(
+            parse_credential("protocol=https\nhost=example.com\nusername=me\npassword=a=b\n"),
+            Some((String::from("me"), String::from("a=b")))
+        );
+        assert_eq!(parse_credential("protocol=https\nhost=example.com\n"), None);
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(
+            url_host("git@github.com:owner/repo.git").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            url_host("ssh://git@example.com:2222/owner/repo.git").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            url_host("https://github.com/owner/repo").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(url_host("/tmp/repo"), None);
+    }
+
+    #[test]
+    fn test_url_owner() {
+        assert_eq!(
+            url_owner("git@github.com:lizqwerscott/git_manager.git").as_deref(),
+            Some("lizqwerscott")
+        );
+        assert_eq!(
+            url_owner("ssh://git@example.com:2222/owner/repo.git").as_deref(),
+            Some("owner")
+        );
+        assert_eq!(
+            url_owner("https://gitlab.com/group/subgroup/repo").as_deref(),
+            Some("group/subgroup")
+        );
+        assert_eq!(url_owner("https://example.com/repo.git"), None);
+        assert_eq!(url_owner("/tmp/repo"), None);
+    }
+
+    #[test]
+    fn test_url_slug() {
+        let slug = "github.com/lizqwerscott/git_manager";
+        assert_eq!(
+            url_slug("git@github.com:lizqwerscott/git_manager.git"),
+            slug
+        );
+        assert_eq!(
+            url_slug("https://github.com/lizqwerscott/git_manager"),
+            slug
+        );
+        assert_eq!(
+            url_slug("ssh://git@GitHub.com:22/lizqwerscott/git_manager.git/"),
+            slug
+        );
+        assert_eq!(url_slug("/tmp/Repo.git"), "/tmp/repo.git");
+    }
+
+    #[test]
+    fn test_ssh_config_identity_files() {
+        let config = "\
+IdentityFile ~/.ssh/global
+Host github.com gh
+    IdentityFile ~/.ssh/github
+Host *.example.com !internal.example.com
+    IdentityFile=/keys/example
+Host *
+    IdentityFile ~/.ssh/default
+";
+        let home = Path::new("/home/test");
+        assert_eq!(
+            ssh_config_identity_files(config, "github.com", home),
+            vec![
+                PathBuf::from("/home/test/.ssh/global"),
+                PathBuf::from("/home/test/.ssh/github"),
+                PathBuf::from("/home/test/.ssh/default"),
+            ]
+        );
+        assert_eq!(
+            ssh_config_identity_files(config, "git.example.com", home)[1],
+            PathBuf::from("/keys/example")
+        );
+        assert_eq!(
+            ssh_config_identity_files(config, "internal.example.com", home).len(),
+            2
+        );
+    }
+}
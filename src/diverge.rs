@@ -0,0 +1,277 @@
+use ratatui::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::{tr, trf};
+use crate::utils::{git_output, now_timestamp, shell_quote, BDEResult};
+
+/// 预览中最多显示的提交数量
+const PREVIEW_COMMIT_LEN: usize = 5;
+
+/// 当前分支和上游分支分叉的信息
+#[derive(Debug, Clone)]
+pub struct DivergeInfo {
+    pub path: PathBuf,
+    pub branch: String,
+    pub upstream: String,
+    /// 只在本地的提交, 最新的在前
+    pub local_commits: Vec<String>,
+    /// 只在上游的提交, 最新的在前
+    pub upstream_commits: Vec<String>,
+    pub base: String,
+    pub worktree_cleanp: bool,
+    /// 另存本地提交时使用的新分支名字
+    pub backup_branch: String,
+}
+
+fn git_lines(path: &Path, args: &[&str]) -> BDEResult<Vec<String>> {
+    let res = git_output(path, args)?;
+    Ok(res
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect())
+}
+
+impl DivergeInfo {
+    /// 当前分支同时领先和落后上游分支时返回分叉信息
+    pub fn load(path: &Path) -> BDEResult<Option<DivergeInfo>> {
+        let branch = git_lines(path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let upstream = git_lines(
+            path,
+            &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+        )?;
+        let (Some(branch), Some(upstream)) = (branch.first(), upstream.first()) else {
+            return Ok(None);
+        };
+
+        let local_commits = git_lines(path, &["log", "--oneline", "@{u}..HEAD"])?;
+        let upstream_commits = git_lines(path, &["log", "--oneline", "HEAD..@{u}"])?;
+        if local_commits.is_empty() || upstream_commits.is_empty() {
+            return Ok(None);
+        }
+
+        let base = match git_lines(path, &["merge-base", "HEAD", "@{u}"])?.first() {
+            Some(base) => git_lines(path, &["log", "--oneline", "-1", base])?
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+        let worktree_cleanp =
+            git_lines(path, &["status", "--porcelain", "--untracked-files=no"])?.is_empty();
+
+        Ok(Some(DivergeInfo {
+            path: PathBuf::from(path),
+            branch: branch.clone(),
+            upstream: upstream.clone(),
+            local_commits,
+            upstream_commits,
+            base,
+            worktree_cleanp,
+            backup_branch: format!("{}-local-{}", branch, now_timestamp()),
+        }))
+    }
+}
+
+/// 解决分叉的方案
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvePlan {
+    Rebase,
+    Merge,
+    Reset,
+    CreateBranch,
+}
+
+fn commit_lines(
+    prefix: &str,
+    commits: &[String],
+    suffix: &str,
+    style: Style,
+) -> Vec<Line<'static>> {
+    let mut lines: Vec<Line<'static>> = commits
+        .iter()
+        .take(PREVIEW_COMMIT_LEN)
+        .map(|commit| {
+            Line::from(Span::styled(
+                format!("{}{}{}", prefix, commit, suffix),
+                style,
+            ))
+        })
+        .collect();
+
+    if commits.len() > PREVIEW_COMMIT_LEN {
+        lines.push(Line::from(Span::styled(
+            trf!(
+                "{}... 还有 {} 个提交",
+                "{}... {} more commits",
+                prefix,
+                commits.len() - PREVIEW_COMMIT_LEN
+            ),
+            style,
+        )));
+    }
+
+    lines
+}
+
+impl ResolvePlan {
+    pub const ALL: [ResolvePlan; 4] = [
+        ResolvePlan::Rebase,
+        ResolvePlan::Merge,
+        ResolvePlan::CreateBranch,
+        ResolvePlan::Reset,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ResolvePlan::Rebase => tr("变基到上游 (rebase)", "Rebase onto upstream"),
+            ResolvePlan::Merge => tr("合并上游 (merge)", "Merge upstream"),
+            ResolvePlan::CreateBranch => {
+                tr("本地提交另存为新分支", "Move local commits to a new branch")
+            }
+            ResolvePlan::Reset => tr("丢弃本地提交 (reset)", "Drop local commits (reset)"),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ResolvePlan::Rebase => tr(
+                "把本地提交重新放到上游最新提交之后, 历史保持一条直线, 本地提交的哈希会改变.",
+                "Replay local commits on top of upstream. History stays linear, local commit hashes change.",
+            ),
+            ResolvePlan::Merge => tr(
+                "创建一个合并提交把上游的修改合并进来, 保留完整的历史.",
+                "Create a merge commit that brings in upstream changes, keeping the full history.",
+            ),
+            ResolvePlan::CreateBranch => tr(
+                "把本地提交保存到一个新分支, 当前分支重置为上游, 之后可以再处理新分支.",
+                "Save local commits to a new branch and reset the current branch to upstream.",
+            ),
+            ResolvePlan::Reset => tr(
+                "当前分支直接重置为上游, 本地提交会丢失 (仍然可以通过 reflog 找回).",
+                "Reset the current branch to upstream. Local commits are lost (still in the reflog).",
+            ),
+        }
+    }
+
+    /// 执行方案之后的历史预览
+    pub fn preview(&self, info: &DivergeInfo) -> Vec<Line<'static>> {
+        let local_style = Style::default().fg(Color::Green);
+        let upstream_style = Style::default().fg(Color::Cyan);
+        let drop_style = Style::default()
+            .fg(Color::Red)
+            .add_modifier(Modifier::CROSSED_OUT);
+
+        let mut lines = Vec::new();
+        match self {
+            ResolvePlan::Rebase => {
+                lines.extend(commit_lines(
+                    "* ",
+                    &info.local_commits,
+                    tr(" (新的提交)", " (new commit)"),
+                    local_style,
+                ));
+                lines.extend(commit_lines(
+                    "* ",
+                    &info.upstream_commits,
+                    "",
+                    upstream_style,
+                ));
+            }
+            ResolvePlan::Merge => {
+                lines.push(Line::from(format!(
+                    "*   Merge {} into {}",
+                    info.upstream, info.branch
+                )));
+                lines.push(Line::from("|\\"));
+                lines.extend(commit_lines(
+                    "| * ",
+                    &info.upstream_commits,
+                    "",
+                    upstream_style,
+                ));
+                lines.extend(commit_lines("* | ", &info.local_commits, "", local_style));
+                lines.push(Line::from("|/"));
+            }
+            ResolvePlan::CreateBranch => {
+                lines.extend(commit_lines(
+                    "* ",
+                    &info.upstream_commits,
+                    "",
+                    upstream_style,
+                ));
+                lines.extend(commit_lines(
+                    "| * ",
+                    &info.local_commits,
+                    &format!(" ({})", info.backup_branch),
+                    local_style,
+                ));
+                lines.push(Line::from("|/"));
+            }
+            ResolvePlan::Reset => {
+                lines.extend(commit_lines(
+                    "* ",
+                    &info.upstream_commits,
+                    "",
+                    upstream_style,
+                ));
+                lines.extend(commit_lines(
+                    "x ",
+                    &info.local_commits,
+                    tr(" (丢弃)", " (dropped)"),
+                    drop_style,
+                ));
+            }
+        }
+        lines.push(Line::from(format!("* {}", info.base)));
+
+        lines
+    }
+
+    /// 执行方案的命令, 出现冲突时会放弃操作并恢复到执行之前的状态
+    pub fn command(&self, info: &DivergeInfo) -> String {
+        let plan_command = match self {
+            ResolvePlan::Rebase => trf!(
+                "git -c color.ui=always rebase @{{u}} || {{ git rebase --abort; echo '出现冲突, 已放弃 rebase'; exit 1; }}",
+                "git -c color.ui=always rebase @{{u}} || {{ git rebase --abort; echo 'conflict, rebase aborted'; exit 1; }}",
+            ),
+            ResolvePlan::Merge => trf!(
+                "git -c color.ui=always merge --no-edit @{{u}} || {{ git merge --abort; echo '出现冲突, 已放弃 merge'; exit 1; }}",
+                "git -c color.ui=always merge --no-edit @{{u}} || {{ git merge --abort; echo 'conflict, merge aborted'; exit 1; }}",
+            ),
+            // 新分支的名字来自当前分支, 可能有 shell 的特殊字符
+            ResolvePlan::CreateBranch => format!(
+                "git branch {} && git reset --hard @{{u}}",
+                shell_quote(Path::new(&info.backup_branch))
+            ),
+            ResolvePlan::Reset => String::from("git reset --hard @{u}"),
+        };
+
+        format!("cd {} && {}", shell_quote(&info.path), plan_command)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{DivergeInfo, ResolvePlan};
+
+    #[test]
+    fn test_create_branch_command() {
+        let info = DivergeInfo {
+            path: PathBuf::from("/r"),
+            branch: String::from("x';touch pwn;'"),
+            upstream: String::from("origin/x"),
+            local_commits: Vec::new(),
+            upstream_commits: Vec::new(),
+            base: String::new(),
+            worktree_cleanp: true,
+            backup_branch: String::from("x';touch pwn;'-local-1"),
+        };
+        assert_eq!(
+            ResolvePlan::CreateBranch.command(&info),
+            r#"cd '/r' && git branch 'x'\'';touch pwn;'\''-local-1' && git reset --hard @{u}"#
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::i18n::tr;
+
+/// 所有操作返回的错误, 库的使用者可以按种类处理
+#[derive(Debug, Error)]
+pub enum GitManagerError {
+    /// 读写文件或者启动命令失败
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// libgit2 返回的错误
+    #[error("{0}")]
+    Git(#[from] git2::Error),
+    /// 命令 (比如 git fetch) 超过了超时时间
+    #[error("Command timed out")]
+    CommandTimeout,
+    /// 远程仓库需要输入密码或者确认, 不能在后台 fetch
+    #[error("Remote requires authentication")]
+    AuthRequired,
+    /// 命令执行失败, 包括退出码和输出
+    #[error("{0}")]
+    Command(String),
+    /// 解析输入失败, 比如脚本, 颜色或者命令输出
+    #[error("{0}")]
+    Parse(String),
+    /// 配置文件或者环境不正确
+    #[error("{0}")]
+    Config(String),
+    /// 读写缓存和配置的 JSON 失败
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    /// 其它错误
+    #[error("{0}")]
+    Other(String),
+}
+
+impl GitManagerError {
+    /// 错误的种类, 界面中显示在错误信息之前
+    pub fn category(&self) -> &'static str {
+        match self {
+            GitManagerError::Io(_) => "io",
+            GitManagerError::Git(_) => "git",
+            GitManagerError::CommandTimeout => "timeout",
+            GitManagerError::AuthRequired => "auth",
+            GitManagerError::Command(_) => "command",
+            GitManagerError::Parse(_) => "parse",
+            GitManagerError::Config(_) => "config",
+            GitManagerError::Json(_) => "json",
+            GitManagerError::Other(_) => "error",
+        }
+    }
+
+    /// 针对错误种类的处理建议
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            GitManagerError::Io(_) => Some(tr(
+                "检查路径是否存在以及是否有权限",
+                "Check that the path exists and is accessible",
+            )),
+            GitManagerError::CommandTimeout => Some(tr(
+                "检查网络连接和远程仓库是否可以访问",
+                "Check the network and whether the remote is reachable",
+            )),
+            GitManagerError::AuthRequired => Some(tr(
+                "配置 ssh-agent, ssh_key 或者 git credential helper 之后按 r 重新检查",
+                "Set up ssh-agent, ssh_key or a git credential helper, then press r to recheck",
+            )),
+            GitManagerError::Config(_) | GitManagerError::Json(_) => Some(tr(
+                "检查配置文件 ~/.config/git_manager/config.json",
+                "Check the config file ~/.config/git_manager/config.json",
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for GitManagerError {
+    fn from(err: String) -> Self {
+        GitManagerError::Other(err)
+    }
+}
+
+impl From<&str> for GitManagerError {
+    fn from(err: &str) -> Self {
+        GitManagerError::Other(String::from(err))
+    }
+}
+
+impl From<std::num::ParseIntError> for GitManagerError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        GitManagerError::Parse(err.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for GitManagerError {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        GitManagerError::Parse(err.to_string())
+    }
+}
+
+impl<T> From<tokio::sync::mpsc::error::SendError<T>> for GitManagerError {
+    fn from(err: tokio::sync::mpsc::error::SendError<T>) -> Self {
+        GitManagerError::Other(err.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for GitManagerError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        GitManagerError::Other(err.to_string())
+    }
+}
@@ -1,4 +1,5 @@
-use git2::{Cred, CredentialType, Error, Repository};
+use git2::Repository;
+use git_manager::credentials::git2_credentials_callback;
 use std::collections::HashMap;
 
     pub fn get_remote_branch_remote_name(
@@ -116,12 +117,11 @@ use std::collections::HashMap;
     }
 
     pub async fn test_remote_connect(path: &Path, remote_name: &str) -> bool {
-        let command = format!("cd {} && git remote show {}", path.display(), remote_name);
-
-        match run_command_timeout_no(&command, 5).await {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        Cmd::git(path)
+            .args(["remote", "show", remote_name])
+            .status_timeout(5)
+            .await
+            .unwrap_or(false)
     }
 
     pub fn get_head_remote_status(repo: &Repository) -> BDEResult<Option<(bool, bool)>> {
@@ -131,7 +131,7 @@ use std::collections::HashMap;
 
             // 获取远程分支最新动态
             let mut callbacks = git2::RemoteCallbacks::new();
-            callbacks.credentials(git_credentials_callback);
+            callbacks.credentials(git2_credentials_callback());
 
             let mut opts = git2::FetchOptions::new();
             opts.remote_callbacks(callbacks);
@@ -227,26 +227,6 @@ use std::collections::HashMap;
         Ok(new_status)
     }
 
-fn git_credentials_callback(
-    _url: &str,
-    username: Option<&str>,
-    _cred_type: CredentialType,
-) -> Result<Cred, Error> {
-    let public = PathBuf::from("/home/lizqwer/.ssh/id_rsa.pub");
-    let private = PathBuf::from("/home/lizqwer/.ssh/id_rsa");
-
-    // let password = env::var("GIT_PASSWORD").ok();
-
-    let cred = Cred::ssh_key(
-        username.unwrap(),
-        Some(public.as_path()),
-        private.as_path(),
-        None,
-    );
-
-    Ok(cred?)
-}
-
 #[cfg(test)]
 mod test {
     use std::path::PathBuf;
@@ -295,7 +275,7 @@ mod test {
         let remotes = repo.remotes()?;
 
         let mut callbacks = git2::RemoteCallbacks::new();
-        callbacks.credentials(git_credentials_callback);
+        callbacks.credentials(git2_credentials_callback());
 
         let mut opts = git2::FetchOptions::new();
         opts.remote_callbacks(callbacks);
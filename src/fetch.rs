@@ -0,0 +1,345 @@
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::credentials::git_env;
+use crate::gitrepo::get_save_data_dir;
+use crate::metrics::WORKER_METRICS;
+use crate::utils::{shell_quote, Cmd};
+
+/// 单个仓库 fetch 的超时时间 (秒)
+pub const FETCH_TIMEOUT: u64 = 5;
+/// 同一主机的仓库每批最多这么多个, 超过时分成多批并行执行
+pub const FETCH_BATCH_SIZE: usize = 16;
+/// 同一主机同时执行的 fetch 批次数量的默认值
+pub const DEFAULT_HOST_CONCURRENCY: usize = 2;
+/// 每批 fetch 开始之前随机等待的最长时间的默认值 (毫秒)
+pub const DEFAULT_FETCH_JITTER_MS: u64 = 300;
+
+/// 离线模式, 检查状态时不进行任何网络检查, 可以在运行时切换
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn toggle_offline() -> bool {
+    let offline = !offline();
+    set_offline(offline);
+    offline
+}
+
+static HOST_CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_HOST_CONCURRENCY);
+static FETCH_JITTER_MS: AtomicU64 = AtomicU64::new(DEFAULT_FETCH_JITTER_MS);
+/// 每个主机的 fetch 名额, 避免同时建立大量 ssh 连接被服务器限流
+static HOST_SLOTS: Mutex<BTreeMap<String, Arc<Semaphore>>> = Mutex::new(BTreeMap::new());
+
+/// 设置每个主机同时 fetch 的批次数量和开始之前随机等待的最长时间
+pub fn set_fetch_limits(host_concurrency: usize, jitter_ms: u64) {
+    HOST_CONCURRENCY.store(host_concurrency.max(1), Ordering::Relaxed);
+    FETCH_JITTER_MS.store(jitter_ms, Ordering::Relaxed);
+    // 已经创建的名额使用旧的数量, 清空之后按新的数量重新创建
+    if let Ok(mut slots) = HOST_SLOTS.lock() {
+        slots.clear();
+    }
+}
+
+/// 0 到 max 之间的随机时间, 每次调用使用新的随机 hash 种子
+fn jitter(max: u64) -> Duration {
+    if max == 0 {
+        return Duration::ZERO;
+    }
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(Instant::now().elapsed().as_nanos());
+    Duration::from_millis(hasher.finish() % (max + 1))
+}
+
+/// 等待主机的 fetch 名额, 返回的名额 drop 之前同一主机的其它 fetch 需要等待
+///
+/// 拿到名额之后再随机等待一段时间, 避免同时发起连接. 没有主机 (本地路径) 时不需要等待
+pub async fn host_permit(host: &str) -> Option<OwnedSemaphorePermit> {
+    if host.is_empty() {
+        return None;
+    }
+    let semaphore = HOST_SLOTS
+        .lock()
+        .ok()?
+        .entry(String::from(host))
+        .or_insert_with(|| Arc::new(Semaphore::new(HOST_CONCURRENCY.load(Ordering::Relaxed))))
+        .clone();
+    let permit = semaphore.acquire_owned().await.ok();
+    tokio::time::sleep(jitter(FETCH_JITTER_MS.load(Ordering::Relaxed))).await;
+    permit
+}
+
+/// 一个仓库 fetch 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchResult {
+    Done,
+    /// git fetch 失败时的退出码, 和之前一样不影响状态检查
+    Failed(i32),
+    TimedOut,
+    /// 远程仓库需要输入密码或者确认
+    AuthRequired,
+}
+
+/// git fetch 失败时的错误输出是否表示远程仓库需要交互认证
+///
+/// fetch 时禁止了终端提示 (GIT_TERMINAL_PROMPT=0, ssh BatchMode), 需要输入时 git 直接失败
+pub fn auth_required(stderr: &str) -> bool {
+    const PATTERNS: [&str; 7] = [
+        "terminal prompts disabled",
+        "could not read username",
+        "could not read password",
+        "authentication failed",
+        "invalid username or password",
+        "permission denied (publickey",
+        "host key verification failed",
+    ];
+    let stderr = stderr.to_lowercase();
+    PATTERNS.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// 检查状态时如何进行网络检查
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FetchMode {
+    /// 不进行网络检查
+    Skip,
+    /// 检查状态时单独执行 git fetch
+    Run,
+    /// 已经在批量 fetch 中完成
+    Fetched(FetchResult),
+}
+
+/// 把仓库按远程仓库的主机分组, 每组最多 FETCH_BATCH_SIZE 个, 返回 (主机, 仓库)
+pub fn fetch_batches(
+    repos: impl IntoIterator<Item = (String, PathBuf)>,
+) -> Vec<(String, Vec<PathBuf>)> {
+    let mut hosts: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for (host, path) in repos {
+        hosts.entry(host).or_default().push(path);
+    }
+
+    hosts
+        .into_iter()
+        .flat_map(|(host, paths)| {
+            paths
+                .chunks(FETCH_BATCH_SIZE)
+                .map(|chunk| (host.clone(), chunk.to_vec()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// 批量 fetch 使用的环境变量, ssh 开启连接复用, 同一主机只需要握手一次
+fn batch_env() -> Vec<(String, String)> {
+    let control_path = get_save_data_dir().ok().map(|dir| dir.join("ssh-%C"));
+    git_env()
+        .into_iter()
+        .map(|(key, value)| match &control_path {
+            Some(control_path) if key == "GIT_SSH_COMMAND" => {
+                let value = format!(
+                    "{} -o ControlMaster=auto -o ControlPath={} -o ControlPersist=60",
+                    value,
+                    shell_quote(control_path)
+                );
+                (key, value)
+            }
+            _ => (key, value),
+        })
+        .collect()
+}
+
+/// 在一个进程中依次 fetch 同一主机的仓库, 返回每个仓库各自的结果
+pub async fn batch_fetch(paths: &[PathBuf]) -> HashMap<PathBuf, FetchResult> {
+    let _network = WORKER_METRICS.network();
+    let start = Instant::now();
+
+    let mut results = if cfg!(windows) {
+        direct_fetch(paths).await
+    } else {
+        script_fetch(paths).await
+    };
+    WORKER_METRICS.add_fetch(start.elapsed());
+    tracing::debug!(repos = paths.len(), elapsed = ?start.elapsed(), "batch fetch finished");
+
+    // 没有输出结果的仓库 (进程启动失败或者整体超时) 当作超时
+    for path in paths {
+        results.entry(path.clone()).or_insert_with(|| {
+            tracing::warn!("fetch {}: no result, treated as timed out", path.display());
+            FetchResult::TimedOut
+        });
+    }
+    results
+}
+
+/// 等待主机的 fetch 名额之后再批量 fetch
+pub async fn scheduled_fetch(host: &str, paths: &[PathBuf]) -> HashMap<PathBuf, FetchResult> {
+    let _permit = host_permit(host).await;
+    batch_fetch(paths).await
+}
+
+/// 依次直接执行 git fetch, Windows 中没有 bash 和 timeout 命令
+async fn direct_fetch(paths: &[PathBuf]) -> HashMap<PathBuf, FetchResult> {
+    let mut results = HashMap::new();
+    for path in paths {
+        let result = match Cmd::git(path)
+            .args(["fetch", "--quiet"])
+            .status_timeout(FETCH_TIMEOUT)
+            .await
+        {
+            Ok(true) => FetchResult::Done,
+            Ok(false) => FetchResult::Failed(1),
+            Err(_) => FetchResult::TimedOut,
+        };
+        tracing::debug!("fetch {}: {:?}", path.display(), result);
+        results.insert(path.clone(), result);
+    }
+    results
+}
+
+/// 用一个 bash 脚本 fetch 所有仓库, ssh 连接可以复用
+async fn script_fetch(paths: &[PathBuf]) -> HashMap<PathBuf, FetchResult> {
+    let mut script = String::new();
+    for (index, path) in paths.iter().enumerate() {
+        // 输出 "序号 退出码 错误输出", 错误输出合并成一行, 用来判断是否需要认证
+        script.push_str(&format!(
+            "err=$(cd {} && timeout {} git fetch --quiet 2>&1 >/dev/null); \
+             echo \"{} $? $(printf '%s' \"$err\" | tr '\\n' ' ')\"\n",
+            shell_quote(path),
+            FETCH_TIMEOUT,
+            index
+        ));
+    }
+
+    let mut results = HashMap::new();
+
+    let child = tokio::process::Command::new("bash")
+        .arg("-c")
+        .arg(script)
+        .envs(batch_env())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+
+    // 读取结果期间保持 child, 它被 drop 时进程会被结束
+    if let Ok((_child, Some(stdout))) = child.map(|mut child| {
+        let stdout = child.stdout.take();
+        (child, stdout)
+    }) {
+        let mut lines = BufReader::new(stdout).lines();
+        let total_timeout = Duration::from_secs(FETCH_TIMEOUT * paths.len() as u64 + 5);
+        let _ = tokio::time::timeout(total_timeout, async {
+            while let Ok(Some(line)) = lines.next_line().await {
+                let mut parts = line.splitn(3, ' ');
+                let (Some(index), Some(code)) = (parts.next(), parts.next()) else {
+                    continue;
+                };
+                let (Ok(index), Ok(code)) = (index.parse::<usize>(), code.parse::<i32>()) else {
+                    continue;
+                };
+                let result = match code {
+                    0 => FetchResult::Done,
+                    // timeout 命令超时的退出码
+                    124 => FetchResult::TimedOut,
+                    _ if auth_required(parts.next().unwrap_or_default()) => {
+                        FetchResult::AuthRequired
+                    }
+                    code => FetchResult::Failed(code),
+                };
+                if let Some(path) = paths.get(index) {
+                    tracing::debug!("fetch {}: {:?}", path.display(), result);
+                    results.insert(path.clone(), result);
+                }
+            }
+        })
+        .await;
+    }
+    results
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{auth_required, fetch_batches, host_permit, set_fetch_limits, FETCH_BATCH_SIZE};
+
+    #[test]
+    fn test_auth_required() {
+        assert!(auth_required(
+            "fatal: could not read Username for 'https://github.com': terminal prompts disabled"
+        ));
+        assert!(auth_required(
+            "git@example.com: Permission denied (publickey). fatal: Could not read from remote repository."
+        ));
+        assert!(!auth_required(
+            "fatal: unable to access 'https://example.com/': Could not resolve host: example.com"
+        ));
+        assert!(!auth_required(""));
+    }
+
+    #[test]
+    fn test_fetch_batches() {
+        let mut repos = vec![
+            (String::from("github.com"), PathBuf::from("/a")),
+            (String::from("gitlab.com"), PathBuf::from("/b")),
+            (String::from("github.com"), PathBuf::from("/c")),
+        ];
+        for index in 0..FETCH_BATCH_SIZE {
+            repos.push((
+                String::from("example.com"),
+                PathBuf::from(format!("/e{}", index)),
+            ));
+        }
+        repos.push((String::from("example.com"), PathBuf::from("/last")));
+
+        let batches = fetch_batches(repos);
+        assert_eq!(batches.len(), 4);
+        assert_eq!(batches[0].0, "example.com");
+        assert_eq!(batches[0].1.len(), FETCH_BATCH_SIZE);
+        assert_eq!(batches[1].1, vec![PathBuf::from("/last")]);
+        assert_eq!(
+            batches[2],
+            (
+                String::from("github.com"),
+                vec![PathBuf::from("/a"), PathBuf::from("/c")]
+            )
+        );
+        assert_eq!(batches[3].1, vec![PathBuf::from("/b")]);
+    }
+
+    #[tokio::test]
+    async fn test_host_permit() {
+        set_fetch_limits(1, 0);
+        let wait = std::time::Duration::from_millis(50);
+
+        let permit = host_permit("github.com").await;
+        assert!(permit.is_some());
+        // 同一主机需要等待, 其它主机和本地路径不受影响
+        assert!(tokio::time::timeout(wait, host_permit("github.com"))
+            .await
+            .is_err());
+        assert!(tokio::time::timeout(wait, host_permit("gitlab.com"))
+            .await
+            .is_ok());
+        assert!(host_permit("").await.is_none());
+
+        drop(permit);
+        assert!(tokio::time::timeout(wait, host_permit("github.com"))
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,458 @@
+//! 解析远程仓库地址, 得到代码托管平台 (GitHub, GitLab, Gitea) 上的网页地址,
+//! 以及通过平台的 API 查询 CI 状态和打开的 PR 数量
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use crate::error::GitManagerError;
+use crate::i18n::{tr, trf};
+use crate::utils::{BDEResult, Cmd};
+
+/// CI 状态和 PR 数量缓存的时间, 超过之后打开详情时重新查询
+pub const FORGE_STATUS_TTL: u64 = 10 * 60;
+
+/// 一页最多返回的 PR 数量, 达到之后显示为 100+
+const PER_PAGE: usize = 100;
+
+/// 代码托管平台, 根据主机名判断, 自建的实例需要主机名中包含平台的名字
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Gitea,
+    Unknown,
+}
+
+impl fmt::Display for Forge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Forge::GitHub => write!(f, "GitHub"),
+            Forge::GitLab => write!(f, "GitLab"),
+            Forge::Gitea => write!(f, "Gitea"),
+            Forge::Unknown => write!(f, "-"),
+        }
+    }
+}
+
+/// 远程仓库地址中的主机和仓库路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUrl {
+    /// 网页使用的主机名, https 地址中的端口也在这里
+    pub host: String,
+    /// owner/repo, GitLab 的子组为 group/subgroup/repo, 不包括 .git
+    pub path: String,
+    /// 地址使用 http 而不是 https
+    insecure: bool,
+}
+
+impl RemoteUrl {
+    /// 支持 https://host/path, ssh://user@host:port/path, git://host/path 和 user@host:path,
+    /// 本地路径返回 None
+    pub fn parse(url: &str) -> Option<RemoteUrl> {
+        let url = url.trim();
+        let (scheme, host, path) = match url.split_once("://") {
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                let host = authority.rsplit('@').next()?;
+                let host = match scheme {
+                    "http" | "https" => host,
+                    // ssh 的端口不是网页的端口
+                    _ => host.split(':').next()?,
+                };
+                (scheme, host, path)
+            }
+            None => {
+                let (authority, path) = url.split_once(':')?;
+                // C:\repo 这样的 Windows 路径
+                if authority.len() < 2 || authority.contains('/') || path.starts_with('\\') {
+                    return None;
+                }
+                ("ssh", authority.rsplit('@').next()?, path)
+            }
+        };
+        if scheme == "file" || host.is_empty() {
+            return None;
+        }
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if !path.contains('/') {
+            return None;
+        }
+
+        // GitHub 的 443 端口 ssh
+        let host = match host {
+            "ssh.github.com" => "github.com",
+            host => host,
+        };
+
+        Some(RemoteUrl {
+            host: host.to_lowercase(),
+            path: String::from(path),
+            insecure: scheme == "http",
+        })
+    }
+
+    pub fn forge(&self) -> Forge {
+        let host = self.host.split(':').next().unwrap_or_default();
+        if host.contains("github") {
+            Forge::GitHub
+        } else if host.contains("gitlab") {
+            Forge::GitLab
+        } else if host.contains("gitea") || host == "codeberg.org" {
+            Forge::Gitea
+        } else {
+            Forge::Unknown
+        }
+    }
+
+    /// 仓库首页的地址
+    pub fn web_url(&self) -> String {
+        let scheme = if self.insecure { "http" } else { "https" };
+        format!("{}://{}/{}", scheme, self.host, self.path)
+    }
+}
+
+/// 默认分支最新提交的 CI 状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CiState {
+    Success,
+    Failure,
+    Pending,
+    /// 没有配置 CI
+    None,
+}
+
+impl fmt::Display for CiState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CiState::Success => write!(f, "{}", tr("✔ 通过", "✔ passing")),
+            CiState::Failure => write!(f, "{}", tr("✖ 失败", "✖ failing")),
+            CiState::Pending => write!(f, "{}", tr("● 进行中", "● pending")),
+            CiState::None => write!(f, "{}", tr("没有 CI", "no CI")),
+        }
+    }
+}
+
+/// 从平台 API 查询到的仓库状态
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ForgeStatus {
+    /// 查询的分支
+    pub branch: String,
+    pub ci: CiState,
+    /// 打开的 PR (GitLab 中为 MR) 数量, 最多为 PER_PAGE
+    pub open_prs: usize,
+    /// 查询的时间
+    pub time: u64,
+}
+
+impl ForgeStatus {
+    pub fn stalep(&self, now: u64) -> bool {
+        now.saturating_sub(self.time) > FORGE_STATUS_TTL
+    }
+
+    /// PR 数量, 达到一页的上限时加上 +
+    pub fn open_prs_text(&self) -> String {
+        if self.open_prs >= PER_PAGE {
+            format!("{}+", self.open_prs)
+        } else {
+            self.open_prs.to_string()
+        }
+    }
+}
+
+/// 查询仓库在 branch 上的 CI 状态和打开的 PR 数量, 使用 curl, 需要在后台线程中执行
+///
+/// 只支持 GitHub 和 GitLab, token 为空时使用匿名访问, 只能访问公开仓库并且有频率限制
+pub fn forge_status(
+    remote: &RemoteUrl,
+    branch: &str,
+    token: Option<&str>,
+    now: u64,
+) -> BDEResult<ForgeStatus> {
+    let scheme = if remote.insecure { "http" } else { "https" };
+    let (ci, open_prs) = match remote.forge() {
+        Forge::GitHub => {
+            let base = if remote.host == "github.com" {
+                String::from("https://api.github.com")
+            } else {
+                format!("{}://{}/api/v3", scheme, remote.host)
+            };
+            let header = token.map(|token| format!("Authorization: Bearer {}", token));
+            let get = |url: String| api_get(&url, header.as_deref());
+            let commit = format!("{}/repos/{}/commits/{}", base, remote.path, encode(branch));
+
+            let ci = match parse_check_runs(&get(format!("{}/check-runs?per_page=100", commit))?) {
+                // 没有使用 GitHub Actions 时查询旧的 status API
+                CiState::None => parse_combined_status(&get(format!("{}/status", commit))?),
+                ci => ci,
+            };
+            let pulls = get(format!(
+                "{}/repos/{}/pulls?state=open&per_page={}",
+                base, remote.path, PER_PAGE
+            ))?;
+            (ci, array_len(&pulls))
+        }
+        Forge::GitLab => {
+            let base = format!(
+                "{}://{}/api/v4/projects/{}",
+                scheme,
+                remote.host,
+                encode(&remote.path)
+            );
+            let header = token.map(|token| format!("PRIVATE-TOKEN: {}", token));
+            let get = |url: String| api_get(&url, header.as_deref());
+
+            let ci = parse_pipelines(&get(format!(
+                "{}/pipelines?ref={}&per_page=1",
+                base,
+                encode(branch)
+            ))?);
+            let merge_requests = get(format!(
+                "{}/merge_requests?state=opened&per_page={}",
+                base, PER_PAGE
+            ))?;
+            (ci, array_len(&merge_requests))
+        }
+        forge => {
+            return Err(GitManagerError::Other(trf!(
+                "不支持查询 {} ({}) 的 CI 状态",
+                "CI status is not supported for {} ({})",
+                remote.host,
+                forge
+            )))
+        }
+    };
+
+    Ok(ForgeStatus {
+        branch: String::from(branch),
+        ci,
+        open_prs,
+        time: now,
+    })
+}
+
+/// 用 curl 请求 API, header 通过标准输入传给 curl, 不会出现在进程列表中
+fn api_get(url: &str, header: Option<&str>) -> BDEResult<Value> {
+    let mut cmd = Cmd::new("curl").args([
+        "--silent",
+        "--location",
+        "--max-time",
+        "15",
+        "--header",
+        "Accept: application/json",
+        "--header",
+        "User-Agent: git_manager",
+        "--write-out",
+        "\n%{http_code}",
+    ]);
+    if let Some(header) = header {
+        cmd = cmd.args(["--header", "@-"]).input(header);
+    }
+    parse_response(url, &cmd.arg(url).output()?)
+}
+
+/// curl 的输出最后一行是状态码, 之前是响应的内容
+fn parse_response(url: &str, output: &str) -> BDEResult<Value> {
+    let (body, code) = output.trim_end().rsplit_once('\n').unwrap_or(("", output));
+    match code.trim() {
+        "200" => Ok(serde_json::from_str(body)?),
+        "000" | "" => Err(GitManagerError::Command(trf!(
+            "无法连接 {}",
+            "Cannot connect to {}",
+            url
+        ))),
+        code => {
+            let message = serde_json::from_str::<Value>(body)
+                .ok()
+                .and_then(|body| body["message"].as_str().map(String::from))
+                .unwrap_or_default();
+            Err(GitManagerError::Command(format!(
+                "HTTP {} {}: {}",
+                code, message, url
+            )))
+        }
+    }
+}
+
+/// 百分号编码 URL 中的一段, 分支名和 GitLab 的项目路径中可能有 /
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                String::from(byte as char)
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn array_len(value: &Value) -> usize {
+    value.as_array().map(Vec::len).unwrap_or(0)
+}
+
+/// GitHub Actions 的 check run, 有一个失败就是失败, 有一个没有完成就是进行中
+fn parse_check_runs(value: &Value) -> CiState {
+    let Some(runs) = value["check_runs"]
+        .as_array()
+        .filter(|runs| !runs.is_empty())
+    else {
+        return CiState::None;
+    };
+
+    let failedp = runs.iter().any(|run| {
+        matches!(
+            run["conclusion"].as_str(),
+            Some("failure" | "timed_out" | "cancelled" | "action_required")
+        )
+    });
+    if failedp {
+        CiState::Failure
+    } else if runs.iter().any(|run| run["status"] != "completed") {
+        CiState::Pending
+    } else {
+        CiState::Success
+    }
+}
+
+/// GitHub 旧的 commit status, 没有任何 status 时 state 也是 pending
+fn parse_combined_status(value: &Value) -> CiState {
+    if value["total_count"].as_u64().unwrap_or(0) == 0 {
+        return CiState::None;
+    }
+    match value["state"].as_str() {
+        Some("success") => CiState::Success,
+        Some("failure" | "error") => CiState::Failure,
+        _ => CiState::Pending,
+    }
+}
+
+/// GitLab 分支上最新的流水线
+fn parse_pipelines(value: &Value) -> CiState {
+    match value[0]["status"].as_str() {
+        None => CiState::None,
+        Some("success") => CiState::Success,
+        Some("failed" | "canceled") => CiState::Failure,
+        Some(_) => CiState::Pending,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{
+        encode, parse_check_runs, parse_combined_status, parse_pipelines, parse_response, CiState,
+        Forge, RemoteUrl,
+    };
+
+    #[test]
+    fn test_web_url() {
+        let web_url = |url: &str| RemoteUrl::parse(url).map(|remote| remote.web_url());
+        let expected = Some(String::from("https://github.com/lizqwerscott/git_manager"));
+
+        assert_eq!(
+            web_url("git@github.com:lizqwerscott/git_manager.git"),
+            expected
+        );
+        assert_eq!(
+            web_url("https://github.com/lizqwerscott/git_manager"),
+            expected
+        );
+        assert_eq!(
+            web_url("ssh://git@ssh.github.com:443/lizqwerscott/git_manager.git"),
+            expected
+        );
+        assert_eq!(
+            web_url("https://user@GitHub.com/lizqwerscott/git_manager.git/"),
+            expected
+        );
+        assert_eq!(
+            web_url("ssh://git@gitlab.example.com:2222/group/sub/repo.git").as_deref(),
+            Some("https://gitlab.example.com/group/sub/repo")
+        );
+        assert_eq!(
+            web_url("http://gitea.lan:3000/owner/repo.git").as_deref(),
+            Some("http://gitea.lan:3000/owner/repo")
+        );
+        assert_eq!(web_url("/tmp/remote.git"), None);
+        assert_eq!(web_url("file:///tmp/owner/remote.git"), None);
+        assert_eq!(web_url("C:\\repos\\remote.git"), None);
+        assert_eq!(web_url("https://github.com/repo"), None);
+    }
+
+    #[test]
+    fn test_forge() {
+        let forge = |url: &str| RemoteUrl::parse(url).map(|remote| remote.forge());
+
+        assert_eq!(forge("git@github.com:owner/repo.git"), Some(Forge::GitHub));
+        assert_eq!(
+            forge("https://gitlab.com/group/sub/repo"),
+            Some(Forge::GitLab)
+        );
+        assert_eq!(forge("https://codeberg.org/owner/repo"), Some(Forge::Gitea));
+        assert_eq!(
+            forge("git@git.example.com:owner/repo"),
+            Some(Forge::Unknown)
+        );
+    }
+
+    #[test]
+    fn test_parse_ci() {
+        let runs = |runs: serde_json::Value| parse_check_runs(&json!({ "check_runs": runs }));
+        assert_eq!(runs(json!([])), CiState::None);
+        assert_eq!(
+            runs(json!([
+                {"status": "completed", "conclusion": "success"},
+                {"status": "completed", "conclusion": "skipped"}
+            ])),
+            CiState::Success
+        );
+        assert_eq!(
+            runs(json!([
+                {"status": "in_progress", "conclusion": null},
+                {"status": "completed", "conclusion": "failure"}
+            ])),
+            CiState::Failure
+        );
+        assert_eq!(
+            runs(json!([{"status": "queued", "conclusion": null}])),
+            CiState::Pending
+        );
+
+        assert_eq!(
+            parse_combined_status(&json!({"state": "pending", "total_count": 0})),
+            CiState::None
+        );
+        assert_eq!(
+            parse_combined_status(&json!({"state": "error", "total_count": 2})),
+            CiState::Failure
+        );
+
+        assert_eq!(parse_pipelines(&json!([])), CiState::None);
+        assert_eq!(
+            parse_pipelines(&json!([{"status": "running"}])),
+            CiState::Pending
+        );
+        assert_eq!(
+            parse_pipelines(&json!([{"status": "failed"}])),
+            CiState::Failure
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let url = "https://api.github.com/repos/owner/repo/pulls";
+        assert_eq!(parse_response(url, "[1, 2]\n200").unwrap(), json!([1, 2]));
+        assert_eq!(
+            parse_response(url, "{\"message\": \"Bad credentials\"}\n401")
+                .unwrap_err()
+                .to_string(),
+            format!("HTTP 401 Bad credentials: {}", url)
+        );
+        assert!(parse_response(url, "\n000").is_err());
+        assert_eq!(encode("feature/a b"), "feature%2Fa%20b");
+    }
+}
@@ -0,0 +1,262 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::i18n::{tr, trf};
+use crate::utils::{timestamp_to_datetime, Cmd};
+
+/// 时间显示为 24 小时制还是 12 小时制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimeFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+/// 数字, 时间和大小的显示格式, 状态栏, 详情和报告都使用这里的格式
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter {
+    pub time_format: TimeFormat,
+    /// 小数点使用逗号, 比如 de_DE
+    pub decimal_comma: bool,
+    /// 本地时区和 UTC 的差, 单位秒
+    pub utc_offset: i64,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter {
+            time_format: TimeFormat::H24,
+            decimal_comma: false,
+            utc_offset: 0,
+        }
+    }
+}
+
+/// 这些语言的小数点使用逗号
+const DECIMAL_COMMA_LANGUAGES: [&str; 12] = [
+    "de", "fr", "es", "it", "pt", "ru", "nl", "pl", "sv", "da", "fi", "tr",
+];
+
+/// 根据 locale 名字 (比如 de_DE.UTF-8) 判断小数点是否使用逗号
+pub fn locale_decimal_comma(locale: &str) -> bool {
+    let language = locale
+        .split(['_', '.', '-', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    DECIMAL_COMMA_LANGUAGES.contains(&language.as_str())
+}
+
+/// 环境变量中数字格式使用的 locale
+pub fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// 本地时区和 UTC 的差, 单位秒, 获取失败时为 0
+pub fn local_utc_offset() -> i64 {
+    let offset = Cmd::new("date").arg("+%z").output().unwrap_or_default();
+    let offset = offset.trim();
+    let (sign, digits) = match offset.split_at_checked(1) {
+        Some(("-", digits)) => (-1, digits),
+        Some(("+", digits)) => (1, digits),
+        _ => return 0,
+    };
+    let hours: i64 = digits
+        .get(..2)
+        .and_then(|item| item.parse().ok())
+        .unwrap_or(0);
+    let minutes: i64 = digits
+        .get(2..4)
+        .and_then(|item| item.parse().ok())
+        .unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+/// 距离现在多久, 比如 "3 天前"
+pub fn format_relative_time(timestamp: u64, now: u64) -> String {
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..=59 => String::from(tr("刚刚", "just now")),
+        60..=3599 => trf!("{} 分钟前", "{} min ago", seconds / 60),
+        3600..=86399 => trf!("{} 小时前", "{} h ago", seconds / 3600),
+        86400..=2_591_999 => trf!("{} 天前", "{} days ago", seconds / 86400),
+        2_592_000..=31_535_999 => trf!("{} 个月前", "{} months ago", seconds / 2_592_000),
+        _ => trf!("{} 年前", "{} years ago", seconds / 31_536_000),
+    }
+}
+
+/// 距离现在多久的简短形式, 用于表格中, 比如 "3d ago"
+pub fn format_age(timestamp: u64, now: u64) -> String {
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..=59 => String::from(tr("刚刚", "now")),
+        60..=3599 => trf!("{}分钟前", "{}m ago", seconds / 60),
+        3600..=86399 => trf!("{}小时前", "{}h ago", seconds / 3600),
+        86400..=2_591_999 => trf!("{}天前", "{}d ago", seconds / 86400),
+        2_592_000..=31_535_999 => trf!("{}个月前", "{}mo ago", seconds / 2_592_000),
+        _ => trf!("{}年前", "{}y ago", seconds / 31_536_000),
+    }
+}
+
+/// 超过 width 时去掉中间的部分, 用 … 代替, 保留开头和结尾, 宽度按照终端的显示宽度计算
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return String::from(text);
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // 结尾通常是仓库的名字, 比开头多保留一些
+    let keep = width - 1;
+    let head_width = keep / 2;
+
+    let mut head = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > head_width {
+            break;
+        }
+        used += c_width;
+        head.push(c);
+    }
+
+    // 开头因为宽字符没有用完的宽度留给结尾
+    let tail_width = keep - used;
+    let mut tail = Vec::new();
+    let mut used = 0;
+    for c in text.chars().rev() {
+        let c_width = c.width().unwrap_or(0);
+        if used + c_width > tail_width {
+            break;
+        }
+        used += c_width;
+        tail.push(c);
+    }
+
+    head.push('…');
+    head.extend(tail.into_iter().rev());
+    head
+}
+
+impl Formatter {
+    fn number(&self, value: f64, precision: usize) -> String {
+        let text = format!("{:.*}", precision, value);
+        if self.decimal_comma {
+            text.replace('.', ",")
+        } else {
+            text
+        }
+    }
+
+    /// 耗时, 比如 "450 ms", "3.2 s", "2 min 5 s"
+    pub fn duration(&self, duration: Duration) -> String {
+        let seconds = duration.as_secs_f64();
+        if seconds < 1.0 {
+            format!("{} ms", duration.as_millis())
+        } else if seconds < 60.0 {
+            format!("{} s", self.number(seconds, 1))
+        } else {
+            let seconds = duration.as_secs();
+            format!("{} min {} s", seconds / 60, seconds % 60)
+        }
+    }
+
+    /// 文件大小, 使用 1024 进制, 比如 "1.4 GiB"
+    pub fn size(&self, bytes: u64) -> String {
+        const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+        let mut value = bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            format!("{} B", bytes)
+        } else {
+            format!("{} {}", self.number(value, 1), UNITS[unit])
+        }
+    }
+
+    /// 本地时间, 比如 "2024-03-01 14:05" 或者 "2024-03-01 02:05 PM"
+    pub fn timestamp(&self, timestamp: u64) -> String {
+        let local = (timestamp as i64 + self.utc_offset).max(0) as u64;
+        let (year, month, day, hour, minute, _) = timestamp_to_datetime(local);
+
+        match self.time_format {
+            TimeFormat::H24 => {
+                format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+            }
+            TimeFormat::H12 => {
+                let period = if hour < 12 { "AM" } else { "PM" };
+                let hour = match hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                format!(
+                    "{}-{:02}-{:02} {:02}:{:02} {}",
+                    year, month, day, hour, minute, period
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{
+        format_age, format_relative_time, locale_decimal_comma, truncate_middle, Formatter,
+        TimeFormat,
+    };
+
+    #[test]
+    fn test_formatter() {
+        let mut formatter = Formatter::default();
+        assert_eq!(formatter.duration(Duration::from_millis(450)), "450 ms");
+        assert_eq!(formatter.duration(Duration::from_millis(3240)), "3.2 s");
+        assert_eq!(formatter.duration(Duration::from_secs(125)), "2 min 5 s");
+        assert_eq!(formatter.size(512), "512 B");
+        assert_eq!(formatter.size(1_503_238_554), "1.4 GiB");
+        assert_eq!(formatter.timestamp(951_831_900), "2000-02-29 13:45");
+
+        formatter.time_format = TimeFormat::H12;
+        formatter.decimal_comma = true;
+        formatter.utc_offset = 3600;
+        assert_eq!(formatter.timestamp(951_831_900), "2000-02-29 02:45 PM");
+        assert_eq!(formatter.duration(Duration::from_millis(3240)), "3,2 s");
+    }
+
+    #[test]
+    fn test_locale_and_relative_time() {
+        assert!(locale_decimal_comma("de_DE.UTF-8"));
+        assert!(!locale_decimal_comma("en_US.UTF-8"));
+        assert!(!locale_decimal_comma("C"));
+        assert_eq!(format_relative_time(0, 30), "刚刚");
+        assert_eq!(format_relative_time(0, 3 * 86400), "3 天前");
+        assert_eq!(format_age(0, 3 * 86400), "3天前");
+        assert_eq!(format_age(0, 70 * 86400), "2个月前");
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(truncate_middle("~/code/repo", 20), "~/code/repo");
+        assert_eq!(
+            truncate_middle("~/code/rust/git_manager", 12),
+            "~/cod…anager"
+        );
+        assert_eq!(truncate_middle("~/项目/仓库", 7), "~/…仓库");
+        assert_eq!(truncate_middle("abc", 1), "…");
+        assert_eq!(truncate_middle("abc", 0), "");
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+/// 内置的 .gitignore 模板
+#[derive(Debug)]
+pub struct GitignoreTemplate {
+    pub name: &'static str,
+    /// 仓库根目录下存在其中任意一个文件时使用这个模板
+    pub markers: &'static [&'static str],
+    pub content: &'static str,
+}
+
+pub const GITIGNORE_TEMPLATES: [GitignoreTemplate; 7] = [
+    GitignoreTemplate {
+        name: "Rust",
+        markers: &["Cargo.toml"],
+        content: "/target/\n**/*.rs.bk\n*.pdb\n",
+    },
+    GitignoreTemplate {
+        name: "Node",
+        markers: &["package.json"],
+        content: "node_modules/\nnpm-debug.log*\nyarn-debug.log*\nyarn-error.log*\n.npm\ndist/\ncoverage/\n.env\n",
+    },
+    GitignoreTemplate {
+        name: "Python",
+        markers: &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"],
+        content: "__pycache__/\n*.py[cod]\n*.egg-info/\n.eggs/\nbuild/\ndist/\n.venv/\nvenv/\n.pytest_cache/\n.mypy_cache/\n",
+    },
+    GitignoreTemplate {
+        name: "Go",
+        markers: &["go.mod"],
+        content: "*.exe\n*.test\n*.out\nvendor/\n",
+    },
+    GitignoreTemplate {
+        name: "Java",
+        markers: &["pom.xml", "build.gradle", "build.gradle.kts"],
+        content: "*.class\n*.jar\n*.war\ntarget/\n.gradle/\nbuild/\nlocal.properties\n",
+    },
+    GitignoreTemplate {
+        name: "C/C++",
+        markers: &["CMakeLists.txt", "Makefile", "meson.build"],
+        content: "*.o\n*.obj\n*.so\n*.a\n*.dylib\nbuild/\ncompile_commands.json\n.cache/\n",
+    },
+    GitignoreTemplate {
+        name: "Emacs Lisp",
+        markers: &["Eask", "Cask"],
+        content: "*.elc\n.eask/\n.cask/\n",
+    },
+];
+
+/// 根据仓库根目录下的标志文件检测需要的模板
+pub fn detect_templates(path: &Path) -> Vec<&'static GitignoreTemplate> {
+    GITIGNORE_TEMPLATES
+        .iter()
+        .filter(|template| {
+            template
+                .markers
+                .iter()
+                .any(|marker| path.join(marker).exists())
+        })
+        .collect()
+}
+
+/// 计算需要追加到 .gitignore 的行, 已经存在的行不会重复添加
+pub fn merge_templates(existing: &str, templates: &[&GitignoreTemplate]) -> Vec<String> {
+    let mut exist_lines: Vec<&str> = existing.lines().map(|line| line.trim()).collect();
+    let mut new_lines = Vec::new();
+
+    for template in templates {
+        let mut template_lines = Vec::new();
+        for line in template.content.lines() {
+            if !exist_lines.contains(&line) {
+                exist_lines.push(line);
+                template_lines.push(String::from(line));
+            }
+        }
+
+        if !template_lines.is_empty() {
+            new_lines.push(format!("# {}", template.name));
+            new_lines.append(&mut template_lines);
+        }
+    }
+
+    new_lines
+}
+
+/// 读取仓库现有的 .gitignore 并计算需要追加的行
+pub fn gitignore_diff(path: &Path) -> (Vec<&'static GitignoreTemplate>, Vec<String>) {
+    let templates = detect_templates(path);
+    let existing = fs::read_to_string(path.join(".gitignore")).unwrap_or_default();
+    let new_lines = merge_templates(&existing, &templates);
+
+    (templates, new_lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{merge_templates, GITIGNORE_TEMPLATES};
+
+    #[test]
+    fn test_merge_templates() {
+        let rust = &GITIGNORE_TEMPLATES[0];
+        let new_lines = merge_templates("/target/\n", &[rust]);
+        assert_eq!(new_lines, vec!["# Rust", "**/*.rs.bk", "*.pdb"]);
+
+        let new_lines = merge_templates("/target/\n**/*.rs.bk\n*.pdb\n", &[rust]);
+        assert!(new_lines.is_empty());
+    }
+}
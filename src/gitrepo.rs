@@ -1,11 +1,26 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tokio::task::JoinSet;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
 
-use crate::utils::{run_command, run_command_timeout_no, BDEResult};
+use crate::backend::{backend_for, run_fetch, GitBackend};
+use crate::credentials::{url_host, url_owner, url_slug};
+use crate::error::GitManagerError;
+use crate::fetch::{offline, FetchMode, FetchResult};
+use crate::forge::ForgeStatus;
+use crate::i18n::{tr, trf};
+use crate::instance;
+use crate::metrics::WORKER_METRICS;
+use crate::paths::{home_dir, ExcludePattern};
+use crate::scan::{scan_stream, RepoEvent, ScanOptions};
+use crate::size::RepoSize;
+use crate::utils::{ba_error, git_output, now_timestamp, BDEResult};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum GitStatus {
@@ -13,22 +28,78 @@ pub enum GitStatus {
     NeedPull,
     NeedPush,
     NeedCommit,
+    /// 只有未跟踪的文件, 没有修改已跟踪的文件
+    Untracked,
     Timeout,
+    /// HEAD 不在任何分支上
+    DetachedHead,
+    /// rebase 没有完成
+    Rebasing,
+    /// merge, cherry-pick 或者 revert 没有完成
+    Merging,
+    /// 有没有解决的冲突
+    Conflicted,
+    /// 缓存中的路径已经不存在或者不再是 Git 仓库
+    Missing,
+    /// 远程仓库需要输入密码, 不再自动 fetch, 按 r 重新检查
+    AuthRequired,
 }
 
 impl fmt::Display for GitStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            GitStatus::Clean => write!(f, "干净"),
-            GitStatus::NeedPull => write!(f, "需要拉取"),
-            GitStatus::NeedPush => write!(f, "需要推送"),
-            GitStatus::NeedCommit => write!(f, "需要Commit"),
-            GitStatus::Timeout => write!(f, "超时"),
+            GitStatus::Clean => write!(f, "{}", tr("干净", "Clean")),
+            GitStatus::NeedPull => write!(f, "{}", tr("需要拉取", "Need pull")),
+            GitStatus::NeedPush => write!(f, "{}", tr("需要推送", "Need push")),
+            GitStatus::NeedCommit => write!(f, "{}", tr("需要Commit", "Need commit")),
+            GitStatus::Untracked => write!(f, "{}", tr("未跟踪文件", "Untracked")),
+            GitStatus::Timeout => write!(f, "{}", tr("超时", "Timeout")),
+            GitStatus::DetachedHead => write!(f, "{}", tr("分离HEAD", "Detached HEAD")),
+            GitStatus::Rebasing => write!(f, "{}", tr("正在变基", "Rebasing")),
+            GitStatus::Merging => write!(f, "{}", tr("正在合并", "Merging")),
+            GitStatus::Conflicted => write!(f, "{}", tr("有冲突", "Conflicted")),
+            GitStatus::Missing => write!(f, "{}", tr("已丢失", "Missing")),
+            GitStatus::AuthRequired => write!(f, "{}", tr("需要认证", "Auth required")),
             // GitStatus::Another => write!(f, "其它"),
         }
     }
 }
 
+impl GitStatus {
+    pub const ALL: [GitStatus; 12] = [
+        GitStatus::Clean,
+        GitStatus::NeedPull,
+        GitStatus::NeedPush,
+        GitStatus::NeedCommit,
+        GitStatus::Untracked,
+        GitStatus::Timeout,
+        GitStatus::DetachedHead,
+        GitStatus::Rebasing,
+        GitStatus::Merging,
+        GitStatus::Conflicted,
+        GitStatus::Missing,
+        GitStatus::AuthRequired,
+    ];
+
+    /// 状态对应的符号, 不依赖颜色也可以区分状态
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            GitStatus::Clean => "✔",
+            GitStatus::NeedPull => "↓",
+            GitStatus::NeedPush => "↑",
+            GitStatus::NeedCommit => "✎",
+            GitStatus::Untracked => "?",
+            GitStatus::Timeout => "⧗",
+            GitStatus::DetachedHead => "⌀",
+            GitStatus::Rebasing => "⟳",
+            GitStatus::Merging => "⑂",
+            GitStatus::Conflicted => "✖",
+            GitStatus::Missing => "∅",
+            GitStatus::AuthRequired => "⚿",
+        }
+    }
+}
+
 // Implement the FromStr trait for the enum
 impl FromStr for GitStatus {
     type Err = ();
@@ -39,8 +110,24 @@ impl FromStr for GitStatus {
             "NeedPull" => Ok(GitStatus::NeedPull),
             "NeedPush" => Ok(GitStatus::NeedPush),
             "NeedCommit" => Ok(GitStatus::NeedCommit),
+            "Untracked" => Ok(GitStatus::Untracked),
             "Timeout" => Ok(GitStatus::Timeout),
-            _ => Err(()),
+            "DetachedHead" => Ok(GitStatus::DetachedHead),
+            "Rebasing" => Ok(GitStatus::Rebasing),
+            "Merging" => Ok(GitStatus::Merging),
+            "Conflicted" => Ok(GitStatus::Conflicted),
+            "Missing" => Ok(GitStatus::Missing),
+            "AuthRequired" => Ok(GitStatus::AuthRequired),
+            // 也可以使用界面上显示的名字, 忽略空格和大小写
+            _ => GitStatus::ALL
+                .into_iter()
+                .find(|status| {
+                    status
+                        .to_string()
+                        .replace(' ', "")
+                        .eq_ignore_ascii_case(&s.replace(' ', ""))
+                })
+                .ok_or(()),
         }
     }
 }
@@ -51,17 +138,275 @@ pub struct GitRepo {
     pub path: PathBuf,
     pub status: GitStatus,
     pub last_commit_time: u64,
+    /// 最后一次提交信息的第一行
+    #[serde(default)]
+    pub last_commit_summary: String,
+    /// 最后一次提交的作者, 比如 "name <email>"
+    #[serde(default)]
+    pub last_commit_author: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 仅由这些未跟踪的大目录导致需要 Commit
+    #[serde(default)]
+    pub large_untracked: Vec<String>,
+    /// 上一次进行网络检查 (fetch) 的时间
+    #[serde(default)]
+    pub last_fetch_time: u64,
+    /// 上一次成功 fetch 的时间, 包括在 git_manager 之外的 fetch 和 pull, 从未 fetch 时为 0
+    #[serde(default)]
+    pub last_fetch_success: u64,
+    #[serde(default)]
+    pub has_remote: bool,
+    /// 当前分支设置了上游分支
+    #[serde(default)]
+    pub has_upstream: bool,
+    #[serde(default)]
+    pub submodules: Vec<SubmoduleInfo>,
+    /// 当前分支, 分离 HEAD 时为空
+    #[serde(default)]
+    pub branch: String,
+    /// 所有本地分支和上游分支的差异
+    #[serde(default)]
+    pub branches: Vec<BranchInfo>,
+    /// 通过 git worktree 创建的工作树所属的主仓库
+    #[serde(default)]
+    pub worktree_of: Option<PathBuf>,
+    /// 包含这个仓库的最近的外层仓库, 扫描时设置
+    #[serde(default)]
+    pub nested_in: Option<PathBuf>,
+    /// origin 的地址, 没有 origin 时使用第一个远程仓库, 没有远程仓库时为空
+    #[serde(default)]
+    pub remote_url: String,
+    /// 所有远程仓库的地址, 用于按远程仓库搜索
+    #[serde(default)]
+    pub remote_urls: Vec<String>,
+    /// 远程分支, 比如 origin/main, 刷新时缓存, 切换分支时不需要再读取仓库
+    #[serde(default)]
+    pub remote_branches: Vec<String>,
+    /// 远程仓库的默认分支 (origin/HEAD 指向的分支)
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    /// 上一次网络检查使用的后端
+    #[serde(default)]
+    pub fetch_backend: Option<GitBackend>,
+    /// 不进行网络检查, 用于总是超时的仓库 (比如需要 VPN 的仓库)
+    #[serde(default)]
+    pub no_fetch: bool,
+    /// 状态变化的记录, 最早的在前面
+    #[serde(default)]
+    pub status_history: Vec<StatusRecord>,
+    /// 在列表中打开这个仓库的时间, 最早的在前面, 用于按使用频率排序
+    #[serde(default)]
+    pub visits: Vec<u64>,
+    /// 第一个提交的 id, 用于识别移动过的仓库, 没有提交时为空
+    #[serde(default)]
+    pub root_commit: Option<String>,
+    /// 占用的磁盘空间, 在打开详情或者按大小排序时计算
+    #[serde(default)]
+    pub size: Option<RepoSize>,
+    /// 从 GitHub/GitLab 查询的 CI 状态和 PR 数量, 在打开详情时查询
+    #[serde(default)]
+    pub forge_status: Option<ForgeStatus>,
+    /// 扫描时通过其它路径 (符号链接, bind mount) 找到的同一个仓库, path 是真实路径
+    #[serde(default)]
+    pub aliases: Vec<PathBuf>,
+}
+
+/// 超过这么久没有进行网络检查, 缓存的远程分支可能已经过时, 单位秒
+pub const BRANCH_CACHE_STALE_SECS: u64 = 3600;
+
+/// 仓库中的一个 git 标签
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitTag {
+    pub name: String,
+    /// 指向的提交的短 id
+    pub id: String,
+    /// 附注标签的创建时间, 轻量标签使用提交时间
+    pub time: u64,
+    /// 附注标签的第一行信息, 轻量标签为空
+    pub message: String,
+}
+
+/// 本地分支和它的上游分支
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BranchInfo {
+    pub name: String,
+    pub upstream: Option<String>,
+    /// 本地领先上游的提交数量
+    pub ahead: usize,
+    /// 本地落后上游的提交数量
+    pub behind: usize,
+}
+
+/// 最多保留这么多条状态变化的记录
+pub const STATUS_HISTORY_LEN: usize = 20;
+
+/// 一次状态变化, 状态不变时不记录, 所以 time 是进入这个状态的时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatusRecord {
+    pub status: GitStatus,
+    pub time: u64,
+}
+
+/// 检查状态失败时, 需要认证的仓库显示为需要认证, 其它错误当作超时
+fn status_or_error(res: BDEResult<GitStatus>) -> GitStatus {
+    match res {
+        Ok(status) => status,
+        Err(GitManagerError::AuthRequired) => GitStatus::AuthRequired,
+        Err(_) => GitStatus::Timeout,
+    }
+}
+
+/// 在记录中加入这次刷新的状态, 超时和需要认证表示不知道状态, 不记录
+fn record_status(mut history: Vec<StatusRecord>, status: GitStatus, now: u64) -> Vec<StatusRecord> {
+    if matches!(status, GitStatus::Timeout | GitStatus::AuthRequired)
+        || history.last().is_some_and(|last| last.status == status)
+    {
+        return history;
+    }
+    history.push(StatusRecord { status, time: now });
+    if history.len() > STATUS_HISTORY_LEN {
+        history.drain(..history.len() - STATUS_HISTORY_LEN);
+    }
+    history
+}
+
+/// 最多保留这么多次打开的时间
+pub const VISITS_LEN: usize = 50;
+
+/// 使用频率的分数, 综合打开的次数和时间, 每次打开越近权重越大
+fn frecency(visits: &[u64], now: u64) -> u64 {
+    const DAY: u64 = 24 * 3600;
+    visits
+        .iter()
+        .map(|time| match now.saturating_sub(*time) {
+            age if age < DAY => 100,
+            age if age < 7 * DAY => 70,
+            age if age < 30 * DAY => 50,
+            age if age < 90 * DAY => 30,
+            _ => 10,
+        })
+        .sum()
+}
+
+/// 子模块的状态
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SubmoduleInfo {
+    pub path: String,
+    /// 还没有初始化 (git submodule update --init)
+    pub uninitializedp: bool,
+    /// 检出的提交和父仓库记录的提交不同
+    pub outdatedp: bool,
+    /// 子模块的工作区有修改
+    pub dirtyp: bool,
+}
+
+impl SubmoduleInfo {
+    pub fn describe(&self) -> &'static str {
+        if self.uninitializedp {
+            tr("未初始化", "not initialized")
+        } else if self.outdatedp && self.dirtyp {
+            tr("提交不同, 有修改", "different commit, modified")
+        } else if self.outdatedp {
+            tr(
+                "提交和父仓库记录的不同",
+                "commit differs from the recorded one",
+            )
+        } else if self.dirtyp {
+            tr("有修改", "modified")
+        } else {
+            tr("干净", "clean")
+        }
+    }
+}
+
+/// 自动刷新时只对活跃的仓库进行网络检查, 不活跃的仓库使用更长的检查周期
+#[derive(Debug, Clone, Copy)]
+pub struct ActivePolicy {
+    /// 最近多少天内有提交算作活跃仓库
+    pub active_days: u64,
+    /// 不活跃仓库的网络检查间隔, 单位秒
+    pub dormant_fetch_interval: u64,
+}
+
+impl ActivePolicy {
+    pub fn need_fetch(&self, repo: &GitRepo, now: u64) -> bool {
+        let activep = now.saturating_sub(repo.last_commit_time) <= self.active_days * 24 * 3600;
+        activep || now.saturating_sub(repo.last_fetch_time) >= self.dormant_fetch_interval
+    }
+}
+
+/// 常见的依赖或构建目录, 未被忽略时直接认为是大目录
+const LARGE_UNTRACKED_DIR: [&str; 8] = [
+    "node_modules",
+    "target",
+    "build",
+    "dist",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".gradle",
+];
+
+/// 目录中文件数量超过这个值认为是大目录
+const LARGE_UNTRACKED_FILE_LEN: usize = 1000;
+
+fn count_dir_files(path: &Path, limit: usize) -> usize {
+    let mut count = 0;
+    let mut dirs = vec![PathBuf::from(path)];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            count += 1;
+            if count >= limit {
+                return count;
+            }
+
+            if entry.file_type().map(|item| item.is_dir()).unwrap_or(false) {
+                dirs.push(entry.path());
+            }
+        }
+    }
+
+    count
 }
 
 impl GitRepo {
     pub async fn build(path: &Path) -> BDEResult<Self> {
+        let fetch = if offline() {
+            FetchMode::Skip
+        } else {
+            FetchMode::Run
+        };
+        GitRepo::build_with(path, fetch).await
+    }
+
+    /// 读取新的仓库, fetch 为 Skip 时只检查本地状态
+    pub async fn build_with(path: &Path, fetch: FetchMode) -> BDEResult<Self> {
         let last_commit_time = GitRepo::get_last_commit_time(path)?;
 
-        let status = match GitRepo::get_status(path).await {
-            Ok(res) => res,
-            Err(_) => GitStatus::Timeout,
+        let status = status_or_error(GitRepo::get_status(path, fetch).await);
+
+        let large_untracked = if status == GitStatus::Untracked {
+            GitRepo::get_large_untracked(path)?
+        } else {
+            Vec::new()
         };
 
+        let (has_remote, has_upstream) = GitRepo::get_remote_info(path);
+        let submodules = GitRepo::get_submodules(path);
+        let (branch, branches) = GitRepo::get_branches(path);
+        let worktree_of = worktree_parent(path);
+        let remote_url = GitRepo::get_remote_url_by_path(path);
+        let remote_urls = GitRepo::get_remote_urls(path);
+        let (default_branch, remote_branches) = GitRepo::get_remote_branches(path);
+        let (last_commit_summary, last_commit_author) = GitRepo::get_last_commit(path);
+        let root_commit = GitRepo::get_root_commit(path);
+
         let file_name = path.file_name().unwrap().to_str().unwrap();
 
         Ok(GitRepo {
@@ -69,20 +414,94 @@ impl GitRepo {
             path: PathBuf::from(path),
             status,
             last_commit_time,
+            last_commit_summary,
+            last_commit_author,
+            tags: Vec::new(),
+            large_untracked,
+            last_fetch_time: if fetch != FetchMode::Skip {
+                now_timestamp()
+            } else {
+                0
+            },
+            last_fetch_success: GitRepo::get_last_fetch_success(path).unwrap_or(0),
+            has_remote,
+            has_upstream,
+            submodules,
+            branch,
+            branches,
+            worktree_of,
+            nested_in: None,
+            remote_url,
+            remote_urls,
+            remote_branches,
+            default_branch,
+            fetch_backend: (has_remote && fetch != FetchMode::Skip).then(|| backend_for(path)),
+            no_fetch: false,
+            status_history: record_status(Vec::new(), status, now_timestamp()),
+            visits: Vec::new(),
+            root_commit,
+            size: None,
+            forge_status: None,
+            aliases: Vec::new(),
         })
     }
 
-    pub async fn build_from_last(repo: GitRepo) -> BDEResult<Self> {
+    /// 根据上一次的结果刷新仓库, fetchp 为 false, 离线模式或者仓库设置了 no_fetch 时不进行网络检查
+    pub async fn build_from_last(repo: GitRepo, fetchp: bool) -> BDEResult<Self> {
+        let fetch = if fetchp && repo.fetch_allowed() {
+            FetchMode::Run
+        } else {
+            FetchMode::Skip
+        };
+        GitRepo::build_from_last_with(repo, fetch).await
+    }
+
+    /// 根据上一次的结果刷新仓库, fetch 为 Fetched 时使用批量 fetch 的结果
+    pub async fn build_from_last_with(repo: GitRepo, fetch: FetchMode) -> BDEResult<Self> {
         let path = repo.path;
+        // 需要认证的仓库不再 fetch, 直到按 r 单独刷新
+        let fetch = if repo.status == GitStatus::AuthRequired {
+            FetchMode::Skip
+        } else {
+            fetch
+        };
 
         let last_commit_time = GitRepo::get_last_commit_time(&path)?;
-        let status = if repo.status != GitStatus::Timeout {
-            match GitRepo::get_status(&path).await {
-                Ok(res) => res,
-                Err(_) => GitStatus::Timeout,
-            }
+        let status = match repo.status {
+            GitStatus::Timeout => GitStatus::Timeout,
+            // 工作区干净时和远程仓库的比较不可信, 继续显示需要认证
+            GitStatus::AuthRequired => match GitRepo::get_status(&path, fetch).await {
+                Ok(GitStatus::Clean | GitStatus::NeedPull | GitStatus::NeedPush) => {
+                    GitStatus::AuthRequired
+                }
+                res => status_or_error(res),
+            },
+            _ => status_or_error(GitRepo::get_status(&path, fetch).await),
+        };
+
+        let large_untracked = if status == GitStatus::Untracked {
+            GitRepo::get_large_untracked(&path)?
         } else {
-            GitStatus::Timeout
+            Vec::new()
+        };
+
+        let (has_remote, has_upstream) = GitRepo::get_remote_info(&path);
+        let submodules = GitRepo::get_submodules(&path);
+        let (branch, branches) = GitRepo::get_branches(&path);
+        let worktree_of = worktree_parent(&path);
+        let remote_url = GitRepo::get_remote_url_by_path(&path);
+        let remote_urls = GitRepo::get_remote_urls(&path);
+        let (default_branch, remote_branches) = GitRepo::get_remote_branches(&path);
+        let (last_commit_summary, last_commit_author) = GitRepo::get_last_commit(&path);
+        // 第一个提交不会改变, 只在还没有提交时重新读取
+        let root_commit = repo.root_commit.or_else(|| GitRepo::get_root_commit(&path));
+        let last_fetch_success =
+            GitRepo::get_last_fetch_success(&path).unwrap_or(repo.last_fetch_success);
+        let fetch_backend = match fetch {
+            FetchMode::Run if has_remote => Some(backend_for(&path)),
+            // 批量 fetch 使用 git 命令
+            FetchMode::Fetched(_) => Some(GitBackend::Cli),
+            _ => repo.fetch_backend,
         };
 
         Ok(GitRepo {
@@ -90,32 +509,84 @@ impl GitRepo {
             path,
             status,
             last_commit_time,
+            last_commit_summary,
+            last_commit_author,
+            tags: repo.tags,
+            large_untracked,
+            last_fetch_time: if fetch != FetchMode::Skip {
+                now_timestamp()
+            } else {
+                repo.last_fetch_time
+            },
+            last_fetch_success,
+            has_remote,
+            has_upstream,
+            submodules,
+            branch,
+            branches,
+            worktree_of,
+            nested_in: repo.nested_in,
+            remote_url,
+            remote_urls,
+            remote_branches,
+            default_branch,
+            fetch_backend,
+            no_fetch: repo.no_fetch,
+            status_history: record_status(repo.status_history, status, now_timestamp()),
+            visits: repo.visits,
+            root_commit,
+            size: repo.size,
+            forge_status: repo.forge_status,
+            aliases: repo.aliases,
         })
     }
 
-    pub async fn get_status(path: &Path) -> BDEResult<GitStatus> {
-        let status_res = run_command(format!("cd {} && git status", path.display()).as_str())?;
+    pub async fn get_status(path: &Path, fetch: FetchMode) -> BDEResult<GitStatus> {
+        let start = Instant::now();
+        let mut fetch_duration = Duration::ZERO;
+        let status = GitRepo::check_status(path, fetch, &mut fetch_duration).await;
+        WORKER_METRICS.add_status(start.elapsed().saturating_sub(fetch_duration));
+        status
+    }
+
+    async fn check_status(
+        path: &Path,
+        fetch: FetchMode,
+        fetch_duration: &mut Duration,
+    ) -> BDEResult<GitStatus> {
+        if let Some(status) = GitRepo::get_head_state(path) {
+            return Ok(status);
+        }
+
+        // 子模块工作区的修改单独记录在 submodules 中, 不算作父仓库需要 Commit
+        let status_res = git_output(path, ["status", "--ignore-submodules=dirty"])?;
         let working_tree_clean = status_res.contains("working tree clean");
 
         Ok(if working_tree_clean {
             let mut new_status = GitStatus::Clean;
 
-            let have_remote =
-                !run_command(format!("cd {} && git remote show", path.display()).as_str())?
-                    .is_empty();
+            let have_remote = !git_output(path, ["remote", "show"])?.is_empty();
 
             if have_remote {
                 let mut now_need_pull = status_res.contains("git pull");
                 let mut now_need_push = status_res.contains("git push");
 
-                if !now_need_push && !now_need_pull {
-                    run_command_timeout_no(
-                        format!("cd {} && git fetch", path.display()).as_str(),
-                        5,
-                    )
-                    .await?;
-                    let status_after_fetch_res =
-                        run_command(format!("cd {} && git status", path.display()).as_str())?;
+                // 批量 fetch 在检查状态之前完成, 这里的状态已经是最新的
+                if fetch == FetchMode::Fetched(FetchResult::TimedOut) {
+                    return Err(GitManagerError::CommandTimeout);
+                }
+                if fetch == FetchMode::Fetched(FetchResult::AuthRequired) {
+                    return Err(GitManagerError::AuthRequired);
+                }
+
+                if fetch == FetchMode::Run && !now_need_push && !now_need_pull {
+                    let fetch_start = Instant::now();
+                    let _network = WORKER_METRICS.network();
+                    let fetch_res = run_fetch(path).await;
+                    *fetch_duration = fetch_start.elapsed();
+                    WORKER_METRICS.add_fetch(*fetch_duration);
+                    fetch_res?;
+                    let status_after_fetch_res = git_output(path, ["status"])?;
                     now_need_pull = status_after_fetch_res.contains("git pull");
                     now_need_push = status_after_fetch_res.contains("git push");
                 }
@@ -130,20 +601,663 @@ impl GitRepo {
             }
 
             new_status
+        } else if GitRepo::only_untracked(path)? {
+            GitStatus::Untracked
         } else {
             GitStatus::NeedCommit
         })
     }
 
+    /// 工作区的修改只有未跟踪的文件
+    fn only_untracked(path: &Path) -> BDEResult<bool> {
+        let status_res = git_output(path, ["status", "--porcelain", "--ignore-submodules=dirty"])?;
+
+        Ok(status_res.lines().all(|line| line.starts_with("?? ")))
+    }
+
+    /// 冲突, 没有完成的 rebase/merge 或者分离的 HEAD, 都没有时返回 None
+    pub fn get_head_state(path: &Path) -> Option<GitStatus> {
+        let repo = git2::Repository::open(path).ok()?;
+
+        if repo
+            .index()
+            .map(|index| index.has_conflicts())
+            .unwrap_or(false)
+        {
+            return Some(GitStatus::Conflicted);
+        }
+
+        match repo.state() {
+            git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+            | git2::RepositoryState::ApplyMailboxOrRebase => Some(GitStatus::Rebasing),
+            git2::RepositoryState::Merge
+            | git2::RepositoryState::CherryPick
+            | git2::RepositoryState::CherryPickSequence
+            | git2::RepositoryState::Revert
+            | git2::RepositoryState::RevertSequence => Some(GitStatus::Merging),
+            _ if repo.head_detached().unwrap_or(false) => Some(GitStatus::DetachedHead),
+            _ => None,
+        }
+    }
+
+    /// 有冲突没有解决的文件, 相对于仓库目录
+    pub fn get_conflicted_files(path: &Path) -> Vec<String> {
+        let Ok(index) = git2::Repository::open(path).and_then(|repo| repo.index()) else {
+            return Vec::new();
+        };
+        let Ok(conflicts) = index.conflicts() else {
+            return Vec::new();
+        };
+        conflicts
+            .filter_map(|conflict| {
+                let conflict = conflict.ok()?;
+                // 一边删除一边修改时只有部分阶段有文件
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+                Some(String::from_utf8_lossy(&entry.path).to_string())
+            })
+            .collect()
+    }
+
+    /// 返回 (是否有远程仓库, 当前分支是否设置了上游分支)
+    pub fn get_remote_info(path: &Path) -> (bool, bool) {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return (false, false);
+        };
+
+        let has_remote = repo
+            .remotes()
+            .map(|remotes| !remotes.is_empty())
+            .unwrap_or(false);
+
+        let has_upstream = repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(String::from))
+            .and_then(|name| repo.find_branch(&name, git2::BranchType::Local).ok())
+            .map(|branch| branch.upstream().is_ok())
+            .unwrap_or(false);
+
+        (has_remote, has_upstream)
+    }
+
+    /// origin 的地址, 没有 origin 时使用第一个远程仓库, 没有远程仓库时为空
+    pub fn get_remote_url_by_path(path: &Path) -> String {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return String::new();
+        };
+        let remote = match repo.find_remote("origin") {
+            Ok(remote) => Some(remote),
+            Err(_) => repo.remotes().ok().and_then(|remotes| {
+                let name = remotes.iter().flatten().next()?.to_string();
+                repo.find_remote(&name).ok()
+            }),
+        };
+
+        remote
+            .and_then(|remote| remote.url().map(String::from))
+            .unwrap_or_default()
+    }
+
+    /// 所有远程仓库的地址, 按远程仓库的名字排序
+    pub fn get_remote_urls(path: &Path) -> Vec<String> {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return Vec::new();
+        };
+        let Ok(remotes) = repo.remotes() else {
+            return Vec::new();
+        };
+        let mut names: Vec<&str> = remotes.iter().flatten().collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .filter_map(|name| repo.find_remote(name).ok()?.url().map(String::from))
+            .collect()
+    }
+
+    /// 返回 (默认分支, 所有远程分支), 打开仓库失败时返回空
+    pub fn get_remote_branches(path: &Path) -> (Option<String>, Vec<String>) {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return (None, Vec::new());
+        };
+
+        let default_branch = repo
+            .find_reference("refs/remotes/origin/HEAD")
+            .ok()
+            .and_then(|head| {
+                head.symbolic_target()
+                    .and_then(|target| target.strip_prefix("refs/remotes/"))
+                    .map(String::from)
+            });
+
+        let mut remote_branches: Vec<String> = repo
+            .branches(Some(git2::BranchType::Remote))
+            .map(|branches| {
+                branches
+                    .flatten()
+                    .filter_map(|(branch, _)| branch.name().ok().flatten().map(String::from))
+                    .filter(|name| !name.ends_with("/HEAD"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        remote_branches.sort();
+
+        (default_branch, remote_branches)
+    }
+
+    /// 进入当前状态的时间, 没有记录时为 None
+    pub fn status_since(&self) -> Option<u64> {
+        self.status_history
+            .last()
+            .filter(|record| record.status == self.status)
+            .map(|record| record.time)
+    }
+
+    /// 记录一次打开
+    pub fn record_visit(&mut self, now: u64) {
+        self.visits.push(now);
+        if self.visits.len() > VISITS_LEN {
+            self.visits.drain(..self.visits.len() - VISITS_LEN);
+        }
+    }
+
+    /// 经常打开并且最近打开过的仓库分数高
+    pub fn frecency(&self, now: u64) -> u64 {
+        frecency(&self.visits, now)
+    }
+
+    /// 是否可以进行网络检查
+    pub fn fetch_allowed(&self) -> bool {
+        !self.no_fetch && !offline()
+    }
+
+    /// 有远程仓库, 并且超过 max_age 秒没有成功 fetch, 这时需要拉取等远程状态可能已经过时
+    pub fn fetch_stalep(&self, now: u64, max_age: u64) -> bool {
+        self.has_remote
+            && self.status != GitStatus::Missing
+            && now.saturating_sub(self.last_fetch_success) > max_age
+    }
+
+    /// 缓存的远程分支是否已经过时
+    pub fn branch_cache_stalep(&self, now: u64) -> bool {
+        self.has_remote && now.saturating_sub(self.last_fetch_time) > BRANCH_CACHE_STALE_SECS
+    }
+
+    /// 远程仓库的主机名
+    pub fn host(&self) -> Option<String> {
+        url_host(&self.remote_url)
+    }
+
+    /// 远程仓库的所有者
+    pub fn owner(&self) -> Option<String> {
+        url_owner(&self.remote_url)
+    }
+
+    /// 任意一个远程仓库的地址包含 pattern, 协议不同的地址都按 host/owner/repo 比较
+    ///
+    /// pattern 需要是小写的
+    pub fn remote_matches(&self, pattern: &str) -> bool {
+        // 旧的缓存中没有 remote_urls
+        std::iter::once(&self.remote_url)
+            .chain(self.remote_urls.iter())
+            .filter(|url| !url.is_empty())
+            .any(|url| url.to_lowercase().contains(pattern) || url_slug(url).contains(pattern))
+    }
+
+    /// 子模块和它们的状态, 打开仓库失败时返回空
+    pub fn get_submodules(path: &Path) -> Vec<SubmoduleInfo> {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return Vec::new();
+        };
+        let Ok(submodules) = repo.submodules() else {
+            return Vec::new();
+        };
+
+        submodules
+            .iter()
+            .filter_map(|submodule| {
+                let name = submodule.name()?;
+                let status = repo
+                    .submodule_status(name, git2::SubmoduleIgnore::None)
+                    .ok()?;
+                Some(SubmoduleInfo {
+                    path: submodule.path().display().to_string(),
+                    uninitializedp: status.contains(git2::SubmoduleStatus::WD_UNINITIALIZED),
+                    outdatedp: status.contains(git2::SubmoduleStatus::WD_MODIFIED),
+                    dirtyp: status.intersects(
+                        git2::SubmoduleStatus::WD_INDEX_MODIFIED
+                            | git2::SubmoduleStatus::WD_WD_MODIFIED
+                            | git2::SubmoduleStatus::WD_UNTRACKED,
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// 返回 (当前分支, 所有本地分支), 打开仓库失败时返回空
+    pub fn get_branches(path: &Path) -> (String, Vec<BranchInfo>) {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return (String::new(), Vec::new());
+        };
+
+        let current = repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(String::from))
+            .unwrap_or_default();
+
+        let Ok(local_branches) = repo.branches(Some(git2::BranchType::Local)) else {
+            return (current, Vec::new());
+        };
+
+        let mut branches = Vec::new();
+        for (branch, _) in local_branches.flatten() {
+            let Ok(Some(name)) = branch.name() else {
+                continue;
+            };
+            let name = String::from(name);
+
+            let mut info = BranchInfo {
+                name,
+                upstream: None,
+                ahead: 0,
+                behind: 0,
+            };
+            if let Ok(upstream) = branch.upstream() {
+                info.upstream = upstream.name().ok().flatten().map(String::from);
+                if let (Some(local), Some(remote)) =
+                    (branch.get().target(), upstream.get().target())
+                {
+                    if let Ok((ahead, behind)) = repo.graph_ahead_behind(local, remote) {
+                        info.ahead = ahead;
+                        info.behind = behind;
+                    }
+                }
+            }
+            branches.push(info);
+        }
+        branches.sort_by(|a, b| (a.name != current, &a.name).cmp(&(b.name != current, &b.name)));
+
+        (current, branches)
+    }
+
+    /// 当前分支领先和落后上游的提交数量
+    pub fn current_ahead_behind(&self) -> (usize, usize) {
+        self.branches
+            .iter()
+            .find(|branch| branch.name == self.branch)
+            .map_or((0, 0), |branch| (branch.ahead, branch.behind))
+    }
+
+    /// 当前分支和上游分叉, 不能快进
+    pub fn divergedp(&self) -> bool {
+        let (ahead, behind) = self.current_ahead_behind();
+        ahead > 0 && behind > 0
+    }
+
+    /// 除了当前分支之外, 有分支领先上游需要推送
+    pub fn other_branch_need_push(&self) -> bool {
+        self.branches
+            .iter()
+            .any(|branch| branch.name != self.branch && branch.ahead > 0)
+    }
+
+    /// 分支的摘要, 比如 "3 个分支, 1 个落后"
+    pub fn branch_summary(&self) -> String {
+        let ahead = self.branches.iter().filter(|item| item.ahead > 0).count();
+        let behind = self.branches.iter().filter(|item| item.behind > 0).count();
+        let no_upstream = self
+            .branches
+            .iter()
+            .filter(|item| item.upstream.is_none())
+            .count();
+
+        let mut summary = vec![trf!("{} 个分支", "{} branches", self.branches.len())];
+        if ahead > 0 {
+            summary.push(trf!("{} 个领先", "{} ahead", ahead));
+        }
+        if behind > 0 {
+            summary.push(trf!("{} 个落后", "{} behind", behind));
+        }
+        if no_upstream > 0 {
+            summary.push(trf!("{} 个没有上游", "{} without upstream", no_upstream));
+        }
+        summary.join(", ")
+    }
+
+    /// 有子模块的提交不同或者有修改
+    pub fn submodule_dirty(&self) -> bool {
+        self.submodules
+            .iter()
+            .any(|submodule| submodule.outdatedp || submodule.dirtyp)
+    }
+
+    /// 如果工作区的改动全部是未跟踪的大目录, 返回这些目录, 否则返回空
+    pub fn get_large_untracked(path: &Path) -> BDEResult<Vec<String>> {
+        let status_res = git_output(path, ["status", "--porcelain", "--ignore-submodules=dirty"])?;
+
+        let mut large_dirs = Vec::new();
+        for line in status_res.lines() {
+            let Some(untracked) = line.strip_prefix("?? ") else {
+                return Ok(Vec::new());
+            };
+
+            let Some(dir) = untracked.strip_suffix('/') else {
+                return Ok(Vec::new());
+            };
+
+            let dir_name = Path::new(dir)
+                .file_name()
+                .and_then(|item| item.to_str())
+                .unwrap_or(dir);
+
+            if LARGE_UNTRACKED_DIR.contains(&dir_name)
+                || count_dir_files(&path.join(dir), LARGE_UNTRACKED_FILE_LEN)
+                    >= LARGE_UNTRACKED_FILE_LEN
+            {
+                large_dirs.push(String::from(untracked));
+            } else {
+                return Ok(Vec::new());
+            }
+        }
+
+        Ok(large_dirs)
+    }
+
+    /// 将路径追加到仓库的 .gitignore 中
+    pub fn append_gitignore(&self, items: &[String]) -> BDEResult<()> {
+        let gitignore_path = self.path.join(".gitignore");
+        let old_content = fs::read_to_string(&gitignore_path).unwrap_or_default();
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(gitignore_path)?;
+
+        if !old_content.is_empty() && !old_content.ends_with('\n') {
+            writeln!(file)?;
+        }
+
+        for item in items {
+            writeln!(file, "{}", item)?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取远程仓库的地址, 没有 remote_name 时使用第一个远程仓库
+    pub fn get_remote_url(&self, remote_name: &str) -> BDEResult<String> {
+        let repo = git2::Repository::open(&self.path)?;
+
+        let remote = match repo.find_remote(remote_name) {
+            Ok(remote) => remote,
+            Err(_) => {
+                let remotes = repo.remotes()?;
+                let first_remote =
+                    remotes.iter().flatten().next().ok_or_else(|| {
+                        ba_error(tr("仓库没有远程仓库", "repository has no remote"))
+                    })?;
+                repo.find_remote(first_remote)?
+            }
+        };
+
+        remote.url().map(String::from).ok_or_else(|| {
+            ba_error(tr(
+                "远程仓库地址不是有效的 UTF-8",
+                "remote url is not valid UTF-8",
+            ))
+        })
+    }
+
+    /// 解析空格分隔的标签, 去掉重复的标签
+    pub fn parse_tags(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in input.split_whitespace() {
+            if !tags.iter().any(|item| item == tag) {
+                tags.push(String::from(tag));
+            }
+        }
+        tags
+    }
+
+    /// 在 path 创建新的仓库, remote_url 不为空时添加为 origin
+    pub fn init(path: &Path, remote_url: &str) -> BDEResult<()> {
+        fs::create_dir_all(path)?;
+        let repo = git2::Repository::init(path)?;
+
+        if !remote_url.is_empty() {
+            repo.remote("origin", remote_url)?;
+        }
+
+        Ok(())
+    }
+
+    /// 仓库中所有的 git 标签, 新的在前
+    pub fn get_git_tags(path: &Path) -> Vec<GitTag> {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return Vec::new();
+        };
+        let mut oids = Vec::new();
+        let _ = repo.tag_foreach(|oid, name| {
+            let name = String::from_utf8_lossy(name);
+            oids.push((oid, name.trim_start_matches("refs/tags/").to_string()));
+            true
+        });
+
+        let mut tags: Vec<GitTag> = oids
+            .into_iter()
+            .filter_map(|(oid, name)| {
+                let object = repo.find_object(oid, None).ok()?;
+                let commit = object.peel_to_commit().ok()?;
+                let (time, message) = match object.as_tag() {
+                    Some(tag) => (
+                        tag.tagger()
+                            .map(|tagger| tagger.when().seconds())
+                            .unwrap_or(commit.time().seconds()),
+                        tag.message()
+                            .and_then(|message| message.lines().next())
+                            .unwrap_or("")
+                            .to_string(),
+                    ),
+                    None => (commit.time().seconds(), String::new()),
+                };
+                Some(GitTag {
+                    name,
+                    id: commit.id().to_string()[..7].to_string(),
+                    time: time.max(0) as u64,
+                    message,
+                })
+            })
+            .collect();
+        tags.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.name.cmp(&b.name)));
+        tags
+    }
+
+    /// 在 HEAD 上创建附注标签, 名字已经存在或者不合法时返回错误
+    pub fn create_tag(path: &Path, name: &str, message: &str) -> BDEResult<()> {
+        let repo = git2::Repository::open(path)?;
+        let target = repo.head()?.peel(git2::ObjectType::Commit)?;
+        let tagger = repo.signature()?;
+        repo.tag(name, &target, &tagger, message, false)?;
+        Ok(())
+    }
+
+    /// 添加远程仓库, 名字已经存在或者不合法时返回错误
+    pub fn add_remote(path: &Path, name: &str, url: &str) -> BDEResult<()> {
+        let repo = git2::Repository::open(path)?;
+        repo.remote(name, url)?;
+        Ok(())
+    }
+
+    /// 检查仓库是否可以安全删除, 返回不能删除的原因, 为空时表示工作区干净并且所有分支都已经推送
+    pub fn check_fully_pushed(&self) -> BDEResult<Vec<String>> {
+        let mut repo = git2::Repository::open(&self.path)?;
+        let mut reasons = Vec::new();
+
+        let mut status_options = git2::StatusOptions::new();
+        status_options
+            .include_untracked(true)
+            .include_ignored(false);
+        if !repo.statuses(Some(&mut status_options))?.is_empty() {
+            reasons.push(String::from(tr(
+                "工作区有未提交的修改",
+                "working tree has uncommitted changes",
+            )));
+        }
+
+        if repo.remotes()?.is_empty() {
+            reasons.push(String::from(tr("没有远程仓库", "no remote")));
+        }
+
+        let mut stash_len = 0;
+        repo.stash_foreach(|_, _, _| {
+            stash_len += 1;
+            true
+        })?;
+        if stash_len > 0 {
+            reasons.push(trf!("有 {} 个 stash", "{} stash entries", stash_len));
+        }
+
+        GitRepo::check_branches_pushed(&repo, reasons)
+    }
+
+    fn check_branches_pushed(
+        repo: &git2::Repository,
+        mut reasons: Vec<String>,
+    ) -> BDEResult<Vec<String>> {
+        for branch in repo.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+            let branch_name = branch.name()?.unwrap_or("").to_string();
+
+            let Ok(upstream) = branch.upstream() else {
+                reasons.push(trf!(
+                    "分支 {} 没有上游分支",
+                    "branch {} has no upstream",
+                    branch_name
+                ));
+                continue;
+            };
+
+            if let (Some(local_oid), Some(upstream_oid)) =
+                (branch.get().target(), upstream.get().target())
+            {
+                let (ahead, _) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+                if ahead > 0 {
+                    reasons.push(trf!(
+                        "分支 {} 有 {} 个提交没有推送",
+                        "branch {} has {} unpushed commits",
+                        branch_name,
+                        ahead
+                    ));
+                }
+            }
+        }
+
+        Ok(reasons)
+    }
+
+    /// 所有分支中最早的没有推送到上游的提交, 返回提交时间和第一行信息, 没有上游的分支不算
+    pub fn oldest_unpushed_commit(&self) -> Option<(u64, String)> {
+        let repo = git2::Repository::open(&self.path).ok()?;
+        let mut revwalk = repo.revwalk().ok()?;
+        for (branch, _) in repo.branches(Some(git2::BranchType::Local)).ok()?.flatten() {
+            let Ok(upstream) = branch.upstream() else {
+                continue;
+            };
+            if let (Some(local_oid), Some(upstream_oid)) =
+                (branch.get().target(), upstream.get().target())
+            {
+                revwalk.push(local_oid).ok()?;
+                revwalk.hide(upstream_oid).ok()?;
+            }
+        }
+
+        revwalk
+            .flatten()
+            .filter_map(|oid| repo.find_commit(oid).ok())
+            .map(|commit| {
+                (
+                    commit.time().seconds().max(0) as u64,
+                    commit.summary().unwrap_or("").to_string(),
+                )
+            })
+            .min_by_key(|(time, _)| *time)
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|item| item == tag)
+    }
+
+    /// HEAD 指向的提交的第一行信息和作者, 没有提交时为空
+    pub fn get_last_commit(path: &Path) -> (String, String) {
+        let Ok(repo) = git2::Repository::open(path) else {
+            return (String::new(), String::new());
+        };
+        let Some(commit) = repo.head().ok().and_then(|head| head.peel_to_commit().ok()) else {
+            return (String::new(), String::new());
+        };
+
+        let author = commit.author();
+        let author = match (author.name(), author.email()) {
+            (Some(name), Some(email)) if !email.is_empty() => format!("{} <{}>", name, email),
+            (Some(name), _) => String::from(name),
+            (None, Some(email)) => String::from(email),
+            (None, None) => String::new(),
+        };
+        (String::from(commit.summary().unwrap_or_default()), author)
+    }
+
+    /// HEAD 指向的提交的短 id 和完整信息, 在详情中显示
+    pub fn get_last_commit_message(path: &Path) -> Option<(String, String)> {
+        let repo = git2::Repository::open(path).ok()?;
+        let commit = repo.head().ok()?.peel_to_commit().ok()?;
+        let id = commit.as_object().short_id().ok()?;
+        Some((
+            String::from(id.as_str().unwrap_or_default()),
+            String::from(commit.message().unwrap_or_default()),
+        ))
+    }
+
+    /// 上一次成功 fetch 的时间, 即 FETCH_HEAD 的修改时间, 工作树使用主仓库的 FETCH_HEAD
+    ///
+    /// fetch 失败时 git 也会清空 FETCH_HEAD, 这时返回 None, 使用之前记录的时间
+    pub fn get_last_fetch_success(path: &Path) -> Option<u64> {
+        let repo = git2::Repository::open(path).ok()?;
+        // 工作树的 git 目录中 commondir 文件记录主仓库的 git 目录
+        let git_dir = match fs::read_to_string(repo.path().join("commondir")) {
+            Ok(common_dir) => repo.path().join(common_dir.trim()),
+            Err(_) => repo.path().to_path_buf(),
+        };
+        let metadata = fs::metadata(git_dir.join("FETCH_HEAD")).ok()?;
+        if metadata.len() == 0 {
+            return None;
+        }
+        let modified = metadata.modified().ok()?;
+        Some(
+            modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs(),
+        )
+    }
+
+    /// 从 HEAD 开始最早的没有父提交的提交, 有多个时取第一个找到的
+    pub fn get_root_commit(path: &Path) -> Option<String> {
+        let repo = git2::Repository::open(path).ok()?;
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.push_head().ok()?;
+        revwalk
+            .set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)
+            .ok()?;
+        revwalk.find_map(Result::ok).map(|id| id.to_string())
+    }
+
     pub fn get_last_commit_time(path: &Path) -> BDEResult<u64> {
-        let res = run_command(
-            format!(
-                "cd {} && git show --pretty=format:'%ct' | head -1",
-                path.display()
-            )
-            .as_str(),
-        )?;
-        // println!("{}: commit time: {}", self.path.display(), res);
+        let res = git_output(path, ["log", "-1", "--format=%ct"])?;
         Ok(if res.trim().is_empty() {
             0
         } else {
@@ -158,177 +1272,703 @@ impl fmt::Display for GitRepo {
     }
 }
 
-fn get_save_git_repo_path() -> BDEResult<PathBuf> {
-    let repo_data_dir = PathBuf::from("/home/lizqwer/.cache/git_manager/");
+pub fn get_save_data_dir() -> BDEResult<PathBuf> {
+    let repo_data_dir = home_dir()?.join(".cache").join("git_manager");
     if !repo_data_dir.exists() {
         fs::create_dir_all(&repo_data_dir)?;
     }
 
-    let repo_data_path = repo_data_dir.join("repo.json");
+    Ok(repo_data_dir)
+}
+
+/// 当前标签页的名字, 决定读写哪一个缓存文件
+static CACHE_ROOT: RwLock<String> = RwLock::new(String::new());
 
-    Ok(repo_data_path)
+pub fn set_cache_root(root: &str) {
+    if let Ok(mut state) = CACHE_ROOT.write() {
+        *state = String::from(root);
+    }
 }
 
-fn search_all_git_path(search_path: &Path) -> BDEResult<Vec<PathBuf>> {
-    let ignore_dir = vec![".cache", ".local", ".cargo", "clasp"];
-    // 一旦 Fetch 在一些需要输入密码的情况下会导致仓库无法被删除
-    let search_git_str = "^\\..*git$";
+pub fn cache_root() -> String {
+    CACHE_ROOT
+        .read()
+        .map(|root| root.clone())
+        .unwrap_or_default()
+}
 
-    let ignore_dir_str: Vec<String> = ignore_dir
-        .into_iter()
-        .map(|item| format!("-E {}", item))
-        .collect();
+/// 没有名字的标签页使用 repo.json, 其它的使用 repo-<名字>.json
+fn get_save_git_repo_path(root: &str) -> BDEResult<PathBuf> {
+    let file_name = if root.is_empty() {
+        String::from("repo.json")
+    } else {
+        let name: String = root
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("repo-{}.json", name)
+    };
+    Ok(get_save_data_dir()?.join(file_name))
+}
+
+fn get_save_ignore_repo_path() -> BDEResult<PathBuf> {
+    Ok(get_save_data_dir()?.join("ignore.json"))
+}
 
-    let command = format!(
-        "fd -I -t d -H {} '{}' {}",
-        ignore_dir_str.join(" "),
-        search_git_str,
-        search_path.display()
+/// 工作树的 .git 是一个文件, 内容为 gitdir: <主仓库>/.git/worktrees/<名字>,
+/// 返回主仓库的路径, 子模块和普通仓库返回 None
+pub fn worktree_parent(path: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(path.join(".git")).ok()?;
+    let gitdir = Path::new(content.trim().strip_prefix("gitdir:")?.trim());
+    // 较新的 git 可以使用相对路径
+    let gitdir = if gitdir.is_relative() {
+        path.join(gitdir).canonicalize().ok()?
+    } else {
+        gitdir.to_path_buf()
+    };
+
+    let worktrees_dir = gitdir.parent()?;
+    if worktrees_dir.file_name()? != "worktrees" {
+        return None;
+    }
+    let common_dir = worktrees_dir.parent()?;
+    let parent = if common_dir.file_name()? == ".git" {
+        common_dir.parent()?
+    } else {
+        // 裸仓库的工作树
+        common_dir
+    };
+
+    Some(parent.to_path_buf())
+}
+
+/// 查找仓库时默认跳过的目录
+pub const DEFAULT_SCAN_EXCLUDE: [&str; 4] = [".cache", ".local", ".cargo", "clasp"];
+
+/// 查找仓库的选项
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// 跳过的目录, 不会进入其中查找
+    pub exclude: Vec<ExcludePattern>,
+    /// 仓库最多在搜索目录下几层, 0 时不限制
+    pub max_depth: usize,
+    /// 进入指向目录的符号链接, 同一个目录只进入一次, 避免循环
+    pub follow_symlinks: bool,
+    /// 跳过的挂载点, 比如网络文件系统, 搜索目录本身在其中时仍然扫描
+    pub skip_mounts: Vec<PathBuf>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            exclude: DEFAULT_SCAN_EXCLUDE
+                .iter()
+                .map(|pattern| ExcludePattern::new(pattern, Path::new("")))
+                .collect(),
+            max_depth: 0,
+            follow_symlinks: false,
+            skip_mounts: Vec::new(),
+        }
+    }
+}
+
+impl SearchOptions {
+    fn skipp(&self, dir: &Path) -> bool {
+        self.exclude.iter().any(|pattern| pattern.matches(dir))
+            || self.skip_mounts.iter().any(|mount| mount == dir)
+    }
+
+    /// path 或者它的上层目录被跳过, 用来去掉缓存中已经不再扫描的仓库
+    pub fn excludedp(&self, path: &Path) -> bool {
+        path.ancestors().any(|dir| self.skipp(dir))
+    }
+}
+
+/// 跟随符号链接时用来判断目录是否已经进入过
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// 目录的设备号和 inode
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path).ok()?;
+    metadata.is_dir().then(|| (metadata.dev(), metadata.ino()))
+}
+
+/// 没有 inode 时使用解析了符号链接之后的路径
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    let path = fs::canonicalize(path).ok()?;
+    path.is_dir().then_some(path)
+}
+
+/// 递归查找 dir 中名字为 .*git 的目录和 .git 文件, 没有权限的目录直接跳过,
+/// dir 在搜索目录下的层数为 depth
+fn walk_git_entries(
+    dir: &Path,
+    depth: usize,
+    options: &SearchOptions,
+    visited: &mut HashSet<DirId>,
+    git_dirs: &mut Vec<PathBuf>,
+    git_files: &mut Vec<PathBuf>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        let path = entry.path();
+        let dirp = if file_type.is_symlink() {
+            options.follow_symlinks && path.is_dir()
+        } else {
+            file_type.is_dir()
+        };
+
+        if dirp {
+            if options.skipp(&path) {
+                continue;
+            }
+            // 一旦 Fetch 在一些需要输入密码的情况下会导致仓库无法被删除
+            if name.starts_with('.') && name.ends_with("git") {
+                git_dirs.push(path);
+            } else if options.max_depth == 0 || depth < options.max_depth {
+                if options.follow_symlinks && !dir_id(&path).is_some_and(|id| visited.insert(id)) {
+                    continue;
+                }
+                walk_git_entries(&path, depth + 1, options, visited, git_dirs, git_files);
+            }
+        } else if file_type.is_file() && name == ".git" {
+            git_files.push(entry.path());
+        }
+    }
+}
+
+pub fn search_all_git_path(search_path: &Path) -> BDEResult<Vec<PathBuf>> {
+    search_all_git_path_with(search_path, &SearchOptions::default())
+}
+
+pub fn search_all_git_path_with(
+    search_path: &Path,
+    options: &SearchOptions,
+) -> BDEResult<Vec<PathBuf>> {
+    let mut git_dirs = Vec::new();
+    let mut git_files = Vec::new();
+    let mut visited = HashSet::from_iter(dir_id(search_path));
+    walk_git_entries(
+        search_path,
+        0,
+        options,
+        &mut visited,
+        &mut git_dirs,
+        &mut git_files,
     );
 
-    //println!("command: {}", command);
-    let find_res = run_command(&command)?;
-    let all_paths: Vec<PathBuf> = find_res
-        .split('\n')
-        .filter_map(|path| Path::new(path).parent().map(PathBuf::from))
+    let mut all_paths: Vec<PathBuf> = git_dirs
+        .iter()
+        .filter_map(|path| path.parent().map(PathBuf::from))
         .collect();
 
+    // 工作树的 .git 是文件, 子模块的 .git 文件不算作仓库
+    for path in git_files.iter().filter_map(|path| path.parent()) {
+        if worktree_parent(path).is_some() && !all_paths.iter().any(|item| item == path) {
+            all_paths.push(path.to_path_buf());
+        }
+    }
+
     Ok(all_paths)
 }
 
-pub fn save_all_git_repo(repos: &[GitRepo]) -> BDEResult<()> {
-    let repo_data_path = get_save_git_repo_path()?;
+/// 写入缓存文件, 只有持有实例锁时才会写入, 先写临时文件再替换, 其它实例不会读到写了一半的文件
+pub fn write_cache_file(path: &Path, content: &str) -> BDEResult<()> {
+    if !instance::try_acquire(&get_save_data_dir()?)? {
+        return Ok(());
+    }
 
-    let repo_data_json = serde_json::to_string_pretty(&repos)?;
-    fs::write(repo_data_path, repo_data_json)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)?;
+    fs::rename(tmp_path, path)?;
     Ok(())
 }
 
+pub fn save_all_git_repo(repos: &[GitRepo]) -> BDEResult<()> {
+    save_repo_cache(&cache_root(), repos)
+}
+
 pub fn load_all_repo() -> BDEResult<Option<Vec<GitRepo>>> {
-    let repo_data_path = get_save_git_repo_path()?;
+    load_repo_cache(&cache_root())
+}
+
+/// 保存一个标签页的仓库
+pub fn save_repo_cache(root: &str, repos: &[GitRepo]) -> BDEResult<()> {
+    let repo_data_path = get_save_git_repo_path(root)?;
+
+    let repo_data_json = serde_json::to_string_pretty(&repos)?;
+    write_cache_file(&repo_data_path, &repo_data_json)
+}
+
+/// 读取一个标签页的仓库, 还没有扫描过时为 None
+pub fn load_repo_cache(root: &str) -> BDEResult<Option<Vec<GitRepo>>> {
+    let repo_data_path = get_save_git_repo_path(root)?;
 
     if !repo_data_path.exists() {
         Ok(None)
     } else {
         let repo_data_json = fs::read_to_string(repo_data_path)?;
-        let repos = serde_json::from_str(&repo_data_json)?;
+        let mut repos: Vec<GitRepo> = serde_json::from_str(&repo_data_json)?;
+        // 已经被删除的仓库在读取时就标记出来, 不用等到刷新
+        for repo in repos.iter_mut().filter(|repo| !repo.path.exists()) {
+            repo.status = GitStatus::Missing;
+        }
         Ok(Some(repos))
     }
 }
 
-pub async fn generate_git_repo(all_paths: Vec<PathBuf>) -> BDEResult<(Vec<GitRepo>, u64)> {
-    let mut set = JoinSet::new();
-    for path in all_paths {
-        // let path_str = path.display().to_string();
+pub fn save_ignore_repos(ignore_repos: &[PathBuf]) -> BDEResult<()> {
+    let ignore_data_path = get_save_ignore_repo_path()?;
 
-        set.spawn(async move {
-            // let path = Path::new(&path_str);
-            match GitRepo::build(&path).await {
-                Ok(repo) => Some(repo),
-                Err(err) => {
-                    println!("build err({}): {}", path.display(), err);
-                    None
-                }
-            }
+    let ignore_data_json = serde_json::to_string_pretty(&ignore_repos)?;
+    write_cache_file(&ignore_data_path, &ignore_data_json)
+}
+
+pub fn load_ignore_repos() -> BDEResult<Vec<PathBuf>> {
+    let ignore_data_path = get_save_ignore_repo_path()?;
+
+    if !ignore_data_path.exists() {
+        Ok(Vec::new())
+    } else {
+        let ignore_data_json = fs::read_to_string(ignore_data_path)?;
+        Ok(serde_json::from_str(&ignore_data_json)?)
+    }
+}
+
+fn get_save_filter_history_path() -> BDEResult<PathBuf> {
+    Ok(get_save_data_dir()?.join("filter_history.json"))
+}
+
+pub fn save_filter_history(history: &[String]) -> BDEResult<()> {
+    let history_json = serde_json::to_string_pretty(history)?;
+    write_cache_file(&get_save_filter_history_path()?, &history_json)
+}
+
+pub fn load_filter_history() -> BDEResult<Vec<String>> {
+    let history_path = get_save_filter_history_path()?;
+
+    if !history_path.exists() {
+        Ok(Vec::new())
+    } else {
+        let history_json = fs::read_to_string(history_path)?;
+        Ok(serde_json::from_str(&history_json)?)
+    }
+}
+
+fn get_save_maintenance_path() -> BDEResult<PathBuf> {
+    Ok(get_save_data_dir()?.join("maintenance.json"))
+}
+
+/// 保存最后一次维护所有仓库的时间
+pub fn save_maintenance_time(time: u64) -> BDEResult<()> {
+    write_cache_file(
+        &get_save_maintenance_path()?,
+        &serde_json::to_string(&time)?,
+    )
+}
+
+/// 最后一次维护所有仓库的时间, 没有维护过时为 0
+pub fn load_maintenance_time() -> BDEResult<u64> {
+    let maintenance_path = get_save_maintenance_path()?;
+
+    if !maintenance_path.exists() {
+        Ok(0)
+    } else {
+        let maintenance_json = fs::read_to_string(maintenance_path)?;
+        Ok(serde_json::from_str(&maintenance_json)?)
+    }
+}
+
+/// 检查失败的仓库, 扫描本身失败时 path 为空
+#[derive(Debug, Clone)]
+pub struct RepoError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// 扫描过程中发送给界面的结果, root 是扫描的标签页, 切换标签页之后用来丢弃旧的结果
+#[derive(Debug, Clone)]
+pub enum ScanUpdate {
+    /// 开始并行查找这些搜索目录中的仓库
+    Searching { root: String, paths: Vec<PathBuf> },
+    /// 一个搜索目录查找完成, 找到了 repos 个仓库
+    RootScanned {
+        root: String,
+        path: PathBuf,
+        repos: usize,
+    },
+    /// 所有仓库的本地状态检查完成
+    Local {
+        root: String,
+        repos: Vec<GitRepo>,
+        errors: Vec<RepoError>,
+    },
+    /// 网络检查之后更新的一个仓库
+    Fetched { root: String, repo: Box<GitRepo> },
+    /// 扫描全部完成, 由调用 get_all_git_repo 的一方发送
+    Finished {
+        root: String,
+        repos: Vec<GitRepo>,
+        errors: Vec<RepoError>,
+    },
+}
+
+/// 扫描所有仓库, 中间结果发送到 progress, 全部完成之后返回最终结果
+pub async fn get_all_git_repo(
+    search_paths: &[PathBuf],
+    options: ScanOptions,
+    progress: Option<&UnboundedSender<ScanUpdate>>,
+) -> BDEResult<(Vec<GitRepo>, Vec<RepoError>)> {
+    WORKER_METRICS.reset_durations();
+
+    let root = options.root.clone();
+    if let Some(progress) = progress {
+        let _ = progress.send(ScanUpdate::Searching {
+            root: root.clone(),
+            paths: search_paths.to_vec(),
         });
     }
+    let mut stream = scan_stream(search_paths.to_vec(), options);
 
     let mut git_repos: Vec<GitRepo> = Vec::new();
-    let mut err_len = 0;
-    while let Some(res) = set.join_next().await {
-        match res {
-            Ok(repo) => {
-                if let Some(repo) = repo {
-                    git_repos.push(repo);
-                } else {
-                    err_len += 1;
+    let mut errors = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            RepoEvent::Status(repo) => git_repos.push(*repo),
+            RepoEvent::Fetched(repo) => {
+                if let Some(progress) = progress {
+                    let _ = progress.send(ScanUpdate::Fetched {
+                        root: root.clone(),
+                        repo: repo.clone(),
+                    });
+                }
+                match git_repos.iter().position(|item| item.path == repo.path) {
+                    Some(index) => git_repos[index] = *repo,
+                    None => git_repos.push(*repo),
                 }
             }
-            Err(_) => {
-                err_len += 1;
+            RepoEvent::LocalFinished => {
+                if let Some(progress) = progress {
+                    let mut repos = git_repos.clone();
+                    repos.sort_by_key(|item| item.last_commit_time);
+                    repos.reverse();
+                    let _ = progress.send(ScanUpdate::Local {
+                        root: root.clone(),
+                        repos,
+                        errors: errors.clone(),
+                    });
+                }
             }
+            RepoEvent::RootScanned { root: path, repos } => {
+                if let Some(progress) = progress {
+                    let _ = progress.send(ScanUpdate::RootScanned {
+                        root: root.clone(),
+                        path,
+                        repos,
+                    });
+                }
+            }
+            RepoEvent::Error { path, error } => errors.push(RepoError { path, error }),
+            RepoEvent::Failed(err) => return Err(ba_error(&err)),
+            RepoEvent::Discovered(_) | RepoEvent::Finished { .. } => {}
         }
     }
 
     git_repos.sort_by_key(|item| item.last_commit_time);
     git_repos.reverse();
 
-    Ok((git_repos, err_len))
+    // 搜索完成之后保存
+    save_repo_cache(&root, &git_repos)?;
+
+    Ok((git_repos, errors))
 }
 
-pub async fn get_all_git_repo(search_path: &Path) -> BDEResult<(Vec<GitRepo>, u64)> {
-    let mut all_git_paths = search_all_git_path(search_path)?;
+#[cfg(test)]
+mod test {
+    use super::{
+        frecency, record_status, search_all_git_path, search_all_git_path_with, worktree_parent,
+        ActivePolicy, GitRepo, GitStatus, SearchOptions, STATUS_HISTORY_LEN,
+    };
+    use crate::paths::ExcludePattern;
+    use crate::utils::git_output;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
-    let repos = load_all_repo()?;
-    let (res_repos, err_len) = if let Some(repos) = repos {
-        // 刷新旧 Git repo 状态
-        let mut set = JoinSet::new();
-        for repo in repos {
-            all_git_paths.retain(|item| item != &repo.path);
+    #[test]
+    fn test_worktree_parent() {
+        let dir = std::env::temp_dir().join(format!("gtm-worktree-{}", std::process::id()));
+        let worktree = dir.join("feature");
+        let submodule = dir.join("main/lib");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::create_dir_all(&submodule).unwrap();
 
-            set.spawn(async move {
-                match GitRepo::build_from_last(repo).await {
-                    Ok(repo) => Some(repo),
-                    Err(_) => {
-                        // println!("build err({}): {}", repo.path.display(), err);
-                        None
-                    }
-                }
-            });
-        }
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}/main/.git/worktrees/feature\n", dir.display()),
+        )
+        .unwrap();
+        fs::write(submodule.join(".git"), "gitdir: ../.git/modules/lib\n").unwrap();
 
-        // 将新增加的 Git repo 路径写入
-        for path in all_git_paths {
-            set.spawn(async move {
-                match GitRepo::build(&path).await {
-                    Ok(repo) => Some(repo),
-                    Err(_) => {
-                        // println!("build err({}): {}", repo.path.display(), err);
-                        None
-                    }
-                }
-            });
-        }
+        assert_eq!(worktree_parent(&worktree), Some(dir.join("main")));
+        assert_eq!(worktree_parent(&submodule), None);
+        assert_eq!(worktree_parent(&dir), None);
 
-        let mut git_repos: Vec<GitRepo> = Vec::new();
-        let mut err_len = 0;
-        while let Some(res) = set.join_next().await {
-            match res {
-                Ok(repo) => {
-                    if let Some(repo) = repo {
-                        git_repos.push(repo);
-                    } else {
-                        err_len += 1;
-                    }
-                }
-                Err(_) => {
-                    err_len += 1;
-                }
-            }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_search_all_git_path() {
+        let dir = std::env::temp_dir().join(format!("gtm-search-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a/.git/modules/lib")).unwrap();
+        fs::create_dir_all(dir.join("b/c/.git")).unwrap();
+        fs::create_dir_all(dir.join(".cache/d/.git")).unwrap();
+        fs::create_dir_all(dir.join("a/lib")).unwrap();
+        fs::write(dir.join("a/lib/.git"), "gitdir: ../.git/modules/lib\n").unwrap();
+
+        let mut paths = search_all_git_path(&dir).unwrap();
+        paths.sort();
+        assert_eq!(paths, vec![dir.join("a"), dir.join("b/c")]);
+
+        let options = SearchOptions {
+            exclude: vec![ExcludePattern::new("**/b/**", Path::new(""))],
+            ..SearchOptions::default()
+        };
+        let mut paths = search_all_git_path_with(&dir, &options).unwrap();
+        paths.sort();
+        assert_eq!(paths, vec![dir.join(".cache/d"), dir.join("a")]);
+
+        let options = SearchOptions {
+            max_depth: 1,
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            search_all_git_path_with(&dir, &options).unwrap(),
+            vec![dir.join("a")]
+        );
+
+        // 指向上层目录的符号链接只在跟随时进入, 并且不会循环
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&dir, dir.join("b/loop")).unwrap();
+            std::os::unix::fs::symlink(dir.join("b"), dir.join("link")).unwrap();
+            let mut paths = search_all_git_path(&dir).unwrap();
+            paths.sort();
+            assert_eq!(paths, vec![dir.join("a"), dir.join("b/c")]);
+            let options = SearchOptions {
+                follow_symlinks: true,
+                ..SearchOptions::default()
+            };
+            let paths = search_all_git_path_with(&dir, &options).unwrap();
+            assert_eq!(paths.len(), 2);
         }
 
-        git_repos.sort_by_key(|item| item.last_commit_time);
-        git_repos.reverse();
+        fs::remove_dir_all(dir).unwrap();
+    }
 
-        (git_repos, err_len)
-    } else {
-        // 本地搜索
-        let res = generate_git_repo(all_git_paths).await?;
-        (res.0, res.1)
-    };
-    // 搜索完成之后保存
-    save_all_git_repo(&res_repos)?;
+    #[test]
+    fn test_create_tag() {
+        let dir = std::env::temp_dir().join(format!("gtm-tags-{}", std::process::id()));
+        let repo = git2::Repository::init(&dir).unwrap();
+        let signature = git2::Signature::new("a", "a@b.c", &git2::Time::new(100, 0)).unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "a").unwrap();
+        config.set_str("user.email", "a@b.c").unwrap();
+        repo.tag_lightweight("light", &repo.find_object(commit, None).unwrap(), false)
+            .unwrap();
 
-    Ok((res_repos, err_len))
-}
+        GitRepo::create_tag(&dir, "v1.0", "release 1.0\n\nnotes").unwrap();
+        assert!(GitRepo::create_tag(&dir, "v1.0", "again").is_err());
+        assert!(GitRepo::create_tag(&dir, "bad..name", "bad").is_err());
 
-#[cfg(test)]
-mod test {
-    use super::GitStatus;
+        let tags = GitRepo::get_git_tags(&dir);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].name, "v1.0");
+        assert_eq!(tags[0].message, "release 1.0");
+        assert_eq!(tags[0].id, commit.to_string()[..7]);
+        assert_eq!(tags[1].name, "light");
+        assert_eq!(tags[1].time, 100);
+        assert_eq!(tags[1].message, "");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflicted_files() {
+        let dir = std::env::temp_dir().join(format!("gtm-conflict-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let git = |args: &[&str]| {
+            let mut full = vec!["-c", "user.name=a", "-c", "user.email=a@b.c"];
+            full.extend(args);
+            let _ = git_output(&dir, full);
+        };
+        git(&["init", "-q", "-b", "main"]);
+        fs::write(dir.join("f"), "base\n").unwrap();
+        fs::write(dir.join("g"), "base\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "base"]);
+        git(&["checkout", "-q", "-b", "other"]);
+        fs::write(dir.join("f"), "other\n").unwrap();
+        git(&["commit", "-q", "-am", "other"]);
+        git(&["checkout", "-q", "main"]);
+        fs::write(dir.join("f"), "main\n").unwrap();
+        fs::write(dir.join("g"), "main\n").unwrap();
+        git(&["commit", "-q", "-am", "main"]);
+        assert!(GitRepo::get_conflicted_files(&dir).is_empty());
+
+        // 有冲突时 merge 失败
+        git(&["merge", "-q", "other"]);
+        assert_eq!(GitRepo::get_conflicted_files(&dir), vec![String::from("f")]);
+        assert_eq!(GitRepo::get_head_state(&dir), Some(GitStatus::Conflicted));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_status_from_str() {
+        assert_eq!("NeedPush".parse(), Ok(GitStatus::NeedPush));
+        assert_eq!("需要推送".parse(), Ok(GitStatus::NeedPush));
+        assert_eq!("Unknown".parse::<GitStatus>(), Err(()));
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            GitRepo::parse_tags(" work  favorite work "),
+            vec!["work", "favorite"]
+        );
+    }
+
+    #[test]
+    fn test_fetch_stalep() {
+        let now = 10 * 24 * 3600;
+        let mut repo: GitRepo = serde_json::from_str(
+            r#"{"name": "test", "path": "/tmp/test", "status": "NeedPull", "last_commit_time": 0}"#,
+        )
+        .unwrap();
+        assert!(!repo.fetch_stalep(now, 3600));
+        repo.has_remote = true;
+        assert!(repo.fetch_stalep(now, 3600));
+        repo.last_fetch_success = now - 100;
+        assert!(!repo.fetch_stalep(now, 3600));
+        repo.last_fetch_success = now - 7200;
+        assert!(repo.fetch_stalep(now, 3600));
+    }
+
+    #[test]
+    fn test_active_policy() {
+        let policy = ActivePolicy {
+            active_days: 1,
+            dormant_fetch_interval: 100,
+        };
+        let now = 10 * 24 * 3600;
+        let mut repo = GitRepo {
+            name: String::from("test"),
+            path: PathBuf::from("/tmp/test"),
+            status: GitStatus::Clean,
+            last_commit_time: now - 3600,
+            last_commit_summary: String::new(),
+            last_commit_author: String::new(),
+            tags: Vec::new(),
+            large_untracked: Vec::new(),
+            last_fetch_time: now,
+            last_fetch_success: now,
+            has_remote: true,
+            has_upstream: true,
+            submodules: Vec::new(),
+            branch: String::from("master"),
+            branches: Vec::new(),
+            worktree_of: None,
+            nested_in: None,
+            remote_url: String::new(),
+            remote_urls: Vec::new(),
+            remote_branches: Vec::new(),
+            default_branch: None,
+            fetch_backend: None,
+            no_fetch: false,
+            status_history: Vec::new(),
+            visits: Vec::new(),
+            root_commit: None,
+            size: None,
+            forge_status: None,
+            aliases: Vec::new(),
+        };
+        assert!(policy.need_fetch(&repo, now));
+
+        repo.last_commit_time = 0;
+        assert!(!policy.need_fetch(&repo, now));
+
+        repo.last_fetch_time = now - 100;
+        assert!(policy.need_fetch(&repo, now));
+    }
+
+    #[test]
+    fn test_frecency() {
+        let day = 24 * 3600;
+        let now = 365 * day;
+        assert_eq!(frecency(&[], now), 0);
+        // 最近打开一次比很久以前打开几次分数高
+        assert!(frecency(&[now - 3600], now) > frecency(&[0, day, 2 * day], now));
+        assert!(frecency(&[now - 3600, now - 2 * day], now) > frecency(&[now - 3600], now));
+    }
+
+    #[test]
+    fn test_record_status() {
+        let history = record_status(Vec::new(), GitStatus::Clean, 10);
+        let history = record_status(history, GitStatus::Clean, 20);
+        let history = record_status(history, GitStatus::Timeout, 30);
+        let history = record_status(history, GitStatus::NeedPush, 40);
+        let history = record_status(history, GitStatus::NeedPush, 50);
+        let changes: Vec<(GitStatus, u64)> = history
+            .iter()
+            .map(|record| (record.status, record.time))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![(GitStatus::Clean, 10), (GitStatus::NeedPush, 40)]
+        );
+
+        let history = (0..STATUS_HISTORY_LEN as u64 + 5).fold(history, |history, time| {
+            let status = if time % 2 == 0 {
+                GitStatus::Clean
+            } else {
+                GitStatus::NeedCommit
+            };
+            record_status(history, status, time)
+        });
+        assert_eq!(history.len(), STATUS_HISTORY_LEN);
+    }
 
     #[test]
     // #[ignore]
     fn test() {
-        println!("status: {}", GitStatus::NeedPull.to_string());
+        println!("status: {}", GitStatus::NeedPull);
     }
 }
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use crate::forge::CiState;
+use crate::format::format_relative_time;
+use crate::gitrepo::{GitRepo, GitStatus};
+use crate::i18n::{tr, trf};
+
+/// 健康分数各项的权重
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HealthWeights {
+    /// 最近有提交
+    pub activity: f64,
+    /// 工作区干净并且和远程同步
+    pub clean: f64,
+    /// 当前分支设置了上游分支
+    pub upstream: f64,
+    /// CI 通过
+    pub ci: f64,
+    /// 有远程仓库并且所有提交都已经推送
+    pub backup: f64,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        HealthWeights {
+            activity: 1.0,
+            clean: 1.0,
+            upstream: 1.0,
+            ci: 1.0,
+            backup: 1.0,
+        }
+    }
+}
+
+/// 健康分数中的一项, score 为 None 表示无法得知, 不计入总分
+#[derive(Debug, Clone)]
+pub struct HealthFactor {
+    pub name: &'static str,
+    pub score: Option<f64>,
+    pub weight: f64,
+    pub reason: String,
+}
+
+/// 超过这么多天没有提交, 活跃度为 0
+const INACTIVE_DAYS: f64 = 365.0;
+
+pub fn health_breakdown(
+    repo: &GitRepo,
+    weights: &HealthWeights,
+    active_days: u64,
+    now: u64,
+) -> Vec<HealthFactor> {
+    let commit_days = now.saturating_sub(repo.last_commit_time) as f64 / 86400.0;
+    let activity = if commit_days <= active_days as f64 {
+        1.0
+    } else {
+        (1.0 - (commit_days - active_days as f64) / INACTIVE_DAYS).max(0.0)
+    };
+
+    let clean = match repo.status {
+        GitStatus::Clean => 1.0,
+        GitStatus::NeedPull | GitStatus::NeedPush => 0.5,
+        GitStatus::DetachedHead | GitStatus::Untracked | GitStatus::AuthRequired => 0.5,
+        GitStatus::NeedCommit
+        | GitStatus::Timeout
+        | GitStatus::Rebasing
+        | GitStatus::Merging
+        | GitStatus::Conflicted
+        | GitStatus::Missing => 0.0,
+    };
+
+    let backup = if !repo.has_remote {
+        0.0
+    } else if repo.status == GitStatus::NeedPush {
+        0.5
+    } else {
+        1.0
+    };
+
+    // 没有 CI 或者还在运行时不计入
+    let ci = match repo.forge_status.as_ref().map(|status| status.ci) {
+        Some(CiState::Success) => Some(1.0),
+        Some(CiState::Failure) => Some(0.0),
+        _ => None,
+    };
+
+    vec![
+        HealthFactor {
+            name: tr("活跃度", "Activity"),
+            score: Some(activity),
+            weight: weights.activity,
+            reason: trf!(
+                "{}提交",
+                "committed {}",
+                format_relative_time(repo.last_commit_time, now)
+            ),
+        },
+        HealthFactor {
+            name: tr("状态", "Status"),
+            score: Some(clean),
+            weight: weights.clean,
+            reason: repo.status.to_string(),
+        },
+        HealthFactor {
+            name: tr("上游分支", "Upstream"),
+            score: Some(if repo.has_upstream { 1.0 } else { 0.0 }),
+            weight: weights.upstream,
+            reason: String::from(if repo.has_upstream {
+                tr("已设置", "set")
+            } else {
+                tr("未设置", "not set")
+            }),
+        },
+        HealthFactor {
+            name: "CI",
+            score: ci,
+            weight: weights.ci,
+            reason: match &repo.forge_status {
+                Some(status) if ci.is_some() => format!("{} ({})", status.ci, status.branch),
+                _ => String::from(tr("未知, 不计入", "unknown, not counted")),
+            },
+        },
+        HealthFactor {
+            name: tr("备份", "Backup"),
+            score: Some(backup),
+            weight: weights.backup,
+            reason: String::from(if !repo.has_remote {
+                tr("没有远程仓库", "no remote")
+            } else if repo.status == GitStatus::NeedPush {
+                tr("有提交没有推送", "unpushed commits")
+            } else {
+                tr("已推送到远程仓库", "pushed to remote")
+            }),
+        },
+    ]
+}
+
+/// 0 到 100 的健康分数, 为各项分数的加权平均
+pub fn health_score(factors: &[HealthFactor]) -> u8 {
+    let mut total = 0.0;
+    let mut total_weight = 0.0;
+
+    for factor in factors {
+        if let Some(score) = factor.score {
+            total += score * factor.weight;
+            total_weight += factor.weight;
+        }
+    }
+
+    if total_weight <= 0.0 {
+        0
+    } else {
+        (total / total_weight * 100.0).round() as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{health_score, HealthFactor};
+
+    #[test]
+    fn test_health_score() {
+        let factors = vec![
+            HealthFactor {
+                name: "a",
+                score: Some(1.0),
+                weight: 3.0,
+                reason: String::new(),
+            },
+            HealthFactor {
+                name: "b",
+                score: Some(0.0),
+                weight: 1.0,
+                reason: String::new(),
+            },
+            HealthFactor {
+                name: "c",
+                score: None,
+                weight: 10.0,
+                reason: String::new(),
+            },
+        ];
+        assert_eq!(health_score(&factors), 75);
+        assert_eq!(health_score(&[]), 0);
+    }
+}
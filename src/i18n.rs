@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Language {
+    /// 根据语言选择中文或者英文
+    pub fn pick<T>(self, zh: T, en: T) -> T {
+        match self {
+            Language::Zh => zh,
+            Language::En => en,
+        }
+    }
+}
+
+/// 当前的界面语言, 启动时根据配置设置
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::En,
+        _ => Language::Zh,
+    }
+}
+
+/// 根据当前语言选择文本
+pub fn tr(zh: &'static str, en: &'static str) -> &'static str {
+    language().pick(zh, en)
+}
+
+/// 根据当前语言选择格式化的文本, 用法和 format! 相同:
+/// trf!("{} 个分支", "{} branches", len)
+macro_rules! trf {
+    ($zh:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::i18n::language() {
+            $crate::i18n::Language::Zh => format!($zh $(, $arg)*),
+            $crate::i18n::Language::En => format!($en $(, $arg)*),
+        }
+    };
+}
+
+pub(crate) use trf;
+
+#[cfg(test)]
+mod test {
+    use super::Language;
+
+    #[test]
+    fn test_language() {
+        assert_eq!(Language::Zh.pick("干净", "clean"), "干净");
+        assert_eq!(Language::En.pick("干净", "clean"), "clean");
+        assert_eq!(
+            serde_json::from_str::<Language>("\"en\"").unwrap(),
+            Language::En
+        );
+    }
+}
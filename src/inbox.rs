@@ -0,0 +1,254 @@
+use std::path::PathBuf;
+
+use crate::gitrepo::{GitRepo, GitStatus};
+use crate::i18n::{tr, trf};
+use crate::states::AppAction;
+
+/// 待处理列表中的一项, 每项对应一个可以直接执行的操作
+#[derive(Debug, Clone)]
+pub struct AttentionItem {
+    pub path: PathBuf,
+    pub name: String,
+    /// 需要处理的问题, 比如 "3 个提交需要推送"
+    pub message: String,
+    /// 按 Enter 执行的操作, 在选中这个仓库之后执行
+    pub action: AppAction,
+    pub action_name: &'static str,
+    /// 越小越需要优先处理
+    pub priority: u8,
+}
+
+impl AttentionItem {
+    fn new(repo: &GitRepo, priority: u8, message: String, action: AppAction) -> Self {
+        AttentionItem {
+            path: repo.path.clone(),
+            name: repo.name.clone(),
+            message,
+            action,
+            action_name: action_name(action),
+            priority,
+        }
+    }
+}
+
+fn action_name(action: AppAction) -> &'static str {
+    match action {
+        AppAction::SelectOpenEditor => tr("打开编辑器", "open editor"),
+        AppAction::OpenMergeTool => tr("解决冲突", "resolve conflicts"),
+        AppAction::ToggleIgnoreRepo => tr("隐藏", "hide"),
+        AppAction::StartRefresh => tr("重新刷新", "refresh"),
+        AppAction::RefreshSelected => tr("重新检查", "recheck"),
+        AppAction::StartIgnoreUntracked => tr("忽略", "ignore"),
+        AppAction::StartResolveDiverge => tr("解决分叉", "resolve divergence"),
+        AppAction::SelectPull => tr("拉取", "pull"),
+        AppAction::SelectPush => tr("推送", "push"),
+        AppAction::OpenBranches => tr("切换分支", "switch branch"),
+        _ => tr("查看详情", "open detail"),
+    }
+}
+
+/// 一个仓库需要处理的问题
+pub fn repo_attention_items(repo: &GitRepo) -> Vec<AttentionItem> {
+    let mut items = Vec::new();
+    let (ahead, behind) = repo.current_ahead_behind();
+
+    match repo.status {
+        GitStatus::Conflicted => items.push(AttentionItem::new(
+            repo,
+            0,
+            String::from(tr("有冲突没有解决", "unresolved conflicts")),
+            AppAction::OpenMergeTool,
+        )),
+        GitStatus::Merging => items.push(AttentionItem::new(
+            repo,
+            0,
+            String::from(tr("合并没有完成", "merge in progress")),
+            AppAction::SelectOpenEditor,
+        )),
+        GitStatus::Rebasing => items.push(AttentionItem::new(
+            repo,
+            0,
+            String::from(tr("变基没有完成", "rebase in progress")),
+            AppAction::SelectOpenEditor,
+        )),
+        GitStatus::Missing => items.push(AttentionItem::new(
+            repo,
+            1,
+            String::from(tr("路径已经不存在", "path no longer exists")),
+            AppAction::ToggleIgnoreRepo,
+        )),
+        GitStatus::AuthRequired => items.push(AttentionItem::new(
+            repo,
+            1,
+            String::from(tr(
+                "远程仓库需要认证, 已停止自动 fetch",
+                "remote requires authentication, automatic fetch stopped",
+            )),
+            AppAction::RefreshSelected,
+        )),
+        GitStatus::Timeout => items.push(AttentionItem::new(
+            repo,
+            1,
+            String::from(tr("网络检查超时", "network check timed out")),
+            AppAction::StartRefresh,
+        )),
+        GitStatus::NeedPull if ahead > 0 && behind > 0 => items.push(AttentionItem::new(
+            repo,
+            2,
+            trf!(
+                "和上游分叉: 领先 {}, 落后 {}",
+                "diverged from upstream: {} ahead, {} behind",
+                ahead,
+                behind
+            ),
+            AppAction::StartResolveDiverge,
+        )),
+        GitStatus::NeedPull => items.push(AttentionItem::new(
+            repo,
+            3,
+            if behind > 0 {
+                trf!("{} 个提交需要拉取", "{} commits to pull", behind)
+            } else {
+                String::from(tr("需要拉取", "needs pull"))
+            },
+            AppAction::SelectPull,
+        )),
+        GitStatus::NeedPush => items.push(AttentionItem::new(
+            repo,
+            3,
+            if ahead > 0 {
+                trf!("{} 个提交需要推送", "{} commits to push", ahead)
+            } else {
+                String::from(tr("需要推送", "needs push"))
+            },
+            AppAction::SelectPush,
+        )),
+        GitStatus::NeedCommit if !repo.large_untracked.is_empty() => {
+            items.push(AttentionItem::new(
+                repo,
+                4,
+                trf!(
+                    "未跟踪的大目录: {}",
+                    "large untracked directories: {}",
+                    repo.large_untracked.join(" ")
+                ),
+                AppAction::StartIgnoreUntracked,
+            ))
+        }
+        GitStatus::NeedCommit => items.push(AttentionItem::new(
+            repo,
+            4,
+            String::from(tr("有修改没有提交", "uncommitted changes")),
+            AppAction::SelectOpenEditor,
+        )),
+        GitStatus::Untracked => items.push(AttentionItem::new(
+            repo,
+            5,
+            String::from(tr("有未跟踪的文件", "untracked files")),
+            AppAction::SelectOpenEditor,
+        )),
+        GitStatus::DetachedHead => items.push(AttentionItem::new(
+            repo,
+            5,
+            String::from(tr("HEAD 不在任何分支上", "HEAD is detached")),
+            AppAction::OpenBranches,
+        )),
+        GitStatus::Clean => {}
+    }
+
+    if repo.other_branch_need_push() {
+        items.push(AttentionItem::new(
+            repo,
+            4,
+            String::from(tr(
+                "其他分支有提交没有推送",
+                "other branches have unpushed commits",
+            )),
+            AppAction::OpenBranches,
+        ));
+    }
+    if repo.submodule_dirty() {
+        items.push(AttentionItem::new(
+            repo,
+            5,
+            String::from(tr("子模块有修改", "submodules changed")),
+            AppAction::OpenDetail,
+        ));
+    }
+
+    items
+}
+
+/// 所有仓库需要处理的问题, 按优先级排序
+pub fn attention_items<'a>(repos: impl IntoIterator<Item = &'a GitRepo>) -> Vec<AttentionItem> {
+    let mut items: Vec<AttentionItem> = repos.into_iter().flat_map(repo_attention_items).collect();
+    items.sort_by_key(|item| item.priority);
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::attention_items;
+    use crate::gitrepo::{BranchInfo, GitRepo, GitStatus};
+    use crate::states::AppAction;
+
+    fn repo(name: &str, status: GitStatus) -> GitRepo {
+        GitRepo {
+            name: String::from(name),
+            path: PathBuf::from("/tmp").join(name),
+            status,
+            last_commit_time: 0,
+            last_commit_summary: String::new(),
+            last_commit_author: String::new(),
+            tags: Vec::new(),
+            large_untracked: Vec::new(),
+            last_fetch_time: 0,
+            last_fetch_success: 0,
+            has_remote: true,
+            has_upstream: true,
+            submodules: Vec::new(),
+            branch: String::from("master"),
+            branches: Vec::new(),
+            worktree_of: None,
+            nested_in: None,
+            remote_url: String::new(),
+            remote_urls: Vec::new(),
+            remote_branches: Vec::new(),
+            default_branch: None,
+            fetch_backend: None,
+            no_fetch: false,
+            status_history: Vec::new(),
+            visits: Vec::new(),
+            root_commit: None,
+            size: None,
+            forge_status: None,
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_attention_items() {
+        let mut push = repo("push", GitStatus::NeedPush);
+        push.branches = vec![BranchInfo {
+            name: String::from("master"),
+            upstream: Some(String::from("origin/master")),
+            ahead: 3,
+            behind: 0,
+        }];
+        let repos = vec![
+            repo("clean", GitStatus::Clean),
+            push,
+            repo("merge", GitStatus::Merging),
+        ];
+
+        let items = attention_items(&repos);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].name, "merge");
+        assert!(matches!(items[0].action, AppAction::SelectOpenEditor));
+        assert_eq!(items[1].name, "push");
+        assert!(items[1].message.contains('3'));
+        assert!(matches!(items[1].action, AppAction::SelectPush));
+    }
+}
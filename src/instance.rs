@@ -0,0 +1,107 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+use crate::utils::BDEResult;
+
+/// 当前实例持有的锁文件, 持有锁的实例才会写入缓存
+static INSTANCE_LOCK: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+const LOCK_FILE_NAME: &str = "instance.lock";
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use winapi::shared::minwindef::{DWORD, FALSE};
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::minwinbase::STILL_ACTIVE;
+    use winapi::um::processthreadsapi::{GetExitCodeProcess, OpenProcess};
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    // SAFETY: 打开的句柄在返回之前关闭, exit_code 在调用期间有效
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+        if handle.is_null() {
+            // 没有权限打开时进程仍然存在
+            return std::io::Error::last_os_error().raw_os_error()
+                == Some(winapi::shared::winerror::ERROR_ACCESS_DENIED as i32);
+        }
+        let mut exit_code: DWORD = 0;
+        let ok = GetExitCodeProcess(handle, &mut exit_code);
+        CloseHandle(handle);
+        ok != 0 && exit_code == STILL_ACTIVE
+    }
+}
+
+#[cfg(not(windows))]
+fn process_alive(pid: u32) -> bool {
+    if Path::new("/proc").exists() {
+        return Path::new(&format!("/proc/{}", pid)).exists();
+    }
+
+    process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// 正在运行并且持有锁的其它实例的 PID
+pub fn lock_owner(dir: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(dir.join(LOCK_FILE_NAME))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    (pid != process::id() && process_alive(pid)).then_some(pid)
+}
+
+/// 尝试获取锁, 锁被正在运行的其它实例持有时返回 false, 已经退出的实例留下的锁会被清理
+pub fn try_acquire(dir: &Path) -> BDEResult<bool> {
+    let Ok(mut lock) = INSTANCE_LOCK.lock() else {
+        return Ok(false);
+    };
+    if lock.is_some() {
+        return Ok(true);
+    }
+
+    let lock_path = dir.join(LOCK_FILE_NAME);
+    for _ in 0..2 {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(mut file) => {
+                write!(file, "{}", process::id())?;
+                *lock = Some(lock_path);
+                return Ok(true);
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                if lock_owner(dir).is_some() {
+                    return Ok(false);
+                }
+                fs::remove_file(&lock_path)?;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(false)
+}
+
+/// 当前实例持有锁
+pub fn holding() -> bool {
+    INSTANCE_LOCK.lock().is_ok_and(|lock| lock.is_some())
+}
+
+/// 退出时释放锁
+pub fn release() {
+    if let Ok(mut lock) = INSTANCE_LOCK.lock() {
+        if let Some(lock_path) = lock.take() {
+            let _ = fs::remove_file(lock_path);
+        }
+    }
+}
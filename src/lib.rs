@@ -1,35 +1,231 @@
+//! 管理本地所有 Git 仓库的库和终端界面
+//!
+//! 界面 (gtm) 只是其中一个使用者, 其它工具可以直接使用下面的模块:
+//!
+//! - [`repo`] 模块: 仓库的数据类型, 状态检查和缓存
+//! - [`scan`] 模块: 在后台扫描目录并逐个返回仓库的状态
+//! - [`ops`] 模块: 拉取, 推送和 fetch 等 git 操作
+//! - [`paths`] 模块: 展开配置中的搜索目录, 显示时把 HOME 替换为 ~
+//!
+//! 所有操作的错误类型为 [`GitManagerError`]
+
 use crossterm::{
     event::{self, Event, KeyCode},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use ratatui::prelude::*;
-use std::io::stdout;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsString;
+use std::fs;
+use std::io::stderr;
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 
+mod activity;
+mod authors;
+mod backend;
+mod clipboard;
 mod components;
+mod config;
+pub mod credentials;
+mod diverge;
+mod error;
+mod fetch;
+mod forge;
+mod format;
+mod gitignore;
 mod gitrepo;
+mod health;
+mod i18n;
+mod inbox;
+mod instance;
+mod logging;
+mod manifest;
+mod metrics;
+mod multiplexer;
+pub mod ops;
+pub mod paths;
+pub mod repo;
+pub mod scan;
+mod script;
+mod search;
+mod size;
+mod stale;
 mod states;
+mod store;
+mod theme;
+mod tombstone;
+mod trash;
+mod tui;
 pub mod utils;
 
-use gitrepo::get_all_git_repo;
-use gitrepo::GitRepo;
+pub use error::GitManagerError;
+pub use repo::{GitRepo, GitStatus};
+pub use scan::{scan_stream, RepoEvent, RepoStream, ScanOptions};
+
+use activity::{commit_activity, head_id, heatmap_lines, Activity};
+use authors::{
+    branch_tips, git_user_email, repo_authors, summarize, AuthorSummary, AuthorsEvent, RepoAuthors,
+};
+use clipboard::copy_to_clipboard;
+use config::{load_config, Config, NestedRepos};
+use diverge::DivergeInfo;
+use forge::{forge_status, Forge, ForgeStatus, RemoteUrl};
+use format::{format_relative_time, Formatter};
+use gitignore::gitignore_diff;
+use gitrepo::RepoError;
+use gitrepo::{
+    get_all_git_repo, get_save_data_dir, load_all_repo, load_filter_history, load_ignore_repos,
+    load_maintenance_time, load_repo_cache, save_all_git_repo, save_filter_history,
+    save_ignore_repos, save_maintenance_time, set_cache_root, ScanUpdate,
+};
+use health::{health_breakdown, health_score};
+use i18n::{tr, trf};
+use inbox::{attention_items, AttentionItem};
+use metrics::WORKER_METRICS;
+use ops::{GitOperation, OpEvent, OpState};
+use paths::expand_path;
+use script::{parse_script, ScriptCommand};
+use search::{
+    search_commits, search_files, CommitMatch, CommitQuery, FileMatch, FileQuery, SearchEvent,
+};
+use size::{repo_size, RepoSize};
+use stale::{delete_branch, stale_branches};
 use states::{AppAction, AppMode};
-use utils::{copy_to_clipboard, BDEResult};
+use store::RepoStore;
+use theme::Theme;
+use tombstone::{load_tombstones, save_tombstones, Tombstone, TombstoneReason};
+use trash::{move_repo, move_to_archive, move_to_trash};
+use utils::{
+    ba_error, editor_args, now_timestamp, open_url, run_command_stream, shell_quote, BDEResult,
+    Cmd, CommandOutput,
+};
+
+use components::{
+    authors::Authors,
+    branches::Branches,
+    commands::CustomCommands,
+    commit_search::CommitSearch,
+    confirm::Confirm,
+    dashboard::Dashboard,
+    detail::Detail,
+    errors::Errors,
+    file_search::FileSearch,
+    inbox::Inbox,
+    input::Input,
+    logview::LogView,
+    output::OutputPane,
+    palette::Palette,
+    prompt::Prompt,
+    reposhow::{ReposShow, SortBy},
+    stale::StaleBranches,
+    statusbar::StatusBar,
+    tabs::RootTabs,
+    toast::Toasts,
+    tombstones::Tombstones,
+    wizard::Wizard,
+    Component,
+};
 
-use components::{input::Input, reposhow::ReposShow, statusbar::StatusBar, Component};
+/// 详情中最多显示的 git 标签数量
+const MAX_DETAIL_TAGS: usize = 10;
+
+/// 后台计算的仓库大小, 失败时为错误信息
+type SizeResult = (PathBuf, Result<RepoSize, String>);
+/// 后台查询的 CI 状态和 PR 数量, 失败时为错误信息
+type ForgeResult = (PathBuf, Result<ForgeStatus, String>);
+
+/// 一个标签页的搜索目录和切换到其它标签页时保存的状态
+#[derive(Debug, Default)]
+struct RepoTab {
+    name: String,
+    /// 允许扫描的目录, HOME 之外的目录确认之后加入
+    search_paths: Vec<PathBuf>,
+    /// 需要确认的 HOME 之外的目录
+    outside_paths: Vec<PathBuf>,
+    repos: Vec<GitRepo>,
+    errors: Vec<RepoError>,
+    last_refresh: Option<std::time::Instant>,
+    /// 本次运行中已经开始扫描过
+    scannedp: bool,
+}
 
 #[derive(Debug)]
 struct App {
-    repos: Vec<GitRepo>,
+    repos: RepoStore,
+    ignore_repos: Vec<PathBuf>,
     runp: bool,
+    config: Config,
+    /// 时间, 耗时和大小的显示格式, 启动时根据配置生成
+    formatter: Formatter,
+    /// 配色, 启动时根据配置生成
+    theme: Theme,
+    /// 正在创建的仓库路径, 等待输入远程仓库地址
+    init_repo_path: Option<PathBuf>,
+    /// 正在添加远程仓库的仓库路径和远程仓库名字, 等待输入地址
+    add_remote: Option<(PathBuf, String)>,
+    /// 正在创建的标签的仓库和名字
+    new_tag: Option<(PathBuf, String)>,
+    /// 确认删除的仓库, 确认期间列表可能重新排序, 不能使用选中的位置
+    delete_repo: Option<PathBuf>,
+    /// 确认追加到 .gitignore 的仓库和未跟踪的大目录
+    ignore_untracked: Option<(PathBuf, Vec<String>)>,
+    /// 确认追加 .gitignore 模板的仓库和新的行
+    gitignore_template: Option<(PathBuf, Vec<String>)>,
+    /// 输入私钥密码之后继续执行的 action
+    passphrase_action: Option<AppAction>,
+    /// 下一次循环直接执行的 action
+    next_action: Option<AppAction>,
+    /// 下一次定时维护所有仓库的时间, 没有设置维护间隔时为空
+    maintenance_due: Option<u64>,
+    /// 退出之后输出的路径, 用于切换目录
+    exit_path: Option<PathBuf>,
+    /// --print-path 模式, 按 Enter 退出并输出选中的仓库路径
+    print_pathp: bool,
+    /// 无界面运行时剩下的脚本命令
+    script: Option<VecDeque<ScriptCommand>>,
+    /// 正在后台计算大小的仓库
+    size_pending: HashSet<PathBuf>,
+    /// 正在后台查询 CI 状态的仓库
+    forge_pending: HashSet<PathBuf>,
+    /// 上一次查询 CI 状态失败的原因, 只在本次运行中保留
+    forge_errors: HashMap<PathBuf, String>,
+    /// 详情中显示的提交活动, HEAD 改变时重新计算
+    activity: HashMap<PathBuf, Activity>,
+    /// 统计过的提交作者, 分支没有变化时重复使用
+    author_cache: HashMap<PathBuf, RepoAuthors>,
+    /// 详情中显示的仓库, 大小计算完成之后更新详情
+    detail_path: Option<PathBuf>,
+    /// 每个搜索目录一个标签页, 至少有一个
+    tabs: Vec<RepoTab>,
+    /// 当前的标签页
+    tab: usize,
 
     run_mode: AppMode,
 
     component_input: Input,
     component_repos_show: ReposShow,
     component_statusbar: StatusBar,
+    component_prompt: Prompt,
+    component_confirm: Confirm,
+    component_output: OutputPane,
+    component_detail: Detail,
+    component_wizard: Wizard,
+    component_tombstones: Tombstones,
+    component_branches: Branches,
+    component_inbox: Inbox,
+    component_log: LogView,
+    component_errors: Errors,
+    component_dashboard: Dashboard,
+    component_authors: Authors,
+    component_commit_search: CommitSearch,
+    component_file_search: FileSearch,
+    component_stale: StaleBranches,
+    component_commands: CustomCommands,
+    component_tabs: RootTabs,
+    component_toasts: Toasts,
+    component_palette: Palette,
 }
 
 impl App {
@@ -38,7 +234,11 @@ impl App {
             return Ok(None);
         }
 
-        if let Event::Key(key) = event::read()? {
+        let event = event::read()?;
+        if let Event::Resize(_, _) = event {
+            return Ok(Some(AppAction::Resize));
+        }
+        if let Event::Key(key) = event {
             if key.kind == event::KeyEventKind::Press {
                 return Ok(match self.run_mode {
                     AppMode::Normal => match key.code {
@@ -46,6 +246,30 @@ impl App {
                         _ => self.component_repos_show.handle_events(key)?,
                     },
                     AppMode::Editing => self.component_input.handle_events(key)?,
+                    AppMode::Prompt => self.component_prompt.handle_events(key)?,
+                    AppMode::Confirm => self.component_confirm.handle_events(key)?,
+                    AppMode::Output => self.component_output.handle_events(key)?,
+                    AppMode::Detail => self.component_detail.handle_events(key)?,
+                    AppMode::Wizard => self.component_wizard.handle_events(key)?,
+                    AppMode::Tombstones => self.component_tombstones.handle_events(key)?,
+                    AppMode::Branches => self.component_branches.handle_events(key)?,
+                    AppMode::Inbox => self.component_inbox.handle_events(key)?,
+                    AppMode::Log => self.component_log.handle_events(key)?,
+                    AppMode::Errors => self.component_errors.handle_events(key)?,
+                    AppMode::Dashboard => self.component_dashboard.handle_events(key)?,
+                    AppMode::Authors => self.component_authors.handle_events(key)?,
+                    AppMode::CommitSearch => self.component_commit_search.handle_events(key)?,
+                    AppMode::FileSearch => self.component_file_search.handle_events(key)?,
+                    AppMode::StaleBranches => self.component_stale.handle_events(key)?,
+                    AppMode::CustomCommands => self.component_commands.handle_events(key)?,
+                    AppMode::Palette => {
+                        // 选择命令之后先回到普通模式, 命令可能会再进入其它模式
+                        let action = self.component_palette.handle_events(key)?;
+                        if action.is_some() {
+                            self.run_mode = AppMode::Normal;
+                        }
+                        action
+                    }
                 });
             }
         }
@@ -53,13 +277,864 @@ impl App {
         Ok(None)
     }
 
+    /// 拉取和推送的目标仓库, 有标记的仓库时使用标记的仓库, 否则使用选中的仓库
+    fn target_repos(&self) -> Vec<GitRepo> {
+        let marked = &self.component_repos_show.marked;
+        if marked.is_empty() {
+            self.component_repos_show
+                .get_select_repo_id()
+                .map(|repo_id| vec![self.repos[repo_id].clone()])
+                .unwrap_or_default()
+        } else {
+            self.repos
+                .iter()
+                .filter(|repo| marked.contains(&repo.path))
+                .cloned()
+                .collect()
+        }
+    }
+
+    /// 批量执行命令的仓库, 有标记的仓库时使用标记的仓库, 否则使用当前过滤之后显示的所有仓库
+    fn foreach_repos(&self) -> Vec<GitRepo> {
+        if self.component_repos_show.marked.is_empty() {
+            self.component_repos_show
+                .show_repos
+                .iter()
+                .map(|repo| self.repos[repo.id].clone())
+                .collect()
+        } else {
+            self.target_repos()
+        }
+    }
+
+    /// 维护的仓库, 当前标签页中除了丢失的仓库之外的所有仓库
+    fn maintenance_paths(&self) -> Vec<PathBuf> {
+        self.repos
+            .iter()
+            .filter(|repo| repo.status != GitStatus::Missing)
+            .map(|repo| repo.path.clone())
+            .collect()
+    }
+
+    /// 当前显示的仓库生成的 Markdown 报告
+    fn report_markdown(&self) -> String {
+        let mut report = String::from(tr("# Git 仓库报告\n\n", "# Git repository report\n\n"));
+        report.push_str(&trf!(
+            "生成时间: {}\n\n",
+            "Generated at: {}\n\n",
+            self.formatter.timestamp(now_timestamp())
+        ));
+        if !self.component_input.input.trim().is_empty() {
+            report.push_str(&trf!(
+                "过滤: `{}`\n\n",
+                "Filter: `{}`\n\n",
+                self.component_input.input.trim()
+            ));
+        }
+
+        report.push_str(tr(
+            "| 仓库名字 | 仓库路径 | 分支 | 仓库状态 | 标签 | 健康 |\n",
+            "| Name | Path | Branch | Status | Tags | Health |\n",
+        ));
+        report.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for show_repo in &self.component_repos_show.show_repos {
+            let repo = &self.repos[show_repo.id];
+            report.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                repo.name,
+                repo.path.display(),
+                repo.branch,
+                show_repo.status_text,
+                show_repo.tags,
+                show_repo.health
+            ));
+        }
+
+        report
+    }
+
+    /// 没有隐藏的仓库中需要处理的问题
+    fn attention_items(&self) -> Vec<AttentionItem> {
+        attention_items(
+            self.repos
+                .iter()
+                .filter(|repo| !self.ignore_repos.contains(&repo.path)),
+        )
+    }
+
+    /// 脚本模式下弹窗的内容输出到标准错误
+    fn print_popup(&self) {
+        let (title, lines) = match self.run_mode {
+            AppMode::Confirm => (&self.component_confirm.title, &self.component_confirm.lines),
+            AppMode::Detail => (&self.component_detail.title, &self.component_detail.lines),
+            _ => return,
+        };
+
+        eprintln!("[{}]", title);
+        for line in lines {
+            let text: String = line
+                .spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            eprintln!("  {}", text);
+        }
+    }
+
+    /// 后台任务都完成之后再执行下一条脚本命令
+    async fn next_script_action(&mut self) -> BDEResult<Option<AppAction>> {
+        let metrics = WORKER_METRICS.snapshot();
+        if self.component_repos_show.refresh_repop
+            || self.component_output.runningp
+            || metrics.queued + metrics.running > 0
+            || self
+                .component_repos_show
+                .operations
+                .values()
+                .any(OpState::activep)
+        {
+            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+            return Ok(None);
+        }
+
+        // 后台任务的结果在这次循环中才收到, 先更新显示的仓库
+        self.component_repos_show.update_show_repos(
+            &self.repos,
+            &self.ignore_repos,
+            &self.component_input.input,
+        )?;
+
+        let Some(command) = self.script.as_mut().and_then(|script| script.pop_front()) else {
+            return Ok(Some(AppAction::Quit));
+        };
+        self.print_popup();
+        // 只是提示信息的弹窗输出之后直接关闭
+        let infop = match self.run_mode {
+            AppMode::Detail => true,
+            AppMode::Confirm => matches!(
+                self.component_confirm.action,
+                None | Some(AppAction::ExitConfirm)
+            ),
+            _ => false,
+        };
+        if infop {
+            self.run_mode = AppMode::Normal;
+        }
+
+        Ok(Some(match command {
+            ScriptCommand::Refresh => AppAction::StartRefresh,
+            ScriptCommand::Filter(input) => {
+                self.component_input.set_input(&input);
+                AppAction::ExitFilter
+            }
+            ScriptCommand::SelectAll => AppAction::MarkAll,
+            ScriptCommand::Pull => AppAction::SelectPull,
+            ScriptCommand::Push => AppAction::SelectPush,
+            ScriptCommand::Report(path) => {
+                self.component_prompt.input = path.display().to_string();
+                AppAction::ApplyReport
+            }
+            ScriptCommand::Confirm => match self.run_mode {
+                AppMode::Confirm => self
+                    .component_confirm
+                    .action
+                    .take()
+                    .unwrap_or(AppAction::ExitConfirm),
+                _ => {
+                    return Err(ba_error(tr(
+                        "confirm: 当前没有需要确认的弹窗",
+                        "confirm: no confirmation popup is open",
+                    )))
+                }
+            },
+            ScriptCommand::Cancel => match self.run_mode {
+                AppMode::Confirm => AppAction::ExitConfirm,
+                AppMode::Prompt => AppAction::ExitPrompt,
+                AppMode::Detail => AppAction::ExitDetail,
+                AppMode::Wizard => AppAction::ExitWizard,
+                AppMode::Tombstones => AppAction::ExitTombstones,
+                AppMode::Branches => AppAction::ExitBranches,
+                AppMode::Inbox => AppAction::ExitInbox,
+                AppMode::Log => AppAction::ExitLog,
+                AppMode::Errors => AppAction::ExitErrors,
+                AppMode::Dashboard => AppAction::ExitDashboard,
+                AppMode::Authors => AppAction::ExitAuthors,
+                AppMode::CommitSearch => AppAction::ExitCommitSearch,
+                AppMode::FileSearch => AppAction::ExitFileSearch,
+                AppMode::StaleBranches => AppAction::ExitStaleBranches,
+                AppMode::CustomCommands => AppAction::ExitCustomCommands,
+                AppMode::Output => AppAction::ExitOutput,
+                AppMode::Palette => AppAction::ExitPalette,
+                _ => AppAction::ExitFilter,
+            },
+            ScriptCommand::Quit => AppAction::Quit,
+        }))
+    }
+
+    /// 在后台依次计算仓库的大小, 已经在计算的仓库跳过
+    fn start_size_task(
+        &mut self,
+        paths: Vec<PathBuf>,
+        size_tx: &mpsc::UnboundedSender<SizeResult>,
+    ) {
+        let paths: Vec<PathBuf> = paths
+            .into_iter()
+            .filter(|path| self.size_pending.insert(path.clone()))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        let size_tx = size_tx.clone();
+        let mut task = WORKER_METRICS.queue();
+        tokio::spawn(async move {
+            task.start();
+            for path in paths {
+                let size_path = path.clone();
+                let res = tokio::task::spawn_blocking(move || {
+                    repo_size(&size_path, now_timestamp()).map_err(|err| err.to_string())
+                })
+                .await
+                .unwrap_or_else(|err| Err(err.to_string()));
+                let _ = size_tx.send((path, res));
+            }
+        });
+    }
+
+    /// 搜索提交和文件的仓库, 不包括隐藏和已经丢失的仓库, 工作树只在 worktreesp 时搜索
+    fn search_repos(&self, worktreesp: bool) -> Vec<(PathBuf, String)> {
+        self.repos
+            .iter()
+            .filter(|repo| {
+                !self.ignore_repos.contains(&repo.path)
+                    && (worktreesp || repo.worktree_of.is_none())
+                    && repo.status != GitStatus::Missing
+            })
+            .map(|repo| (repo.path.clone(), repo.name.clone()))
+            .collect()
+    }
+
+    /// 作者统计的起始时间, 按天对齐, 同一天内可以重复使用缓存
+    fn authors_since(&self) -> u64 {
+        let today = now_timestamp() / 86400 * 86400;
+        today.saturating_sub(self.component_authors.days() * 86400)
+    }
+
+    /// 统计的仓库, 不包括隐藏的仓库, 工作树和主仓库的分支相同, 也不包括
+    fn author_repos(&self) -> impl Iterator<Item = &GitRepo> {
+        self.repos
+            .iter()
+            .filter(|repo| !self.ignore_repos.contains(&repo.path) && repo.worktree_of.is_none())
+    }
+
+    fn author_summaries(&self, since: u64) -> Vec<AuthorSummary> {
+        summarize(
+            self.author_repos().filter_map(|repo| {
+                self.author_cache
+                    .get(&repo.path)
+                    .map(|authors| (repo.name.as_str(), authors))
+            }),
+            since,
+        )
+    }
+
+    /// 打开分叉解决向导, 没有分叉或者检查失败时显示原因
+    fn start_resolve_diverge(&mut self, path: &Path) {
+        match DivergeInfo::load(path) {
+            Ok(Some(info)) => {
+                self.component_wizard.start(info);
+                self.run_mode = AppMode::Wizard;
+            }
+            Ok(None) => {
+                self.component_detail.start(
+                    tr("分叉解决向导", "Divergence wizard"),
+                    vec![Line::from(trf!(
+                        "{} 当前分支没有和上游分支分叉.",
+                        "The current branch of {} has not diverged from upstream.",
+                        path.display()
+                    ))],
+                );
+                self.run_mode = AppMode::Detail;
+            }
+            Err(err) => {
+                self.component_detail.start(
+                    tr("分叉解决向导", "Divergence wizard"),
+                    vec![Line::from(trf!(
+                        "检查分叉失败: {}",
+                        "Failed to check divergence: {}",
+                        err
+                    ))],
+                );
+                self.run_mode = AppMode::Detail;
+            }
+        }
+    }
+
+    /// 在操作队列中对 repos 执行 git 操作, 输出窗口空闲时显示输出
+    fn start_git_operation(
+        &mut self,
+        operation: GitOperation,
+        repos: Vec<GitRepo>,
+        output_tx: &mpsc::UnboundedSender<CommandOutput>,
+        op_tx: &mpsc::UnboundedSender<OpEvent>,
+        repo_tx: &mpsc::UnboundedSender<GitRepo>,
+    ) {
+        let git_command = operation.name();
+        // 已经有任务在排队或者执行的仓库不再重复执行
+        let (busy, repos): (Vec<GitRepo>, Vec<GitRepo>) = repos.into_iter().partition(|repo| {
+            self.component_repos_show
+                .operations
+                .get(&repo.path)
+                .is_some_and(OpState::activep)
+        });
+        if !busy.is_empty() {
+            self.component_toasts.info(trf!(
+                "跳过 {} 个正在执行操作的仓库",
+                "Skipped {} repos with a running operation",
+                busy.len()
+            ));
+        }
+        if !repos.is_empty() {
+            let title = match repos.as_slice() {
+                [repo] => format!("git {}: {}", git_command, repo.path.display()),
+                _ => trf!(
+                    "git {}: {} 个仓库",
+                    "git {}: {} repos",
+                    git_command,
+                    repos.len()
+                ),
+            };
+            // 输出窗口正在使用时只在仓库列表中显示任务状态, 不显示输出
+            let output_tx = if self.component_output.runningp {
+                mpsc::unbounded_channel().0
+            } else {
+                self.component_output.start(&title);
+                self.run_mode = AppMode::Output;
+                output_tx.clone()
+            };
+
+            let paths: Vec<PathBuf> = repos.iter().map(|repo| repo.path.clone()).collect();
+            for path in &paths {
+                self.component_repos_show
+                    .operations
+                    .insert(path.clone(), OpState::Queued(operation));
+            }
+            let jobs = self.config.op_jobs;
+            let op_tx = op_tx.clone();
+            let repo_tx = repo_tx.clone();
+            let mut task = WORKER_METRICS.queue();
+            tokio::spawn(async move {
+                task.start();
+                let res = ops::run_jobs(operation, &paths, jobs, output_tx.clone(), op_tx)
+                    .await
+                    .map_err(|err| err.to_string());
+                if let Err(err) = res {
+                    let _ = output_tx.send(CommandOutput::Stderr(err));
+                    let _ = output_tx.send(CommandOutput::Exit(None));
+                }
+
+                for repo in repos {
+                    if let Ok(repo) = GitRepo::build_from_last(repo, true).await {
+                        let _ = repo_tx.send(repo);
+                    }
+                }
+            });
+        }
+    }
+
+    /// 在后台统计所有仓库的提交作者, 分支没有变化的仓库使用缓存
+    fn start_authors_task(&mut self, authors_tx: &mpsc::UnboundedSender<AuthorsEvent>) {
+        let since = self.authors_since();
+        // 只需要缓存的分支和起始时间判断是否需要重新统计
+        let repos: Vec<(PathBuf, Option<RepoAuthors>)> = self
+            .author_repos()
+            .map(|repo| {
+                let cached = self
+                    .author_cache
+                    .get(&repo.path)
+                    .map(|authors| RepoAuthors {
+                        tips: authors.tips.clone(),
+                        since: authors.since,
+                        commits: Vec::new(),
+                    });
+                (repo.path.clone(), cached)
+            })
+            .collect();
+        self.component_authors.progress = Some((0, repos.len()));
+
+        let authors_tx = authors_tx.clone();
+        let mut task = WORKER_METRICS.queue();
+        tokio::spawn(async move {
+            task.start();
+            let done_tx = authors_tx.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                for (path, cached) in repos {
+                    let authors = branch_tips(&path)
+                        .filter(|tips| !cached.is_some_and(|cached| cached.covers(tips, since)))
+                        .and_then(|tips| repo_authors(&path, tips, since));
+                    let _ = authors_tx.send(AuthorsEvent::Repo(path, authors));
+                }
+            })
+            .await;
+            let _ = done_tx.send(AuthorsEvent::Done(since));
+        });
+    }
+
+    /// 在后台查询 CI 状态和 PR 数量, 没有开启, 不支持的平台或者缓存没有过时的时候跳过
+    fn start_forge_task(&mut self, repo: &GitRepo, forge_tx: &mpsc::UnboundedSender<ForgeResult>) {
+        if !self.config.forge_status
+            || fetch::offline()
+            || self.forge_pending.contains(&repo.path)
+            || repo
+                .forge_status
+                .as_ref()
+                .is_some_and(|status| !status.stalep(now_timestamp()))
+        {
+            return;
+        }
+        let Some(remote) = RemoteUrl::parse(&repo.remote_url)
+            .filter(|remote| matches!(remote.forge(), Forge::GitHub | Forge::GitLab))
+        else {
+            return;
+        };
+        let branch = match &repo.default_branch {
+            Some(branch) => branch.split_once('/').map_or(branch.as_str(), |(_, b)| b),
+            None if !repo.branch.is_empty() => repo.branch.as_str(),
+            None => return,
+        }
+        .to_string();
+        let token = self.config.forge_tokens.get(&remote.host).cloned();
+
+        let path = repo.path.clone();
+        self.forge_pending.insert(path.clone());
+        let forge_tx = forge_tx.clone();
+        let mut task = WORKER_METRICS.queue();
+        tokio::spawn(async move {
+            task.start();
+            let res = tokio::task::spawn_blocking(move || {
+                forge_status(&remote, &branch, token.as_deref(), now_timestamp())
+                    .map_err(|err| err.to_string())
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+            let _ = forge_tx.send((path, res));
+        });
+    }
+
+    /// 详情中 CI 状态和 PR 数量的一行, 没有开启时为空
+    fn forge_status_text(&self, repo: &GitRepo) -> Option<String> {
+        if !self.config.forge_status {
+            return None;
+        }
+        if let Some(err) = self.forge_errors.get(&repo.path) {
+            return Some(trf!("CI: 查询失败: {}", "CI: query failed: {}", err));
+        }
+        Some(match &repo.forge_status {
+            _ if self.forge_pending.contains(&repo.path) => {
+                String::from(tr("CI: 正在查询...", "CI: querying..."))
+            }
+            Some(status) => trf!(
+                "CI: {} ({}), 打开的 PR: {}, 查询于 {}",
+                "CI: {} ({}), open PRs: {}, checked {}",
+                status.ci,
+                status.branch,
+                status.open_prs_text(),
+                format_relative_time(status.time, now_timestamp())
+            ),
+            None => String::from(tr("CI: -", "CI: -")),
+        })
+    }
+
+    /// 使用配置中的主题和样式
+    fn apply_theme(&mut self) -> BDEResult<()> {
+        self.theme = Theme::from_name(&self.config.theme).with_styles(&self.config.theme_styles)?;
+        self.component_repos_show.theme = self.theme.clone();
+        self.component_statusbar.theme = self.theme.clone();
+        self.component_tabs.theme = self.theme.clone();
+        self.component_toasts.theme = self.theme.clone();
+        self.component_detail.theme = self.theme.clone();
+        Ok(())
+    }
+
+    /// 复制到剪贴板, 结果显示在提示中
+    fn copy(&mut self, text: &str) {
+        match copy_to_clipboard(text) {
+            Ok(()) => self
+                .component_toasts
+                .info(trf!("已复制: {}", "Copied: {}", text)),
+            Err(err) => {
+                tracing::warn!("copy to clipboard: {}", err);
+                self.component_toasts
+                    .error(trf!("复制失败: {}", "Copy failed: {}", err));
+            }
+        }
+    }
+
+    /// 切换到另一个标签页, 当前标签页的仓库和错误保存起来,
+    /// 本次运行中还没有扫描过的标签页先显示上一次运行的缓存
+    fn switch_tab(&mut self, index: usize) -> BDEResult<()> {
+        let current = &mut self.tabs[self.tab];
+        current.repos = self.repos.iter().cloned().collect();
+        current.errors = self.component_errors.errors.clone();
+        current.last_refresh = self.component_statusbar.last_refresh;
+
+        self.tab = index;
+        self.component_tabs.selected = index;
+        let tab = &mut self.tabs[index];
+        set_cache_root(&tab.name);
+        let repos = if tab.scannedp {
+            std::mem::take(&mut tab.repos)
+        } else {
+            load_all_repo()?.unwrap_or_default()
+        };
+        self.repos.replace_all(repos);
+        self.component_errors
+            .replace_all(std::mem::take(&mut tab.errors));
+        self.component_statusbar.last_refresh = tab.last_refresh;
+        self.component_repos_show.marked.clear();
+        self.component_repos_show.state.select(Some(0));
+        self.component_repos_show.refresh_repop = false;
+        self.detail_path = None;
+        Ok(())
+    }
+
+    /// HEAD 改变之后重新统计仓库的提交活动
+    fn update_activity(&mut self, path: &Path) {
+        let Some(head) = head_id(path) else {
+            self.activity.remove(path);
+            return;
+        };
+        if self
+            .activity
+            .get(path)
+            .is_some_and(|activity| activity.head == head)
+        {
+            return;
+        }
+        match commit_activity(path, now_timestamp(), self.formatter.utc_offset) {
+            Some(activity) => {
+                self.activity.insert(path.to_path_buf(), activity);
+            }
+            None => {
+                self.activity.remove(path);
+            }
+        }
+    }
+
+    fn repo_detail_lines(&self, repo: &GitRepo) -> Vec<Line<'static>> {
+        let heading = self.theme.heading;
+        let mut lines = vec![Line::from(trf!(
+            "路径: {}",
+            "Path: {}",
+            repo.path.display()
+        ))];
+        if !repo.aliases.is_empty() {
+            let aliases: Vec<String> = repo
+                .aliases
+                .iter()
+                .map(|alias| alias.display().to_string())
+                .collect();
+            lines.push(Line::from(trf!(
+                "其它路径: {}",
+                "Aliases: {}",
+                aliases.join(", ")
+            )));
+        }
+        lines.extend([
+            Line::from(match repo.status_since() {
+                Some(since) => trf!(
+                    "状态: {} {} ({}变为这个状态)",
+                    "Status: {} {} (since {})",
+                    repo.status.glyph(),
+                    repo.status,
+                    format_relative_time(since, now_timestamp())
+                ),
+                None => trf!(
+                    "状态: {} {}",
+                    "Status: {} {}",
+                    repo.status.glyph(),
+                    repo.status
+                ),
+            }),
+            Line::from(trf!("标签: {}", "Tags: {}", repo.tags.join(" "))),
+            Line::from(trf!(
+                "最后提交: {} ({})",
+                "Last commit: {} ({})",
+                self.formatter.timestamp(repo.last_commit_time),
+                format_relative_time(repo.last_commit_time, now_timestamp())
+            )),
+            Line::from(trf!("作者: {}", "Author: {}", repo.last_commit_author)),
+            Line::from(match &repo.size {
+                Some(size) => trf!(
+                    "大小: {} (git {}), 对象: {}, 最大的对象: {} {}",
+                    "Size: {} (git {}), objects: {}, largest object: {} {}",
+                    self.formatter.size(size.disk_bytes),
+                    self.formatter.size(size.git_bytes),
+                    size.objects,
+                    size.largest_object_id,
+                    self.formatter.size(size.largest_object)
+                ),
+                None if self.size_pending.contains(&repo.path) => {
+                    String::from(tr("大小: 正在计算...", "Size: calculating..."))
+                }
+                None => String::from(tr("大小: -", "Size: -")),
+            }),
+            Line::from(trf!(
+                "远程仓库: {}",
+                "Remote: {}",
+                repo.get_remote_url("origin")
+                    .unwrap_or_else(|_| String::from(tr("无", "none")))
+            )),
+            Line::from(trf!(
+                "网页: {}",
+                "Web: {}",
+                RemoteUrl::parse(&repo.remote_url)
+                    .map(|remote| format!("{} ({})", remote.web_url(), remote.forge()))
+                    .unwrap_or_else(|| String::from("-"))
+            )),
+            Line::from(trf!(
+                "网络检查后端: {} (上次使用: {})",
+                "Fetch backend: {} (last used: {})",
+                backend::backend_for(&repo.path),
+                repo.fetch_backend
+                    .map(|backend| backend.to_string())
+                    .unwrap_or_else(|| String::from("-"))
+            )),
+            Line::from(match repo.last_fetch_success {
+                0 => String::from(tr("上次成功 fetch: 从未", "Last successful fetch: never")),
+                time => trf!(
+                    "上次成功 fetch: {} ({})",
+                    "Last successful fetch: {} ({})",
+                    self.formatter.timestamp(time),
+                    format_relative_time(time, now_timestamp())
+                ),
+            }),
+            Line::from(""),
+        ]);
+        if let Some(text) = self.forge_status_text(repo) {
+            lines.insert(lines.len() - 1, Line::from(text));
+        }
+
+        if let Some((id, message)) = GitRepo::get_last_commit_message(&repo.path) {
+            lines.push(Line::from(Span::styled(
+                trf!("最后提交 {}", "Last commit {}", id),
+                heading,
+            )));
+            for line in message.trim_end().lines() {
+                lines.push(Line::from(format!("  {}", line)));
+            }
+            lines.push(Line::from(""));
+        }
+
+        let conflicted = GitRepo::get_conflicted_files(&repo.path);
+        if !conflicted.is_empty() {
+            lines.push(Line::from(Span::styled(
+                trf!(
+                    "冲突的文件: {} (按 = 打开 mergetool)",
+                    "Conflicted files: {} (press = to open mergetool)",
+                    conflicted.len()
+                ),
+                heading,
+            )));
+            for file in conflicted {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", file),
+                    self.theme.conflicted,
+                )));
+            }
+            lines.push(Line::from(""));
+        }
+
+        if let Some(activity) = self.activity.get(&repo.path) {
+            lines.extend(heatmap_lines(
+                activity,
+                now_timestamp(),
+                self.formatter.utc_offset,
+                heading,
+            ));
+            lines.push(Line::from(""));
+        }
+
+        if !repo.branches.is_empty() {
+            lines.push(Line::from(Span::styled(
+                trf!("分支: {}", "Branches: {}", repo.branch_summary()),
+                heading,
+            )));
+            for branch in &repo.branches {
+                let current = if branch.name == repo.branch { "*" } else { " " };
+                let line = match &branch.upstream {
+                    Some(upstream) => trf!(
+                        "  {} {:<28} {} 领先 {} 落后 {}",
+                        "  {} {:<28} {} ahead {} behind {}",
+                        current,
+                        branch.name,
+                        upstream,
+                        branch.ahead,
+                        branch.behind
+                    ),
+                    None => trf!(
+                        "  {} {:<28} 没有上游分支",
+                        "  {} {:<28} no upstream",
+                        current,
+                        branch.name
+                    ),
+                };
+                lines.push(if branch.ahead > 0 || branch.behind > 0 {
+                    Line::from(line.yellow())
+                } else {
+                    Line::from(line)
+                });
+            }
+            lines.push(Line::from(""));
+        }
+
+        let tags = GitRepo::get_git_tags(&repo.path);
+        if !tags.is_empty() {
+            lines.push(Line::from(Span::styled(
+                trf!("Git 标签: {}", "Git tags: {}", tags.len()),
+                heading,
+            )));
+            for tag in tags.iter().take(MAX_DETAIL_TAGS) {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {:<24} ", tag.name)),
+                    Span::raw(format!("{} ", tag.id)).yellow(),
+                    Span::raw(format!(
+                        "{:<12} ",
+                        format_relative_time(tag.time, now_timestamp())
+                    ))
+                    .dark_gray(),
+                    Span::raw(tag.message.clone()),
+                ]));
+            }
+            if tags.len() > MAX_DETAIL_TAGS {
+                lines.push(Line::from(trf!(
+                    "  还有 {} 个",
+                    "  {} more",
+                    tags.len() - MAX_DETAIL_TAGS
+                )));
+            }
+            lines.push(Line::from(""));
+        }
+
+        if !repo.submodules.is_empty() {
+            lines.push(Line::from(Span::styled(
+                trf!("子模块: {}", "Submodules: {}", repo.submodules.len()),
+                heading,
+            )));
+            for submodule in &repo.submodules {
+                let line = format!("  {:<30} {}", submodule.path, submodule.describe());
+                lines.push(if submodule.outdatedp || submodule.dirtyp {
+                    Line::from(line.yellow())
+                } else {
+                    Line::from(line)
+                });
+            }
+            lines.push(Line::from(""));
+        }
+
+        if repo.no_fetch {
+            lines.push(Line::from(Span::styled(
+                tr(
+                    "不进行网络检查 (按 X 恢复)",
+                    "Network checks disabled (press X to enable)",
+                ),
+                heading,
+            )));
+            lines.push(Line::from(""));
+        }
+
+        if let Some(parent) = &repo.nested_in {
+            lines.push(Line::from(Span::styled(
+                trf!("嵌套在仓库中: {}", "Nested in: {}", parent.display()),
+                heading,
+            )));
+            lines.push(Line::from(""));
+        }
+
+        if let Some(parent) = &repo.worktree_of {
+            lines.push(Line::from(Span::styled(
+                trf!("工作树, 主仓库: {}", "Worktree of: {}", parent.display()),
+                heading,
+            )));
+            lines.push(Line::from(""));
+        } else {
+            let worktrees: Vec<&GitRepo> = self
+                .repos
+                .iter()
+                .filter(|item| item.worktree_of.as_ref() == Some(&repo.path))
+                .collect();
+            if !worktrees.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    trf!("工作树: {}", "Worktrees: {}", worktrees.len()),
+                    heading,
+                )));
+                for worktree in worktrees {
+                    lines.push(Line::from(format!(
+                        "  {:<30} {} {}",
+                        worktree.path.display(),
+                        worktree.branch,
+                        worktree.status
+                    )));
+                }
+                lines.push(Line::from(""));
+            }
+        }
+
+        if repo.status_history.len() > 1 {
+            lines.push(Line::from(Span::styled(
+                tr("状态变化:", "Status changes:"),
+                heading,
+            )));
+            for record in repo.status_history.iter().rev() {
+                lines.push(Line::from(format!(
+                    "  {:<20} {} {}",
+                    self.formatter.timestamp(record.time),
+                    record.status.glyph(),
+                    record.status
+                )));
+            }
+            lines.push(Line::from(""));
+        }
+
+        let factors = health_breakdown(
+            repo,
+            &self.config.health_weights,
+            self.config.active_days,
+            now_timestamp(),
+        );
+        lines.push(Line::from(Span::styled(
+            trf!("健康分数: {}", "Health score: {}", health_score(&factors)),
+            heading,
+        )));
+        lines.push(Line::from(
+            tr(
+                "各项分数的加权平均, 无法得知的项不计入, 权重可以在配置文件 health_weights 中修改",
+                "Weighted average of the factors, unknown ones are skipped, weights can be changed with health_weights in the config file",
+            ),
+        ));
+        for factor in factors {
+            let score = match factor.score {
+                Some(score) => format!("{:>3.0}", score * 100.0),
+                None => String::from("  -"),
+            };
+            lines.push(Line::from(format!(
+                "  {:<8} {} x {:.1}  {}",
+                factor.name, score, factor.weight, factor.reason
+            )));
+        }
+
+        lines
+    }
+
     fn ui(&mut self, f: &mut Frame) -> BDEResult<()> {
         let main_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(1),
+                Constraint::Length(2),
                 Constraint::Length(3),
+                Constraint::Length(self.component_tabs.height()),
                 Constraint::Min(0),
+                Constraint::Length(if self.component_output.showp { 12 } else { 0 }),
             ])
             .split(f.size());
 
@@ -67,26 +1142,240 @@ impl App {
             .draw(self.run_mode, f, main_layout[0])?;
 
         self.component_repos_show
-            .draw(self.run_mode, f, main_layout[2])?;
+            .draw(self.run_mode, f, main_layout[3])?;
 
         self.component_input
             .draw(self.run_mode, f, main_layout[1])?;
 
+        self.component_tabs.draw(self.run_mode, f, main_layout[2])?;
+
+        self.component_prompt
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_confirm
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_output
+            .draw(self.run_mode, f, main_layout[4])?;
+
+        self.component_detail
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_wizard
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_tombstones
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_branches
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_inbox
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_log.draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_errors
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_dashboard
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_authors
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_commit_search
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_file_search
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_stale
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_commands
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_palette
+            .draw(self.run_mode, f, main_layout[3])?;
+
+        self.component_toasts
+            .draw(self.run_mode, f, main_layout[3])?;
+
         Ok(())
     }
 
     async fn run(&mut self) -> BDEResult<()> {
-        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+        i18n::set_language(self.config.language);
+        let mut terminal = match self.script {
+            Some(_) => None,
+            None => Some(Terminal::new(CrosstermBackend::new(stderr()))?),
+        };
         let (run_tx, mut run_rx) = mpsc::unbounded_channel();
         let (search_data_tx, mut search_data_rx) = mpsc::unbounded_channel();
         let (data_tx, mut data_rx) = mpsc::unbounded_channel();
         let (time_tx, mut time_rx) = mpsc::unbounded_channel();
+        let (repo_tx, mut repo_rx) = mpsc::unbounded_channel::<GitRepo>();
+        let (repo_error_tx, mut repo_error_rx) = mpsc::unbounded_channel::<RepoError>();
+        let (auto_refresh_tx, mut auto_refresh_rx) = mpsc::unbounded_channel();
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel::<CommandOutput>();
+        let (search_paths_tx, mut search_paths_rx) = mpsc::unbounded_channel();
+        let (size_tx, mut size_rx) = mpsc::unbounded_channel::<SizeResult>();
+        let (forge_tx, mut forge_rx) = mpsc::unbounded_channel::<ForgeResult>();
+        let (authors_tx, mut authors_rx) = mpsc::unbounded_channel::<AuthorsEvent>();
+        let (search_tx, mut search_rx) = mpsc::unbounded_channel::<SearchEvent<CommitMatch>>();
+        let (file_search_tx, mut file_search_rx) =
+            mpsc::unbounded_channel::<SearchEvent<FileMatch>>();
+        let (op_tx, mut op_rx) = mpsc::unbounded_channel::<OpEvent>();
+
+        // HOME 之外的目录需要确认之后才会扫描
+        self.tabs = self
+            .config
+            .search_roots()
+            .into_iter()
+            .map(|root| {
+                let (search_paths, outside_paths) =
+                    self.config.split_search_paths(&root.search_paths)?;
+                Ok(RepoTab {
+                    name: root.name,
+                    search_paths,
+                    outside_paths,
+                    ..RepoTab::default()
+                })
+            })
+            .collect::<BDEResult<_>>()?;
+        self.component_tabs.names = self.tabs.iter().map(|tab| tab.name.clone()).collect();
+        self.tabs[0].scannedp = true;
+        set_cache_root(&self.tabs[0].name);
+        let mut search_root = self.tabs[0].name.clone();
+        let mut search_paths = self.tabs[0].search_paths.clone();
+        let outside_paths: Vec<&PathBuf> = self
+            .tabs
+            .iter()
+            .flat_map(|tab| &tab.outside_paths)
+            .collect();
+        // 另一个实例正在运行时只读取缓存, 不写入, 避免互相覆盖
+        let data_dir = get_save_data_dir()?;
+        let lock_lines = if instance::try_acquire(&data_dir)? {
+            Vec::new()
+        } else {
+            let owner = instance::lock_owner(&data_dir)
+                .map(|pid| pid.to_string())
+                .unwrap_or_else(|| String::from(tr("未知", "unknown")));
+            vec![
+                Line::from(trf!(
+                    "另一个实例 (PID {}) 正在使用缓存.",
+                    "Another instance (PID {}) is using the cache.",
+                    owner
+                )),
+                Line::from(tr(
+                    "本次以只读缓存模式运行, 标签, 隐藏等修改不会保存.",
+                    "Running with a read-only cache, changes such as tags and ignores are not saved.",
+                )),
+                Line::from(tr(
+                    "另一个实例退出之后会自动恢复写入.",
+                    "Writing resumes automatically after the other instance exits.",
+                )),
+            ]
+        };
+
+        if !outside_paths.is_empty() {
+            let mut lines = vec![Line::from(tr(
+                "下面的搜索目录在 HOME 之外, 是否在本次运行中扫描:",
+                "These search paths are outside HOME, scan them in this run:",
+            ))];
+            for path in &outside_paths {
+                lines.push(Line::from(format!("  {}", path.display()).yellow()));
+            }
+            lines.push(Line::from(tr(
+                "如需保持允许, 请在配置文件中设置 \"allow_outside_home\": true",
+                "To always allow them, set \"allow_outside_home\": true in the config file",
+            )));
+            // 两个提示放在同一个弹窗中, 否则只读缓存的提示会替换掉这个确认
+            if !lock_lines.is_empty() {
+                lines.push(Line::from(""));
+                lines.extend(lock_lines);
+            }
+
+            self.component_confirm.start(
+                tr("扫描 HOME 之外的目录", "Scan paths outside HOME"),
+                lines,
+                AppAction::ApplyScanOutsideHome,
+            );
+            self.run_mode = AppMode::Confirm;
+        } else if !lock_lines.is_empty() {
+            self.component_confirm.start(
+                tr(
+                    "另一个 git_manager 正在运行",
+                    "Another git_manager is running",
+                ),
+                lock_lines,
+                AppAction::ExitConfirm,
+            );
+            self.run_mode = AppMode::Confirm;
+        }
+
+        let auto_refresh_interval =
+            tokio::time::Duration::from_secs(self.config.auto_refresh_interval);
+        let mut auto_refreshp = self.config.auto_refresh;
+        let active_policy = self.config.active_policy();
+        let skip_nested = self.config.nested_repos == NestedRepos::Skip;
+        let search_options = self.config.search_options()?;
+        self.component_statusbar.auto_refresh_interval =
+            auto_refreshp.then_some(self.config.auto_refresh_interval);
+        self.formatter = self.config.formatter();
+        self.component_statusbar.formatter = self.formatter;
+        self.component_tombstones.formatter = self.formatter;
+        self.component_repos_show.formatter = self.formatter;
+        self.component_repos_show.absolute_dates = self.config.absolute_dates;
+        self.component_repos_show.show_commit_summary = self.config.show_commit_summary;
+        self.component_repos_show.pagedp = self.config.paged;
+        self.apply_theme()?;
+        self.component_statusbar.reduced_motion = self.config.reduced_motion;
+        self.component_repos_show.reduced_motion = self.config.reduced_motion;
+        if self.config.open_inbox && self.script.is_none() {
+            self.next_action = Some(AppAction::OpenInbox);
+        }
+        self.component_repos_show.health_weights = self.config.health_weights.clone();
+        self.component_repos_show.active_days = self.config.active_days;
+        self.component_repos_show.stale_fetch_days = self.config.stale_fetch_days;
+        self.component_repos_show.nested_repos = self.config.nested_repos;
+        backend::set_backend(self.config.git_backend);
+        fetch::set_offline(self.config.offline);
+        if self.config.maintenance_interval_days > 0 {
+            self.maintenance_due = Some(
+                load_maintenance_time().unwrap_or(0)
+                    + self.config.maintenance_interval_days * 24 * 3600,
+            );
+        }
+        fetch::set_fetch_limits(
+            self.config.fetch_host_concurrency,
+            self.config.fetch_jitter_ms,
+        );
+        backend::set_repo_backends(
+            self.config
+                .repo_backends
+                .iter()
+                .map(|(path, backend)| Ok((expand_path(path)?, *backend)))
+                .collect::<BDEResult<_>>()?,
+        );
+        credentials::set_ssh_key(
+            self.config
+                .ssh_key
+                .as_deref()
+                .map(expand_path)
+                .transpose()?,
+        );
 
         tokio::spawn(async move {
             let mut runp = true;
             let mut get_datap = true;
+            let mut last_refresh = tokio::time::Instant::now();
 
             while runp {
+                // 手动刷新会检查所有仓库
+                let mut policy = None;
+
                 if let Ok(data) = run_rx.try_recv() {
                     runp = data;
                 };
@@ -95,111 +1384,2503 @@ impl App {
                     get_datap = data;
                 };
 
+                if let Ok(data) = auto_refresh_rx.try_recv() {
+                    auto_refreshp = data;
+                };
+
+                if let Ok((root, paths)) = search_paths_rx.try_recv() {
+                    search_root = root;
+                    search_paths = paths;
+                };
+
+                // 自动刷新, 从上一次刷新完成开始计时
+                if !get_datap && auto_refreshp && last_refresh.elapsed() >= auto_refresh_interval {
+                    get_datap = true;
+                    policy = active_policy;
+                }
+
                 if get_datap {
                     let start = tokio::time::Instant::now();
-                    let test_path_1 = "~/";
-                    // let test_path_2 = "~/AndroidStudioProjects/";
-                    let search_path = Path::new(test_path_1);
-                    match get_all_git_repo(search_path).await {
-                        Ok(res) => {
-                            data_tx.send(res).unwrap();
-                        }
-                        Err(_) => {
-                            data_tx.send((Vec::new(), 0)).unwrap();
-                        }
-                    }
+                    let options = ScanOptions {
+                        active_policy: policy,
+                        use_cache: true,
+                        skip_nested,
+                        search: search_options.clone(),
+                        root: search_root.clone(),
+                    };
+                    let (repos, errors) =
+                        match get_all_git_repo(&search_paths, options, Some(&data_tx)).await {
+                            Ok(res) => res,
+                            Err(err) => {
+                                let error = RepoError {
+                                    path: PathBuf::new(),
+                                    error: err.to_string(),
+                                };
+                                (Vec::new(), vec![error])
+                            }
+                        };
+                    data_tx
+                        .send(ScanUpdate::Finished {
+                            root: search_root.clone(),
+                            repos,
+                            errors,
+                        })
+                        .unwrap();
                     let duration = start.elapsed();
-                    time_tx.send(duration).unwrap();
+                    time_tx.send((search_root.clone(), duration)).unwrap();
                     get_datap = false;
+                    last_refresh = tokio::time::Instant::now();
+                } else {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
                 }
             }
         });
 
         while self.runp {
-            if let Ok(data) = data_rx.try_recv() {
-                self.repos = data.0;
-                self.component_repos_show.refresh_repop = false;
+            // 其它标签页的扫描结果保存起来, 切换过去时显示
+            while let Ok(update) = data_rx.try_recv() {
+                let current = self.tabs[self.tab].name.clone();
+                match update {
+                    ScanUpdate::Searching { root, paths } if root == current => {
+                        self.component_statusbar.root_progress =
+                            paths.into_iter().map(|path| (path, None)).collect();
+                    }
+                    ScanUpdate::RootScanned { root, path, repos } if root == current => {
+                        if let Some(progress) = self
+                            .component_statusbar
+                            .root_progress
+                            .iter_mut()
+                            .find(|(item, _)| item == &path)
+                        {
+                            progress.1 = Some(repos);
+                        }
+                    }
+                    ScanUpdate::Searching { .. } | ScanUpdate::RootScanned { .. } => {}
+                    ScanUpdate::Local {
+                        root,
+                        repos,
+                        errors,
+                    }
+                    | ScanUpdate::Finished {
+                        root,
+                        repos,
+                        errors,
+                    } => {
+                        if root == current {
+                            self.component_statusbar.root_progress.clear();
+                            self.repos.replace_all(repos);
+                            self.component_errors.replace_all(errors);
+                            self.component_repos_show.refresh_repop = false;
+                        } else if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.name == root)
+                        {
+                            tab.repos = repos;
+                            tab.errors = errors;
+                        }
+                    }
+                    ScanUpdate::Fetched { root, repo } if root == current => {
+                        self.component_errors.remove(&repo.path);
+                        self.repos.upsert(*repo);
+                        save_all_git_repo(&self.repos)?;
+                    }
+                    ScanUpdate::Fetched { .. } => {}
+                }
             }
 
-            if let Ok(duraction) = time_rx.try_recv() {
-                self.component_statusbar.search_repo_duration = duraction.as_secs_f64();
+            // 单个仓库重新检查之后的结果
+            while let Ok(new_repo) = repo_rx.try_recv() {
+                // 分支弹窗等待的刷新结果
+                if self.component_branches.path.as_ref() == Some(&new_repo.path) {
+                    self.component_branches.stalep = false;
+                    self.component_branches.update(&new_repo);
+                }
+                self.component_errors.remove(&new_repo.path);
+                self.repos.upsert(new_repo);
+                save_all_git_repo(&self.repos)?;
             }
 
-            if let Some(action) = self.handle_events()? {
-                match action {
-                    AppAction::Quit => {
-                        run_tx.send(false)?;
-                        self.runp = false;
-                        break;
+            // 后台计算的仓库大小
+            let mut size_changedp = false;
+            while let Ok((path, size)) = size_rx.try_recv() {
+                self.size_pending.remove(&path);
+                let size = match size {
+                    Ok(size) => size,
+                    Err(err) => {
+                        tracing::warn!("size {}: {}", path.display(), err);
+                        continue;
                     }
-                    AppAction::StartRefresh => {
-                        if !self.component_repos_show.refresh_repop {
-                            self.component_repos_show.refresh_repop = true;
-                            self.component_repos_show.show_repos.clear();
-                            search_data_tx.send(true)?;
-                        }
+                };
+                if let Some(index) = self.repos.iter().position(|repo| repo.path == path) {
+                    self.repos.update(index, |repo| repo.size = Some(size));
+                    size_changedp = true;
+                }
+                if self.run_mode == AppMode::Detail && self.detail_path.as_ref() == Some(&path) {
+                    if let Some(repo) = self.repos.iter().find(|repo| repo.path == path) {
+                        self.component_detail.lines = self.repo_detail_lines(repo);
                     }
-                    AppAction::StartFilter => {
-                        if !self.component_repos_show.refresh_repop {
-                            self.run_mode = AppMode::Editing;
-                        }
+                }
+            }
+            if size_changedp {
+                save_all_git_repo(&self.repos)?;
+            }
+
+            // 后台搜索的提交
+            while let Ok(event) = search_rx.try_recv() {
+                match event {
+                    SearchEvent::Matches(id, matches) => {
+                        self.component_commit_search.add(id, matches)
                     }
-                    AppAction::ExitFilter => {
-                        self.run_mode = AppMode::Normal;
+                    SearchEvent::Done(id) => self.component_commit_search.done(id),
+                }
+            }
+
+            while let Ok(event) = file_search_rx.try_recv() {
+                match event {
+                    SearchEvent::Matches(id, matches) => {
+                        self.component_file_search.add(id, matches)
                     }
-                    AppAction::SelectNext => {
-                        self.component_repos_show.next();
+                    SearchEvent::Done(id) => self.component_file_search.done(id),
+                }
+            }
+
+            // 后台统计的提交作者
+            while let Ok(event) = authors_rx.try_recv() {
+                match event {
+                    AuthorsEvent::Repo(path, authors) => {
+                        if let Some(authors) = authors {
+                            self.author_cache.insert(path, authors);
+                        }
+                        if let Some((done, _)) = &mut self.component_authors.progress {
+                            *done += 1;
+                        }
                     }
-                    AppAction::SelectPervious => {
-                        self.component_repos_show.previous();
+                    AuthorsEvent::Done(since) if since == self.authors_since() => {
+                        let summaries = self.author_summaries(since);
+                        self.component_authors.set_summaries(summaries);
                     }
-                    AppAction::SelectEnter => {}
-                    AppAction::SelectCopyPath => {
-                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
-                            let repo = &self.repos[repo_id];
-                            let path = repo.path.display().to_string();
-                            let _ = copy_to_clipboard(&path);
+                    AuthorsEvent::Done(_) => {}
+                }
+            }
+
+            // 后台查询的 CI 状态
+            let mut forge_changedp = false;
+            while let Ok((path, status)) = forge_rx.try_recv() {
+                self.forge_pending.remove(&path);
+                match status {
+                    Ok(status) => {
+                        self.forge_errors.remove(&path);
+                        if let Some(index) = self.repos.iter().position(|repo| repo.path == path) {
+                            self.repos
+                                .update(index, |repo| repo.forge_status = Some(status));
+                            forge_changedp = true;
                         }
                     }
-                    _ => {}
+                    Err(err) => {
+                        tracing::warn!("forge status {}: {}", path.display(), err);
+                        self.forge_errors.insert(path.clone(), err);
+                    }
+                }
+                if self.run_mode == AppMode::Detail && self.detail_path.as_ref() == Some(&path) {
+                    if let Some(repo) = self.repos.iter().find(|repo| repo.path == path) {
+                        self.component_detail.lines = self.repo_detail_lines(repo);
+                    }
                 }
             }
+            if forge_changedp {
+                save_all_git_repo(&self.repos)?;
+            }
 
-            self.component_input.update_complection()?;
-
-            self.component_statusbar.all_repo_len = self.repos.len();
-            self.component_statusbar.show_repo_len = self.component_repos_show.show_repos.len();
+            // 重试之后仍然失败的仓库
+            while let Ok(error) = repo_error_rx.try_recv() {
+                self.component_errors.upsert(error);
+            }
+            self.component_statusbar.error_len = self.component_errors.errors.len();
 
-            self.component_repos_show
-                .update_show_repos(&self.repos, &self.component_input.input)?;
+            if self.run_mode == AppMode::Inbox {
+                self.component_inbox.update(self.attention_items());
+            }
 
-            terminal.draw(|f| match self.ui(f) {
-                Ok(_) => {}
-                Err(err) => {
-                    panic!("{}", err);
+            // 拉取, 推送任务的状态
+            while let Ok(event) = op_rx.try_recv() {
+                if let Some(OpState::Failed(operation)) = event.state {
+                    let name = event
+                        .path
+                        .file_name()
+                        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+                    self.component_toasts.error(trf!(
+                        "git {}: {} 失败",
+                        "git {}: {} failed",
+                        operation.name(),
+                        name
+                    ));
                 }
-            })?;
-        }
+                match event.state {
+                    Some(state) => {
+                        self.component_repos_show
+                            .operations
+                            .insert(event.path, state);
+                    }
+                    None => {
+                        self.component_repos_show.operations.remove(&event.path);
+                    }
+                }
+            }
 
-        Ok(())
-    }
+            while let Ok(output) = output_rx.try_recv() {
+                if self.script.is_some() {
+                    match &output {
+                        CommandOutput::Stdout(line) => println!("{}", line),
+                        CommandOutput::Stderr(line) => eprintln!("{}", line),
+                        CommandOutput::Exit(code) => println!("exit: {:?}", code),
+                    }
+                }
+                if let CommandOutput::Exit(code) = output {
+                    let title = &self.component_output.title;
+                    if code == Some(0) {
+                        self.component_toasts
+                            .info(trf!("{}: 完成", "{}: done", title));
+                    } else {
+                        self.component_toasts
+                            .error(trf!("{}: 失败", "{}: failed", title));
+                    }
+                }
+                self.component_output.push(output);
+            }
+
+            if let Ok((root, duraction)) = time_rx.try_recv() {
+                if root == self.tabs[self.tab].name {
+                    self.component_statusbar.search_repo_duration = duraction;
+                    self.component_dashboard.record_scan(duraction);
+                    self.component_statusbar.last_refresh = Some(std::time::Instant::now());
+                } else if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.name == root) {
+                    tab.last_refresh = Some(std::time::Instant::now());
+                }
+            }
+
+            // 到了定时维护的时间, 等刷新和其它命令完成之后在后台执行
+            if self
+                .maintenance_due
+                .is_some_and(|due| due <= now_timestamp())
+                && self.next_action.is_none()
+                && self.script.is_none()
+                && !self.component_repos_show.refresh_repop
+                && !self.component_output.runningp
+            {
+                self.next_action = Some(AppAction::ApplyMaintenance);
+            }
+
+            let action = match self.next_action.take() {
+                Some(action) => Some(action),
+                None if self.script.is_some() => self.next_script_action().await?,
+                None => self.handle_events()?,
+            };
+            if let Some(action) = action {
+                tracing::debug!(?action, "action");
+                match action {
+                    AppAction::Quit => {
+                        run_tx.send(false)?;
+                        self.runp = false;
+                        break;
+                    }
+                    AppAction::StartRefresh
+                    | AppAction::SelectPull
+                    | AppAction::SelectPush
+                    | AppAction::SelectSync
+                    | AppAction::ApplyBackup
+                        if self.config.prompt_passphrase
+                            && self.script.is_none()
+                            && credentials::need_passphrase() =>
+                    {
+                        self.passphrase_action = Some(action);
+                        self.component_prompt.start_secret(
+                            tr("SSH 私钥密码", "SSH key passphrase"),
+                            AppAction::SubmitPassphrase,
+                        );
+                        self.run_mode = AppMode::Prompt;
+                    }
+                    AppAction::SubmitPassphrase => {
+                        credentials::set_passphrase(
+                            &self.component_prompt.input,
+                            &get_save_data_dir()?,
+                        )?;
+                        self.component_prompt.input.clear();
+                        self.next_action = self.passphrase_action.take();
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartRefresh if !self.component_repos_show.refresh_repop => {
+                        self.component_repos_show.refresh_repop = true;
+                        self.component_repos_show.show_repos.clear();
+                        search_data_tx.send(true)?;
+                    }
+                    AppAction::RefreshSelected if !self.component_repos_show.refresh_repop => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let mut repo = self.repos[repo_id].clone();
+                            // 完整刷新时跳过超时和需要认证的仓库, 单独刷新时重新检查
+                            if matches!(repo.status, GitStatus::Timeout | GitStatus::AuthRequired) {
+                                repo.status = GitStatus::Clean;
+                            }
+                            let repo_tx = repo_tx.clone();
+                            let repo_error_tx = repo_error_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let path = repo.path.clone();
+                                match GitRepo::build_from_last(repo, true).await {
+                                    Ok(repo) => {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                    Err(err) => {
+                                        tracing::warn!("refresh {}: {}", path.display(), err);
+                                        let error = err.to_string();
+                                        let _ = repo_error_tx.send(RepoError { path, error });
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    AppAction::StartFilter if !self.component_repos_show.refresh_repop => {
+                        self.run_mode = AppMode::Editing;
+                    }
+                    AppAction::ExitFilter => {
+                        self.run_mode = AppMode::Normal;
+                        if self.component_input.push_history() {
+                            save_filter_history(&self.component_input.history)?;
+                        }
+                    }
+                    AppAction::SelectNext => {
+                        self.component_repos_show.next();
+                    }
+                    AppAction::SelectPervious => {
+                        self.component_repos_show.previous();
+                    }
+                    AppAction::NextPage => {
+                        self.component_repos_show.next_page();
+                    }
+                    AppAction::PerviousPage => {
+                        self.component_repos_show.previous_page();
+                    }
+                    AppAction::ScrollPageDown => {
+                        self.component_repos_show.scroll_page_down();
+                    }
+                    AppAction::ScrollPageUp => {
+                        self.component_repos_show.scroll_page_up();
+                    }
+                    AppAction::Resize => {
+                        // 立即按新的大小重新绘制, 清除旧布局留下的内容
+                        if let Some(terminal) = &mut terminal {
+                            terminal.autoresize()?;
+                            terminal.clear()?;
+                        }
+                    }
+                    AppAction::SelectFirst => {
+                        self.component_repos_show.select_first();
+                    }
+                    AppAction::SelectLast => {
+                        self.component_repos_show.select_last();
+                    }
+                    AppAction::SelectRow(row) => {
+                        self.component_repos_show.select_row(row);
+                    }
+                    AppAction::TogglePaged => {
+                        self.component_repos_show.pagedp = !self.component_repos_show.pagedp;
+                    }
+                    AppAction::SelectEnter => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.repos
+                                .update(repo_id, |repo| repo.record_visit(now_timestamp()));
+                            save_all_git_repo(&self.repos)?;
+                        }
+                        self.next_action = Some(if self.print_pathp {
+                            AppAction::SelectCdExit
+                        } else {
+                            self.config.enter_action.action()
+                        });
+                    }
+                    AppAction::OpenDetail => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            if self.repos[repo_id].size.is_none() {
+                                self.start_size_task(vec![path.clone()], &size_tx);
+                            }
+                            let repo = self.repos[repo_id].clone();
+                            self.start_forge_task(&repo, &forge_tx);
+                            self.update_activity(&path);
+                            self.detail_path = Some(path);
+                            let lines = self.repo_detail_lines(&self.repos[repo_id]);
+                            self.component_detail
+                                .start(&self.repos[repo_id].name, lines);
+                            self.run_mode = AppMode::Detail;
+                        }
+                    }
+                    AppAction::ExitDetail => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::ToggleSort => {
+                        self.component_repos_show.sort_by = match self.component_repos_show.sort_by
+                        {
+                            SortBy::CommitTime => SortBy::Health,
+                            SortBy::Health => SortBy::Owner,
+                            SortBy::Owner => SortBy::Size,
+                            SortBy::Size => SortBy::Frecency,
+                            SortBy::Frecency => SortBy::CommitTime,
+                        };
+                        if self.component_repos_show.sort_by == SortBy::Size {
+                            let paths = self
+                                .repos
+                                .iter()
+                                .filter(|repo| repo.size.is_none())
+                                .map(|repo| repo.path.clone())
+                                .collect();
+                            self.start_size_task(paths, &size_tx);
+                        }
+                    }
+                    AppAction::SelectCopyPath => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            let path = repo.path.display().to_string();
+                            self.copy(&path);
+                        }
+                    }
+                    AppAction::SelectCdExit => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.exit_path = Some(self.repos[repo_id].path.clone());
+                            self.next_action = Some(AppAction::Quit);
+                        }
+                    }
+                    AppAction::SelectOpenEditor => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            if let Some(terminal) = &mut terminal {
+                                let editor = self.config.editor_command();
+                                let args = editor_args(&editor, &path, None);
+                                if let Err(err) = run_editor(terminal, &editor, &path, &args) {
+                                    tracing::warn!("open editor {}: {}", path.display(), err);
+                                    self.component_confirm.start(
+                                        tr("打开编辑器失败", "Failed to open editor"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                    }
+                    AppAction::OpenMergeTool => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = self.repos[repo_id].clone();
+                            if GitRepo::get_conflicted_files(&repo.path).is_empty() {
+                                self.component_toasts.info(trf!(
+                                    "{} 没有冲突的文件",
+                                    "{} has no conflicted files",
+                                    repo.name
+                                ));
+                            } else if let Some(terminal) = &mut terminal {
+                                let args: Vec<OsString> = self
+                                    .config
+                                    .merge_tool
+                                    .iter()
+                                    .filter(|tool| !tool.trim().is_empty())
+                                    .map(|tool| OsString::from(format!("--tool={}", tool.trim())))
+                                    .collect();
+                                if let Err(err) =
+                                    run_editor(terminal, "git mergetool", &repo.path, &args)
+                                {
+                                    tracing::warn!("mergetool {}: {}", repo.path.display(), err);
+                                    self.component_confirm.start(
+                                        tr("打开 mergetool 失败", "Failed to open mergetool"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                                // 解决之后重新检查状态
+                                let repo_tx = repo_tx.clone();
+                                let mut task = WORKER_METRICS.queue();
+                                tokio::spawn(async move {
+                                    task.start();
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                });
+                            }
+                        }
+                    }
+                    AppAction::SelectCopyRemoteUrl => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            match self.repos[repo_id].get_remote_url("origin") {
+                                Ok(url) => self.copy(&url),
+                                Err(err) => self.component_toasts.error(err.to_string()),
+                            }
+                        }
+                    }
+                    AppAction::SelectOpenMultiplexer => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            if let Err(err) = multiplexer::open_in_multiplexer(
+                                &self.config.multiplexer_commands,
+                                &repo.name,
+                                &repo.path,
+                            )
+                            .await
+                            {
+                                tracing::warn!("open multiplexer {}: {}", repo.path.display(), err);
+                                self.component_confirm.start(
+                                    tr("打开新窗口失败", "Failed to open a new window"),
+                                    error_lines(&err),
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                        }
+                    }
+                    AppAction::SelectOpenBrowser => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            let res = repo
+                                .get_remote_url("origin")
+                                .and_then(|url| {
+                                    RemoteUrl::parse(&url).ok_or_else(|| {
+                                        ba_error(&trf!(
+                                            "无法从 {} 得到网页地址",
+                                            "Cannot derive a web URL from {}",
+                                            url
+                                        ))
+                                    })
+                                })
+                                .and_then(|remote| open_url(&remote.web_url()));
+
+                            if let Err(err) = res {
+                                tracing::warn!("open browser {}: {}", repo.path.display(), err);
+                                self.component_confirm.start(
+                                    tr("打开浏览器失败", "Failed to open browser"),
+                                    error_lines(&err),
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                        }
+                    }
+                    AppAction::SelectCopyCloneCommand => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            match self.repos[repo_id].get_remote_url("origin") {
+                                Ok(url) => self.copy(&format!("git clone {}", url)),
+                                Err(err) => self.component_toasts.error(err.to_string()),
+                            }
+                        }
+                    }
+                    AppAction::StartTagEdit => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.component_prompt.start(
+                                tr(
+                                    "标签 (空格分隔, 如: favorite work archived)",
+                                    "Tags (space separated, e.g. favorite work archived)",
+                                ),
+                                &self.repos[repo_id].tags.join(" "),
+                                AppAction::FinishTagEdit,
+                            );
+                            self.run_mode = AppMode::Prompt;
+                        }
+                    }
+                    AppAction::FinishTagEdit => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let tags = GitRepo::parse_tags(&self.component_prompt.input);
+                            self.repos.update(repo_id, |repo| repo.tags = tags);
+                            save_all_git_repo(&self.repos)?;
+                        }
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartRenameRepo => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.component_prompt.start(
+                                tr("新的仓库路径", "New repo path"),
+                                &self.repos[repo_id].path.display().to_string(),
+                                AppAction::SubmitRenameRepo,
+                            );
+                            self.run_mode = AppMode::Prompt;
+                        }
+                    }
+                    AppAction::SubmitRenameRepo => {
+                        self.run_mode = AppMode::Normal;
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            let target = expand_path(self.component_prompt.input.trim())?;
+                            let metrics = WORKER_METRICS.snapshot();
+                            // 后台任务可能正在读写仓库, 移动之后它们的结果会写回旧路径
+                            let busyp = self.component_repos_show.refresh_repop
+                                || self.component_output.runningp
+                                || metrics.running + metrics.queued > 0
+                                || path.join(".git/index.lock").exists();
+
+                            let res = if target == path {
+                                Ok(())
+                            } else if busyp {
+                                Err(ba_error(tr(
+                                    "仓库正在刷新或者执行命令, 请等待完成之后再移动",
+                                    "Repos are refreshing or running a command, wait until it finishes",
+                                )))
+                            } else {
+                                move_repo(&path, &target)
+                            };
+
+                            match res {
+                                Ok(_) if target != path => {
+                                    self.repos.update(repo_id, |repo| {
+                                        repo.path = target.clone();
+                                        if let Some(name) = target.file_name() {
+                                            repo.name = name.to_string_lossy().to_string();
+                                        }
+                                    });
+                                    for index in 0..self.repos.len() {
+                                        if self.repos[index].worktree_of.as_ref() == Some(&path) {
+                                            self.repos.update(index, |repo| {
+                                                repo.worktree_of = Some(target.clone())
+                                            });
+                                        }
+                                    }
+                                    if self.component_repos_show.marked.remove(&path) {
+                                        self.component_repos_show.marked.insert(target.clone());
+                                    }
+                                    if let Some(ignore_path) =
+                                        self.ignore_repos.iter_mut().find(|item| **item == path)
+                                    {
+                                        *ignore_path = target;
+                                        save_ignore_repos(&self.ignore_repos)?;
+                                    }
+                                    save_all_git_repo(&self.repos)?;
+                                }
+                                Ok(_) => {}
+                                Err(err) => {
+                                    tracing::warn!("move repo {}: {}", path.display(), err);
+                                    self.component_confirm.start(
+                                        tr("移动仓库失败", "Failed to move repo"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                        self.component_prompt.input.clear();
+                    }
+                    AppAction::ExitPrompt => {
+                        self.init_repo_path = None;
+                        self.passphrase_action = None;
+                        self.component_prompt.input.clear();
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartInitRepo => {
+                        self.component_prompt.start(
+                            tr("新仓库路径", "New repo path"),
+                            "~/",
+                            AppAction::SubmitInitPath,
+                        );
+                        self.run_mode = AppMode::Prompt;
+                    }
+                    AppAction::SubmitInitPath => {
+                        let path = expand_path(self.component_prompt.input.trim())?;
+                        if path.join(".git").exists() {
+                            self.component_confirm.start(
+                                tr("创建仓库", "Create repo"),
+                                vec![Line::from(trf!(
+                                    "{} 已经是 Git 仓库",
+                                    "{} is already a Git repo",
+                                    path.display()
+                                ))],
+                                AppAction::ExitConfirm,
+                            );
+                            self.run_mode = AppMode::Confirm;
+                        } else {
+                            self.init_repo_path = Some(path);
+                            self.component_prompt.start(
+                                tr(
+                                    "远程仓库地址 (可选, 留空跳过)",
+                                    "Remote URL (optional, leave empty to skip)",
+                                ),
+                                "",
+                                AppAction::SubmitInitRemote,
+                            );
+                        }
+                    }
+                    AppAction::SubmitInitRemote => {
+                        self.run_mode = AppMode::Normal;
+                        if let Some(path) = self.init_repo_path.take() {
+                            match GitRepo::init(&path, self.component_prompt.input.trim()) {
+                                Ok(_) => {
+                                    let repo_tx = repo_tx.clone();
+                                    let mut task = WORKER_METRICS.queue();
+                                    tokio::spawn(async move {
+                                        task.start();
+                                        if let Ok(repo) = GitRepo::build(&path).await {
+                                            let _ = repo_tx.send(repo);
+                                        }
+                                    });
+                                }
+                                Err(err) => {
+                                    tracing::warn!("create repo: {}", err);
+                                    self.component_confirm.start(
+                                        tr("创建仓库失败", "Failed to create repo"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                    }
+                    AppAction::StartAddRemote => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            self.add_remote = Some((repo.path.clone(), String::new()));
+                            // 没有远程仓库时一般添加 origin
+                            let name = if repo.has_remote { "" } else { "origin" };
+                            self.component_prompt.start(
+                                tr("远程仓库名字", "Remote name"),
+                                name,
+                                AppAction::SubmitRemoteName,
+                            );
+                            self.run_mode = AppMode::Prompt;
+                        }
+                    }
+                    AppAction::SubmitRemoteName => {
+                        let name = self.component_prompt.input.trim().to_string();
+                        match &mut self.add_remote {
+                            Some((_, remote_name)) if !name.is_empty() => {
+                                *remote_name = name;
+                                self.component_prompt.start(
+                                    tr("远程仓库地址", "Remote URL"),
+                                    "",
+                                    AppAction::SubmitRemoteUrl,
+                                );
+                            }
+                            _ => {
+                                self.add_remote = None;
+                                self.run_mode = AppMode::Normal;
+                            }
+                        }
+                    }
+                    AppAction::SubmitRemoteUrl => {
+                        self.run_mode = AppMode::Normal;
+                        let url = self.component_prompt.input.trim().to_string();
+                        if let (Some((path, name)), false) =
+                            (self.add_remote.take(), url.is_empty())
+                        {
+                            match GitRepo::add_remote(&path, &name, &url) {
+                                Ok(_) => {
+                                    self.component_toasts.info(trf!(
+                                        "已添加远程仓库 {}",
+                                        "Added remote {}",
+                                        name
+                                    ));
+                                    if let Some(repo) =
+                                        self.repos.iter().find(|repo| repo.path == path).cloned()
+                                    {
+                                        let repo_tx = repo_tx.clone();
+                                        let mut task = WORKER_METRICS.queue();
+                                        tokio::spawn(async move {
+                                            task.start();
+                                            if let Ok(repo) =
+                                                GitRepo::build_from_last(repo, false).await
+                                            {
+                                                let _ = repo_tx.send(repo);
+                                            }
+                                        });
+                                    }
+                                }
+                                Err(err) => {
+                                    tracing::warn!("add remote {}: {}", path.display(), err);
+                                    self.component_confirm.start(
+                                        tr("添加远程仓库失败", "Failed to add remote"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                    }
+                    AppAction::ToggleIgnoreRepo => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            let tombstones = &mut self.component_tombstones.tombstones;
+                            if self.ignore_repos.contains(&path) {
+                                self.ignore_repos.retain(|item| item != &path);
+                                tombstones.retain(|item| {
+                                    item.path != path || item.reason != TombstoneReason::Ignored
+                                });
+                            } else {
+                                tombstones.push(Tombstone::new(&path, TombstoneReason::Ignored));
+                                self.ignore_repos.push(path);
+                            }
+                            save_ignore_repos(&self.ignore_repos)?;
+                            save_tombstones(tombstones)?;
+                        }
+                    }
+                    AppAction::StartIgnoreUntracked => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            if !repo.large_untracked.is_empty() {
+                                let mut lines = vec![Line::from(trf!(
+                                    "{} 只因为下面这些未跟踪的大目录需要 Commit,",
+                                    "{} only needs a commit because of these large untracked directories,",
+                                    repo.name
+                                ))];
+                                lines.push(Line::from(tr(
+                                    "是否将它们追加到 .gitignore:",
+                                    "append them to .gitignore:",
+                                )));
+                                for item in &repo.large_untracked {
+                                    lines.push(Line::from(format!("  + {}", item).green()));
+                                }
+                                self.ignore_untracked =
+                                    Some((repo.path.clone(), repo.large_untracked.clone()));
+
+                                self.component_confirm.start(
+                                    tr("忽略未跟踪的大目录", "Ignore large untracked directories"),
+                                    lines,
+                                    AppAction::ApplyIgnoreUntracked,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                        }
+                    }
+                    AppAction::ApplyIgnoreUntracked => {
+                        // 确认期间列表可能重新排序, 使用确认中显示的仓库和目录
+                        let target = self.ignore_untracked.take().and_then(|(path, items)| {
+                            let repo = self.repos.iter().find(|repo| repo.path == path)?;
+                            Some((repo.clone(), items))
+                        });
+                        if let Some((repo, items)) = target {
+                            repo.append_gitignore(&items)?;
+
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                if let Ok(repo) = GitRepo::build_from_last(repo, true).await {
+                                    let _ = repo_tx.send(repo);
+                                }
+                            });
+                        }
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartGitignoreTemplate => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            let (templates, new_lines) = gitignore_diff(&repo.path);
+
+                            if templates.is_empty() || new_lines.is_empty() {
+                                let message = if templates.is_empty() {
+                                    tr(
+                                        "没有检测到仓库使用的语言",
+                                        "No language detected for the repo",
+                                    )
+                                } else {
+                                    tr(
+                                        ".gitignore 已经包含模板中的所有内容",
+                                        ".gitignore already contains everything in the templates",
+                                    )
+                                };
+                                self.component_confirm.start(
+                                    tr(".gitignore 模板", ".gitignore templates"),
+                                    vec![Line::from(message)],
+                                    AppAction::ExitConfirm,
+                                );
+                            } else {
+                                let names: Vec<&str> =
+                                    templates.iter().map(|item| item.name).collect();
+                                let mut lines = vec![Line::from(trf!(
+                                    "检测到 {}, 将追加到 {}/.gitignore:",
+                                    "Detected {}, will append to {}/.gitignore:",
+                                    names.join(", "),
+                                    repo.path.display()
+                                ))];
+                                for line in &new_lines {
+                                    lines.push(Line::from(format!("+ {}", line).green()));
+                                }
+                                self.gitignore_template = Some((repo.path.clone(), new_lines));
+
+                                self.component_confirm.start(
+                                    tr(".gitignore 模板", ".gitignore templates"),
+                                    lines,
+                                    AppAction::ApplyGitignoreTemplate,
+                                );
+                            }
+                            self.run_mode = AppMode::Confirm;
+                        }
+                    }
+                    AppAction::ApplyGitignoreTemplate => {
+                        // 确认期间列表可能重新排序, 使用确认中显示的仓库和内容
+                        let target = self.gitignore_template.take().and_then(|(path, lines)| {
+                            let repo = self.repos.iter().find(|repo| repo.path == path)?;
+                            Some((repo.clone(), lines))
+                        });
+                        if let Some((repo, new_lines)) = target {
+                            repo.append_gitignore(&new_lines)?;
+
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                if let Ok(repo) = GitRepo::build_from_last(repo, true).await {
+                                    let _ = repo_tx.send(repo);
+                                }
+                            });
+                        }
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartCreateTag => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.new_tag = Some((self.repos[repo_id].path.clone(), String::new()));
+                            self.component_prompt.start(
+                                tr("标签名字", "Tag name"),
+                                "",
+                                AppAction::SubmitTagName,
+                            );
+                            self.run_mode = AppMode::Prompt;
+                        }
+                    }
+                    AppAction::SubmitTagName => {
+                        let name = self.component_prompt.input.trim().to_string();
+                        match &mut self.new_tag {
+                            Some((_, tag_name)) if !name.is_empty() => {
+                                *tag_name = name.clone();
+                                self.component_prompt.start(
+                                    tr("标签信息", "Tag message"),
+                                    &name,
+                                    AppAction::SubmitTagMessage,
+                                );
+                            }
+                            _ => {
+                                self.new_tag = None;
+                                self.run_mode = AppMode::Normal;
+                            }
+                        }
+                    }
+                    AppAction::SubmitTagMessage => {
+                        self.run_mode = AppMode::Normal;
+                        let message = self.component_prompt.input.trim().to_string();
+                        if let Some((path, name)) = self.new_tag.clone() {
+                            let message = if message.is_empty() {
+                                name.clone()
+                            } else {
+                                message
+                            };
+                            match GitRepo::create_tag(&path, &name, &message) {
+                                Ok(_) => {
+                                    self.component_toasts.info(trf!(
+                                        "已创建标签 {}",
+                                        "Created tag {}",
+                                        name
+                                    ));
+                                    let has_remote = self
+                                        .repos
+                                        .iter()
+                                        .any(|repo| repo.path == path && repo.has_remote);
+                                    if has_remote {
+                                        self.component_confirm.start(
+                                            tr("推送标签", "Push tag"),
+                                            vec![Line::from(trf!(
+                                                "推送 {} 到远程仓库?",
+                                                "Push {} to the remote?",
+                                                name
+                                            ))],
+                                            AppAction::PushTags,
+                                        );
+                                        self.run_mode = AppMode::Confirm;
+                                    } else {
+                                        self.new_tag = None;
+                                    }
+                                }
+                                Err(err) => {
+                                    self.new_tag = None;
+                                    tracing::warn!("create tag {}: {}", path.display(), err);
+                                    self.component_confirm.start(
+                                        tr("创建标签失败", "Failed to create tag"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                    }
+                    AppAction::PushTags => {
+                        self.run_mode = AppMode::Normal;
+                        if let Some((path, _)) = self.new_tag.take() {
+                            let repos = self
+                                .repos
+                                .iter()
+                                .filter(|repo| repo.path == path)
+                                .cloned()
+                                .collect();
+                            self.start_git_operation(
+                                GitOperation::PushTags,
+                                repos,
+                                &output_tx,
+                                &op_tx,
+                                &repo_tx,
+                            );
+                        }
+                    }
+                    AppAction::SelectPull | AppAction::SelectPush | AppAction::SelectSync => {
+                        let operation = match action {
+                            AppAction::SelectPull => GitOperation::Pull,
+                            AppAction::SelectPush => GitOperation::Push,
+                            _ => GitOperation::Sync,
+                        };
+                        let mut repos = self.target_repos();
+                        // 拉取只快进, 和上游分叉的仓库不执行, 引导用户选择解决的办法
+                        if operation == GitOperation::Pull {
+                            let diverged: Vec<GitRepo>;
+                            (diverged, repos) = repos.into_iter().partition(GitRepo::divergedp);
+                            if !repos.is_empty() && !diverged.is_empty() {
+                                self.component_toasts.info(trf!(
+                                    "跳过 {} 个和上游分叉的仓库, 选中之后按 w 解决",
+                                    "Skipped {} diverged repos, select one and press w to resolve",
+                                    diverged.len()
+                                ));
+                            } else if let [repo] = diverged.as_slice() {
+                                let path = repo.path.clone();
+                                self.start_resolve_diverge(&path);
+                            } else if !diverged.is_empty() {
+                                self.component_detail.start(
+                                    tr("不能快进", "Cannot fast-forward"),
+                                    diverged_guide_lines(&diverged),
+                                );
+                                self.run_mode = AppMode::Detail;
+                            }
+                        }
+                        self.start_git_operation(operation, repos, &output_tx, &op_tx, &repo_tx);
+                    }
+                    AppAction::StartForeach => {
+                        let repos = self.foreach_repos();
+                        if !repos.is_empty() {
+                            self.component_prompt.start(
+                                &trf!(
+                                    "在 {} 个仓库中执行的命令",
+                                    "Command to run in {} repos",
+                                    repos.len()
+                                ),
+                                "",
+                                AppAction::SubmitForeach,
+                            );
+                            self.run_mode = AppMode::Prompt;
+                        }
+                    }
+                    AppAction::SubmitForeach => {
+                        self.run_mode = AppMode::Normal;
+                        let command = self.component_prompt.input.trim().to_string();
+                        let repos = self.foreach_repos();
+                        if !command.is_empty() && !repos.is_empty() {
+                            self.component_output.start(&trf!(
+                                "{}: {} 个仓库",
+                                "{}: {} repos",
+                                command,
+                                repos.len()
+                            ));
+                            self.run_mode = AppMode::Output;
+
+                            let paths: Vec<PathBuf> =
+                                repos.iter().map(|repo| repo.path.clone()).collect();
+                            let jobs = self.config.op_jobs;
+                            let output_tx = output_tx.clone();
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let res = ops::foreach(&command, &paths, jobs, output_tx.clone())
+                                    .await
+                                    .map_err(|err| err.to_string());
+                                if let Err(err) = res {
+                                    let _ = output_tx.send(CommandOutput::Stderr(err));
+                                    let _ = output_tx.send(CommandOutput::Exit(None));
+                                }
+
+                                // 命令可能修改了仓库, 重新检查状态
+                                for repo in repos {
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    AppAction::StartMaintenance => {
+                        let repos = self.maintenance_paths();
+                        if !repos.is_empty() {
+                            let mut steps = vec!["git gc --auto", "git remote prune origin"];
+                            if self.config.maintenance_run {
+                                steps.push("git maintenance run");
+                            }
+                            self.component_confirm.start(
+                                tr("维护仓库", "Maintain repos"),
+                                vec![
+                                    Line::from(trf!(
+                                        "在 {} 个仓库中执行:",
+                                        "Run in {} repos:",
+                                        repos.len()
+                                    )),
+                                    Line::from(format!("  {}", steps.join(", "))),
+                                ],
+                                AppAction::ApplyMaintenance,
+                            );
+                            self.run_mode = AppMode::Confirm;
+                        }
+                    }
+                    AppAction::ApplyMaintenance => {
+                        let paths = self.maintenance_paths();
+                        let now = now_timestamp();
+                        if self.config.maintenance_interval_days > 0 {
+                            self.maintenance_due =
+                                Some(now + self.config.maintenance_interval_days * 24 * 3600);
+                        }
+                        save_maintenance_time(now)?;
+
+                        if self.component_output.runningp {
+                            self.component_toasts.error(tr(
+                                "正在执行其它命令, 请等待完成之后再维护",
+                                "Another command is running, maintain repos after it finishes",
+                            ));
+                        } else if !paths.is_empty() {
+                            self.component_output.start(&trf!(
+                                "维护 {} 个仓库",
+                                "Maintain {} repos",
+                                paths.len()
+                            ));
+                            // 定时维护在后台执行, 不切换到输出窗口
+                            if self.run_mode == AppMode::Confirm {
+                                self.run_mode = AppMode::Output;
+                            }
+
+                            let maintenance_run = self.config.maintenance_run;
+                            let jobs = self.config.op_jobs;
+                            let formatter = self.formatter;
+                            let output_tx = output_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let res = ops::maintenance(
+                                    &paths,
+                                    maintenance_run,
+                                    jobs,
+                                    formatter,
+                                    output_tx.clone(),
+                                )
+                                .await;
+                                if let Err(err) = res {
+                                    let _ = output_tx.send(CommandOutput::Stderr(err.to_string()));
+                                    let _ = output_tx.send(CommandOutput::Exit(None));
+                                }
+                            });
+                        }
+                        if self.run_mode == AppMode::Confirm {
+                            self.run_mode = AppMode::Normal;
+                        }
+                    }
+                    AppAction::StartBackup => {
+                        let repos = self.target_repos();
+                        match &self.config.backup_remote {
+                            _ if repos.is_empty() => {}
+                            Some(template) => {
+                                self.component_confirm.start(
+                                    tr("备份仓库", "Back up repos"),
+                                    vec![
+                                        Line::from(trf!(
+                                            "把 {} 个仓库的所有分支和标签推送到远程仓库 {}:",
+                                            "Push all branches and tags of {} repos to remote {}:",
+                                            repos.len(),
+                                            ops::BACKUP_REMOTE
+                                        )),
+                                        Line::from(format!("  {}", template).yellow()),
+                                    ],
+                                    AppAction::ApplyBackup,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                            None => {
+                                self.component_confirm.start(
+                                    tr("备份仓库", "Back up repos"),
+                                    vec![Line::from(tr(
+                                        "没有设置备份地址, 请在配置文件中设置 \"backup_remote\", 比如 \"git@backup-host:{name}.git\"",
+                                        "No backup address, set \"backup_remote\" in the config file, e.g. \"git@backup-host:{name}.git\"",
+                                    ))],
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                        }
+                    }
+                    AppAction::ApplyBackup if !self.component_output.runningp => {
+                        let repos = self.target_repos();
+                        if let (Some(template), false) =
+                            (&self.config.backup_remote, repos.is_empty())
+                        {
+                            let (targets, skipped) = backup_targets(&repos, template);
+                            self.component_output.start(&trf!(
+                                "备份: {} 个仓库",
+                                "Backup: {} repos",
+                                repos.len()
+                            ));
+                            self.run_mode = AppMode::Output;
+
+                            let output_tx = output_tx.clone();
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                for path in skipped {
+                                    let _ = output_tx.send(CommandOutput::Stderr(trf!(
+                                        "跳过 {}: 没有 origin, 无法得到 {{owner}}",
+                                        "Skip {}: no origin for {{owner}}",
+                                        path.display()
+                                    )));
+                                }
+                                if let Err(err) = ops::backup(&targets, output_tx.clone()).await {
+                                    let _ = output_tx.send(CommandOutput::Stderr(err.to_string()));
+                                    let _ = output_tx.send(CommandOutput::Exit(None));
+                                }
+
+                                // 添加了远程仓库
+                                for repo in repos {
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    AppAction::ToggleMark => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            if !self.component_repos_show.marked.remove(&path) {
+                                self.component_repos_show.marked.insert(path);
+                            }
+                        }
+                    }
+                    AppAction::MarkAll => {
+                        for show_repo in &self.component_repos_show.show_repos {
+                            self.component_repos_show
+                                .marked
+                                .insert(self.repos[show_repo.id].path.clone());
+                        }
+                    }
+                    AppAction::ClearMarks => {
+                        self.component_repos_show.marked.clear();
+                    }
+                    AppAction::StartReport => {
+                        self.component_prompt.start(
+                            tr("报告文件路径", "Report file path"),
+                            "~/git_manager_report.md",
+                            AppAction::ApplyReport,
+                        );
+                        self.run_mode = AppMode::Prompt;
+                    }
+                    AppAction::ApplyReport => {
+                        self.run_mode = AppMode::Normal;
+                        let res = expand_path(self.component_prompt.input.trim())
+                            .and_then(|path| Ok(fs::write(path, self.report_markdown())?));
+                        if let Err(err) = res {
+                            tracing::warn!("write report: {}", err);
+                            self.component_confirm.start(
+                                tr("生成报告失败", "Failed to write report"),
+                                error_lines(&err),
+                                AppAction::ExitConfirm,
+                            );
+                            self.run_mode = AppMode::Confirm;
+                        }
+                    }
+                    AppAction::FocusOutput if self.component_output.showp => {
+                        self.run_mode = AppMode::Output;
+                    }
+                    AppAction::ExitOutput => {
+                        self.component_output.showp = false;
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::ApplyScanOutsideHome => {
+                        for tab in &mut self.tabs {
+                            let outside_paths = std::mem::take(&mut tab.outside_paths);
+                            tab.search_paths.extend(outside_paths);
+                        }
+                        let tab = &self.tabs[self.tab];
+                        search_paths_tx.send((tab.name.clone(), tab.search_paths.clone()))?;
+                        search_data_tx.send(true)?;
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartDeleteRepo => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = &self.repos[repo_id];
+                            let target = match &self.config.archive_dir {
+                                Some(archive_dir) => {
+                                    trf!("归档目录 {}", "the archive directory {}", archive_dir)
+                                }
+                                None => String::from(tr("回收站", "the trash")),
+                            };
+
+                            match repo.check_fully_pushed() {
+                                Ok(reasons) if reasons.is_empty() => {
+                                    self.delete_repo = Some(repo.path.clone());
+                                    self.component_confirm.start(
+                                        tr("删除仓库", "Delete repo"),
+                                        vec![
+                                            Line::from(trf!(
+                                                "{} 工作区干净, 所有分支都已经推送.",
+                                                "{} is clean and all branches are pushed.",
+                                                repo.path.display()
+                                            )),
+                                            Line::from(
+                                                trf!("是否移动到{}?", "Move it to {}?", target)
+                                                    .red(),
+                                            ),
+                                        ],
+                                        AppAction::ApplyDeleteRepo,
+                                    );
+                                }
+                                Ok(reasons) => {
+                                    let mut lines = vec![Line::from(trf!(
+                                        "{} 不能删除:",
+                                        "{} cannot be deleted:",
+                                        repo.path.display()
+                                    ))];
+                                    for reason in reasons {
+                                        lines.push(Line::from(format!("  - {}", reason).yellow()));
+                                    }
+                                    self.component_confirm.start(
+                                        tr("删除仓库", "Delete repo"),
+                                        lines,
+                                        AppAction::ExitConfirm,
+                                    );
+                                }
+                                Err(err) => {
+                                    self.component_confirm.start(
+                                        tr("删除仓库", "Delete repo"),
+                                        vec![Line::from(trf!(
+                                            "检查仓库失败: {}",
+                                            "Failed to check repo: {}",
+                                            err
+                                        ))],
+                                        AppAction::ExitConfirm,
+                                    );
+                                }
+                            }
+                            self.run_mode = AppMode::Confirm;
+                        }
+                    }
+                    AppAction::ApplyDeleteRepo => {
+                        self.run_mode = AppMode::Normal;
+                        // 确认期间仓库可能有了新的修改, 删除之前重新检查
+                        let checked = self
+                            .delete_repo
+                            .take()
+                            .and_then(|path| self.repos.iter().position(|repo| repo.path == path))
+                            .map(|repo_id| (repo_id, self.repos[repo_id].check_fully_pushed()));
+                        match checked {
+                            Some((repo_id, Ok(reasons))) if reasons.is_empty() => {
+                                let path = self.repos[repo_id].path.clone();
+                                // 移动之后无法再读取 origin
+                                let tombstone = Tombstone::new(&path, TombstoneReason::Deleted);
+                                let res = match &self.config.archive_dir {
+                                    Some(archive_dir) => move_to_archive(&path, archive_dir),
+                                    None => move_to_trash(&path),
+                                };
+
+                                match res {
+                                    Ok(_) => {
+                                        self.repos.remove(repo_id);
+                                        save_all_git_repo(&self.repos)?;
+                                        if self.ignore_repos.contains(&path) {
+                                            self.ignore_repos.retain(|item| item != &path);
+                                            save_ignore_repos(&self.ignore_repos)?;
+                                        }
+                                        let tombstones = &mut self.component_tombstones.tombstones;
+                                        tombstones.retain(|item| item.path != path);
+                                        tombstones.push(tombstone);
+                                        save_tombstones(tombstones)?;
+                                    }
+                                    Err(err) => {
+                                        tracing::warn!("delete repo {}: {}", path.display(), err);
+                                        self.component_confirm.start(
+                                            tr("删除仓库失败", "Failed to delete repo"),
+                                            error_lines(&err),
+                                            AppAction::ExitConfirm,
+                                        );
+                                        self.run_mode = AppMode::Confirm;
+                                    }
+                                }
+                            }
+                            Some((repo_id, Ok(reasons))) => {
+                                let mut lines = vec![Line::from(trf!(
+                                    "{} 不能删除:",
+                                    "{} cannot be deleted:",
+                                    self.repos[repo_id].path.display()
+                                ))];
+                                for reason in reasons {
+                                    lines.push(Line::from(format!("  - {}", reason).yellow()));
+                                }
+                                self.component_confirm.start(
+                                    tr("删除仓库", "Delete repo"),
+                                    lines,
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                            Some((_, Err(err))) => {
+                                self.component_confirm.start(
+                                    tr("删除仓库", "Delete repo"),
+                                    vec![Line::from(trf!(
+                                        "检查仓库失败: {}",
+                                        "Failed to check repo: {}",
+                                        err
+                                    ))],
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                            None => {}
+                        }
+                    }
+                    AppAction::StartPruneMissing => {
+                        let missing: Vec<&GitRepo> = self
+                            .repos
+                            .iter()
+                            .filter(|repo| repo.status == GitStatus::Missing)
+                            .collect();
+                        if missing.is_empty() {
+                            self.component_confirm.start(
+                                tr("清理丢失的仓库", "Prune missing repos"),
+                                vec![Line::from(tr(
+                                    "没有丢失的仓库.",
+                                    "There are no missing repos.",
+                                ))],
+                                AppAction::ExitConfirm,
+                            );
+                        } else {
+                            let mut lines = vec![Line::from(trf!(
+                                "下面 {} 个仓库已经不存在, 是否从列表中移除:",
+                                "These {} repos no longer exist, remove them from the list:",
+                                missing.len()
+                            ))];
+                            for repo in missing {
+                                lines.push(Line::from(
+                                    format!("  {}", repo.path.display()).yellow(),
+                                ));
+                            }
+                            self.component_confirm.start(
+                                tr("清理丢失的仓库", "Prune missing repos"),
+                                lines,
+                                AppAction::ApplyPruneMissing,
+                            );
+                        }
+                        self.run_mode = AppMode::Confirm;
+                    }
+                    AppAction::ApplyPruneMissing => {
+                        let mut pruned = Vec::new();
+                        for index in (0..self.repos.len()).rev() {
+                            // 刷新之后可能又出现了
+                            if self.repos[index].status == GitStatus::Missing
+                                && !self.repos[index].path.exists()
+                            {
+                                pruned.push(self.repos.remove(index).path);
+                            }
+                        }
+                        pruned.reverse();
+                        tracing::info!(?pruned, "prune missing repos");
+                        save_all_git_repo(&self.repos)?;
+                        if self.ignore_repos.iter().any(|path| pruned.contains(path)) {
+                            self.ignore_repos.retain(|path| !pruned.contains(path));
+                            save_ignore_repos(&self.ignore_repos)?;
+                        }
+
+                        let mut lines = vec![Line::from(trf!(
+                            "已经移除 {} 个丢失的仓库:",
+                            "Removed {} missing repos:",
+                            pruned.len()
+                        ))];
+                        for path in &pruned {
+                            lines.push(Line::from(format!("  {}", path.display())));
+                        }
+                        self.component_confirm.start(
+                            tr("清理丢失的仓库", "Prune missing repos"),
+                            lines,
+                            AppAction::ExitConfirm,
+                        );
+                        self.run_mode = AppMode::Confirm;
+                    }
+                    AppAction::StartResolveDiverge => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let path = self.repos[repo_id].path.clone();
+                            self.start_resolve_diverge(&path);
+                        }
+                    }
+                    AppAction::ApplyResolvePlan if !self.component_output.runningp => {
+                        let info = self
+                            .component_wizard
+                            .info
+                            .clone()
+                            .filter(|info| info.worktree_cleanp);
+                        // 工作区不干净时停留在向导, 界面上已经有提示
+                        if let (Some(info), Some(plan)) =
+                            (info, self.component_wizard.get_select_plan())
+                        {
+                            self.component_wizard.info = None;
+                            self.component_output.start(&format!(
+                                "{}: {}",
+                                plan.title(),
+                                info.path.display()
+                            ));
+                            self.run_mode = AppMode::Output;
+
+                            let repo = self
+                                .repos
+                                .iter()
+                                .find(|repo| repo.path == info.path)
+                                .cloned();
+                            let command = plan.command(&info);
+                            let output_tx = output_tx.clone();
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let res = run_command_stream(&command, output_tx.clone())
+                                    .await
+                                    .map_err(|err| err.to_string());
+                                if let Err(err) = res {
+                                    let _ = output_tx.send(CommandOutput::Stderr(err));
+                                    let _ = output_tx.send(CommandOutput::Exit(None));
+                                }
+
+                                if let Some(repo) = repo {
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    AppAction::OpenCustomCommands => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            if self.config.custom_commands.is_empty() {
+                                self.component_toasts.error(tr(
+                                    "没有自定义命令, 请在配置文件的 custom_commands 中添加",
+                                    "No custom commands, add them to custom_commands in the config",
+                                ));
+                            } else {
+                                self.component_commands.commands =
+                                    self.config.custom_commands.clone();
+                                self.component_commands.start(&self.repos[repo_id]);
+                                self.run_mode = AppMode::CustomCommands;
+                            }
+                        }
+                    }
+                    AppAction::ApplyCustomCommand => {
+                        // 先取得选中的命令, 替换变量需要仓库路径
+                        let selected = self.component_commands.get_select();
+                        if let (Some((name, command)), Some(path)) =
+                            (selected, self.component_commands.path.take())
+                        {
+                            self.component_output
+                                .start(&format!("{}: {}", name, path.display()));
+                            self.run_mode = AppMode::Output;
+
+                            let repo = self.repos.iter().find(|item| item.path == path).cloned();
+                            let command = format!("cd {} && {}", shell_quote(&path), command);
+                            let output_tx = output_tx.clone();
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let res = run_command_stream(&command, output_tx.clone())
+                                    .await
+                                    .map_err(|err| err.to_string());
+                                if let Err(err) = res {
+                                    let _ = output_tx.send(CommandOutput::Stderr(err));
+                                    let _ = output_tx.send(CommandOutput::Exit(None));
+                                }
+
+                                // 命令可能修改了仓库, 重新检查状态
+                                if let Some(repo) = repo {
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                }
+                            });
+                        } else {
+                            self.run_mode = AppMode::Normal;
+                        }
+                    }
+                    AppAction::ExitCustomCommands => {
+                        self.component_commands.path = None;
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::ExitWizard => {
+                        self.component_wizard.info = None;
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenTombstones => {
+                        self.component_tombstones.start();
+                        self.run_mode = AppMode::Tombstones;
+                    }
+                    AppAction::PurgeTombstone => {
+                        if let Some(tombstone) = self.component_tombstones.take_select() {
+                            // 清除隐藏留下的墓碑时同时取消隐藏
+                            if tombstone.reason == TombstoneReason::Ignored
+                                && self.ignore_repos.contains(&tombstone.path)
+                            {
+                                self.ignore_repos.retain(|item| item != &tombstone.path);
+                                save_ignore_repos(&self.ignore_repos)?;
+                            }
+                            save_tombstones(&self.component_tombstones.tombstones)?;
+                        }
+                    }
+                    AppAction::ExitTombstones => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenInbox => {
+                        self.component_inbox.update(self.attention_items());
+                        self.run_mode = AppMode::Inbox;
+                    }
+                    AppAction::ResolveAttention => {
+                        if let Some(item) = self.component_inbox.get_select().cloned() {
+                            self.run_mode = AppMode::Normal;
+                            // 仓库被过滤时先清空过滤条件
+                            let mut selectedp = self
+                                .component_repos_show
+                                .select_repo_path(&self.repos, &item.path);
+                            if !selectedp {
+                                self.component_input.set_input("");
+                                self.component_repos_show.update_show_repos(
+                                    &self.repos,
+                                    &self.ignore_repos,
+                                    &self.component_input.input,
+                                )?;
+                                selectedp = self
+                                    .component_repos_show
+                                    .select_repo_path(&self.repos, &item.path);
+                            }
+                            if selectedp {
+                                // 只处理这一个仓库, 清除标记避免操作到其他仓库
+                                self.component_repos_show.marked.clear();
+                                self.next_action = Some(item.action);
+                            }
+                        }
+                    }
+                    AppAction::ExitInbox => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenLog => {
+                        self.component_log.start();
+                        self.run_mode = AppMode::Log;
+                    }
+                    AppAction::ExitLog => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenErrors => {
+                        self.component_errors.start();
+                        self.run_mode = AppMode::Errors;
+                    }
+                    AppAction::RetryRepoError => {
+                        if let Some(path) = self
+                            .component_errors
+                            .get_select()
+                            .map(|error| error.path.clone())
+                            .filter(|path| {
+                                !path.as_os_str().is_empty()
+                                    && !self.component_errors.retrying.contains(path)
+                            })
+                        {
+                            self.component_errors.retrying.push(path.clone());
+                            let repo_tx = repo_tx.clone();
+                            let repo_error_tx = repo_error_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                match GitRepo::build(&path).await.map_err(|err| err.to_string()) {
+                                    Ok(repo) => {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                    Err(error) => {
+                                        tracing::warn!("retry {}: {}", path.display(), error);
+                                        let _ = repo_error_tx.send(RepoError { path, error });
+                                    }
+                                }
+                            });
+                        }
+                    }
+                    AppAction::ExitErrors => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenDashboard => {
+                        let repos: Vec<GitRepo> = self
+                            .repos
+                            .iter()
+                            .filter(|repo| !self.ignore_repos.contains(&repo.path))
+                            .cloned()
+                            .collect();
+                        self.component_dashboard.start(
+                            &repos,
+                            now_timestamp(),
+                            self.config.stale_fetch_days * 24 * 3600,
+                        );
+                        self.run_mode = AppMode::Dashboard;
+                    }
+                    AppAction::ExitDashboard => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenAuthors => {
+                        self.component_authors.me = git_user_email();
+                        self.start_authors_task(&authors_tx);
+                        self.run_mode = AppMode::Authors;
+                    }
+                    AppAction::NextAuthorsWindow => {
+                        self.component_authors.next_window();
+                        self.start_authors_task(&authors_tx);
+                    }
+                    AppAction::ExitAuthors => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartCommitSearch => {
+                        self.component_prompt.start(
+                            tr(
+                                "搜索提交信息, 以 -S 开头时搜索改动的内容",
+                                "Search commit messages, start with -S to search diffs",
+                            ),
+                            &self.component_commit_search.query,
+                            AppAction::SubmitCommitSearch,
+                        );
+                        self.run_mode = AppMode::Prompt;
+                    }
+                    AppAction::SubmitCommitSearch => {
+                        self.run_mode = AppMode::Normal;
+                        let input = self.component_prompt.input.trim().to_string();
+                        if let Some(query) = CommitQuery::parse(&input) {
+                            let id = self.component_commit_search.start(&input);
+                            // 工作树和主仓库共享提交, 只搜索主仓库
+                            let repos = self.search_repos(false);
+                            let jobs = self.config.op_jobs;
+                            let search_tx = search_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                search_commits(id, query, repos, jobs, search_tx).await;
+                            });
+                            self.run_mode = AppMode::CommitSearch;
+                        }
+                    }
+                    AppAction::ShowSearchCommit => {
+                        if let Some(result) = self.component_commit_search.get_select().cloned() {
+                            self.component_output.start(&format!(
+                                "git show {}: {}",
+                                &result.hash[..result.hash.len().min(8)],
+                                result.repo_path.display()
+                            ));
+                            self.run_mode = AppMode::Output;
+                            let output_tx = output_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let code = Cmd::git(&result.repo_path)
+                                    .args(["-c", "color.ui=always", "show", "--stat", "--patch"])
+                                    .arg(&result.hash)
+                                    .stream(&output_tx)
+                                    .await
+                                    .unwrap_or_else(|err| {
+                                        let _ =
+                                            output_tx.send(CommandOutput::Stderr(err.to_string()));
+                                        None
+                                    });
+                                let _ = output_tx.send(CommandOutput::Exit(code));
+                            });
+                        }
+                    }
+                    AppAction::ExitCommitSearch => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::StartFileSearch => {
+                        self.component_prompt.start(
+                            tr(
+                                "搜索文件名, 以 -c 开头时搜索文件内容",
+                                "Search file names, start with -c to search contents",
+                            ),
+                            &self.component_file_search.query,
+                            AppAction::SubmitFileSearch,
+                        );
+                        self.run_mode = AppMode::Prompt;
+                    }
+                    AppAction::SubmitFileSearch => {
+                        self.run_mode = AppMode::Normal;
+                        let input = self.component_prompt.input.trim().to_string();
+                        if let Some(query) = FileQuery::parse(&input) {
+                            let id = self.component_file_search.start(&input);
+                            let repos = self.search_repos(true);
+                            let jobs = self.config.op_jobs;
+                            let file_search_tx = file_search_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                search_files(id, query, repos, jobs, file_search_tx).await;
+                            });
+                            self.run_mode = AppMode::FileSearch;
+                        }
+                    }
+                    AppAction::OpenSearchFile => {
+                        if let (Some(result), Some(terminal)) = (
+                            self.component_file_search.get_select().cloned(),
+                            &mut terminal,
+                        ) {
+                            let editor = self.config.editor_command();
+                            let file = result.repo_path.join(&result.file);
+                            let args = editor_args(&editor, &file, result.line);
+                            if let Err(err) =
+                                run_editor(terminal, &editor, &result.repo_path, &args)
+                            {
+                                tracing::warn!("open editor {}: {}", file.display(), err);
+                                self.component_confirm.start(
+                                    tr("打开编辑器失败", "Failed to open editor"),
+                                    error_lines(&err),
+                                    AppAction::ExitConfirm,
+                                );
+                                self.run_mode = AppMode::Confirm;
+                            }
+                        }
+                    }
+                    AppAction::ExitFileSearch => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenStaleBranches => {
+                        let months = self.config.stale_branch_months;
+                        let now = now_timestamp();
+                        let branches = self
+                            .repos
+                            .iter()
+                            .filter(|repo| !self.ignore_repos.contains(&repo.path))
+                            .flat_map(|repo| stale_branches(repo, now, months * 30 * 24 * 3600))
+                            .collect();
+                        self.component_stale.start(branches, months);
+                        self.run_mode = AppMode::StaleBranches;
+                    }
+                    AppAction::DeleteStaleBranch | AppAction::ApplyForceDeleteStaleBranch => {
+                        self.run_mode = AppMode::StaleBranches;
+                        if let Some(branch) = self.component_stale.get_select().cloned() {
+                            let force = matches!(action, AppAction::ApplyForceDeleteStaleBranch);
+                            match delete_branch(&branch.repo_path, &branch.branch, force) {
+                                Ok(()) => {
+                                    self.component_stale.remove_select();
+                                    if let Some(repo) = self
+                                        .repos
+                                        .iter()
+                                        .find(|repo| repo.path == branch.repo_path)
+                                        .cloned()
+                                    {
+                                        let repo_tx = repo_tx.clone();
+                                        let mut task = WORKER_METRICS.queue();
+                                        tokio::spawn(async move {
+                                            task.start();
+                                            if let Ok(repo) =
+                                                GitRepo::build_from_last(repo, false).await
+                                            {
+                                                let _ = repo_tx.send(repo);
+                                            }
+                                        });
+                                    }
+                                }
+                                Err(err) => {
+                                    tracing::warn!(
+                                        "delete branch {} in {}: {}",
+                                        branch.branch,
+                                        branch.repo_path.display(),
+                                        err
+                                    );
+                                    self.component_confirm.start(
+                                        tr("删除分支失败", "Failed to delete branch"),
+                                        error_lines(&err),
+                                        AppAction::ExitConfirm,
+                                    );
+                                    self.component_confirm.back = Some(AppMode::StaleBranches);
+                                    self.run_mode = AppMode::Confirm;
+                                }
+                            }
+                        }
+                    }
+                    AppAction::StartForceDeleteStaleBranch => {
+                        if let Some(branch) = self.component_stale.get_select() {
+                            self.component_confirm.start(
+                                tr("强制删除分支", "Force delete branch"),
+                                vec![
+                                    Line::from(trf!(
+                                        "{1} 中的分支 {0} 可能有没有合并的提交.",
+                                        "Branch {0} in {1} may have unmerged commits.",
+                                        branch.branch,
+                                        branch.repo_name
+                                    )),
+                                    Line::from(tr("是否强制删除?", "Force delete it?").red()),
+                                ],
+                                AppAction::ApplyForceDeleteStaleBranch,
+                            );
+                            self.component_confirm.back = Some(AppMode::StaleBranches);
+                            self.run_mode = AppMode::Confirm;
+                        }
+                    }
+                    AppAction::ExitStaleBranches => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::OpenBranches => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            let repo = self.repos[repo_id].clone();
+                            let stalep = repo.branch_cache_stalep(now_timestamp());
+                            self.component_branches.start(&repo, stalep);
+                            self.run_mode = AppMode::Branches;
+
+                            // 缓存过时, 先显示缓存, 刷新之后更新列表
+                            if stalep {
+                                let repo_tx = repo_tx.clone();
+                                let mut task = WORKER_METRICS.queue();
+                                tokio::spawn(async move {
+                                    task.start();
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, true).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                });
+                            }
+                        }
+                    }
+                    AppAction::ApplyBranch => {
+                        if let (Some(path), Some(target)) = (
+                            self.component_branches.path.take(),
+                            self.component_branches.get_select_target(),
+                        ) {
+                            let args = target.git_args();
+                            self.component_output.start(&format!(
+                                "git {}: {}",
+                                args.join(" "),
+                                path.display()
+                            ));
+                            self.run_mode = AppMode::Output;
+
+                            let repo = self.repos.iter().find(|item| item.path == path).cloned();
+                            let output_tx = output_tx.clone();
+                            let repo_tx = repo_tx.clone();
+                            let mut task = WORKER_METRICS.queue();
+                            tokio::spawn(async move {
+                                task.start();
+                                let code = Cmd::git(&path)
+                                    .args(["-c", "color.ui=always"])
+                                    .args(&args)
+                                    .stream(&output_tx)
+                                    .await
+                                    .unwrap_or_else(|err| {
+                                        let _ =
+                                            output_tx.send(CommandOutput::Stderr(err.to_string()));
+                                        None
+                                    });
+                                let _ = output_tx.send(CommandOutput::Exit(code));
+
+                                if let Some(repo) = repo {
+                                    if let Ok(repo) = GitRepo::build_from_last(repo, false).await {
+                                        let _ = repo_tx.send(repo);
+                                    }
+                                }
+                            });
+                        } else {
+                            self.run_mode = AppMode::Normal;
+                        }
+                    }
+                    AppAction::ExitBranches => {
+                        self.component_branches.path = None;
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::ExitConfirm => {
+                        self.run_mode = self
+                            .component_confirm
+                            .back
+                            .take()
+                            .unwrap_or(AppMode::Normal);
+                    }
+                    AppAction::ToggleAutoRefresh => {
+                        let auto_refreshp =
+                            self.component_statusbar.auto_refresh_interval.is_none();
+                        self.component_statusbar.auto_refresh_interval =
+                            auto_refreshp.then_some(self.config.auto_refresh_interval);
+                        auto_refresh_tx.send(auto_refreshp)?;
+                    }
+                    AppAction::ToggleGitBackend => {
+                        backend::toggle_backend();
+                    }
+                    AppAction::NextTab | AppAction::PreviousTab if self.tabs.len() > 1 => {
+                        let len = self.tabs.len();
+                        let index = if matches!(action, AppAction::NextTab) {
+                            (self.tab + 1) % len
+                        } else {
+                            (self.tab + len - 1) % len
+                        };
+                        self.switch_tab(index)?;
+                        let tab = &mut self.tabs[self.tab];
+                        search_paths_tx.send((tab.name.clone(), tab.search_paths.clone()))?;
+                        if !tab.scannedp {
+                            tab.scannedp = true;
+                            search_data_tx.send(true)?;
+                        }
+                    }
+                    AppAction::ToggleOffline => {
+                        fetch::toggle_offline();
+                    }
+                    AppAction::ToggleRepoFetch => {
+                        if let Some(repo_id) = self.component_repos_show.get_select_repo_id() {
+                            self.repos
+                                .update(repo_id, |repo| repo.no_fetch = !repo.no_fetch);
+                            save_all_git_repo(&self.repos)?;
+                        }
+                    }
+                    AppAction::OpenPalette => {
+                        self.component_palette.start();
+                        self.run_mode = AppMode::Palette;
+                    }
+                    AppAction::ExitPalette => {
+                        self.run_mode = AppMode::Normal;
+                    }
+                    AppAction::NextTheme => {
+                        // 只在本次运行中生效, 不修改配置文件
+                        let themes = Theme::all_themes();
+                        let index = themes
+                            .iter()
+                            .position(|theme| theme.name == self.config.theme)
+                            .map_or(0, |index| (index + 1) % themes.len());
+                        self.config.theme = String::from(themes[index].name);
+                        self.apply_theme()?;
+                        self.component_toasts.info(trf!(
+                            "主题: {}",
+                            "Theme: {}",
+                            self.config.theme
+                        ));
+                    }
+                    AppAction::ToggleShowHidden => {
+                        self.component_repos_show.show_hiddenp =
+                            !self.component_repos_show.show_hiddenp;
+                    }
+                    AppAction::ToggleStatusFilter(status) => {
+                        let status_filter = &mut self.component_repos_show.status_filter;
+                        if status_filter.contains(&status) {
+                            status_filter.retain(|item| *item != status);
+                        } else {
+                            status_filter.push(status);
+                        }
+                    }
+                    AppAction::ClearStatusFilter => {
+                        self.component_repos_show.status_filter.clear();
+                    }
+                    _ => {}
+                }
+            }
+
+            self.component_input.update_complection(&self.repos)?;
+
+            self.component_statusbar.metrics = WORKER_METRICS.snapshot();
+            self.component_statusbar.show_repo_len = self.component_repos_show.show_repos.len();
+            self.component_statusbar.hidden_repo_len = self.ignore_repos.len();
+            self.component_statusbar.show_hiddenp = self.component_repos_show.show_hiddenp;
+            self.component_statusbar.status_filter =
+                self.component_repos_show.status_filter.clone();
+            self.component_statusbar.read_onlyp = !instance::holding();
+
+            self.component_repos_show.update_show_repos(
+                &self.repos,
+                &self.ignore_repos,
+                &self.component_input.input,
+            )?;
+
+            if let Some(terminal) = &mut terminal {
+                terminal.draw(|f| match self.ui(f) {
+                    Ok(_) => {}
+                    Err(err) => {
+                        panic!("{}", err);
+                    }
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl App {
+    fn new() -> BDEResult<Self> {
+        let component_statusbar = StatusBar::new();
+        let mut repos = RepoStore::new();
+        repos.subscribe(component_statusbar.repo_counts.clone());
+
+        Ok(App {
+            repos,
+            ignore_repos: load_ignore_repos()?,
+            runp: true,
+            config: load_config()?,
+            formatter: Formatter::default(),
+            theme: Theme::default_theme(),
+            init_repo_path: None,
+            add_remote: None,
+            new_tag: None,
+            delete_repo: None,
+            ignore_untracked: None,
+            gitignore_template: None,
+            passphrase_action: None,
+            next_action: None,
+            maintenance_due: None,
+            script: None,
+            size_pending: HashSet::new(),
+            forge_pending: HashSet::new(),
+            forge_errors: HashMap::new(),
+            activity: HashMap::new(),
+            author_cache: HashMap::new(),
+            detail_path: None,
+            tabs: Vec::new(),
+            tab: 0,
+            exit_path: None,
+            print_pathp: false,
+            run_mode: AppMode::Normal,
+            component_input: Input::new(load_filter_history()?),
+            component_repos_show: ReposShow::new(),
+            component_statusbar,
+            component_prompt: Prompt::new(),
+            component_confirm: Confirm::new(),
+            component_output: OutputPane::new(),
+            component_detail: Detail::new(),
+            component_wizard: Wizard::new(),
+            component_tombstones: Tombstones::new(load_tombstones()?),
+            component_branches: Branches::new(),
+            component_inbox: Inbox::new(),
+            component_log: LogView::new(),
+            component_errors: Errors::new(),
+            component_dashboard: Dashboard::new(),
+            component_authors: Authors::new(),
+            component_commit_search: CommitSearch::new(),
+            component_file_search: FileSearch::new(),
+            component_stale: StaleBranches::new(),
+            component_commands: CustomCommands::new(),
+            component_tabs: RootTabs::new(),
+            component_toasts: Toasts::new(),
+            component_palette: Palette::new(),
+        })
+    }
 }
 
 pub async fn run() -> BDEResult<()> {
-    let mut app = App {
-        repos: Vec::new(),
-        runp: true,
-        run_mode: AppMode::Normal,
-        component_input: Input::new(),
-        component_repos_show: ReposShow::new(),
-        component_statusbar: StatusBar::new(),
+    run_tui(false).await
+}
+
+/// 按 Enter 退出并把选中的仓库路径输出到标准输出, 用于 cd "$(gtm --print-path)"
+pub async fn run_print_path() -> BDEResult<()> {
+    run_tui(true).await
+}
+
+async fn run_tui(print_pathp: bool) -> BDEResult<()> {
+    logging::init()?;
+    let mut app = App::new()?;
+    app.print_pathp = print_pathp;
+
+    let guard = tui::TerminalGuard::enter()?;
+    let res = app.run().await;
+    instance::release();
+    drop(guard);
+    if let Err(err) = &res {
+        tracing::error!("exit with error: {}", err);
+    }
+    res?;
+
+    // 界面画在标准错误上, 标准输出只有路径, 可以用 cd "$(gtm)" 切换目录
+    if let Some(path) = app.exit_path {
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+/// 所有标签页缓存中没有隐藏也没有丢失的仓库, 同一个仓库在多个标签页中时只保留一个
+fn load_cached_repos(config: &Config) -> BDEResult<Vec<GitRepo>> {
+    let ignore_repos = load_ignore_repos()?;
+    let mut repos: Vec<GitRepo> = Vec::new();
+    let mut cachedp = false;
+    for root in config.search_roots() {
+        let Some(cached) = load_repo_cache(&root.name)? else {
+            continue;
+        };
+        cachedp = true;
+        for repo in cached {
+            if repo.status != GitStatus::Missing
+                && !ignore_repos.contains(&repo.path)
+                && !repos.iter().any(|item| item.path == repo.path)
+            {
+                repos.push(repo);
+            }
+        }
+    }
+    if !cachedp {
+        return Err(ba_error(tr(
+            "没有仓库缓存, 请先运行 gtm 扫描仓库",
+            "No repo cache, run gtm to scan repos first",
+        )));
+    }
+    Ok(repos)
+}
+
+/// 每个仓库的备份地址, 模板中有 {owner} 但是没有 origin 的仓库放在第二个列表中
+fn backup_targets(repos: &[GitRepo], template: &str) -> (Vec<(PathBuf, String)>, Vec<PathBuf>) {
+    let mut targets = Vec::new();
+    let mut skipped = Vec::new();
+    for repo in repos {
+        match ops::backup_url(template, &repo.name, repo.owner().as_deref()) {
+            Some(url) => targets.push((repo.path.clone(), url)),
+            None => skipped.push(repo.path.clone()),
+        }
+    }
+    (targets, skipped)
+}
+
+/// 不打开界面, 把缓存中没有隐藏的仓库备份到 backup_remote, 输出每个仓库的结果
+///
+/// 有仓库备份失败时返回错误, 退出码不为 0
+pub async fn run_backup() -> BDEResult<()> {
+    logging::init()?;
+    let config = load_config()?;
+    i18n::set_language(config.language);
+    let Some(template) = config.backup_remote.clone() else {
+        return Err(GitManagerError::Config(String::from(tr(
+            "没有设置备份地址, 请在配置文件中设置 \"backup_remote\"",
+            "No backup address, set \"backup_remote\" in the config file",
+        ))));
     };
+    let repos = load_cached_repos(&config)?;
 
-    enable_raw_mode()?;
-    stdout().execute(EnterAlternateScreen)?;
-    app.run().await?;
-    disable_raw_mode()?;
-    stdout().execute(LeaveAlternateScreen)?;
+    let (targets, skipped) = backup_targets(&repos, &template);
+    for path in &skipped {
+        eprintln!(
+            "{}",
+            trf!(
+                "跳过 {}: 没有 origin, 无法得到 {{owner}}",
+                "Skip {}: no origin for {{owner}}",
+                path.display()
+            )
+        );
+    }
 
+    let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move { ops::backup(&targets, output_tx).await });
+    print_output(&mut output_rx).await;
+
+    let results = task.await??;
+    let failed = results.iter().filter(|res| res.error.is_some()).count();
+    if failed > 0 || !skipped.is_empty() {
+        return Err(ba_error(&trf!(
+            "{} 个仓库备份失败, {} 个仓库跳过",
+            "{} repos failed, {} repos skipped",
+            failed,
+            skipped.len()
+        )));
+    }
+    Ok(())
+}
+
+/// 不打开界面, 并行扫描 paths 中的仓库, 为空时使用配置中所有标签页的搜索目录,
+/// 每个搜索目录完成时在标准错误输出进度, 最后在标准输出输出每个仓库的状态和路径
+///
+/// 不读取也不写入仓库缓存
+pub async fn run_scan_paths(paths: &[PathBuf]) -> BDEResult<()> {
+    logging::init()?;
+    let config = load_config()?;
+    i18n::set_language(config.language);
+    fetch::set_offline(config.offline);
+    fetch::set_fetch_limits(config.fetch_host_concurrency, config.fetch_jitter_ms);
+
+    let roots = if paths.is_empty() {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for root in config.search_roots() {
+            let (allow_paths, outside_paths) = config.split_search_paths(&root.search_paths)?;
+            for path in allow_paths.into_iter().chain(outside_paths) {
+                if !roots.contains(&path) {
+                    roots.push(path);
+                }
+            }
+        }
+        roots
+    } else {
+        paths
+            .iter()
+            .map(|path| expand_path(&path.to_string_lossy()))
+            .collect::<BDEResult<_>>()?
+    };
+    let options = ScanOptions {
+        use_cache: false,
+        skip_nested: config.nested_repos == NestedRepos::Skip,
+        search: config.search_options()?,
+        ..ScanOptions::default()
+    };
+
+    let mut stream = scan_stream(roots, options);
+    let mut repos: Vec<GitRepo> = Vec::new();
+    let mut errors = 0;
+    while let Some(event) = stream.next().await {
+        match event {
+            RepoEvent::RootScanned { root, repos } => eprintln!(
+                "{}",
+                trf!(
+                    "{}: 找到 {} 个仓库",
+                    "{}: found {} repos",
+                    paths::tildify(&root),
+                    repos
+                )
+            ),
+            RepoEvent::Status(repo) | RepoEvent::Fetched(repo) => {
+                match repos.iter().position(|item| item.path == repo.path) {
+                    Some(index) => repos[index] = *repo,
+                    None => repos.push(*repo),
+                }
+            }
+            RepoEvent::Error { path, error } => {
+                errors += 1;
+                eprintln!("{}: {}", path.display(), error);
+            }
+            RepoEvent::Failed(err) => return Err(ba_error(&err)),
+            RepoEvent::Discovered(_) | RepoEvent::LocalFinished | RepoEvent::Finished { .. } => {}
+        }
+    }
+
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+    for repo in &repos {
+        println!("{}\t{}", repo.status, repo.path.display());
+    }
+    if errors > 0 {
+        return Err(ba_error(&trf!(
+            "{} 个仓库检查失败",
+            "{} repos failed to check",
+            errors
+        )));
+    }
+    Ok(())
+}
+
+/// 输出清单, path 为空时输出到标准输出
+pub fn run_export_manifest(path: Option<&Path>) -> BDEResult<()> {
+    let config = load_config()?;
+    i18n::set_language(config.language);
+    let repos = load_cached_repos(&config)?;
+
+    let json = serde_json::to_string_pretty(&manifest::build_manifest(&repos))?;
+    match path {
+        Some(path) => fs::write(path, json + "\n")?,
+        None => println!("{}", json),
+    }
     Ok(())
 }
+
+/// 克隆清单中缺少的仓库, 有仓库失败时返回错误
+pub async fn run_restore(path: &Path) -> BDEResult<()> {
+    logging::init()?;
+    i18n::set_language(load_config()?.language);
+    let manifest = manifest::load_manifest(path)?;
+
+    let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move { manifest::restore(&manifest, output_tx).await });
+    print_output(&mut output_rx).await;
+
+    let failed = task
+        .await??
+        .iter()
+        .filter(|(_, status)| matches!(status, manifest::RestoreStatus::Failed(_)))
+        .count();
+    if failed > 0 {
+        return Err(ba_error(&trf!(
+            "{} 个仓库恢复失败",
+            "{} repos failed to restore",
+            failed
+        )));
+    }
+    Ok(())
+}
+
+/// 不打开界面时, 把命令的输出打印到终端, 直到发送的一方结束
+async fn print_output(output_rx: &mut mpsc::UnboundedReceiver<CommandOutput>) {
+    while let Some(output) = output_rx.recv().await {
+        match output {
+            CommandOutput::Stdout(line) => println!("{}", line),
+            CommandOutput::Stderr(line) => eprintln!("{}", line),
+            CommandOutput::Exit(_) => {}
+        }
+    }
+}
+
+/// 在 dir 中打开编辑器, 编辑器需要使用终端, 先暂停界面, 退出编辑器之后恢复
+fn run_editor(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stderr>>,
+    editor: &str,
+    dir: &Path,
+    args: &[OsString],
+) -> BDEResult<()> {
+    disable_raw_mode()?;
+    stderr().execute(LeaveAlternateScreen)?;
+    let res = if cfg!(windows) {
+        // 没有 bash, 按空白分开编辑器命令和它的参数
+        let mut parts = editor.split_whitespace();
+        std::process::Command::new(parts.next().unwrap_or("notepad"))
+            .args(parts)
+            .args(args)
+            .current_dir(dir)
+            .status()
+    } else {
+        // 标准输出可能被 $(gtm) 捕获, 编辑器和界面一样使用标准错误
+        std::process::Command::new("bash")
+            .arg("-c")
+            .arg(format!("{} \"$@\"", editor))
+            .arg("bash")
+            .args(args)
+            .current_dir(dir)
+            .stdout(std::process::Stdio::from(stderr()))
+            .status()
+    };
+    stderr().execute(EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    res?;
+    Ok(())
+}
+
+/// 错误弹窗的内容, 错误种类和处理建议
+fn error_lines(err: &GitManagerError) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(format!("[{}] {}", err.category(), err))];
+    if let Some(hint) = err.hint() {
+        lines.push(Line::from(hint.dark_gray()));
+    }
+    lines
+}
+
+/// 和上游分叉的仓库不能快进时的说明
+fn diverged_guide_lines(repos: &[GitRepo]) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(tr(
+        "这些仓库的当前分支和上游分叉, 拉取只快进, 已经跳过:",
+        "The current branch of these repos has diverged from upstream. Pull only fast-forwards, so they were skipped:",
+    ))];
+    for repo in repos {
+        let (ahead, behind) = repo.current_ahead_behind();
+        lines.push(Line::from(trf!(
+            "  {} ({}): 领先 {}, 落后 {}",
+            "  {} ({}): {} ahead, {} behind",
+            repo.path.display(),
+            repo.branch,
+            ahead,
+            behind
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        tr(
+            "选中仓库之后按 w 打开分叉解决向导, 可以选择变基, 合并, 另存为新分支或者丢弃本地提交.",
+            "Select a repo and press w to open the divergence wizard: rebase, merge, move local commits to a branch or drop them.",
+        )
+        .dark_gray(),
+    ));
+    lines
+}
+
+/// 不打开界面, 按顺序执行脚本文件中的命令, 命令和界面使用同样的 action
+pub async fn run_script(path: &Path) -> BDEResult<()> {
+    logging::init()?;
+    let mut app = App::new()?;
+    app.script = Some(parse_script(&fs::read_to_string(path)?)?);
+    let res = app.run().await;
+    instance::release();
+    res
+}
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+
+use crate::paths::home_dir;
+use crate::utils::BDEResult;
+
+/// 界面中的日志窗口最多保留这么多行
+const MAX_LOG_LINES: usize = 1000;
+
+/// 日志文件, 初始化之前为 None
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// 最近的日志, 界面中的日志窗口显示这些行
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// 日志目录, 优先使用 $XDG_STATE_HOME, 否则为 ~/.local/state/git_manager
+pub fn log_dir() -> BDEResult<PathBuf> {
+    match std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir).join("git_manager")),
+        None => Ok(home_dir()?.join(".local").join("state").join("git_manager")),
+    }
+}
+
+/// 同时写入日志文件和内存中的最近日志
+struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut file) = LOG_FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                file.write_all(buf)?;
+            }
+        }
+
+        if let Ok(mut lines) = LOG_LINES.lock() {
+            for line in String::from_utf8_lossy(buf).lines() {
+                if lines.len() >= MAX_LOG_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(String::from(line));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Ok(mut file) = LOG_FILE.lock() {
+            if let Some(file) = file.as_mut() {
+                file.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 开始记录日志, 日志级别使用 $GTM_LOG, 比如 debug, 默认为 info
+///
+/// 界面画在终端上, 日志只写入文件, 不输出到终端
+pub fn init() -> BDEResult<()> {
+    let dir = log_dir()?;
+    fs::create_dir_all(&dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("log"))?;
+    if let Ok(mut log_file) = LOG_FILE.lock() {
+        *log_file = Some(file);
+    }
+
+    let level = std::env::var("GTM_LOG")
+        .ok()
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(LevelFilter::INFO);
+
+    // 已经初始化过时忽略
+    let _ = tracing_subscriber::fmt()
+        .with_writer(|| LogWriter)
+        .with_ansi(false)
+        .with_target(false)
+        .with_max_level(level)
+        .try_init();
+    Ok(())
+}
+
+/// 最近的日志
+pub fn recent_lines() -> Vec<String> {
+    LOG_LINES
+        .lock()
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
@@ -1,8 +1,34 @@
-use git_manager::run;
-use git_manager::utils::BDEResult;
+use std::path::PathBuf;
+
+use git_manager::utils::{ba_error, BDEResult};
+use git_manager::{
+    run, run_backup, run_export_manifest, run_print_path, run_restore, run_scan_paths, run_script,
+};
 
 #[tokio::main]
 async fn main() -> BDEResult<()> {
-    run().await?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => run().await?,
+        [flag] if flag == "--print-path" => run_print_path().await?,
+        [flag, script] if flag == "--script" => run_script(&PathBuf::from(script)).await?,
+        [command] if command == "backup" => run_backup().await?,
+        [command, paths @ ..] if command == "scan" => {
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            run_scan_paths(&paths).await?
+        }
+        [command] if command == "export-manifest" => run_export_manifest(None)?,
+        [command, file] if command == "export-manifest" => {
+            run_export_manifest(Some(&PathBuf::from(file)))?
+        }
+        [command, flag, file] if command == "restore" && flag == "--manifest" => {
+            run_restore(&PathBuf::from(file)).await?
+        }
+        _ => {
+            return Err(ba_error(
+                "用法: gtm [--print-path | --script <file> | scan [path...] | backup | export-manifest [file] | restore --manifest <file>]",
+            ))
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,225 @@
+//! 仓库清单: 导出所有仓库的路径, 远程仓库和分支, 在新的机器上按清单克隆缺少的仓库
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::gitrepo::GitRepo;
+use crate::i18n::{tr, trf};
+use crate::paths::{expand_path, tildify};
+use crate::utils::{BDEResult, Cmd, CommandOutput};
+
+/// 清单格式的版本, 格式不兼容时增加
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub repos: Vec<ManifestEntry>,
+}
+
+/// 清单中的一个仓库
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ManifestEntry {
+    /// HOME 中的路径以 ~ 开头, 换了用户名也可以恢复
+    pub path: String,
+    /// 远程仓库的名字和地址
+    pub remotes: BTreeMap<String, String>,
+    /// 当前分支, 分离 HEAD 时为空
+    #[serde(default)]
+    pub branch: String,
+}
+
+impl ManifestEntry {
+    /// 克隆使用的远程仓库, 优先使用 origin
+    fn clone_remote(&self) -> Option<(&String, &String)> {
+        self.remotes
+            .get_key_value("origin")
+            .or_else(|| self.remotes.iter().next())
+    }
+}
+
+/// 所有仓库的清单, 没有远程仓库的仓库无法恢复, 工作树跟随主仓库, 都不导出
+pub fn build_manifest(repos: &[GitRepo]) -> Manifest {
+    let mut entries: Vec<ManifestEntry> = repos
+        .iter()
+        .filter(|repo| repo.worktree_of.is_none())
+        .filter_map(|repo| {
+            let git_repo = git2::Repository::open(&repo.path).ok()?;
+            let remotes: BTreeMap<String, String> = git_repo
+                .remotes()
+                .ok()?
+                .iter()
+                .flatten()
+                .filter_map(|name| {
+                    let url = git_repo.find_remote(name).ok()?.url()?.to_string();
+                    Some((String::from(name), url))
+                })
+                .collect();
+            (!remotes.is_empty()).then(|| ManifestEntry {
+                path: tildify(&repo.path),
+                remotes,
+                branch: repo.branch.clone(),
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Manifest {
+        version: MANIFEST_VERSION,
+        repos: entries,
+    }
+}
+
+pub fn load_manifest(path: &Path) -> BDEResult<Manifest> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// 恢复一个仓库的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreStatus {
+    Cloned,
+    /// 路径已经存在, 不做任何修改
+    Exists,
+    Failed(String),
+}
+
+/// 克隆清单中路径不存在的仓库, 添加其它远程仓库并切换到记录的分支
+///
+/// 输出逐行发送到 output_tx, 最后发送 CommandOutput::Exit, 有一个仓库失败时退出码为 1
+pub async fn restore(
+    manifest: &Manifest,
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<Vec<(PathBuf, RestoreStatus)>> {
+    let mut results = Vec::new();
+    for entry in &manifest.repos {
+        let path = expand_path(&entry.path)?;
+        let status = if path.exists() {
+            RestoreStatus::Exists
+        } else {
+            let _ = output_tx.send(CommandOutput::Stdout(format!("==> {}", path.display())));
+            match restore_entry(entry, &path, &output_tx).await {
+                Ok(()) => RestoreStatus::Cloned,
+                Err(err) => {
+                    tracing::warn!("restore {}: {}", path.display(), err);
+                    RestoreStatus::Failed(err.to_string())
+                }
+            }
+        };
+        results.push((path, status));
+    }
+
+    let count = |status: fn(&RestoreStatus) -> bool| {
+        results.iter().filter(|(_, item)| status(item)).count()
+    };
+    let failed = count(|status| matches!(status, RestoreStatus::Failed(_)));
+    let _ = output_tx.send(CommandOutput::Stdout(trf!(
+        "==> 恢复完成: {} 个克隆, {} 个已经存在, {} 个失败",
+        "==> Restore finished: {} cloned, {} existed, {} failed",
+        count(|status| *status == RestoreStatus::Cloned),
+        count(|status| *status == RestoreStatus::Exists),
+        failed
+    )));
+    for (path, status) in &results {
+        if let RestoreStatus::Failed(err) = status {
+            let _ = output_tx.send(CommandOutput::Stdout(format!(
+                "  ✖ {}: {}",
+                path.display(),
+                err
+            )));
+        }
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(if failed > 0 { 1 } else { 0 })));
+    Ok(results)
+}
+
+async fn restore_entry(
+    entry: &ManifestEntry,
+    path: &Path,
+    output_tx: &UnboundedSender<CommandOutput>,
+) -> BDEResult<()> {
+    let Some((origin, url)) = entry.clone_remote() else {
+        return Err(tr("没有远程仓库", "No remote").into());
+    };
+    let parent = path.parent().unwrap_or(path);
+    fs::create_dir_all(parent)?;
+
+    let code = Cmd::git(parent)
+        .args(["clone", "--origin", origin.as_str(), url.as_str()])
+        .arg(path)
+        .stream(output_tx)
+        .await?;
+    if code != Some(0) {
+        return Err(trf!("克隆 {} 失败", "Failed to clone {}", url).into());
+    }
+
+    let repo = git2::Repository::open(path)?;
+    for (name, url) in entry.remotes.iter().filter(|(name, _)| *name != origin) {
+        repo.remote(name, url)?;
+    }
+
+    // 远程仓库中可能已经没有这个分支, 只提示不算作失败
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(String::from));
+    if !entry.branch.is_empty() && head.as_deref() != Some(entry.branch.as_str()) {
+        let code = Cmd::git(path)
+            .args(["checkout", entry.branch.as_str()])
+            .stream(output_tx)
+            .await?;
+        if code != Some(0) {
+            let _ = output_tx.send(CommandOutput::Stderr(trf!(
+                "无法切换到分支 {}",
+                "Cannot check out branch {}",
+                entry.branch
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{Manifest, ManifestEntry};
+
+    #[test]
+    fn test_manifest_json() {
+        let json = r#"{
+            "version": 1,
+            "repos": [
+                {
+                    "path": "~/code/git_manager",
+                    "remotes": {
+                        "backup": "/srv/backup/git_manager.git",
+                        "origin": "git@github.com:lizqwerscott/git_manager.git"
+                    }
+                }
+            ]
+        }"#;
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let entry = &manifest.repos[0];
+        assert_eq!(entry.branch, "");
+        assert_eq!(
+            entry.clone_remote().map(|(name, _)| name.as_str()),
+            Some("origin")
+        );
+
+        let entry = ManifestEntry {
+            path: String::from("/srv/repo"),
+            remotes: BTreeMap::from([(
+                String::from("upstream"),
+                String::from("https://example.com/a/b"),
+            )]),
+            branch: String::from("main"),
+        };
+        assert_eq!(
+            entry.clone_remote().map(|(name, _)| name.as_str()),
+            Some("upstream")
+        );
+    }
+}
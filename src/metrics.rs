@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 后台刷新任务的统计信息
+#[derive(Debug)]
+pub struct WorkerMetrics {
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    network: AtomicUsize,
+    scan_ms: AtomicU64,
+    status_ms: AtomicU64,
+    fetch_ms: AtomicU64,
+}
+
+/// 某一时刻的统计信息, 用于界面显示
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsSnapshot {
+    pub queued: usize,
+    pub running: usize,
+    pub network: usize,
+    pub scan: Duration,
+    /// 所有任务检查状态花费的时间之和
+    pub status: Duration,
+    /// 所有任务 fetch 花费的时间之和
+    pub fetch: Duration,
+}
+
+pub static WORKER_METRICS: WorkerMetrics = WorkerMetrics::new();
+
+impl WorkerMetrics {
+    const fn new() -> Self {
+        WorkerMetrics {
+            queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+            network: AtomicUsize::new(0),
+            scan_ms: AtomicU64::new(0),
+            status_ms: AtomicU64::new(0),
+            fetch_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// 开始新的一次刷新, 清空上一次的耗时
+    pub fn reset_durations(&self) {
+        self.scan_ms.store(0, Ordering::Relaxed);
+        self.status_ms.store(0, Ordering::Relaxed);
+        self.fetch_ms.store(0, Ordering::Relaxed);
+    }
+
+    pub fn add_scan(&self, duration: Duration) {
+        self.scan_ms
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_status(&self, duration: Duration) {
+        self.status_ms
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_fetch(&self, duration: Duration) {
+        self.fetch_ms
+            .fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// 任务进入队列, 返回的 guard 在任务开始执行时调用 start
+    pub fn queue(&'static self) -> TaskGuard {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        TaskGuard {
+            metrics: self,
+            runningp: false,
+        }
+    }
+
+    /// 正在进行的网络操作, guard 被释放时结束
+    pub fn network(&'static self) -> NetworkGuard {
+        self.network.fetch_add(1, Ordering::Relaxed);
+        NetworkGuard { metrics: self }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            queued: self.queued.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            network: self.network.load(Ordering::Relaxed),
+            scan: Duration::from_millis(self.scan_ms.load(Ordering::Relaxed)),
+            status: Duration::from_millis(self.status_ms.load(Ordering::Relaxed)),
+            fetch: Duration::from_millis(self.fetch_ms.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TaskGuard {
+    metrics: &'static WorkerMetrics,
+    runningp: bool,
+}
+
+impl TaskGuard {
+    pub fn start(&mut self) {
+        if !self.runningp {
+            self.runningp = true;
+            self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+            self.metrics.running.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if self.runningp {
+            self.metrics.running.fetch_sub(1, Ordering::Relaxed);
+        } else {
+            self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NetworkGuard {
+    metrics: &'static WorkerMetrics,
+}
+
+impl Drop for NetworkGuard {
+    fn drop(&mut self) {
+        self.metrics.network.fetch_sub(1, Ordering::Relaxed);
+    }
+}
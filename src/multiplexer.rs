@@ -0,0 +1,120 @@
+//! 在 tmux 或者 zellij 中打开仓库: 新建一个以仓库命名的窗口或者标签页, 工作目录为仓库
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::i18n::{tr, trf};
+use crate::utils::{ba_error, BDEResult, Cmd};
+
+/// 等待复用器命令退出的时间 (秒), 命令只是通知复用器新建窗口, 很快就会退出
+const MULTIPLEXER_TIMEOUT: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+}
+
+impl fmt::Display for Multiplexer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Multiplexer::Tmux => write!(f, "tmux"),
+            Multiplexer::Zellij => write!(f, "zellij"),
+        }
+    }
+}
+
+impl Multiplexer {
+    /// 根据复用器设置的环境变量判断当前运行在哪一个复用器中
+    pub fn detect() -> Option<Self> {
+        let setp = |key: &str| std::env::var_os(key).is_some_and(|value| !value.is_empty());
+        if setp("TMUX") {
+            Some(Multiplexer::Tmux)
+        } else if setp("ZELLIJ") {
+            Some(Multiplexer::Zellij)
+        } else {
+            None
+        }
+    }
+
+    /// 默认的命令, {name} 和 {path} 会被替换
+    fn default_command(&self) -> Vec<String> {
+        let command: &[&str] = match self {
+            Multiplexer::Tmux => &["tmux", "new-window", "-n", "{name}", "-c", "{path}"],
+            Multiplexer::Zellij => &[
+                "zellij", "action", "new-tab", "--name", "{name}", "--cwd", "{path}",
+            ],
+        };
+        command.iter().map(|arg| String::from(*arg)).collect()
+    }
+}
+
+/// 替换命令中的 {name} 和 {path}, 每个参数单独替换, 不经过 shell
+fn expand_command(command: &[String], name: &str, path: &Path) -> Vec<String> {
+    let path = path.display().to_string();
+    command
+        .iter()
+        .map(|arg| arg.replace("{name}", name).replace("{path}", &path))
+        .collect()
+}
+
+/// 在当前的复用器中打开仓库, commands 按复用器的名字覆盖默认的命令
+pub async fn open_in_multiplexer(
+    commands: &BTreeMap<String, Vec<String>>,
+    name: &str,
+    path: &Path,
+) -> BDEResult<Multiplexer> {
+    let Some(multiplexer) = Multiplexer::detect() else {
+        return Err(ba_error(tr(
+            "没有在 tmux 或者 zellij 中运行",
+            "Not running inside tmux or zellij",
+        )));
+    };
+    let command = match commands.get(&multiplexer.to_string()) {
+        Some(command) if !command.is_empty() => command.clone(),
+        _ => multiplexer.default_command(),
+    };
+    let command = expand_command(&command, name, path);
+
+    tracing::info!(?command, "open in {}", multiplexer);
+    let successp = Cmd::new(&command[0])
+        .args(&command[1..])
+        .status_timeout(MULTIPLEXER_TIMEOUT)
+        .await?;
+    if !successp {
+        return Err(ba_error(&trf!(
+            "{} 执行失败",
+            "{} failed",
+            command.join(" ")
+        )));
+    }
+    Ok(multiplexer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{expand_command, Multiplexer};
+
+    #[test]
+    fn test_expand_command() {
+        let command = expand_command(
+            &Multiplexer::Tmux.default_command(),
+            "git_manager",
+            Path::new("/home/me/code/git manager"),
+        );
+        assert_eq!(
+            command,
+            vec![
+                "tmux",
+                "new-window",
+                "-n",
+                "git_manager",
+                "-c",
+                "/home/me/code/git manager"
+            ]
+        );
+    }
+}
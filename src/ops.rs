@@ -0,0 +1,700 @@
+//! 对仓库执行的 git 操作, 界面中的拉取和推送也使用这里的命令
+//!
+//! ```no_run
+//! # async fn example() -> git_manager::utils::BDEResult<()> {
+//! use git_manager::ops::{run, GitOperation};
+//!
+//! let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+//! run(GitOperation::Pull, &[std::path::PathBuf::from("/path/to/repo")], tx).await?;
+//! while let Ok(output) = rx.try_recv() {
+//!     println!("{:?}", output);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+pub use crate::backend::run_fetch as fetch;
+pub use crate::fetch::{batch_fetch, FetchResult, FETCH_TIMEOUT};
+pub use crate::utils::CommandOutput;
+
+use crate::format::Formatter;
+use crate::i18n::trf;
+use crate::size::git_dir_size;
+use crate::utils::{BDEResult, Cmd};
+
+/// 可以对多个仓库批量执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitOperation {
+    /// 只快进 (pull --ff-only), 和上游分叉时失败, 不会自动合并或者变基
+    Pull,
+    Push,
+    /// 先 pull --rebase 再 push
+    Sync,
+    /// 推送本地的标签, 不推送分支
+    PushTags,
+}
+
+impl GitOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GitOperation::Pull => "pull",
+            GitOperation::Push => "push",
+            GitOperation::Sync => "sync",
+            GitOperation::PushTags => "push --tags",
+        }
+    }
+
+    /// 在仓库目录中依次执行的 git 参数, 有一步失败时停止
+    pub fn steps(&self) -> &'static [&'static [&'static str]] {
+        match self {
+            GitOperation::Pull => &[&["pull", "--ff-only"]],
+            GitOperation::Push => &[&["push"]],
+            GitOperation::Sync => &[&["pull", "--rebase"], &["push"]],
+            GitOperation::PushTags => &[&["push", "--tags"]],
+        }
+    }
+}
+
+/// 对 paths 中的仓库执行操作, 输出逐行发送到 output_tx
+///
+/// 直接启动 git, 不需要 bash, 有一个仓库失败时退出码为 1
+pub async fn run(
+    operation: GitOperation,
+    paths: &[PathBuf],
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<()> {
+    tracing::info!("run {} in {} repos", operation.name(), paths.len());
+    let mut status = 0;
+    for path in paths {
+        if paths.len() > 1 {
+            let _ = output_tx.send(CommandOutput::Stdout(format!("==> {}", path.display())));
+        }
+        if !run_steps(operation, path, &output_tx).await? {
+            status = 1;
+        }
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(status)));
+    Ok(())
+}
+
+/// 同时执行的操作任务数量的默认值
+pub const DEFAULT_OP_JOBS: usize = 4;
+
+/// 一个仓库上的操作任务的状态, 成功完成之后不再保留
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpState {
+    Queued(GitOperation),
+    Running(GitOperation),
+    Failed(GitOperation),
+}
+
+impl OpState {
+    /// 排队或者正在执行, 这时不能再对这个仓库执行操作
+    pub fn activep(&self) -> bool {
+        !matches!(self, OpState::Failed(_))
+    }
+}
+
+/// 任务状态的变化, state 为空时表示成功完成
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpEvent {
+    pub path: PathBuf,
+    pub state: Option<OpState>,
+}
+
+/// 在仓库中依次执行操作的每一步, 返回是否成功
+async fn run_steps(
+    operation: GitOperation,
+    path: &Path,
+    output_tx: &UnboundedSender<CommandOutput>,
+) -> BDEResult<bool> {
+    for args in operation.steps() {
+        let code = Cmd::git(path)
+            .args(["-c", "color.ui=always"])
+            .args(*args)
+            .stream(output_tx)
+            .await?;
+        if code != Some(0) {
+            tracing::warn!(
+                "{} failed ({:?}): {}",
+                operation.name(),
+                code,
+                path.display()
+            );
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// 把 paths 中每个仓库的操作作为一个任务, 最多同时执行 jobs 个
+///
+/// 任务状态的变化发送到 state_tx. 多个仓库时每个仓库的输出在任务完成之后
+/// 加上仓库路径一起发送到 output_tx, 不会互相交错. 有一个仓库失败时退出码为 1
+pub async fn run_jobs(
+    operation: GitOperation,
+    paths: &[PathBuf],
+    jobs: usize,
+    output_tx: UnboundedSender<CommandOutput>,
+    state_tx: UnboundedSender<OpEvent>,
+) -> BDEResult<()> {
+    tracing::info!(
+        "run {} in {} repos, {} jobs",
+        operation.name(),
+        paths.len(),
+        jobs
+    );
+    for path in paths {
+        let _ = state_tx.send(OpEvent {
+            path: path.clone(),
+            state: Some(OpState::Queued(operation)),
+        });
+    }
+
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let bufferp = paths.len() > 1;
+    let mut set = JoinSet::new();
+    for path in paths.iter().cloned() {
+        let semaphore = semaphore.clone();
+        let output_tx = output_tx.clone();
+        let state_tx = state_tx.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let _ = state_tx.send(OpEvent {
+                path: path.clone(),
+                state: Some(OpState::Running(operation)),
+            });
+
+            let successp = if bufferp {
+                let (buffer_tx, mut buffer_rx) = mpsc::unbounded_channel();
+                let res = run_steps(operation, &path, &buffer_tx).await;
+                let _ = output_tx.send(CommandOutput::Stdout(format!("==> {}", path.display())));
+                while let Ok(output) = buffer_rx.try_recv() {
+                    let _ = output_tx.send(output);
+                }
+                res
+            } else {
+                run_steps(operation, &path, &output_tx).await
+            };
+            let successp = successp.unwrap_or_else(|err| {
+                let _ = output_tx.send(CommandOutput::Stderr(err.to_string()));
+                false
+            });
+
+            let _ = state_tx.send(OpEvent {
+                path,
+                state: (!successp).then_some(OpState::Failed(operation)),
+            });
+            successp
+        });
+    }
+
+    let mut status = 0;
+    while let Some(res) = set.join_next().await {
+        if !matches!(res, Ok(true)) {
+            status = 1;
+        }
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(status)));
+    Ok(())
+}
+
+/// 一个仓库中执行命令的结果, code 为空时命令没有正常退出
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeachResult {
+    pub path: PathBuf,
+    pub code: Option<i32>,
+}
+
+/// 在每个仓库目录中执行 shell 命令, 最多同时执行 jobs 个
+///
+/// 每个仓库的输出在命令完成之后加上仓库路径一起发送到 output_tx, 最后输出每个仓库的退出码,
+/// 有一个仓库失败时退出码为 1
+pub async fn foreach(
+    command: &str,
+    paths: &[PathBuf],
+    jobs: usize,
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<Vec<ForeachResult>> {
+    tracing::info!(
+        "foreach in {} repos, {} jobs: {}",
+        paths.len(),
+        jobs,
+        command
+    );
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut set = JoinSet::new();
+    for (index, path) in paths.iter().cloned().enumerate() {
+        let semaphore = semaphore.clone();
+        let output_tx = output_tx.clone();
+        let command = String::from(command);
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let (buffer_tx, mut buffer_rx) = mpsc::unbounded_channel();
+            let code = Cmd::shell(&path, &command)
+                .stream(&buffer_tx)
+                .await
+                .unwrap_or_else(|err| {
+                    let _ = buffer_tx.send(CommandOutput::Stderr(err.to_string()));
+                    None
+                });
+            let _ = output_tx.send(CommandOutput::Stdout(format!("==> {}", path.display())));
+            while let Ok(output) = buffer_rx.try_recv() {
+                let _ = output_tx.send(output);
+            }
+            (index, ForeachResult { path, code })
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(res) = set.join_next().await {
+        if let Ok(res) = res {
+            results.push(res);
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<ForeachResult> = results.into_iter().map(|(_, res)| res).collect();
+
+    let failed = results.iter().filter(|res| res.code != Some(0)).count();
+    let _ = output_tx.send(CommandOutput::Stdout(trf!(
+        "==> 执行完成: {} 个成功, {} 个失败",
+        "==> Finished: {} succeeded, {} failed",
+        results.len() - failed,
+        failed
+    )));
+    for res in &results {
+        let line = match res.code {
+            Some(0) => format!("  ✔ {}", res.path.display()),
+            code => format!(
+                "  ✖ {} ({})",
+                res.path.display(),
+                code.map_or(String::from("-"), |code| code.to_string())
+            ),
+        };
+        let _ = output_tx.send(CommandOutput::Stdout(line));
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(if failed > 0 { 1 } else { 0 })));
+    Ok(results)
+}
+
+/// 一个仓库的维护结果, error 为空时成功
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceResult {
+    pub path: PathBuf,
+    /// 维护前后 git 目录的大小
+    pub before: u64,
+    pub after: u64,
+    pub error: Option<String>,
+}
+
+impl MaintenanceResult {
+    /// 释放的空间, 维护之后变大时为 0
+    pub fn reclaimed(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+/// 在仓库中执行的维护命令, 没有 origin 时跳过 remote prune
+fn maintenance_steps(path: &Path, maintenance_run: bool) -> Vec<&'static [&'static str]> {
+    let mut steps: Vec<&'static [&'static str]> = vec![&["gc", "--auto"]];
+    let has_origin = git2::Repository::open(path)
+        .and_then(|repo| repo.find_remote("origin").map(|_| ()))
+        .is_ok();
+    if has_origin {
+        steps.push(&["remote", "prune", "origin"]);
+    }
+    if maintenance_run {
+        steps.push(&["maintenance", "run"]);
+    }
+    steps
+}
+
+async fn blocking_git_dir_size(path: &Path) -> u64 {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || git_dir_size(&path).unwrap_or(0))
+        .await
+        .unwrap_or(0)
+}
+
+/// 对每个仓库执行 git gc --auto 和 git remote prune origin, maintenance_run 时再执行
+/// git maintenance run, 最多同时维护 jobs 个仓库
+///
+/// 每个仓库的输出在完成之后加上仓库路径一起发送到 output_tx, 最后输出释放的空间,
+/// 有一个仓库失败时退出码为 1
+pub async fn maintenance(
+    paths: &[PathBuf],
+    maintenance_run: bool,
+    jobs: usize,
+    formatter: Formatter,
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<Vec<MaintenanceResult>> {
+    tracing::info!("maintenance {} repos, {} jobs", paths.len(), jobs);
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut set = JoinSet::new();
+    for (index, path) in paths.iter().cloned().enumerate() {
+        let semaphore = semaphore.clone();
+        let output_tx = output_tx.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let before = blocking_git_dir_size(&path).await;
+
+            let (buffer_tx, mut buffer_rx) = mpsc::unbounded_channel();
+            let mut error = None;
+            for args in maintenance_steps(&path, maintenance_run) {
+                let code = match Cmd::git(&path).args(args).stream(&buffer_tx).await {
+                    Ok(code) => code,
+                    Err(err) => {
+                        error = Some(err.to_string());
+                        break;
+                    }
+                };
+                if code != Some(0) {
+                    error = Some(trf!(
+                        "git {} 失败, 退出码 {}",
+                        "git {} failed with exit code {}",
+                        args.join(" "),
+                        code.map_or(String::from("-"), |code| code.to_string())
+                    ));
+                    break;
+                }
+            }
+            if let Some(error) = &error {
+                tracing::warn!("maintenance {}: {}", path.display(), error);
+            }
+
+            let _ = output_tx.send(CommandOutput::Stdout(format!("==> {}", path.display())));
+            while let Ok(output) = buffer_rx.try_recv() {
+                let _ = output_tx.send(output);
+            }
+            let after = blocking_git_dir_size(&path).await;
+            (
+                index,
+                MaintenanceResult {
+                    path,
+                    before,
+                    after,
+                    error,
+                },
+            )
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(res) = set.join_next().await {
+        if let Ok(res) = res {
+            results.push(res);
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<MaintenanceResult> = results.into_iter().map(|(_, res)| res).collect();
+
+    let failed = results.iter().filter(|res| res.error.is_some()).count();
+    let reclaimed: u64 = results.iter().map(MaintenanceResult::reclaimed).sum();
+    let _ = output_tx.send(CommandOutput::Stdout(trf!(
+        "==> 维护完成: {} 个成功, {} 个失败, 释放 {}",
+        "==> Maintenance finished: {} succeeded, {} failed, {} reclaimed",
+        results.len() - failed,
+        failed,
+        formatter.size(reclaimed)
+    )));
+    for res in &results {
+        let line = match &res.error {
+            Some(error) => format!("  ✖ {}: {}", res.path.display(), error),
+            None if res.reclaimed() > 0 => format!(
+                "  ✔ {}: -{}",
+                res.path.display(),
+                formatter.size(res.reclaimed())
+            ),
+            None => continue,
+        };
+        let _ = output_tx.send(CommandOutput::Stdout(line));
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(if failed > 0 { 1 } else { 0 })));
+    Ok(results)
+}
+
+/// 备份使用的远程仓库的名字
+pub const BACKUP_REMOTE: &str = "backup";
+
+/// 根据模板得到仓库的备份地址, {name} 为仓库名字, {owner} 为 origin 的所有者
+///
+/// 模板中有 {owner} 但是没有 origin 时返回 None
+pub fn backup_url(template: &str, name: &str, owner: Option<&str>) -> Option<String> {
+    let url = template.replace("{name}", name);
+    match owner {
+        Some(owner) => Some(url.replace("{owner}", owner)),
+        None if url.contains("{owner}") => None,
+        None => Some(url),
+    }
+}
+
+/// 一个仓库的备份结果, error 为空时成功
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupResult {
+    pub path: PathBuf,
+    pub error: Option<String>,
+}
+
+/// 添加备份远程仓库, 已经存在但是地址不同时更新地址
+fn ensure_backup_remote(path: &Path, url: &str) -> BDEResult<()> {
+    let repo = git2::Repository::open(path)?;
+    match repo.find_remote(BACKUP_REMOTE) {
+        Ok(remote) if remote.url() == Some(url) => {}
+        Ok(_) => repo.remote_set_url(BACKUP_REMOTE, url)?,
+        Err(_) => {
+            repo.remote(BACKUP_REMOTE, url)?;
+        }
+    }
+    Ok(())
+}
+
+/// 把每个仓库的所有分支和标签推送到备份远程仓库, repos 为 (路径, 备份地址)
+///
+/// 输出逐行发送到 output_tx, 最后输出每个仓库的结果, 有一个仓库失败时退出码为 1
+pub async fn backup(
+    repos: &[(PathBuf, String)],
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<Vec<BackupResult>> {
+    tracing::info!("backup {} repos", repos.len());
+    let mut results = Vec::new();
+    for (path, url) in repos {
+        let _ = output_tx.send(CommandOutput::Stdout(format!(
+            "==> {} -> {}",
+            path.display(),
+            url
+        )));
+
+        let mut error = ensure_backup_remote(path, url)
+            .err()
+            .map(|err| err.to_string());
+        if error.is_none() {
+            for args in [["push", "--all"], ["push", "--tags"]] {
+                let code = Cmd::git(path)
+                    .args(["-c", "color.ui=always"])
+                    .args(args)
+                    .arg(BACKUP_REMOTE)
+                    .stream(&output_tx)
+                    .await?;
+                if code != Some(0) {
+                    error = Some(trf!(
+                        "git {} {} 失败, 退出码 {}",
+                        "git {} {} failed with exit code {}",
+                        args.join(" "),
+                        BACKUP_REMOTE,
+                        code.map_or(String::from("-"), |code| code.to_string())
+                    ));
+                    break;
+                }
+            }
+        }
+
+        if let Some(error) = &error {
+            tracing::warn!("backup {}: {}", path.display(), error);
+        }
+        results.push(BackupResult {
+            path: path.clone(),
+            error,
+        });
+    }
+
+    let failed = results.iter().filter(|res| res.error.is_some()).count();
+    let _ = output_tx.send(CommandOutput::Stdout(trf!(
+        "==> 备份完成: {} 个成功, {} 个失败",
+        "==> Backup finished: {} succeeded, {} failed",
+        results.len() - failed,
+        failed
+    )));
+    for res in &results {
+        let line = match &res.error {
+            Some(error) => format!("  ✖ {}: {}", res.path.display(), error),
+            None => format!("  ✔ {}", res.path.display()),
+        };
+        let _ = output_tx.send(CommandOutput::Stdout(line));
+    }
+    let _ = output_tx.send(CommandOutput::Exit(Some(if failed > 0 { 1 } else { 0 })));
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        backup_url, foreach, run, run_jobs, CommandOutput, ForeachResult, GitOperation, OpEvent,
+        OpState,
+    };
+    use crate::utils::git_output;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_backup_url() {
+        assert_eq!(
+            backup_url("git@backup-host:{name}.git", "repo", None).as_deref(),
+            Some("git@backup-host:repo.git")
+        );
+        assert_eq!(
+            backup_url("/srv/backup/{owner}/{name}.git", "repo", Some("me")).as_deref(),
+            Some("/srv/backup/me/repo.git")
+        );
+        assert_eq!(backup_url("/srv/{owner}/{name}.git", "repo", None), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_jobs() {
+        let dir = std::env::temp_dir().join(format!("gtm-jobs-{}", std::process::id()));
+        let paths = vec![dir.join("a"), dir.join("b")];
+        for path in &paths {
+            fs::create_dir_all(path).unwrap();
+        }
+
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (state_tx, mut state_rx) = tokio::sync::mpsc::unbounded_channel();
+        run_jobs(GitOperation::Pull, &paths, 1, output_tx, state_tx)
+            .await
+            .unwrap();
+
+        // 不是 git 仓库, 每个仓库都经过排队, 执行, 失败
+        let mut events = Vec::new();
+        while let Ok(event) = state_rx.try_recv() {
+            events.push(event);
+        }
+        for path in &paths {
+            let states: Vec<Option<OpState>> = events
+                .iter()
+                .filter(|event| &event.path == path)
+                .map(|OpEvent { state, .. }| *state)
+                .collect();
+            assert_eq!(
+                states,
+                vec![
+                    Some(OpState::Queued(GitOperation::Pull)),
+                    Some(OpState::Running(GitOperation::Pull)),
+                    Some(OpState::Failed(GitOperation::Pull)),
+                ]
+            );
+        }
+
+        let mut outputs = Vec::new();
+        while let Ok(output) = output_rx.try_recv() {
+            outputs.push(output);
+        }
+        let headers = outputs
+            .iter()
+            .filter(
+                |output| matches!(output, CommandOutput::Stdout(line) if line.starts_with("==> ")),
+            )
+            .count();
+        assert_eq!(headers, 2);
+        assert!(matches!(outputs.last(), Some(CommandOutput::Exit(Some(1)))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_foreach() {
+        let dir = std::env::temp_dir().join(format!("gtm-foreach-{}", std::process::id()));
+        let paths = vec![dir.join("a"), dir.join("b"), dir.join("c")];
+        for path in &paths {
+            fs::create_dir_all(path).unwrap();
+        }
+        fs::write(dir.join("b/fail"), "").unwrap();
+
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel();
+        let results = foreach("test ! -e fail && pwd", &paths, 2, output_tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                ForeachResult {
+                    path: paths[0].clone(),
+                    code: Some(0)
+                },
+                ForeachResult {
+                    path: paths[1].clone(),
+                    code: Some(1)
+                },
+                ForeachResult {
+                    path: paths[2].clone(),
+                    code: Some(0)
+                },
+            ]
+        );
+
+        // 每个仓库的输出紧跟在它的路径后面
+        let mut lines = Vec::new();
+        while let Ok(output) = output_rx.try_recv() {
+            if let CommandOutput::Stdout(line) = output {
+                lines.push(line);
+            }
+        }
+        let header = format!("==> {}", paths[2].display());
+        let index = lines.iter().position(|line| *line == header).unwrap();
+        assert_eq!(lines[index + 1], paths[2].display().to_string());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn commit(path: &Path, message: &str) {
+        git_output(
+            path,
+            [
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@b.c",
+                "commit",
+                "--allow-empty",
+                "-m",
+                message,
+            ],
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pull_fast_forward_only() {
+        let dir = std::env::temp_dir().join(format!("gtm-ff-{}", std::process::id()));
+        let upstream = dir.join("upstream");
+        let clone = dir.join("clone");
+        fs::create_dir_all(&upstream).unwrap();
+        git_output(&upstream, ["init", "-q"]).unwrap();
+        commit(&upstream, "init");
+        git_output(&dir, ["clone", "-q", "upstream", "clone"]).unwrap();
+
+        // 只落后上游时快进
+        commit(&upstream, "second");
+        let (output_tx, _output_rx) = tokio::sync::mpsc::unbounded_channel();
+        run(
+            GitOperation::Pull,
+            std::slice::from_ref(&clone),
+            output_tx.clone(),
+        )
+        .await
+        .unwrap();
+        let head = |path: &Path| git_output(path, ["rev-parse", "HEAD"]).unwrap();
+        assert_eq!(head(&clone), head(&upstream));
+
+        // 分叉之后拒绝拉取, 本地分支不变
+        commit(&upstream, "third");
+        commit(&clone, "local");
+        let local = head(&clone);
+        let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel();
+        run(GitOperation::Pull, std::slice::from_ref(&clone), output_tx)
+            .await
+            .unwrap();
+        assert_eq!(head(&clone), local);
+        let mut last = None;
+        while let Ok(output) = output_rx.try_recv() {
+            last = Some(output);
+        }
+        assert!(matches!(last, Some(CommandOutput::Exit(Some(1)))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! 路径工具: HOME 目录, 展开配置和输入中的路径, 界面中显示的路径
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::GitManagerError;
+use crate::utils::BDEResult;
+
+/// HOME 目录, Windows 中没有 HOME 时使用 USERPROFILE
+pub fn home_dir() -> BDEResult<PathBuf> {
+    ["HOME", "USERPROFILE"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| GitManagerError::Config(String::from("没有设置 HOME 环境变量")))
+}
+
+/// 展开开头的 ~ 和其中的 $VAR, ${VAR}, 相对路径基于当前目录, 结果是绝对路径
+///
+/// 没有设置的环境变量保持原样
+pub fn expand_path(path: &str) -> BDEResult<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    Ok(expand_path_with(path, &home_dir()?, &cwd, |name| {
+        std::env::var(name).ok()
+    }))
+}
+
+pub(crate) fn expand_path_with(
+    path: &str,
+    home: &Path,
+    cwd: &Path,
+    var: impl Fn(&str) -> Option<String>,
+) -> PathBuf {
+    let (base, rest) = if path == "~" {
+        (Some(home), "")
+    } else if let Some(rest) = path.strip_prefix("~/").or(path.strip_prefix("~\\")) {
+        (Some(home), rest)
+    } else {
+        (None, path)
+    };
+
+    let rest = PathBuf::from(expand_vars(rest, var));
+    let path = match base {
+        Some(home) => home.join(rest),
+        None if rest.is_relative() => cwd.join(rest),
+        None => rest,
+    };
+    normalize(&path)
+}
+
+/// 替换字符串中的 $VAR 和 ${VAR}
+fn expand_vars(input: &str, var: impl Fn(&str) -> Option<String>) -> String {
+    let mut res = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(index) = rest.find('$') {
+        res.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+
+        let (name, len) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        match (!name.is_empty()).then(|| var(name)).flatten() {
+            Some(value) => res.push_str(&value),
+            None => res.push_str(&rest[index..index + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+
+    res.push_str(rest);
+    res
+}
+
+/// 按字面去掉路径中的 . 和 .., 不访问文件系统, 根目录的 .. 仍是根目录
+fn normalize(path: &Path) -> PathBuf {
+    let mut res = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !res.pop() && !res.has_root() {
+                    res.push(component);
+                }
+            }
+            component => res.push(component),
+        }
+    }
+    res
+}
+
+/// 界面中显示的路径, HOME 中的路径显示为 ~ 开头
+pub fn tildify(path: &Path) -> String {
+    match home_dir() {
+        Ok(home) => tildify_with(path, &home),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+fn tildify_with(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(rest) if rest.as_os_str().is_empty() => String::from("~"),
+        Ok(rest) => Path::new("~").join(rest).display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+/// 扫描时跳过的路径模式, 和 .gitignore 类似
+///
+/// 不含 / 的模式匹配目录的名字, 比如 node_modules, 其它模式匹配完整路径, 比如 /mnt/**.
+/// * 和 ? 不匹配 /, ** 匹配任意多层目录, 以 /** 结尾的模式同时匹配目录本身
+#[derive(Debug, Clone)]
+pub struct ExcludePattern {
+    chars: Vec<char>,
+    namep: bool,
+}
+
+impl ExcludePattern {
+    /// ~/ 开头的模式替换为 HOME 中的路径
+    pub fn new(pattern: &str, home: &Path) -> Self {
+        let pattern = match pattern.strip_prefix("~/") {
+            Some(rest) => home.join(rest).display().to_string(),
+            None => String::from(pattern),
+        };
+        let pattern = pattern.trim_end_matches('/');
+        ExcludePattern {
+            namep: !pattern.contains('/'),
+            chars: pattern.chars().collect(),
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let text: Vec<char> = if self.namep {
+            match path.file_name() {
+                Some(name) => name.to_string_lossy().chars().collect(),
+                None => return false,
+            }
+        } else {
+            path.to_string_lossy().chars().collect()
+        };
+        glob_match(&self.chars, &text)
+            || self
+                .chars
+                .strip_suffix(&['/', '*', '*'])
+                .is_some_and(|dir| glob_match(dir, &text))
+    }
+}
+
+/// 网络文件系统的类型, 扫描时默认跳过这些挂载点
+const NETWORK_FS_TYPES: [&str; 13] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "sshfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "davfs",
+    "afs",
+    "ceph",
+    "glusterfs",
+];
+
+/// /proc/self/mounts 中网络文件系统的挂载点, 没有这个文件的系统返回空
+pub fn network_mounts() -> Vec<PathBuf> {
+    std::fs::read_to_string("/proc/self/mounts")
+        .map(|content| parse_network_mounts(&content))
+        .unwrap_or_default()
+}
+
+/// 每行为 "设备 挂载点 类型 选项 0 0", 挂载点中的空格等字符写成 \040 这样的八进制
+fn parse_network_mounts(content: &str) -> Vec<PathBuf> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            let fs_type = fields.next()?;
+            NETWORK_FS_TYPES
+                .contains(&fs_type)
+                .then(|| PathBuf::from(unescape_octal(mount_point)))
+        })
+        .collect()
+}
+
+fn unescape_octal(input: &str) -> String {
+    let mut res = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(index) = rest.find('\\') {
+        res.push_str(&rest[..index]);
+        let code = rest
+            .get(index + 1..index + 4)
+            .and_then(|code| u8::from_str_radix(code, 8).ok());
+        match code {
+            Some(code) => {
+                res.push(char::from(code));
+                rest = &rest[index + 4..];
+            }
+            None => {
+                res.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|index| glob_match(rest, &text[index..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&index| index == 0 || text[index - 1] != '/')
+            .any(|index| glob_match(rest, &text[index..])),
+        ['?', rest @ ..] => {
+            matches!(text.first(), Some(c) if *c != '/') && glob_match(rest, &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{expand_path_with, parse_network_mounts, tildify_with, ExcludePattern};
+
+    #[test]
+    fn test_expand_path() {
+        let home = Path::new("/home/test");
+        let cwd = Path::new("/work");
+        let var = |name: &str| (name == "PROJ").then(|| String::from("/srv/proj"));
+        let expand = |path: &str| expand_path_with(path, home, cwd, var);
+
+        assert_eq!(expand("~"), PathBuf::from("/home/test"));
+        assert_eq!(expand("~/"), PathBuf::from("/home/test"));
+        assert_eq!(expand("~/code/../src"), PathBuf::from("/home/test/src"));
+        assert_eq!(expand("$PROJ/a"), PathBuf::from("/srv/proj/a"));
+        assert_eq!(expand("${PROJ}/b"), PathBuf::from("/srv/proj/b"));
+        assert_eq!(expand("$NONE/c"), PathBuf::from("/work/$NONE/c"));
+        assert_eq!(expand("./repos"), PathBuf::from("/work/repos"));
+        assert_eq!(expand("/abs/~"), PathBuf::from("/abs/~"));
+        assert_eq!(expand("~/../../../srv"), PathBuf::from("/srv"));
+    }
+
+    #[test]
+    fn test_exclude_pattern() {
+        let home = Path::new("/home/test");
+        let matches =
+            |pattern: &str, path: &str| ExcludePattern::new(pattern, home).matches(Path::new(path));
+
+        assert!(matches("node_modules", "/home/test/web/node_modules"));
+        assert!(!matches("node_modules", "/home/test/web/node_modules_old"));
+        assert!(matches("*.bak", "/home/test/repo.bak"));
+        assert!(matches("**/target/**", "/home/test/crate/target"));
+        assert!(matches("**/target/**", "/home/test/crate/target/debug"));
+        assert!(!matches("**/target/**", "/home/test/crate/targets"));
+        assert!(matches("/mnt/**", "/mnt"));
+        assert!(matches("/mnt/**", "/mnt/nas/photos"));
+        assert!(!matches("/mnt/**", "/home/test/mnt"));
+        assert!(matches("~/vendor/*", "/home/test/vendor/lib"));
+        assert!(!matches("~/vendor/*", "/home/test/vendor/lib/deep"));
+        assert!(matches("/srv/?b", "/srv/ab"));
+    }
+
+    #[test]
+    fn test_parse_network_mounts() {
+        let content = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+nas:/export /mnt/nas nfs4 rw,relatime 0 0
+user@host:/srv /home/test/remote\\040dir fuse.sshfs rw,nosuid 0 0
+//server/share /mnt/smb cifs rw 0 0
+tmpfs /tmp tmpfs rw 0 0
+";
+        assert_eq!(
+            parse_network_mounts(content),
+            vec![
+                PathBuf::from("/mnt/nas"),
+                PathBuf::from("/home/test/remote dir"),
+                PathBuf::from("/mnt/smb"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tildify() {
+        let home = Path::new("/home/test");
+        assert_eq!(tildify_with(Path::new("/home/test"), home), "~");
+        assert_eq!(tildify_with(Path::new("/home/test/a/b"), home), "~/a/b");
+        assert_eq!(
+            tildify_with(Path::new("/home/tester"), home),
+            "/home/tester"
+        );
+        assert_eq!(tildify_with(Path::new("/srv/a"), home), "/srv/a");
+    }
+}
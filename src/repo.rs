@@ -0,0 +1,19 @@
+//! 仓库的数据类型和缓存读写
+//!
+//! ```no_run
+//! # async fn example() -> git_manager::utils::BDEResult<()> {
+//! use git_manager::repo::GitRepo;
+//!
+//! let repo = GitRepo::build(std::path::Path::new("/path/to/repo")).await?;
+//! println!("{}: {}", repo.name, repo.status);
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::backend::GitBackend;
+pub use crate::gitrepo::{
+    get_all_git_repo, get_save_data_dir, load_all_repo, load_ignore_repos, load_repo_cache,
+    save_all_git_repo, save_repo_cache, search_all_git_path, search_all_git_path_with,
+    worktree_parent, ActivePolicy, BranchInfo, GitRepo, GitStatus, RepoError, ScanUpdate,
+    SearchOptions, SubmoduleInfo,
+};
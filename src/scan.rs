@@ -0,0 +1,640 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::OnceCell;
+use tokio::task::JoinSet;
+
+pub use crate::gitrepo::{ActivePolicy, GitRepo, GitStatus};
+
+use crate::backend::{backend_for, GitBackend};
+use crate::fetch::{fetch_batches, host_permit, scheduled_fetch, FetchMode, FetchResult};
+use crate::gitrepo::{load_ignore_repos, load_repo_cache, search_all_git_path_with, SearchOptions};
+use crate::metrics::WORKER_METRICS;
+use crate::tombstone::{load_tombstones, repo_origin};
+use crate::utils::now_timestamp;
+
+/// 扫描选项
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// 只对活跃的仓库进行网络检查, 为 None 时检查所有仓库
+    pub active_policy: Option<ActivePolicy>,
+    /// 使用上一次保存的结果, 已经记录的仓库根据上次的结果刷新
+    pub use_cache: bool,
+    /// 跳过在其它仓库目录中的仓库
+    pub skip_nested: bool,
+    /// 标签页的名字, 每个标签页使用单独的缓存文件, 为空时使用 repo.json
+    pub root: String,
+    /// 查找仓库时跳过的目录
+    pub search: SearchOptions,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            active_policy: None,
+            use_cache: true,
+            skip_nested: false,
+            root: String::new(),
+            search: SearchOptions::default(),
+        }
+    }
+}
+
+/// 扫描过程中逐个产生的事件
+#[derive(Debug, Clone)]
+pub enum RepoEvent {
+    /// 在搜索目录中找到了仓库, 状态稍后通过 Status 返回
+    Discovered(PathBuf),
+    /// 一个搜索目录查找完成, 所有搜索目录并行查找
+    RootScanned { root: PathBuf, repos: usize },
+    /// 仓库本地状态检查完成
+    Status(Box<GitRepo>),
+    /// 所有仓库的本地状态都已经返回, 之后开始网络检查
+    LocalFinished,
+    /// 网络检查之后更新的状态, 代替之前同一个仓库的 Status
+    Fetched(Box<GitRepo>),
+    /// 单个仓库检查失败
+    Error { path: PathBuf, error: String },
+    /// 扫描中止, 之后不会再有事件
+    Failed(String),
+    /// 扫描结束, 之后不会再有事件
+    Finished { repos: usize, errors: u64 },
+}
+
+/// 扫描事件流, poll_next 和 futures 的 Stream::poll_next 签名相同,
+/// 可以直接包装成 Stream 使用
+#[derive(Debug)]
+pub struct RepoStream {
+    rx: UnboundedReceiver<RepoEvent>,
+}
+
+impl RepoStream {
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<RepoEvent>> {
+        self.rx.poll_recv(cx)
+    }
+
+    pub async fn next(&mut self) -> Option<RepoEvent> {
+        self.rx.recv().await
+    }
+}
+
+/// 在后台扫描 roots 下的所有仓库, 边扫描边返回结果
+pub fn scan_stream(roots: Vec<PathBuf>, options: ScanOptions) -> RepoStream {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_scan(roots, options, tx));
+    RepoStream { rx }
+}
+
+async fn run_scan(roots: Vec<PathBuf>, mut options: ScanOptions, tx: UnboundedSender<RepoEvent>) {
+    let now = now_timestamp();
+    // 明确指定在挂载点中的搜索目录时仍然扫描
+    options
+        .search
+        .skip_mounts
+        .retain(|mount| !roots.iter().any(|root| root.starts_with(mount)));
+    tracing::info!(
+        ?roots,
+        use_cache = options.use_cache,
+        root = options.root,
+        "scan started"
+    );
+
+    let scan_start = Instant::now();
+    let mut search_set = JoinSet::new();
+    for (index, root) in roots.iter().enumerate() {
+        let root = root.clone();
+        let search = options.search.clone();
+        search_set.spawn_blocking(move || {
+            let res = search_all_git_path_with(&root, &search).map_err(|err| err.to_string());
+            (index, root, res)
+        });
+    }
+    let mut root_paths = vec![Vec::new(); roots.len()];
+    while let Some(res) = search_set.join_next().await {
+        let (index, root, paths) = match res {
+            Ok((index, root, Ok(paths))) => (index, root, paths),
+            Ok((_, root, Err(err))) => {
+                tracing::error!("scan {}: {}", root.display(), err);
+                let _ = tx.send(RepoEvent::Failed(format!("{}: {}", root.display(), err)));
+                return;
+            }
+            Err(err) => {
+                tracing::error!("scan: {}", err);
+                let _ = tx.send(RepoEvent::Failed(err.to_string()));
+                return;
+            }
+        };
+        tracing::info!(root = %root.display(), repos = paths.len(), "root scanned");
+        let _ = tx.send(RepoEvent::RootScanned {
+            root,
+            repos: paths.len(),
+        });
+        root_paths[index] = paths;
+    }
+    let (mut all_git_paths, discovered_aliases) = merge_paths(root_paths);
+    if options.skip_nested {
+        let nested: Vec<PathBuf> = all_git_paths
+            .iter()
+            .filter(|path| nested_parent(path, &all_git_paths).is_some())
+            .cloned()
+            .collect();
+        all_git_paths.retain(|path| !nested.contains(path));
+    }
+    for path in &all_git_paths {
+        let _ = tx.send(RepoEvent::Discovered(path.clone()));
+    }
+    WORKER_METRICS.add_scan(scan_start.elapsed());
+
+    let loaded = load_ignore_repos().and_then(|ignore_repos| {
+        let repos = if options.use_cache {
+            load_repo_cache(&options.root)?.unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        Ok((ignore_repos, repos, load_tombstones()?))
+    });
+    let (mut ignore_repos, repos, tombstones) = match loaded.map_err(|err| err.to_string()) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            tracing::error!("load cache: {}", err);
+            let _ = tx.send(RepoEvent::Failed(err));
+            return;
+        }
+    };
+    let canonical_ignore: Vec<PathBuf> = ignore_repos
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    ignore_repos.extend(canonical_ignore);
+    // 缓存中的仓库可能在这次扫描的目录之外, 一起用来判断嵌套
+    let mut repos = tokio::task::spawn_blocking(move || canonical_repos(repos))
+        .await
+        .unwrap_or_default();
+    let mut known_paths = all_git_paths.clone();
+    known_paths.extend(repos.iter().map(|repo| repo.path.clone()));
+    if options.skip_nested {
+        repos.retain(|repo| nested_parent(&repo.path, &known_paths).is_none());
+    }
+    repos.retain(|repo| !options.search.excludedp(&repo.path));
+    let mark_nested = |mut repo: GitRepo| {
+        repo.nested_in = nested_parent(&repo.path, &known_paths);
+        for alias in discovered_aliases.get(&repo.path).into_iter().flatten() {
+            if !repo.aliases.contains(alias) {
+                repo.aliases.push(alias.clone());
+            }
+        }
+        Box::new(repo)
+    };
+
+    all_git_paths.retain(|item| !ignore_repos.contains(item));
+
+    let mut repo_len = 0;
+    let mut err_len = 0;
+
+    // 状态检查之前先并行确认缓存中的路径还存在, 丢失的仓库直接标记, 不再逐个检查失败
+    let stat_start = Instant::now();
+    let mut stat_set = JoinSet::new();
+    for repo in repos {
+        stat_set.spawn_blocking(move || {
+            let existp = repo.path.join(".git").exists();
+            (repo, existp)
+        });
+    }
+    let mut repos = Vec::new();
+    let mut missing = Vec::new();
+    while let Some(res) = stat_set.join_next().await {
+        match res {
+            Ok((repo, true)) => repos.push(repo),
+            Ok((repo, false)) => {
+                all_git_paths.retain(|item| item != &repo.path);
+                missing.push(repo);
+            }
+            Err(err) => {
+                tracing::error!("check repo path: {}", err);
+                err_len += 1;
+                let _ = tx.send(RepoEvent::Error {
+                    path: PathBuf::new(),
+                    error: err.to_string(),
+                });
+            }
+        }
+    }
+
+    // 丢失的仓库可能只是被移动了, 和新找到的仓库匹配, 匹配上的使用新的路径,
+    // 保留标签和记录, 旧的路径不再保留
+    if !missing.is_empty() {
+        let candidates: Vec<PathBuf> = all_git_paths
+            .iter()
+            .filter(|path| !repos.iter().any(|repo| &repo.path == *path))
+            .filter(|path| !ignore_repos.contains(path))
+            .cloned()
+            .collect();
+        let candidates = tokio::task::spawn_blocking(move || {
+            candidates
+                .iter()
+                .map(|path| RepoIdentity::read(path))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+        for mut repo in missing {
+            match find_moved(&RepoIdentity::of(&repo), &candidates) {
+                Some(index) => {
+                    let path = candidates[index].path.clone();
+                    tracing::info!("repo moved: {} -> {}", repo.path.display(), path.display());
+                    repo.name = candidates[index].name.clone();
+                    repo.path = path;
+                    repos.push(repo);
+                }
+                None => {
+                    tracing::warn!("missing repo: {}", repo.path.display());
+                    repo.status = GitStatus::Missing;
+                    repo_len += 1;
+                    let _ = tx.send(RepoEvent::Status(mark_nested(repo)));
+                }
+            }
+        }
+    }
+    WORKER_METRICS.add_scan(stat_start.elapsed());
+
+    // 第一阶段只检查本地状态, 结果尽快返回给界面
+    let mut set = JoinSet::new();
+    // 刷新旧 Git repo 状态
+    for repo in repos {
+        all_git_paths.retain(|item| item != &repo.path);
+
+        // 被忽略的仓库不再检查状态, 保留上次的结果
+        if ignore_repos.contains(&repo.path) {
+            repo_len += 1;
+            let _ = tx.send(RepoEvent::Status(mark_nested(repo)));
+            continue;
+        }
+
+        let mut guard = WORKER_METRICS.queue();
+        set.spawn(async move {
+            guard.start();
+            let path = repo.path.clone();
+            let res = GitRepo::build_from_last(repo, false)
+                .await
+                .map_err(|err| err.to_string());
+            (path, res)
+        });
+    }
+
+    // 隐藏或者删除过的仓库不作为新仓库加入
+    if !tombstones.is_empty() {
+        all_git_paths.retain(|path| {
+            let origin = repo_origin(path);
+            !tombstones
+                .iter()
+                .any(|tombstone| tombstone.matches(path, origin.as_deref()))
+        });
+    }
+
+    // 将新增加的 Git repo 路径写入
+    for path in all_git_paths {
+        let mut guard = WORKER_METRICS.queue();
+        set.spawn(async move {
+            guard.start();
+            let res = GitRepo::build_with(&path, FetchMode::Skip)
+                .await
+                .map_err(|err| err.to_string());
+            (path, res)
+        });
+    }
+
+    let mut fetch_repos = Vec::new();
+    while let Some(res) = set.join_next().await {
+        let event = match check_event(res, &mut repo_len, &mut err_len) {
+            RepoEvent::Status(repo) => {
+                let repo = mark_nested(*repo);
+                // 超时的仓库不再进行网络检查, 和之前一样等待手动刷新
+                let fetchp = repo.has_remote
+                    && repo.fetch_allowed()
+                    && !matches!(repo.status, GitStatus::Timeout | GitStatus::AuthRequired)
+                    && options
+                        .active_policy
+                        .is_none_or(|policy| policy.need_fetch(&repo, now));
+                if fetchp {
+                    fetch_repos.push((*repo).clone());
+                }
+                RepoEvent::Status(repo)
+            }
+            event => event,
+        };
+        let _ = tx.send(event);
+    }
+    let _ = tx.send(RepoEvent::LocalFinished);
+
+    // 第二阶段进行网络检查, 更新需要拉取或者推送的仓库
+    let mut set = JoinSet::new();
+    let (cli_repos, other_repos): (Vec<GitRepo>, Vec<GitRepo>) = fetch_repos
+        .into_iter()
+        .partition(|repo| backend_for(&repo.path) == GitBackend::Cli);
+    for repo in other_repos {
+        let mut guard = WORKER_METRICS.queue();
+        set.spawn(async move {
+            let _permit = host_permit(&repo.host().unwrap_or_default()).await;
+            guard.start();
+            let path = repo.path.clone();
+            let res = GitRepo::build_from_last_with(repo, FetchMode::Run)
+                .await
+                .map_err(|err| err.to_string());
+            (path, res)
+        });
+    }
+
+    // 同一批的仓库共用一次 fetch, 第一个开始的任务执行, 其它任务等待结果
+    let mut fetch_repos: HashMap<PathBuf, GitRepo> = cli_repos
+        .into_iter()
+        .map(|repo| (repo.path.clone(), repo))
+        .collect();
+    let batches = fetch_batches(
+        fetch_repos
+            .values()
+            .map(|repo| (repo.host().unwrap_or_default(), repo.path.clone())),
+    );
+    for (host, paths) in batches {
+        let fetched = Arc::new(OnceCell::new());
+        let host = Arc::new(host);
+        let paths = Arc::new(paths);
+        for path in paths.iter() {
+            let Some(repo) = fetch_repos.remove(path) else {
+                continue;
+            };
+            let fetched = fetched.clone();
+            let paths = paths.clone();
+            let host = host.clone();
+            let mut guard = WORKER_METRICS.queue();
+            set.spawn(async move {
+                let results = fetched.get_or_init(|| scheduled_fetch(&host, &paths)).await;
+                let result = results
+                    .get(&repo.path)
+                    .copied()
+                    .unwrap_or(FetchResult::TimedOut);
+                guard.start();
+                let path = repo.path.clone();
+                let res = GitRepo::build_from_last_with(repo, FetchMode::Fetched(result))
+                    .await
+                    .map_err(|err| err.to_string());
+                (path, res)
+            });
+        }
+    }
+
+    // 第一阶段已经计数, 这里不再增加仓库数量
+    let mut fetched_len = 0;
+    while let Some(res) = set.join_next().await {
+        let event = match check_event(res, &mut fetched_len, &mut err_len) {
+            RepoEvent::Status(repo) => RepoEvent::Fetched(repo),
+            event => event,
+        };
+        let _ = tx.send(event);
+    }
+
+    tracing::info!(
+        repos = repo_len,
+        errors = err_len,
+        elapsed = ?scan_start.elapsed(),
+        "scan finished"
+    );
+    let _ = tx.send(RepoEvent::Finished {
+        repos: repo_len,
+        errors: err_len,
+    });
+}
+
+type CheckResult = Result<(PathBuf, Result<GitRepo, String>), tokio::task::JoinError>;
+
+/// 状态检查任务的结果转换成事件, 同时统计成功和失败的数量
+fn check_event(res: CheckResult, repo_len: &mut usize, err_len: &mut u64) -> RepoEvent {
+    match res {
+        Ok((_, Ok(repo))) => {
+            *repo_len += 1;
+            RepoEvent::Status(Box::new(repo))
+        }
+        Ok((path, Err(error))) => {
+            tracing::warn!("check repo {}: {}", path.display(), error);
+            *err_len += 1;
+            RepoEvent::Error { path, error }
+        }
+        Err(err) => {
+            tracing::error!("check repo task: {}", err);
+            *err_len += 1;
+            RepoEvent::Error {
+                path: PathBuf::new(),
+                error: err.to_string(),
+            }
+        }
+    }
+}
+
+/// paths 中包含 path 的最近的仓库, 工作树在主仓库目录中时也算作嵌套
+/// 用来判断两个路径是不是同一个仓库
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RepoIdentity {
+    path: PathBuf,
+    name: String,
+    remote_url: String,
+    root_commit: Option<String>,
+}
+
+impl RepoIdentity {
+    fn of(repo: &GitRepo) -> Self {
+        RepoIdentity {
+            path: repo.path.clone(),
+            name: repo.name.clone(),
+            remote_url: repo.remote_url.clone(),
+            root_commit: repo.root_commit.clone(),
+        }
+    }
+
+    fn read(path: &Path) -> Self {
+        RepoIdentity {
+            path: path.to_path_buf(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            remote_url: GitRepo::get_remote_url_by_path(path),
+            root_commit: GitRepo::get_root_commit(path),
+        }
+    }
+}
+
+/// 丢失的仓库被移动到了哪一个新的仓库
+///
+/// 优先匹配远程仓库地址和名字, 目录改了名字时匹配第一个提交,
+/// 同一个仓库克隆了多份时无法区分, 有多个匹配时不算作移动
+fn find_moved(missing: &RepoIdentity, candidates: &[RepoIdentity]) -> Option<usize> {
+    let unique = |matches: &dyn Fn(&RepoIdentity) -> bool| {
+        let mut found = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| matches(candidate))
+            .map(|(index, _)| index);
+        match (found.next(), found.next()) {
+            (Some(index), None) => Some(index),
+            _ => None,
+        }
+    };
+
+    let same_remote = |candidate: &RepoIdentity| {
+        !missing.remote_url.is_empty()
+            && candidate.remote_url == missing.remote_url
+            && candidate.name == missing.name
+    };
+    let same_root = |candidate: &RepoIdentity| {
+        missing.root_commit.is_some() && candidate.root_commit == missing.root_commit
+    };
+    unique(&same_remote).or_else(|| unique(&same_root))
+}
+
+/// 按搜索目录的顺序合并找到的仓库, 路径统一为真实路径, 通过不同路径 (重叠的搜索目录,
+/// 符号链接, bind mount) 找到的同一个仓库只保留一个, 返回真实路径和每个仓库的其它路径
+fn merge_paths(root_paths: Vec<Vec<PathBuf>>) -> (Vec<PathBuf>, HashMap<PathBuf, Vec<PathBuf>>) {
+    let mut paths = Vec::new();
+    let mut aliases: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for path in root_paths.into_iter().flatten() {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if canonical != path {
+            let alias = aliases.entry(canonical.clone()).or_default();
+            if !alias.contains(&path) {
+                alias.push(path);
+            }
+        }
+        if !paths.contains(&canonical) {
+            paths.push(canonical);
+        }
+    }
+    (paths, aliases)
+}
+
+/// 缓存中的仓库路径统一为真实路径, 原来的路径记为别名, 指向同一个仓库的记录合并,
+/// 已经不再指向这个仓库的别名去掉
+fn canonical_repos(repos: Vec<GitRepo>) -> Vec<GitRepo> {
+    let mut res: Vec<GitRepo> = Vec::new();
+    for mut repo in repos {
+        if let Ok(canonical) = repo.path.canonicalize() {
+            if canonical != repo.path {
+                let alias = std::mem::replace(&mut repo.path, canonical);
+                repo.aliases.push(alias);
+            }
+        }
+        let path = repo.path.clone();
+        repo.aliases
+            .retain(|alias| alias != &path && alias.canonicalize().is_ok_and(|item| item == path));
+
+        match res.iter_mut().find(|item| item.path == repo.path) {
+            Some(item) => {
+                for alias in repo.aliases {
+                    if !item.aliases.contains(&alias) {
+                        item.aliases.push(alias);
+                    }
+                }
+                for tag in repo.tags {
+                    if !item.tags.contains(&tag) {
+                        item.tags.push(tag);
+                    }
+                }
+            }
+            None => res.push(repo),
+        }
+    }
+    res
+}
+
+fn nested_parent(path: &Path, paths: &[PathBuf]) -> Option<PathBuf> {
+    paths
+        .iter()
+        .filter(|item| item.as_path() != path && path.starts_with(item))
+        .max_by_key(|item| item.components().count())
+        .cloned()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{find_moved, nested_parent, RepoIdentity};
+
+    #[test]
+    #[cfg(unix)]
+    fn test_merge_paths() {
+        use super::merge_paths;
+
+        let dir = std::env::temp_dir().join(format!("gtm-merge-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("code/a")).unwrap();
+        std::fs::create_dir_all(dir.join("work/b")).unwrap();
+        std::os::unix::fs::symlink(dir.join("code"), dir.join("link")).unwrap();
+
+        let dir = dir.canonicalize().unwrap();
+        let (paths, aliases) = merge_paths(vec![
+            vec![dir.join("link/a")],
+            vec![dir.join("code//a"), dir.join("work/b"), dir.join("code/a")],
+        ]);
+        assert_eq!(paths, vec![dir.join("code/a"), dir.join("work/b")]);
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases[&dir.join("code/a")], vec![dir.join("link/a")]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_nested_parent() {
+        let paths: Vec<PathBuf> = [
+            "/home/a",
+            "/home/a/vendor/b",
+            "/home/a/vendor/b/c",
+            "/home/ab",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        assert_eq!(nested_parent(Path::new("/home/a"), &paths), None);
+        assert_eq!(nested_parent(Path::new("/home/ab"), &paths), None);
+        assert_eq!(
+            nested_parent(Path::new("/home/a/vendor/b"), &paths),
+            Some(PathBuf::from("/home/a"))
+        );
+        assert_eq!(
+            nested_parent(Path::new("/home/a/vendor/b/c"), &paths),
+            Some(PathBuf::from("/home/a/vendor/b"))
+        );
+    }
+
+    #[test]
+    fn test_find_moved() {
+        let identity = |path: &str, remote_url: &str, root_commit: Option<&str>| RepoIdentity {
+            path: PathBuf::from(path),
+            name: String::from(Path::new(path).file_name().unwrap().to_str().unwrap()),
+            remote_url: String::from(remote_url),
+            root_commit: root_commit.map(String::from),
+        };
+        let missing = identity("/old/tool", "git@example.com:me/tool.git", Some("c1"));
+
+        let candidates = vec![
+            identity("/new/other", "git@example.com:me/other.git", Some("c2")),
+            identity("/new/tool", "git@example.com:me/tool.git", Some("c1")),
+        ];
+        assert_eq!(find_moved(&missing, &candidates), Some(1));
+
+        // 改了名字, 通过第一个提交匹配
+        let candidates = vec![identity("/new/tool-renamed", "", Some("c1"))];
+        assert_eq!(find_moved(&missing, &candidates), Some(0));
+
+        // 同一个仓库的两份克隆, 无法确定是哪一个
+        let candidates = vec![
+            identity("/new/a/tool", "git@example.com:me/tool.git", Some("c1")),
+            identity("/new/b/tool", "git@example.com:me/tool.git", Some("c1")),
+        ];
+        assert_eq!(find_moved(&missing, &candidates), None);
+
+        let candidates = vec![identity("/new/tool", "", None)];
+        assert_eq!(find_moved(&missing, &candidates), None);
+    }
+}
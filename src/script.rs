@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use crate::error::GitManagerError;
+use crate::utils::{ba_error, BDEResult};
+
+/// 脚本中的一条命令, 执行时转换成界面使用的 action
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptCommand {
+    Refresh,
+    Filter(String),
+    SelectAll,
+    Pull,
+    Push,
+    Report(PathBuf),
+    Confirm,
+    Cancel,
+    Quit,
+}
+
+/// 把一行拆成命令和参数, 参数可以用双引号包起来
+fn split_words(line: &str) -> BDEResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quotep = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                if quotep {
+                    words.push(std::mem::take(&mut word));
+                }
+                quotep = !quotep;
+            }
+            '\\' if quotep => {
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c if c.is_whitespace() && !quotep => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+
+    if quotep {
+        return Err(GitManagerError::Parse(String::from("引号没有闭合")));
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// 解析脚本, 每行可以有多条命令, 用 ; 分隔, # 开头的行是注释
+pub fn parse_script(script: &str) -> BDEResult<VecDeque<ScriptCommand>> {
+    let mut commands = VecDeque::new();
+
+    for (index, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        for command in line.split(';') {
+            let words = split_words(command)
+                .map_err(|err| GitManagerError::Parse(format!("第 {} 行: {}", index + 1, err)))?;
+            let Some((name, args)) = words.split_first() else {
+                continue;
+            };
+
+            let command = match (name.as_str(), args) {
+                ("refresh", []) => ScriptCommand::Refresh,
+                ("filter", args) => ScriptCommand::Filter(args.join(" ")),
+                ("select-all", []) => ScriptCommand::SelectAll,
+                ("pull", []) => ScriptCommand::Pull,
+                ("push", []) => ScriptCommand::Push,
+                ("report", [path]) => ScriptCommand::Report(PathBuf::from(path)),
+                ("confirm", []) => ScriptCommand::Confirm,
+                ("cancel", []) => ScriptCommand::Cancel,
+                ("quit", []) => ScriptCommand::Quit,
+                _ => {
+                    return Err(ba_error(&format!(
+                        "第 {} 行: 无法识别的命令 {}",
+                        index + 1,
+                        command.trim()
+                    )))
+                }
+            };
+            commands.push_back(command);
+        }
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{parse_script, ScriptCommand};
+
+    #[test]
+    fn test_parse_script() {
+        let commands = parse_script(
+            "# 推送所有需要推送的仓库\nrefresh; filter \"+NeedPush\"; select-all\npush\nreport out.md; quit\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Vec::from(commands),
+            vec![
+                ScriptCommand::Refresh,
+                ScriptCommand::Filter(String::from("+NeedPush")),
+                ScriptCommand::SelectAll,
+                ScriptCommand::Push,
+                ScriptCommand::Report(PathBuf::from("out.md")),
+                ScriptCommand::Quit,
+            ]
+        );
+
+        assert!(parse_script("filter \"+NeedPush").is_err());
+        assert!(parse_script("report").is_err());
+        assert!(parse_script("jump").is_err());
+    }
+}
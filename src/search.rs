@@ -0,0 +1,321 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::utils::{BDEResult, Cmd};
+
+/// 每个仓库最多返回的提交数量
+const MAX_MATCHES_PER_REPO: usize = 200;
+
+/// 提交搜索的条件, 以 "-S " 开头时搜索改动的内容 (git log -S), 否则搜索提交信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitQuery {
+    Message(String),
+    Diff(String),
+}
+
+impl CommitQuery {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let diff = input
+            .strip_prefix("-S")
+            .filter(|text| text.is_empty() || text.starts_with(char::is_whitespace));
+        let query = match diff {
+            Some(text) => CommitQuery::Diff(text.trim().to_string()),
+            None => CommitQuery::Message(input.to_string()),
+        };
+        (!query.text().is_empty()).then_some(query)
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            CommitQuery::Message(text) | CommitQuery::Diff(text) => text,
+        }
+    }
+
+    fn git_args(&self) -> Vec<String> {
+        let mut args = vec![
+            String::from("log"),
+            String::from("--all"),
+            format!("--max-count={}", MAX_MATCHES_PER_REPO),
+            String::from("--format=%H%x1f%ct%x1f%an%x1f%s"),
+        ];
+        match self {
+            CommitQuery::Message(text) => args.extend([
+                String::from("--regexp-ignore-case"),
+                String::from("--fixed-strings"),
+                format!("--grep={}", text),
+            ]),
+            CommitQuery::Diff(text) => args.push(format!("-S{}", text)),
+        }
+        args
+    }
+}
+
+/// 搜索到的一个提交
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitMatch {
+    pub repo_path: PathBuf,
+    pub repo_name: String,
+    pub hash: String,
+    pub time: u64,
+    pub author: String,
+    pub subject: String,
+}
+
+/// 后台搜索的结果, id 用来丢弃已经被新的搜索替换的结果
+#[derive(Debug)]
+pub enum SearchEvent<T> {
+    Matches(u64, Vec<T>),
+    Done(u64),
+}
+
+/// 解析 git log 的输出, 每行是用 \x1f 分隔的 hash, 时间, 作者和标题
+fn parse_log(repo_path: &Path, repo_name: &str, output: &str) -> Vec<CommitMatch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\x1f');
+            let hash = fields.next()?;
+            let time = fields.next()?.parse().ok()?;
+            let author = fields.next()?;
+            let subject = fields.next().unwrap_or("");
+            Some(CommitMatch {
+                repo_path: repo_path.to_path_buf(),
+                repo_name: repo_name.to_string(),
+                hash: hash.to_string(),
+                time,
+                author: author.to_string(),
+                subject: subject.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 文件搜索的条件, 以 "-c " 开头时搜索文件内容, 有 ripgrep 时使用 ripgrep, 否则使用 git grep,
+/// 否则搜索已跟踪的文件的路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileQuery {
+    Name(String),
+    Content(String),
+}
+
+impl FileQuery {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let content = input
+            .strip_prefix("-c")
+            .filter(|text| text.is_empty() || text.starts_with(char::is_whitespace));
+        let query = match content {
+            Some(text) => FileQuery::Content(text.trim().to_string()),
+            None => FileQuery::Name(input.to_string()),
+        };
+        (!query.text().is_empty()).then_some(query)
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            FileQuery::Name(text) | FileQuery::Content(text) => text,
+        }
+    }
+
+    fn command(&self, repo_path: &Path) -> Cmd {
+        match self {
+            FileQuery::Name(_) => Cmd::git(repo_path).args(["ls-files", "-z"]),
+            // 搜索的内容作为 $0 传给 bash, 不需要转义
+            FileQuery::Content(text) => Cmd::shell(
+                repo_path,
+                "if command -v rg >/dev/null; then \
+                 rg --line-number --no-heading --color=never --fixed-strings --ignore-case -- \"$0\"; \
+                 else git grep --line-number -I --ignore-case --fixed-strings -e \"$0\"; fi",
+            )
+            .arg(text),
+        }
+    }
+}
+
+/// 搜索到的一个文件, 搜索内容时有行号和这一行的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+    pub repo_path: PathBuf,
+    pub repo_name: String,
+    /// 相对于仓库目录的路径
+    pub file: String,
+    pub line: Option<usize>,
+    pub text: String,
+}
+
+/// 解析文件搜索的输出, 搜索文件名时是 git ls-files -z 的输出, 搜索内容时每行是 "文件:行号:内容"
+fn parse_files(
+    repo_path: &Path,
+    repo_name: &str,
+    query: &FileQuery,
+    output: &str,
+) -> Vec<FileMatch> {
+    let file_match = |file: &str, line, text: &str| FileMatch {
+        repo_path: repo_path.to_path_buf(),
+        repo_name: repo_name.to_string(),
+        file: file.to_string(),
+        line,
+        text: text.to_string(),
+    };
+    match query {
+        FileQuery::Name(text) => {
+            let text = text.to_lowercase();
+            let mut files: Vec<&str> = output
+                .split('\0')
+                .filter(|file| !file.is_empty() && file.to_lowercase().contains(&text))
+                .collect();
+            // 有冲突的文件每个阶段出现一次
+            files.dedup();
+            files
+                .into_iter()
+                .take(MAX_MATCHES_PER_REPO)
+                .map(|file| file_match(file, None, ""))
+                .collect()
+        }
+        FileQuery::Content(_) => output
+            .lines()
+            .filter_map(|line| {
+                let (file, rest) = line.split_once(':')?;
+                let (number, text) = rest.split_once(':')?;
+                Some(file_match(file, Some(number.parse().ok()?), text.trim()))
+            })
+            .take(MAX_MATCHES_PER_REPO)
+            .collect(),
+    }
+}
+
+/// 在所有仓库中执行 search, 最多同时搜索 jobs 个仓库, 每个仓库搜索完成之后发送结果
+async fn search_repos<T, F>(
+    id: u64,
+    repos: Vec<(PathBuf, String)>,
+    jobs: usize,
+    search: F,
+    search_tx: UnboundedSender<SearchEvent<T>>,
+) where
+    T: Send + 'static,
+    F: Fn(&Path, &str) -> BDEResult<Vec<T>> + Send + Sync + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let search = Arc::new(search);
+    let mut set = JoinSet::new();
+    for (path, name) in repos {
+        let semaphore = semaphore.clone();
+        let search = search.clone();
+        let search_tx = search_tx.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let res = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || search(&path, &name)
+            })
+            .await;
+            match res {
+                Ok(Ok(matches)) => {
+                    if !matches.is_empty() {
+                        let _ = search_tx.send(SearchEvent::Matches(id, matches));
+                    }
+                }
+                Ok(Err(err)) => tracing::warn!("search {}: {}", path.display(), err),
+                Err(err) => tracing::warn!("search {}: {}", path.display(), err),
+            }
+        });
+    }
+    while set.join_next().await.is_some() {}
+    let _ = search_tx.send(SearchEvent::Done(id));
+}
+
+/// 在所有仓库中搜索提交
+pub async fn search_commits(
+    id: u64,
+    query: CommitQuery,
+    repos: Vec<(PathBuf, String)>,
+    jobs: usize,
+    search_tx: UnboundedSender<SearchEvent<CommitMatch>>,
+) {
+    tracing::info!("search commits in {} repos: {:?}", repos.len(), query);
+    let args = query.git_args();
+    let search = move |path: &Path, name: &str| {
+        let output = Cmd::git(path).args(args.iter()).output()?;
+        Ok(parse_log(path, name, &output))
+    };
+    search_repos(id, repos, jobs, search, search_tx).await;
+}
+
+/// 在所有仓库中搜索文件名或者文件内容
+pub async fn search_files(
+    id: u64,
+    query: FileQuery,
+    repos: Vec<(PathBuf, String)>,
+    jobs: usize,
+    search_tx: UnboundedSender<SearchEvent<FileMatch>>,
+) {
+    tracing::info!("search files in {} repos: {:?}", repos.len(), query);
+    let search = move |path: &Path, name: &str| {
+        let output = query.command(path).output()?;
+        Ok(parse_files(path, name, &query, &output))
+    };
+    search_repos(id, repos, jobs, search, search_tx).await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_commit_query() {
+        assert_eq!(CommitQuery::parse("  "), None);
+        assert_eq!(CommitQuery::parse("-S "), None);
+        assert_eq!(
+            CommitQuery::parse(" fix bug "),
+            Some(CommitQuery::Message(String::from("fix bug")))
+        );
+        let query = CommitQuery::parse("-S foo(").unwrap();
+        assert_eq!(query, CommitQuery::Diff(String::from("foo(")));
+        assert!(query.git_args().contains(&String::from("-Sfoo(")));
+
+        let output = "abc\x1f100\x1fAlice\x1ffix: a\x1fb\nbad line\ndef\x1f200\x1fBob\x1f\n";
+        let matches = parse_log(Path::new("/r"), "r", output);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].subject, "fix: a\x1fb");
+        assert_eq!(matches[1].time, 200);
+        assert_eq!(matches[1].author, "Bob");
+    }
+
+    #[test]
+    fn test_file_query() {
+        assert_eq!(
+            CommitQuery::parse("-c"),
+            Some(CommitQuery::Message(String::from("-c")))
+        );
+        assert_eq!(FileQuery::parse("-c  "), None);
+        assert_eq!(
+            FileQuery::parse("-c fn main"),
+            Some(FileQuery::Content(String::from("fn main")))
+        );
+        let query = FileQuery::parse("Main.RS").unwrap();
+        let matches = parse_files(
+            Path::new("/r"),
+            "r",
+            &query,
+            "src/main.rs\0src/main.rs\0README\0a/main.rs.bak\0",
+        );
+        assert_eq!(
+            matches
+                .iter()
+                .map(|item| item.file.as_str())
+                .collect::<Vec<_>>(),
+            vec!["src/main.rs", "a/main.rs.bak"]
+        );
+
+        let query = FileQuery::parse("-c todo").unwrap();
+        let output = "src/lib.rs:12:    // TODO: a:b\nbad\nx.rs:y:z\n";
+        let matches = parse_files(Path::new("/r"), "r", &query, output);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, Some(12));
+        assert_eq!(matches[0].text, "// TODO: a:b");
+    }
+}
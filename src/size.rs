@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::{git_output, BDEResult};
+
+/// 仓库占用的磁盘空间和对象数量, 计算比较慢, 只在需要时计算
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RepoSize {
+    /// 整个目录 (包括 .git) 的大小
+    pub disk_bytes: u64,
+    /// git 目录的大小, 工作树使用主仓库的 git 目录
+    pub git_bytes: u64,
+    /// 松散对象和打包对象的数量
+    pub objects: u64,
+    /// 最大的对象的大小
+    pub largest_object: u64,
+    /// 最大的对象的短 id
+    #[serde(default)]
+    pub largest_object_id: String,
+    /// 计算的时间
+    #[serde(default)]
+    pub time: u64,
+}
+
+/// 目录的大小, 不跟随符号链接, 没有权限的文件直接跳过
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// git count-objects -v 输出中松散对象和打包对象的数量
+fn parse_count_objects(output: &str) -> u64 {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter(|(key, _)| *key == "count" || *key == "in-pack")
+        .filter_map(|(_, value)| value.trim().parse::<u64>().ok())
+        .sum()
+}
+
+/// 所有对象中最大的一个, 返回 (大小, 短 id)
+fn parse_largest_object(output: &str) -> Option<(u64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (size, id) = line.split_once(' ')?;
+            Some((size.parse::<u64>().ok()?, id.chars().take(7).collect()))
+        })
+        .max_by_key(|(size, _)| *size)
+}
+
+/// git 目录的大小, 工作树使用主仓库的 git 目录
+pub fn git_dir_size(path: &Path) -> BDEResult<u64> {
+    // 可能是相对于仓库的路径
+    let git_dir = path.join(git_output(path, ["rev-parse", "--git-common-dir"])?.trim());
+    Ok(dir_size(&git_dir))
+}
+
+/// 计算仓库的大小, 需要遍历所有文件和对象, 在后台线程中执行
+pub fn repo_size(path: &Path, now: u64) -> BDEResult<RepoSize> {
+    let objects = parse_count_objects(&git_output(path, ["count-objects", "-v"])?);
+    let (largest_object, largest_object_id) = parse_largest_object(&git_output(
+        path,
+        [
+            "cat-file",
+            "--batch-all-objects",
+            "--batch-check=%(objectsize) %(objectname)",
+        ],
+    )?)
+    .unwrap_or_default();
+
+    Ok(RepoSize {
+        disk_bytes: dir_size(path),
+        git_bytes: git_dir_size(path)?,
+        objects,
+        largest_object,
+        largest_object_id,
+        time: now,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_count_objects, parse_largest_object};
+
+    #[test]
+    fn test_parse_objects() {
+        let count = "count: 12\nsize: 48\nin-pack: 300\npacks: 1\nsize-pack: 1024\n";
+        assert_eq!(parse_count_objects(count), 312);
+
+        let objects = "120 1111111111111111\n90000 abcdef0123456789\n7 2222222222222222\n";
+        assert_eq!(
+            parse_largest_object(objects),
+            Some((90000, String::from("abcdef0")))
+        );
+        assert_eq!(parse_largest_object(""), None);
+    }
+}
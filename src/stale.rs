@@ -0,0 +1,114 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::gitrepo::GitRepo;
+use crate::i18n::{tr, trf};
+use crate::utils::{ba_error, BDEResult};
+
+/// 分支为什么算作过时
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleReason {
+    /// 设置了上游分支, 但是远程仓库中已经没有了
+    UpstreamGone,
+    /// 很久没有提交
+    Inactive,
+}
+
+impl fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StaleReason::UpstreamGone => write!(f, "{}", tr("上游已删除", "upstream gone")),
+            StaleReason::Inactive => write!(f, "{}", tr("很久没有提交", "inactive")),
+        }
+    }
+}
+
+/// 一个仓库中过时的本地分支
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleBranch {
+    pub repo_path: PathBuf,
+    pub repo_name: String,
+    pub branch: String,
+    pub reason: StaleReason,
+    pub last_commit_time: u64,
+}
+
+/// 仓库中上游已经删除或者超过 inactive_secs 没有提交的本地分支
+///
+/// 当前分支和远程仓库的默认分支不算作过时
+pub fn stale_branches(repo: &GitRepo, now: u64, inactive_secs: u64) -> Vec<StaleBranch> {
+    let Ok(git_repo) = git2::Repository::open(&repo.path) else {
+        return Vec::new();
+    };
+    let Ok(local_branches) = git_repo.branches(Some(git2::BranchType::Local)) else {
+        return Vec::new();
+    };
+    let default_branch = repo
+        .default_branch
+        .as_deref()
+        .and_then(|branch| branch.split_once('/'))
+        .map(|(_, branch)| branch);
+
+    let mut res = Vec::new();
+    for (branch, _) in local_branches.flatten() {
+        let Ok(Some(name)) = branch.name() else {
+            continue;
+        };
+        if branch.is_head() || Some(name) == default_branch {
+            continue;
+        }
+
+        let last_commit_time = branch
+            .get()
+            .peel_to_commit()
+            .map(|commit| commit.time().seconds().max(0) as u64)
+            .unwrap_or(0);
+
+        // 上游分支的名字来自配置, 远程分支被删除 (fetch --prune) 之后引用不存在
+        let upstream_gone = branch
+            .get()
+            .name()
+            .and_then(|refname| git_repo.branch_upstream_name(refname).ok())
+            .and_then(|upstream| upstream.as_str().map(String::from))
+            .is_some_and(|upstream| git_repo.find_reference(&upstream).is_err());
+
+        let reason = if upstream_gone {
+            StaleReason::UpstreamGone
+        } else if now.saturating_sub(last_commit_time) > inactive_secs {
+            StaleReason::Inactive
+        } else {
+            continue;
+        };
+
+        res.push(StaleBranch {
+            repo_path: repo.path.clone(),
+            repo_name: repo.name.clone(),
+            branch: String::from(name),
+            reason,
+            last_commit_time,
+        });
+    }
+    res
+}
+
+/// 删除本地分支, force 为 false 时只删除已经合并到当前分支的分支
+pub fn delete_branch(path: &Path, name: &str, force: bool) -> BDEResult<()> {
+    let repo = git2::Repository::open(path)?;
+    let mut branch = repo.find_branch(name, git2::BranchType::Local)?;
+
+    if !force {
+        let head = repo.head()?.peel_to_commit()?.id();
+        let target = branch.get().peel_to_commit()?.id();
+        if head != target && !repo.graph_descendant_of(head, target)? {
+            return Err(ba_error(&trf!(
+                "分支 {} 没有合并到当前分支, 按 D 强制删除",
+                "Branch {} is not merged into the current branch, press D to force delete",
+                name
+            )));
+        }
+    }
+
+    branch.delete()?;
+    tracing::info!("delete branch {} in {}", name, path.display());
+    Ok(())
+}
@@ -1,18 +1,170 @@
+use crate::gitrepo::GitStatus;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AppMode {
     Normal,
     Editing,
+    Prompt,
+    Confirm,
+    Output,
+    Detail,
+    Wizard,
+    Tombstones,
+    Palette,
+    Branches,
+    Inbox,
+    Log,
+    Errors,
+    StaleBranches,
+    CustomCommands,
+    Dashboard,
+    Authors,
+    CommitSearch,
+    FileSearch,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum AppAction {
     StartRefresh,
+    /// 只重新检查选中的仓库, 不扫描目录
+    RefreshSelected,
     StartFilter,
     ExitFilter,
     SelectNext,
     SelectPervious,
+    NextPage,
+    PerviousPage,
+    ScrollPageDown,
+    ScrollPageUp,
+    /// 终端大小改变
+    Resize,
+    SelectFirst,
+    SelectLast,
+    /// 跳转到 ID 列为这个数字的行
+    SelectRow(usize),
+    OpenInbox,
+    /// 执行待处理列表中选中项的操作
+    ResolveAttention,
+    ExitInbox,
+    OpenLog,
+    ExitLog,
+    OpenErrors,
+    /// 重新检查选中的失败仓库
+    RetryRepoError,
+    ExitErrors,
+    OpenDashboard,
+    ExitDashboard,
+    OpenAuthors,
+    /// 切换作者统计的天数
+    NextAuthorsWindow,
+    ExitAuthors,
+    /// 在所有仓库中搜索提交
+    StartCommitSearch,
+    SubmitCommitSearch,
+    /// 在输出窗口中显示选中的提交
+    ShowSearchCommit,
+    ExitCommitSearch,
+    /// 在所有仓库中搜索文件名或者文件内容
+    StartFileSearch,
+    SubmitFileSearch,
+    /// 用编辑器打开选中的文件
+    OpenSearchFile,
+    ExitFileSearch,
+    OpenStaleBranches,
+    /// 删除选中的过时分支, 只删除已经合并的分支
+    DeleteStaleBranch,
+    StartForceDeleteStaleBranch,
+    ApplyForceDeleteStaleBranch,
+    ExitStaleBranches,
+    TogglePaged,
     SelectEnter,
+    OpenDetail,
+    SelectCdExit,
+    SelectOpenEditor,
+    SelectOpenMultiplexer,
+    SelectSync,
+    ExitDetail,
+    ToggleSort,
     SelectCopyPath,
+    SelectCopyRemoteUrl,
+    SelectCopyCloneCommand,
+    /// 用浏览器打开 origin 的网页
+    SelectOpenBrowser,
+    StartTagEdit,
+    FinishTagEdit,
+    ExitPrompt,
+    StartInitRepo,
+    SubmitInitPath,
+    SubmitInitRemote,
+    /// 给选中的仓库添加远程仓库, 依次输入名字和地址
+    StartAddRemote,
+    SubmitRemoteName,
+    SubmitRemoteUrl,
+    /// 在选中的仓库的 HEAD 上创建附注标签, 依次输入名字和信息
+    StartCreateTag,
+    SubmitTagName,
+    SubmitTagMessage,
+    /// 推送刚创建的标签
+    PushTags,
+    /// 在选中的仓库中用 git mergetool 解决冲突
+    OpenMergeTool,
+    ToggleIgnoreRepo,
+    ToggleShowHidden,
+    ToggleStatusFilter(GitStatus),
+    ClearStatusFilter,
+    ToggleAutoRefresh,
+    ToggleGitBackend,
+    ToggleOffline,
+    NextTab,
+    PreviousTab,
+    ToggleRepoFetch,
+    SelectPull,
+    FocusOutput,
+    ExitOutput,
+    StartIgnoreUntracked,
+    ApplyIgnoreUntracked,
+    StartGitignoreTemplate,
+    ApplyGitignoreTemplate,
+    ExitConfirm,
+    ApplyScanOutsideHome,
+    StartDeleteRepo,
+    ApplyDeleteRepo,
+    StartPruneMissing,
+    ApplyPruneMissing,
+    StartResolveDiverge,
+    ApplyResolvePlan,
+    ExitWizard,
+    SubmitPassphrase,
+    SelectPush,
+    /// 在过滤之后的所有仓库中执行输入的命令
+    StartForeach,
+    SubmitForeach,
+    /// 对所有仓库执行 gc 和 remote prune
+    StartMaintenance,
+    ApplyMaintenance,
+    /// 把标记的仓库推送到备份远程仓库
+    StartBackup,
+    ApplyBackup,
+    ToggleMark,
+    MarkAll,
+    ClearMarks,
+    StartReport,
+    ApplyReport,
+    OpenTombstones,
+    PurgeTombstone,
+    ExitTombstones,
+    OpenBranches,
+    ApplyBranch,
+    ExitBranches,
+    StartRenameRepo,
+    SubmitRenameRepo,
     ComplectionFinish,
+    /// 选择在选中的仓库中执行的自定义命令
+    OpenCustomCommands,
+    ApplyCustomCommand,
+    ExitCustomCommands,
+    OpenPalette,
+    ExitPalette,
+    NextTheme,
     Quit,
 }
@@ -0,0 +1,184 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::gitrepo::GitRepo;
+
+/// 仓库列表变化时的回调, 组件只需要实现关心的部分, 然后注册到 RepoStore
+pub trait RepoObserver {
+    fn on_added(&mut self, _repo: &GitRepo) {}
+    fn on_updated(&mut self, _old: &GitRepo, _repo: &GitRepo) {}
+    fn on_removed(&mut self, _repo: &GitRepo) {}
+}
+
+/// 所有仓库, 修改都要通过这里进行, 修改之后通知注册的观察者
+///
+/// 读取时可以直接当作 &[GitRepo] 使用
+#[derive(Default)]
+pub struct RepoStore {
+    repos: Vec<GitRepo>,
+    observers: Vec<Rc<RefCell<dyn RepoObserver>>>,
+}
+
+impl fmt::Debug for RepoStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RepoStore")
+            .field("repos", &self.repos)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl Deref for RepoStore {
+    type Target = [GitRepo];
+
+    fn deref(&self) -> &Self::Target {
+        &self.repos
+    }
+}
+
+impl RepoStore {
+    pub fn new() -> Self {
+        RepoStore::default()
+    }
+
+    /// 注册观察者, 已经有的仓库会先作为 on_added 通知一次
+    pub fn subscribe(&mut self, observer: Rc<RefCell<dyn RepoObserver>>) {
+        for repo in &self.repos {
+            observer.borrow_mut().on_added(repo);
+        }
+        self.observers.push(observer);
+    }
+
+    fn notify(&self, f: impl Fn(&mut dyn RepoObserver)) {
+        for observer in &self.observers {
+            f(&mut *observer.borrow_mut());
+        }
+    }
+
+    /// 使用完整刷新的结果替换所有仓库, 按路径对应新旧仓库
+    pub fn replace_all(&mut self, repos: Vec<GitRepo>) {
+        let mut old: HashMap<PathBuf, GitRepo> = std::mem::take(&mut self.repos)
+            .into_iter()
+            .map(|repo| (repo.path.clone(), repo))
+            .collect();
+
+        for repo in &repos {
+            match old.remove(&repo.path) {
+                Some(old_repo) => self.notify(|observer| observer.on_updated(&old_repo, repo)),
+                None => self.notify(|observer| observer.on_added(repo)),
+            }
+        }
+        for repo in old.values() {
+            self.notify(|observer| observer.on_removed(repo));
+        }
+
+        self.repos = repos;
+    }
+
+    /// 更新路径相同的仓库, 没有时加入到最前面
+    pub fn upsert(&mut self, repo: GitRepo) {
+        match self.repos.iter().position(|item| item.path == repo.path) {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.repos[index], repo);
+                self.notify(|observer| observer.on_updated(&old, &self.repos[index]));
+            }
+            None => {
+                self.repos.insert(0, repo);
+                self.notify(|observer| observer.on_added(&self.repos[0]));
+            }
+        }
+    }
+
+    /// 修改一个仓库
+    pub fn update(&mut self, index: usize, f: impl FnOnce(&mut GitRepo)) {
+        let old = self.repos[index].clone();
+        f(&mut self.repos[index]);
+        self.notify(|observer| observer.on_updated(&old, &self.repos[index]));
+    }
+
+    pub fn remove(&mut self, index: usize) -> GitRepo {
+        let repo = self.repos.remove(index);
+        self.notify(|observer| observer.on_removed(&repo));
+        repo
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use super::{RepoObserver, RepoStore};
+    use crate::gitrepo::{GitRepo, GitStatus};
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl RepoObserver for Events {
+        fn on_added(&mut self, repo: &GitRepo) {
+            self.0.push(format!("+{}", repo.name));
+        }
+        fn on_updated(&mut self, _old: &GitRepo, repo: &GitRepo) {
+            self.0.push(format!("~{}", repo.name));
+        }
+        fn on_removed(&mut self, repo: &GitRepo) {
+            self.0.push(format!("-{}", repo.name));
+        }
+    }
+
+    fn repo(name: &str) -> GitRepo {
+        GitRepo {
+            name: String::from(name),
+            path: PathBuf::from("/tmp").join(name),
+            status: GitStatus::Clean,
+            last_commit_time: 0,
+            last_commit_summary: String::new(),
+            last_commit_author: String::new(),
+            tags: Vec::new(),
+            large_untracked: Vec::new(),
+            last_fetch_time: 0,
+            last_fetch_success: 0,
+            has_remote: false,
+            has_upstream: false,
+            submodules: Vec::new(),
+            branch: String::from("master"),
+            branches: Vec::new(),
+            worktree_of: None,
+            nested_in: None,
+            remote_url: String::new(),
+            remote_urls: Vec::new(),
+            remote_branches: Vec::new(),
+            default_branch: None,
+            fetch_backend: None,
+            no_fetch: false,
+            status_history: Vec::new(),
+            visits: Vec::new(),
+            root_commit: None,
+            size: None,
+            forge_status: None,
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_repo_store() {
+        let events = Rc::new(RefCell::new(Events::default()));
+        let mut store = RepoStore::new();
+        store.upsert(repo("a"));
+        store.subscribe(events.clone());
+
+        store.replace_all(vec![repo("a"), repo("b")]);
+        store.update(1, |repo| repo.status = GitStatus::NeedPush);
+        store.remove(0);
+        store.upsert(repo("c"));
+
+        assert_eq!(events.borrow().0, vec!["+a", "~a", "+b", "~b", "-a", "+c"]);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store[1].status, GitStatus::NeedPush);
+    }
+}
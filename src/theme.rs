@@ -0,0 +1,307 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::GitManagerError;
+use crate::gitrepo::GitStatus;
+use crate::i18n::trf;
+use crate::utils::{ba_error, BDEResult};
+
+/// 配置文件中的样式, 颜色可以是名字 (red, light_blue), #rrggbb 或者 0-255 的编号
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StyleConfig {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+}
+
+fn parse_color(name: &str) -> BDEResult<Color> {
+    let name = name.trim().to_lowercase().replace(['-', ' '], "_");
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|item| u8::from_str_radix(item, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(ba_error(&trf!(
+                "无法识别的颜色 #{}",
+                "unknown color #{}",
+                hex
+            ))),
+        };
+    }
+    if let Ok(index) = name.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    Ok(match name.as_str() {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "dark_gray" | "dark_grey" => Color::DarkGray,
+        "light_red" => Color::LightRed,
+        "light_green" => Color::LightGreen,
+        "light_yellow" => Color::LightYellow,
+        "light_blue" => Color::LightBlue,
+        "light_magenta" => Color::LightMagenta,
+        "light_cyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => {
+            return Err(ba_error(&trf!(
+                "无法识别的颜色 {}",
+                "unknown color {}",
+                name
+            )))
+        }
+    })
+}
+
+impl StyleConfig {
+    pub fn to_style(&self) -> BDEResult<Style> {
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.underlined {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        Ok(style)
+    }
+}
+
+/// 界面配色, 状态除了颜色之外还会显示符号, 不依赖颜色也可以区分
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: &'static str,
+    pub clean: Style,
+    pub need_pull: Style,
+    pub need_push: Style,
+    pub need_commit: Style,
+    pub untracked: Style,
+    pub timeout: Style,
+    pub detached_head: Style,
+    /// 没有完成的 rebase/merge
+    pub in_progress: Style,
+    pub conflicted: Style,
+    /// 路径已经不存在
+    pub missing: Style,
+    /// 表格中的普通文字
+    pub text: Style,
+    /// 表头
+    pub header: Style,
+    /// 选中的行
+    pub selected: Style,
+    /// 表格和弹窗的边框
+    pub border: Style,
+    /// 详情中的小标题和分组的名字
+    pub heading: Style,
+    /// 状态栏中的刷新耗时和任务数量
+    pub metrics: Style,
+    /// 状态栏中的只读提示
+    pub read_only: Style,
+}
+
+impl Theme {
+    pub fn default_theme() -> Self {
+        Theme {
+            name: "default",
+            clean: Style::default().fg(Color::Green),
+            need_pull: Style::default().fg(Color::Yellow),
+            need_push: Style::default().fg(Color::Cyan),
+            need_commit: Style::default().fg(Color::Red),
+            untracked: Style::default().fg(Color::LightBlue),
+            timeout: Style::default().fg(Color::DarkGray),
+            detached_head: Style::default().fg(Color::Magenta),
+            in_progress: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+            conflicted: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+            missing: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT),
+            text: Style::default().fg(Color::White),
+            header: Style::default().fg(Color::Yellow),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            border: Style::default(),
+            heading: Style::default().add_modifier(Modifier::BOLD),
+            metrics: Style::default().fg(Color::DarkGray),
+            read_only: Style::default().fg(Color::Red),
+        }
+    }
+
+    /// 针对红绿色盲 (deuteranopia) 的配色, 使用 Okabe-Ito 色板
+    pub fn deuteranopia() -> Self {
+        Theme {
+            name: "deuteranopia",
+            clean: Style::default().fg(Color::Rgb(0, 114, 178)),
+            need_pull: Style::default()
+                .fg(Color::Rgb(230, 159, 0))
+                .add_modifier(Modifier::BOLD),
+            need_push: Style::default()
+                .fg(Color::Rgb(86, 180, 233))
+                .add_modifier(Modifier::BOLD),
+            need_commit: Style::default()
+                .fg(Color::Rgb(240, 228, 66))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            untracked: Style::default().fg(Color::Rgb(240, 228, 66)),
+            timeout: Style::default()
+                .fg(Color::Rgb(204, 121, 167))
+                .add_modifier(Modifier::ITALIC),
+            detached_head: Style::default()
+                .fg(Color::Rgb(204, 121, 167))
+                .add_modifier(Modifier::BOLD),
+            in_progress: Style::default()
+                .fg(Color::Rgb(213, 94, 0))
+                .add_modifier(Modifier::BOLD),
+            conflicted: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Rgb(240, 228, 66))
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            missing: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::CROSSED_OUT),
+            text: Style::default().fg(Color::White),
+            header: Style::default().fg(Color::Rgb(230, 159, 0)),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            border: Style::default(),
+            heading: Style::default().add_modifier(Modifier::BOLD),
+            metrics: Style::default().fg(Color::DarkGray),
+            read_only: Style::default()
+                .fg(Color::Rgb(213, 94, 0))
+                .add_modifier(Modifier::BOLD),
+        }
+    }
+
+    pub fn all_themes() -> Vec<Theme> {
+        vec![Theme::default_theme(), Theme::deuteranopia()]
+    }
+
+    /// 根据名字查找内置配色, 找不到时使用默认配色
+    pub fn from_name(name: &str) -> Self {
+        Theme::all_themes()
+            .into_iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or_else(Theme::default_theme)
+    }
+
+    /// 使用配置文件中的样式覆盖, 样式名字和字段名字相同, 比如 need_commit, header
+    pub fn with_styles(mut self, styles: &BTreeMap<String, StyleConfig>) -> BDEResult<Self> {
+        for (name, style_config) in styles {
+            let style = style_config.to_style().map_err(|err| {
+                GitManagerError::Config(format!("theme_styles.{}: {}", name, err))
+            })?;
+            let field = match name.as_str() {
+                "clean" => &mut self.clean,
+                "need_pull" => &mut self.need_pull,
+                "need_push" => &mut self.need_push,
+                "need_commit" => &mut self.need_commit,
+                "untracked" => &mut self.untracked,
+                "timeout" => &mut self.timeout,
+                "detached_head" => &mut self.detached_head,
+                "in_progress" => &mut self.in_progress,
+                "conflicted" => &mut self.conflicted,
+                "missing" => &mut self.missing,
+                "text" => &mut self.text,
+                "header" => &mut self.header,
+                "selected" => &mut self.selected,
+                "border" => &mut self.border,
+                "heading" => &mut self.heading,
+                "metrics" => &mut self.metrics,
+                "read_only" => &mut self.read_only,
+                _ => {
+                    return Err(ba_error(&trf!(
+                        "theme_styles: 无法识别的样式名字 {}",
+                        "theme_styles: unknown style name {}",
+                        name
+                    )))
+                }
+            };
+            *field = style;
+        }
+        Ok(self)
+    }
+
+    pub fn status_style(&self, status: GitStatus) -> Style {
+        match status {
+            GitStatus::Clean => self.clean,
+            GitStatus::NeedPull => self.need_pull,
+            GitStatus::NeedPush => self.need_push,
+            GitStatus::NeedCommit => self.need_commit,
+            GitStatus::Untracked => self.untracked,
+            GitStatus::Timeout | GitStatus::AuthRequired => self.timeout,
+            GitStatus::DetachedHead => self.detached_head,
+            GitStatus::Rebasing | GitStatus::Merging => self.in_progress,
+            GitStatus::Conflicted => self.conflicted,
+            GitStatus::Missing => self.missing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::style::{Color, Modifier};
+    use std::collections::BTreeMap;
+
+    use super::{StyleConfig, Theme};
+
+    #[test]
+    fn test_with_styles() {
+        let mut styles = BTreeMap::new();
+        styles.insert(
+            String::from("need_commit"),
+            StyleConfig {
+                fg: Some(String::from("#ff8000")),
+                bold: true,
+                ..StyleConfig::default()
+            },
+        );
+        styles.insert(
+            String::from("header"),
+            StyleConfig {
+                fg: Some(String::from("light-blue")),
+                bg: Some(String::from("236")),
+                ..StyleConfig::default()
+            },
+        );
+        let theme = Theme::default_theme().with_styles(&styles).unwrap();
+        assert_eq!(theme.need_commit.fg, Some(Color::Rgb(255, 128, 0)));
+        assert!(theme.need_commit.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(theme.header.fg, Some(Color::LightBlue));
+        assert_eq!(theme.header.bg, Some(Color::Indexed(236)));
+
+        styles.insert(String::from("unknown"), StyleConfig::default());
+        assert!(Theme::default_theme().with_styles(&styles).is_err());
+
+        let mut styles = BTreeMap::new();
+        styles.insert(
+            String::from("clean"),
+            StyleConfig {
+                fg: Some(String::from("#12")),
+                ..StyleConfig::default()
+            },
+        );
+        assert!(Theme::default_theme().with_styles(&styles).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gitrepo::{get_save_data_dir, write_cache_file};
+use crate::i18n::tr;
+use crate::utils::{now_timestamp, BDEResult};
+
+/// 留下墓碑的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TombstoneReason {
+    /// 隐藏了仓库
+    Ignored,
+    /// 仓库移动到了回收站或者归档目录
+    Deleted,
+}
+
+impl fmt::Display for TombstoneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TombstoneReason::Ignored => write!(f, "{}", tr("已隐藏", "ignored")),
+            TombstoneReason::Deleted => write!(f, "{}", tr("已删除", "deleted")),
+        }
+    }
+}
+
+/// 隐藏或者删除的仓库留下的记录, 重新扫描时不会再作为新仓库加入
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Tombstone {
+    pub path: PathBuf,
+    /// origin 的地址, 删除的仓库被移动到归档目录之后根据它识别
+    pub origin: Option<String>,
+    pub reason: TombstoneReason,
+    pub time: u64,
+}
+
+/// 仓库 origin 的地址
+pub fn repo_origin(path: &Path) -> Option<String> {
+    let repo = git2::Repository::open(path).ok()?;
+    let remote = repo.find_remote("origin").ok()?;
+    remote.url().map(String::from)
+}
+
+impl Tombstone {
+    pub fn new(path: &Path, reason: TombstoneReason) -> Self {
+        Tombstone {
+            path: PathBuf::from(path),
+            origin: repo_origin(path),
+            reason,
+            time: now_timestamp(),
+        }
+    }
+
+    /// 路径相同, 或者删除的仓库 origin 相同 (移动到了搜索目录中的归档目录)
+    pub fn matches(&self, path: &Path, origin: Option<&str>) -> bool {
+        self.path == path
+            || (self.reason == TombstoneReason::Deleted
+                && origin.is_some()
+                && self.origin.as_deref() == origin)
+    }
+}
+
+fn get_save_tombstone_path() -> BDEResult<PathBuf> {
+    Ok(get_save_data_dir()?.join("tombstone.json"))
+}
+
+pub fn save_tombstones(tombstones: &[Tombstone]) -> BDEResult<()> {
+    let tombstone_json = serde_json::to_string_pretty(tombstones)?;
+    write_cache_file(&get_save_tombstone_path()?, &tombstone_json)
+}
+
+pub fn load_tombstones() -> BDEResult<Vec<Tombstone>> {
+    let tombstone_path = get_save_tombstone_path()?;
+
+    if !tombstone_path.exists() {
+        Ok(Vec::new())
+    } else {
+        let tombstone_json = fs::read_to_string(tombstone_path)?;
+        Ok(serde_json::from_str(&tombstone_json)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{Tombstone, TombstoneReason};
+
+    #[test]
+    fn test_tombstone_matches() {
+        let mut tombstone = Tombstone {
+            path: PathBuf::from("/home/user/old"),
+            origin: Some(String::from("git@github.com:user/old.git")),
+            reason: TombstoneReason::Deleted,
+            time: 0,
+        };
+        let archived = Path::new("/home/user/archive/old");
+
+        assert!(tombstone.matches(Path::new("/home/user/old"), None));
+        assert!(tombstone.matches(archived, Some("git@github.com:user/old.git")));
+        assert!(!tombstone.matches(archived, Some("git@github.com:user/new.git")));
+        assert!(!tombstone.matches(archived, None));
+
+        tombstone.reason = TombstoneReason::Ignored;
+        assert!(!tombstone.matches(archived, Some("git@github.com:user/old.git")));
+    }
+}
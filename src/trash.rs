@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::i18n::tr;
+use crate::paths::expand_path;
+use crate::utils::{ba_error, now_timestamp, timestamp_to_datetime, BDEResult, Cmd};
+
+/// 移动目录, 跨文件系统时 rename 会失败, 这时使用 mv
+fn move_dir(from: &Path, to: &Path) -> BDEResult<()> {
+    if fs::rename(from, to).is_err() {
+        Cmd::new("mv").arg(from).arg(to).output()?;
+        if from.exists() {
+            return Err(ba_error(
+                format!("移动 {} 到 {} 失败", from.display(), to.display()).as_str(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// 在 dir 中为 name 找一个不重复的名字
+fn unique_name(dir: &Path, name: &str) -> String {
+    let mut new_name = String::from(name);
+    let mut index = 1;
+    while dir.join(&new_name).exists() {
+        new_name = format!("{}.{}", name, index);
+        index += 1;
+    }
+    new_name
+}
+
+fn get_file_name(path: &Path) -> BDEResult<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(String::from)
+        .ok_or_else(|| ba_error(tr("无效的仓库路径", "invalid repo path")))
+}
+
+/// 按照 freedesktop 的回收站规范移动到 ~/.local/share/Trash
+pub fn move_to_trash(path: &Path) -> BDEResult<PathBuf> {
+    let trash_dir = expand_path("~/.local/share/Trash")?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let name = unique_name(&files_dir, &get_file_name(path)?);
+    let (year, month, day, hour, minute, second) = timestamp_to_datetime(now_timestamp());
+    let trash_info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={:04}-{:02}-{:02}T{:02}:{:02}:{:02}\n",
+        path.display(),
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second
+    );
+    fs::write(info_dir.join(format!("{}.trashinfo", name)), trash_info)?;
+
+    let target = files_dir.join(name);
+    move_dir(path, &target)?;
+    Ok(target)
+}
+
+/// 移动到归档目录
+pub fn move_to_archive(path: &Path, archive_dir: &str) -> BDEResult<PathBuf> {
+    let archive_dir = expand_path(archive_dir)?;
+    fs::create_dir_all(&archive_dir)?;
+
+    let target = archive_dir.join(unique_name(&archive_dir, &get_file_name(path)?));
+    move_dir(path, &target)?;
+    Ok(target)
+}
+
+/// 重命名或者移动仓库目录, 目标已经存在时失败, 之后修复工作树和主仓库之间的链接
+pub fn move_repo(path: &Path, target: &Path) -> BDEResult<()> {
+    if target.exists() {
+        return Err(ba_error(format!("{} 已经存在", target.display()).as_str()));
+    }
+    if target.starts_with(path) {
+        return Err(ba_error(tr(
+            "不能移动到仓库自己的目录中",
+            "cannot move a repo into itself",
+        )));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    move_dir(path, target)?;
+    Cmd::git(target).args(["worktree", "repair"]).output()?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crossterm::{
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use std::io::stderr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+use crate::utils::BDEResult;
+
+/// 终端当前处于 raw mode 和备用屏幕中
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+static PANIC_HOOK: Once = Once::new();
+
+/// 界面运行期间持有, 离开作用域 (包括出错返回和 panic) 时恢复终端
+#[derive(Debug)]
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn enter() -> BDEResult<Self> {
+        install_panic_hook();
+        enable_raw_mode()?;
+        stderr().execute(EnterAlternateScreen)?;
+        ACTIVE.store(true, Ordering::SeqCst);
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// 退出 raw mode 和备用屏幕, 已经恢复时不做任何事情
+pub fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = stderr().execute(LeaveAlternateScreen);
+    }
+}
+
+/// 界面线程 panic 时先恢复终端, 否则 panic 信息会输出到备用屏幕中看不到
+///
+/// 后台任务的 panic 由 tokio 捕获, 界面还在运行, 不恢复终端
+fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if std::thread::current().name() == Some("main") {
+                restore();
+            }
+            hook(info);
+        }));
+    });
+}
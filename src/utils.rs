@@ -1,148 +1,376 @@
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::{self, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::signal::ctrl_c;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::time::timeout;
 use tokio::time::Duration;
 
-pub type BDError = Box<dyn std::error::Error>;
-pub type BDEResult<T> = Result<T, BDError>;
+use crate::credentials::git_env;
+use crate::error::GitManagerError;
 
+pub type BDEResult<T> = Result<T, GitManagerError>;
+
+/// 没有更具体种类的错误
+pub fn ba_error(error: &str) -> GitManagerError {
+    GitManagerError::Other(String::from(error))
+}
+
+/// 当前的 Unix 时间戳, 单位秒
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|item| item.as_secs())
+        .unwrap_or(0)
+}
+
+/// 将 Unix 时间戳转换为 UTC 的 (年, 月, 日, 时, 分, 秒)
+pub fn timestamp_to_datetime(timestamp: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        (seconds / 3600) as u32,
+        (seconds % 3600 / 60) as u32,
+        (seconds % 60) as u32,
+    )
+}
+
+/// 直接执行的命令, 参数不经过 shell 解析, 路径中有空格或者引号也不会出错
+///
+/// ```ignore
+/// let status = Cmd::git(path).args(["status", "--porcelain"]).output()?;
+/// ```
 #[derive(Debug, Clone)]
-pub struct GitError {
-    err: String,
+pub struct Cmd {
+    program: OsString,
+    args: Vec<OsString>,
+    dir: Option<PathBuf>,
+    /// 使用 git_env 中的 ssh 设置
+    git_envp: bool,
+    /// 写到标准输入的内容, 比如不能出现在命令行参数中的 token
+    input: Option<String>,
 }
 
-impl GitError {
-    pub fn new(err: &str) -> GitError {
-        GitError {
-            err: err.to_string(),
+impl Cmd {
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Cmd {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            dir: None,
+            git_envp: false,
+            input: None,
         }
     }
-}
 
-impl std::fmt::Display for GitError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.err)
+    /// 在 dir 中执行 git
+    pub fn git(dir: &Path) -> Self {
+        let mut cmd = Cmd::new("git");
+        cmd.dir = Some(dir.to_path_buf());
+        cmd.git_envp = true;
+        cmd
     }
-}
 
-impl std::error::Error for GitError {
-    fn description(&self) -> &str {
-        &self.err
+    /// 在 dir 中用 bash 执行 shell 命令, 和 git 一样使用 git_env 中的 ssh 设置
+    pub fn shell(dir: &Path, command: &str) -> Self {
+        let mut cmd = Cmd::new("bash").arg("-c").arg(command);
+        cmd.dir = Some(dir.to_path_buf());
+        cmd.git_envp = true;
+        cmd
     }
 
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        // 泛型错误。没有记录其内部原因。
-        None
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
     }
-}
-
-pub fn ba_error(error: &str) -> Box<dyn std::error::Error> {
-    Box::new(GitError::new(error))
-}
 
-pub fn run_command(command: &str) -> BDEResult<String> {
-    match process::Command::new("bash")
-        .arg("-c")
-        .arg(command)
-        .output()
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
     {
-        Ok(output) => Ok(String::from_utf8(output.stdout).unwrap()),
-        Err(error) => Err(ba_error(format!("执行命令失败: {}", error).as_mut_str())),
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
     }
-}
 
-pub fn run_command_no(command: &str) -> BDEResult<()> {
-    process::Command::new("bash")
-        .arg("-c")
-        .arg(command)
-        .spawn()?;
+    pub fn input(mut self, input: impl Into<String>) -> Self {
+        self.input = Some(input.into());
+        self
+    }
 
-    Ok(())
-}
+    fn std_command(&self) -> process::Command {
+        let mut command = process::Command::new(&self.program);
+        command.args(&self.args).stdin(Stdio::null());
+        if let Some(dir) = &self.dir {
+            command.current_dir(dir);
+        }
+        if self.git_envp {
+            command.envs(git_env());
+        }
+        command
+    }
 
-pub async fn run_command_timeout(command: &str, timeout_second: u64) -> BDEResult<String> {
-    let timeout_duration = Duration::from_secs(timeout_second);
+    fn tokio_command(&self) -> tokio::process::Command {
+        let mut command = tokio::process::Command::from(self.std_command());
+        command.kill_on_drop(true);
+        command
+    }
 
-    let mut child = tokio::process::Command::new("bash")
-        .arg("-c")
-        .arg(command)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped()) // 捕获标准输出
-        .stderr(Stdio::null()) // 将标准错误重定向到空
-        .spawn()
-        .map_err(|e| format!("Failed to spawn command: {}", e))?;
-
-    // Create a future that resolves when Ctrl+C is pressed
-    let ctrl_c_future = ctrl_c();
-
-    tokio::select! {
-        // Wait for the command to complete
-        _ = child.wait() => {
-            let output = child.wait_with_output().await?;
-            if output.status.success() {
-                Ok(String::from_utf8(output.stdout).unwrap())
-            } else {
-                Err(format!("Command failed with exit code({}): {}", output.status, String::from_utf8(output.stdout).unwrap()).into())
+    fn spawn_error(&self, err: std::io::Error) -> GitManagerError {
+        GitManagerError::Command(format!(
+            "执行命令失败: {}: {}",
+            self.program.to_string_lossy(),
+            err
+        ))
+    }
+
+    /// 执行并返回标准输出, 不检查退出码
+    pub fn output(&self) -> BDEResult<String> {
+        let mut command = self.std_command();
+        command.stdout(Stdio::piped()).stderr(Stdio::null());
+        if self.input.is_some() {
+            command.stdin(Stdio::piped());
+        }
+        let mut child = command.spawn().map_err(|err| self.spawn_error(err))?;
+        if let (Some(input), Some(mut stdin)) = (&self.input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// 在后台启动, 不等待退出, 用于打开浏览器这种可能一直运行的程序
+    pub fn spawn(&self) -> BDEResult<()> {
+        let mut child = self
+            .std_command()
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| self.spawn_error(err))?;
+        // 回收子进程, 避免留下僵尸进程
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    /// 执行并等待退出, 超时或者按下 Ctrl+C 时结束命令, 返回是否成功退出
+    pub async fn status_timeout(&self, timeout_second: u64) -> BDEResult<bool> {
+        let mut child = self
+            .tokio_command()
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| self.spawn_error(err))?;
+
+        tokio::select! {
+            status = child.wait() => Ok(status?.success()),
+            _ = timeout(Duration::from_secs(timeout_second), ctrl_c()) => {
+                child.kill().await?;
+                Err(GitManagerError::CommandTimeout)
             }
         }
+    }
+
+    /// 和 status_timeout 一样, 同时返回标准错误, 用来判断失败的原因
+    pub async fn stderr_timeout(&self, timeout_second: u64) -> BDEResult<(bool, String)> {
+        let child = self
+            .tokio_command()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| self.spawn_error(err))?;
 
-        // Wait for Ctrl+C or timeout
-        _ = timeout(timeout_duration, ctrl_c_future) => {
-            child.kill().await?;
-            Err(ba_error("Command timed out"))
+        tokio::select! {
+            output = child.wait_with_output() => {
+                let output = output?;
+                Ok((
+                    output.status.success(),
+                    String::from_utf8_lossy(&output.stderr).into_owned(),
+                ))
+            }
+            _ = timeout(Duration::from_secs(timeout_second), ctrl_c()) => {
+                Err(GitManagerError::CommandTimeout)
+            }
         }
     }
 }
 
-pub async fn run_command_timeout_no(command: &str, timeout_second: u64) -> BDEResult<()> {
-    let timeout_duration = Duration::from_secs(timeout_second);
+impl Cmd {
+    /// 执行命令, 并把标准输出和标准错误按行实时发送出去, 返回退出码
+    ///
+    /// 不发送 CommandOutput::Exit, 由调用者在所有命令结束之后发送
+    pub async fn stream(
+        &self,
+        output_tx: &UnboundedSender<CommandOutput>,
+    ) -> BDEResult<Option<i32>> {
+        let child = self
+            .tokio_command()
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| self.spawn_error(err))?;
+        stream_child(child, output_tx).await
+    }
+}
+
+/// 转发子进程的输出直到它退出
+async fn stream_child(
+    mut child: tokio::process::Child,
+    output_tx: &UnboundedSender<CommandOutput>,
+) -> BDEResult<Option<i32>> {
+    let mut stdout_lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr_lines = BufReader::new(child.stderr.take().unwrap()).lines();
+
+    let stdout_tx = output_tx.clone();
+    let stdout_task = tokio::spawn(async move {
+        while let Ok(Some(line)) = stdout_lines.next_line().await {
+            let _ = stdout_tx.send(CommandOutput::Stdout(line));
+        }
+    });
+
+    let stderr_tx = output_tx.clone();
+    let stderr_task = tokio::spawn(async move {
+        while let Ok(Some(line)) = stderr_lines.next_line().await {
+            let _ = stderr_tx.send(CommandOutput::Stderr(line));
+        }
+    });
+
+    let status = child.wait().await?;
+    let _ = stdout_task.await;
+    let _ = stderr_task.await;
+    Ok(status.code())
+}
+
+/// 在 dir 中执行 git 并返回标准输出
+pub fn git_output<I, S>(dir: &Path, args: I) -> BDEResult<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Cmd::git(dir).args(args).output()
+}
 
-    let mut child = tokio::process::Command::new("bash")
+/// 这些编辑器可以用 +行号 跳转到指定的行
+const LINE_EDITORS: [&str; 7] = ["vi", "vim", "nvim", "nano", "emacs", "emacsclient", "kak"];
+
+/// 用编辑器打开 file 的参数, 编辑器支持时跳转到第 line 行
+pub fn editor_args(editor: &str, file: &Path, line: Option<usize>) -> Vec<OsString> {
+    let name = editor
+        .split_whitespace()
+        .next()
+        .and_then(|program| Path::new(program).file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let mut args = Vec::new();
+    if let Some(line) = line.filter(|_| LINE_EDITORS.contains(&name)) {
+        args.push(OsString::from(format!("+{}", line)));
+    }
+    args.push(file.as_os_str().to_os_string());
+    args
+}
+
+/// 用默认浏览器打开地址
+pub fn open_url(url: &str) -> BDEResult<()> {
+    let cmd = if cfg!(windows) {
+        // start 会把地址中的 & 当作命令分隔符
+        Cmd::new("rundll32").arg("url.dll,FileProtocolHandler")
+    } else if cfg!(target_os = "macos") {
+        Cmd::new("open")
+    } else {
+        Cmd::new("xdg-open")
+    };
+    cmd.arg(url).spawn()
+}
+
+/// 拼接 shell 脚本时使用, 用单引号包住参数
+pub fn shell_quote(arg: &Path) -> String {
+    format!("'{}'", arg.display().to_string().replace('\'', "'\\''"))
+}
+
+/// 流式命令输出的一行或者命令结束
+#[derive(Debug, Clone)]
+pub enum CommandOutput {
+    Stdout(String),
+    Stderr(String),
+    Exit(Option<i32>),
+}
+
+/// 执行命令, 并把标准输出和标准错误按行实时发送出去
+pub async fn run_command_stream(
+    command: &str,
+    output_tx: UnboundedSender<CommandOutput>,
+) -> BDEResult<()> {
+    tracing::info!("run command: {}", command);
+    let child = tokio::process::Command::new("bash")
         .arg("-c")
         .arg(command)
+        .envs(git_env())
+        .env("GIT_TERMINAL_PROMPT", "0")
         .stdin(Stdio::null())
-        .stdout(Stdio::null()) // 标准输出重定向到空
-        .stderr(Stdio::null()) // 将标准错误重定向到空
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to spawn command: {}", e))?;
-
-    // Create a future that resolves when Ctrl+C is pressed
-    let ctrl_c_future = ctrl_c();
-
-    tokio::select! {
-        // Wait for the command to complete
-        _ = child.wait() => {
-            Ok(())
-            // let output = child.wait_with_output().await?;
-            // if output.status.success() {
-            //     // Ok(String::from_utf8(output.stdout).unwrap())
-            //     Ok(())
-            // } else {
-            //     Err(format!("Command failed with exit code({}): {}", output.status, String::from_utf8(output.stdout).unwrap()).into())
-            // }
-        }
+        .map_err(|e| GitManagerError::Command(format!("Failed to spawn command: {}", e)))?;
 
-        // Wait for Ctrl+C or timeout
-        _ = timeout(timeout_duration, ctrl_c_future) => {
-            child.kill().await?;
-            Err(ba_error("Command timed out"))
-        }
+    let code = stream_child(child, &output_tx).await?;
+    if code == Some(0) {
+        tracing::info!("command finished: {}", command);
+    } else {
+        tracing::warn!("command failed ({:?}): {}", code, command);
     }
-}
+    let _ = output_tx.send(CommandOutput::Exit(code));
 
-// 需要安装 xclip
-pub fn copy_to_clipboard(text: &str) -> BDEResult<()> {
-    let command = format!("echo '{}' | xclip -selection clipboard", text);
-    run_command_no(&command)?;
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use super::copy_to_clipboard;
+    use super::{editor_args, timestamp_to_datetime};
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    #[test]
+    fn test_timestamp_to_datetime() {
+        assert_eq!(timestamp_to_datetime(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(timestamp_to_datetime(951782400), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(
+            timestamp_to_datetime(1700000000),
+            (2023, 11, 14, 22, 13, 20)
+        );
+    }
 
     #[test]
-    fn test_clipboard() {
-        let _ = copy_to_clipboard("hello world bababa");
+    fn test_editor_args() {
+        let file = Path::new("src/main.rs");
+        assert_eq!(
+            editor_args("/usr/bin/nvim -u NONE", file, Some(12)),
+            vec![OsString::from("+12"), OsString::from("src/main.rs")]
+        );
+        assert_eq!(
+            editor_args("code --wait", file, Some(12)),
+            vec![OsString::from("src/main.rs")]
+        );
+        assert_eq!(
+            editor_args("vim", file, None),
+            vec![OsString::from("src/main.rs")]
+        );
     }
 }